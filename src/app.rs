@@ -1,6 +1,8 @@
 //! Application state and core logic
 
+use std::borrow::Cow;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
@@ -53,6 +55,8 @@ pub struct TransportState {
     pub bpm: f64,
     /// Time accumulator for step timing (private)
     step_accumulator: Duration,
+    /// Song position last sent to the audio thread for plugins
+    sent_to_plugins: Option<PluginTransport>,
 }
 
 impl TransportState {
@@ -62,6 +66,28 @@ impl TransportState {
             playback: PlaybackState::default(),
             bpm,
             step_accumulator: Duration::ZERO,
+            sent_to_plugins: None,
+        }
+    }
+
+    /// Song position as plugins see it: the current step in beats, looping
+    /// over the pattern or the whole playlist
+    pub fn plugin_transport(&self) -> PluginTransport {
+        let steps_per_beat = (StepIdx::COUNT / BEATS_PER_BAR as usize) as f64;
+        let bar_beats = BEATS_PER_BAR as f64;
+        let (position_beats, loop_end) = match self.playback {
+            PlaybackState::Stopped => return PluginTransport::stopped(self.bpm),
+            PlaybackState::PlayingPattern { step } => (step.0 as f64 / steps_per_beat, bar_beats),
+            PlaybackState::PlayingArrangement { bar, step } => (
+                bar.0 as f64 * bar_beats + step.0 as f64 / steps_per_beat,
+                BarIdx::COUNT as f64 * bar_beats,
+            ),
+        };
+        PluginTransport {
+            playing: true,
+            tempo: self.bpm,
+            position_beats,
+            loop_beats: Some((0.0, loop_end)),
         }
     }
 
@@ -85,29 +111,45 @@ impl TransportState {
         self.step_accumulator -= self.step_duration();
     }
 
+    /// When the current step was due, given the time now: steps are
+    /// consumed late by whatever time has accumulated past them
+    pub fn step_due_at(&self, now: Instant) -> Instant {
+        now.checked_sub(self.step_accumulator).unwrap_or(now)
+    }
+
     /// Reset the accumulator (called when playback stops/starts)
     pub fn reset_accumulator(&mut self) {
         self.step_accumulator = Duration::ZERO;
     }
 }
+use crate::audio::export::{ExportMode, ExportSettings};
+use crate::audio::export_job::{ExportJob, ExportOutcome, ExportRequest};
 use crate::audio::AudioHandle;
 use crate::browser::BrowserState;
 use crate::command_picker::CommandPicker;
 use crate::confirm_dialog::ConfirmDialog;
-use crate::coords::AppCol;
+use crate::coords::{AppCol, BarIdx, StepIdx};
 use crate::cursor::CursorStates;
+use crate::effects::convolution::is_kernel_param;
+use crate::effects::preset::{EffectPreset, PresetError, PresetLibrary};
 use crate::effects::{EffectSlot, EffectType, EFFECT_SLOTS};
+use crate::export_dialog::ExportDialog;
 use crate::history::{Command, GlobalJumplist, History, JumpPosition};
 use crate::input::context::{PianoRollContext, PlaylistContext, StepGridContext};
 use crate::input::mouse::MouseState;
 use crate::input::vim::{GridSemantics, VimStates, Zone};
 use crate::mixer::{Mixer, TrackId};
 use crate::playback::{PlaybackEvent, PlaybackState};
-use crate::plugin_host::params::build_init_params;
-use crate::plugin_host::{ClapPluginLoader, PluginLoader};
+use crate::plugin_host::params::{build_editor_params, build_init_params};
+use crate::plugin_host::state::{PluginInstances, PluginSlot};
+use crate::plugin_host::transport::{PluginTransport, BEATS_PER_BAR};
+use crate::plugin_host::{
+    ClapPluginLoader, PluginEvent, PluginInfo, PluginLoader, PluginPreset, PluginPresetLibrary,
+};
+use crate::preset_picker::PresetPicker;
 use crate::project::{self, ProjectFile};
 use crate::projects_modal::ProjectsModal;
-use crate::sequencer::{default_channels, Channel, ChannelSource, Note, Pattern};
+use crate::sequencer::{default_channels, note_routes, Channel, ChannelSource, Note, Pattern};
 use crate::ui::areas::ScreenAreas;
 use crate::ui::context_menu::ContextMenu;
 use crate::ui::plugin_editor::PluginEditorState;
@@ -149,7 +191,16 @@ pub struct AppState {
     pub audio: AudioHandle,
 
     /// Plugin loader for loading CLAP/VST plugins
-    pub(crate) plugin_loader: Box<dyn PluginLoader>,
+    pub(crate) plugin_loader: Arc<dyn PluginLoader>,
+
+    /// Main-thread handles of the running plugins, for saving their state
+    pub(crate) plugin_instances: PluginInstances,
+
+    /// Factory and user effect presets
+    pub(crate) preset_library: PresetLibrary,
+
+    /// The user's plugin presets
+    pub(crate) plugin_preset_library: PluginPresetLibrary,
 
     /// Audio sync coordinator for batched updates
     pub audio_sync: AudioSync,
@@ -160,6 +211,9 @@ pub struct AppState {
     /// Undo/redo history
     pub history: History,
 
+    /// Export rendering on a worker thread, if one is running
+    pub export_job: Option<ExportJob>,
+
     /// Dirty flag for auto-save
     pub(crate) dirty: bool,
 
@@ -212,6 +266,9 @@ pub struct UiState {
     /// Projects modal (list and open projects)
     pub projects_modal: ProjectsModal,
 
+    /// Export dialog (format, bit depth, rate, normalization)
+    pub export_dialog: ExportDialog,
+
     /// Plugin editor modal
     pub plugin_editor: PluginEditorState,
 
@@ -230,9 +287,21 @@ pub struct UiState {
     /// Effect picker selection index
     pub effect_picker_selection: usize,
 
+    /// Preset picker shown over the effect editor
+    pub preset_picker: PresetPicker,
+
+    /// Presets of the plugin open in the plugin editor, listed over it
+    pub plugin_preset_picker: PresetPicker<PluginPreset>,
+
     /// Effect register for yank/paste operations (stores last deleted/yanked effect)
     pub effect_register: Option<crate::effects::EffectSlot>,
 
+    /// Effect chain register for copying a whole chain to another track
+    pub effect_chain_register: Option<Box<[Option<crate::effects::EffectSlot>; EFFECT_SLOTS]>>,
+
+    /// Effect slot marked for swapping (track, slot)
+    pub effect_swap_mark: Option<(usize, usize)>,
+
     /// Channel register for yank/paste operations (stores last deleted/yanked channel)
     pub channel_register: Option<crate::sequencer::Channel>,
 
@@ -301,7 +370,7 @@ impl App {
     }
 
     /// Log an event directly (for operations that bypass dispatch like undo/redo)
-    pub fn log_event(&mut self, description: &'static str, is_undoable: bool) {
+    pub fn log_event(&mut self, description: impl Into<Cow<'static, str>>, is_undoable: bool) {
         self.event_log.log(description, is_undoable);
     }
 
@@ -417,10 +486,14 @@ impl App {
             arrangement,
             mixer,
             audio,
-            plugin_loader: Box::new(ClapPluginLoader),
+            plugin_loader: Arc::new(ClapPluginLoader),
+            plugin_instances: PluginInstances::new(),
+            preset_library: PresetLibrary::default(),
+            plugin_preset_library: PluginPresetLibrary::default(),
             audio_sync: AudioSync::new(),
             transport: TransportState::new(bpm),
             history: History::new(),
+            export_job: None,
             dirty: false,
             last_change: Instant::now(),
         };
@@ -449,13 +522,18 @@ impl App {
             browser: BrowserState::new(samples_path),
             command_picker: CommandPicker::new(),
             projects_modal: ProjectsModal::new(),
+            export_dialog: ExportDialog::new(),
             plugin_editor: PluginEditorState::new(),
             screen_areas: ScreenAreas::new(),
             mouse: MouseState::new(),
             context_menu: ContextMenu::new(),
             confirm_dialog: ConfirmDialog::new(),
             effect_picker_selection: 0,
+            preset_picker: PresetPicker::new(),
+            plugin_preset_picker: PresetPicker::new(),
             effect_register: None,
+            effect_chain_register: None,
+            effect_swap_mark: None,
             channel_register: None,
             is_previewing: false,
             preview_channel: None,
@@ -496,9 +574,33 @@ impl App {
         // Sync any effects loaded from the project file to the audio thread
         app.sync_all_effects_to_audio();
 
+        app.log_legacy_plugin_params();
+
         app
     }
 
+    /// Warn about plugin parameters an older project keyed by name that
+    /// couldn't be matched to the plugin; they stay in the project as saved
+    fn log_legacy_plugin_params(&mut self) {
+        let warnings: Vec<String> = self
+            .channels
+            .iter()
+            .filter_map(|channel| {
+                let names = channel.legacy_plugin_params();
+                (!names.is_empty()).then(|| {
+                    format!(
+                        "{}: unrecognized parameters kept as saved: {}",
+                        channel.name,
+                        names.join(", ")
+                    )
+                })
+            })
+            .collect();
+        for warning in warnings {
+            self.log_event(warning, false);
+        }
+    }
+
     /// Create a default mixer with auto-assigned channel routing
     fn create_default_mixer(channels: &[Channel]) -> Mixer {
         let mut mixer = Mixer::new();
@@ -567,9 +669,10 @@ impl App {
 
         use crate::command::AppCommand;
         use crate::history::command::{
-            AddChannelCmd, AddEffectCmd, AddNoteCmd, AddNotesCmd, DeleteChannelCmd, DeleteNotesCmd,
-            DeletePatternCmd, DeleteStepsCmd, RemoveEffectCmd, RemoveNoteCmd, SetStepsCmd,
-            TogglePlacementCmd, ToggleStepCmd,
+            AddChannelCmd, AddEffectCmd, AddNoteCmd, AddNotesCmd, ApplyEffectPresetCmd,
+            DeleteChannelCmd, DeleteNotesCmd, DeletePatternCmd, DeleteStepsCmd,
+            PasteEffectChainCmd, PasteEffectCmd, RemoveEffectCmd, RemoveNoteCmd,
+            SetNoteExpressionCmd, SetStepsCmd, SwapEffectsCmd, TogglePlacementCmd, ToggleStepCmd,
         };
 
         // For undoable commands with history support, use history.execute()
@@ -749,6 +852,25 @@ impl App {
                 }
                 true
             }
+            AppCommand::SetNoteExpression {
+                channel,
+                pattern,
+                note_id,
+                expression,
+                value,
+            } => {
+                let history_cmd = SetNoteExpressionCmd::new(
+                    *pattern,
+                    *channel,
+                    note_id.clone(),
+                    *expression,
+                    *value,
+                );
+                let mut history = std::mem::take(&mut self.history);
+                history.execute(Box::new(history_cmd), self);
+                self.history = history;
+                true
+            }
             AppCommand::DeleteChannel(slot) => {
                 let history_cmd = DeleteChannelCmd::new(*slot);
                 let mut history = std::mem::take(&mut self.history);
@@ -800,6 +922,42 @@ impl App {
                 self.history = history;
                 true
             }
+            AppCommand::ApplyEffectPreset {
+                track,
+                slot,
+                params,
+            } => {
+                let history_cmd = ApplyEffectPresetCmd::new(*track, *slot, params.clone());
+                let mut history = std::mem::take(&mut self.history);
+                history.execute(Box::new(history_cmd), self);
+                self.history = history;
+                true
+            }
+            AppCommand::PasteEffect {
+                track,
+                slot,
+                effect,
+            } => {
+                let history_cmd = PasteEffectCmd::new(*track, *slot, effect.clone());
+                let mut history = std::mem::take(&mut self.history);
+                history.execute(Box::new(history_cmd), self);
+                self.history = history;
+                true
+            }
+            AppCommand::SwapEffects { track, a, b } => {
+                let history_cmd = SwapEffectsCmd::new(*track, *a, *b);
+                let mut history = std::mem::take(&mut self.history);
+                history.execute(Box::new(history_cmd), self);
+                self.history = history;
+                true
+            }
+            AppCommand::PasteEffectChain { track, chain } => {
+                let history_cmd = PasteEffectChainCmd::new(*track, (**chain).clone());
+                let mut history = std::mem::take(&mut self.history);
+                history.execute(Box::new(history_cmd), self);
+                self.history = history;
+                true
+            }
             _ => false,
         };
 
@@ -971,8 +1129,12 @@ impl App {
             AppCommand::SetChannelSample { slot, path } => {
                 self.set_channel_sample(slot, path);
             }
-            AppCommand::SetChannelPlugin { slot, path } => {
-                self.set_channel_plugin(slot, path);
+            AppCommand::SetChannelPlugin {
+                slot,
+                path,
+                plugin_id,
+            } => {
+                self.set_channel_plugin(slot, path, plugin_id);
             }
             AppCommand::SetChannelRouting { slot, track } => {
                 if let Some(vec_idx) = self.channels.iter().position(|c| c.slot == slot) {
@@ -1004,6 +1166,9 @@ impl App {
                     self.audio.set_generator_track(vec_idx, prev);
                 }
             }
+            AppCommand::CycleNoteOut(slot) => {
+                self.cycle_note_out(slot);
+            }
 
             // ================================================================
             // Step grid
@@ -1138,6 +1303,21 @@ impl App {
                     }
                 }
             }
+            AppCommand::SetNoteExpression {
+                channel,
+                pattern,
+                note_id,
+                expression,
+                value,
+            } => {
+                if let Some(ch) = self.channels.get_mut(channel) {
+                    if let Some(slice) = ch.pattern_data.get_mut(&pattern) {
+                        if let Some(note) = slice.notes.iter_mut().find(|n| n.id == note_id) {
+                            note.expressions.set(expression, value);
+                        }
+                    }
+                }
+            }
 
             // ================================================================
             // Playlist / Arrangement
@@ -1206,10 +1386,13 @@ impl App {
                 param,
                 value,
             } => {
+                self.audio.set_effect_param(track, slot, param, value);
                 if let Some(ref mut effect_slot) = self.mixer.tracks[track].effects[slot] {
                     effect_slot.params.insert(param, value);
                 }
-                self.audio.set_effect_param(track, slot, param, value);
+                if is_kernel_param(param) {
+                    self.sync_convolution_kernel(track, slot);
+                }
             }
             AppCommand::ToggleEffectBypass { track, slot } => {
                 if let Some(ref mut effect_slot) = self.mixer.tracks[track].effects[slot] {
@@ -1218,6 +1401,38 @@ impl App {
                     self.audio.set_effect_enabled(track, slot, enabled);
                 }
             }
+            AppCommand::ApplyEffectPreset {
+                track,
+                slot,
+                params,
+            } => {
+                for (&param, &value) in &params {
+                    self.audio.set_effect_param(track, slot, param, value);
+                }
+                if let Some(ref mut effect_slot) = self.mixer.tracks[track].effects[slot] {
+                    effect_slot.params = params;
+                }
+                self.sync_convolution_kernel(track, slot);
+            }
+            AppCommand::PasteEffect {
+                track,
+                slot,
+                effect,
+            } => {
+                self.mixer.tracks[track].effects[slot] = Some(effect);
+                self.queue_effect_slot_sync(track, slot);
+            }
+            AppCommand::SwapEffects { track, a, b } => {
+                self.mixer.tracks[track].effects.swap(a, b);
+                self.queue_effect_slot_sync(track, a);
+                self.queue_effect_slot_sync(track, b);
+            }
+            AppCommand::PasteEffectChain { track, chain } => {
+                self.mixer.tracks[track].effects = *chain;
+                for slot in 0..EFFECT_SLOTS {
+                    self.queue_effect_slot_sync(track, slot);
+                }
+            }
         }
     }
 
@@ -1243,19 +1458,118 @@ impl App {
             audio_sync,
             audio,
             mixer,
+            channels,
             ..
         } = &mut self.state;
         audio_sync.flush(audio, mixer);
+        audio_sync.sync_note_routes(audio, note_routes(channels));
+        audio.free_spent_kernels();
+        for (track_idx, slot_idx) in self.audio_sync.take_plugin_loads() {
+            self.load_effect_plugin(track_idx, slot_idx);
+        }
 
-        if !self.transport.playback.is_playing() {
-            return;
+        self.service_plugins();
+
+        self.poll_export_job();
+
+        if self.transport.playback.is_playing() {
+            // Accumulate time and advance steps as needed
+            self.transport.add_time(delta);
+            while self.transport.should_advance() {
+                self.transport.consume_step();
+                self.advance_step();
+            }
+        }
+
+        self.sync_plugin_transport();
+    }
+
+    /// Run the main-thread callbacks plugins asked for, restart the ones
+    /// that asked to be restarted, and take in the parameter changes
+    /// plugins made themselves
+    fn service_plugins(&mut self) {
+        let restarts = self.plugin_instances.service_main_thread();
+        if !restarts.is_empty() {
+            // Restarted plugins come back from their current state
+            self.capture_plugin_states();
+        }
+        for restart in restarts {
+            match restart {
+                PluginSlot::Channel(slot) => {
+                    if let Some(idx) = self.channels.iter().position(|c| c.slot == slot) {
+                        self.load_channel_plugin(idx);
+                    }
+                }
+                PluginSlot::Effect { track, slot } => self.load_effect_plugin(track, slot),
+            }
+        }
+
+        for event in self.audio.poll_plugin_events() {
+            match event {
+                PluginEvent::ParamChanged {
+                    channel,
+                    param_id,
+                    value,
+                } => {
+                    let Some(channel) = self.state.channels.get_mut(channel) else {
+                        continue;
+                    };
+                    let slot = channel.slot;
+                    if let Some(params) = channel.plugin_params_mut() {
+                        params.insert(param_id, value);
+                    }
+                    let editor = &mut self.ui.plugin_editor;
+                    if editor.visible && editor.channel_idx == slot {
+                        editor.set_param_value(param_id, value);
+                    }
+                    self.mark_dirty();
+                }
+                PluginEvent::EffectParamChanged {
+                    track,
+                    slot,
+                    param_id,
+                    value,
+                } => {
+                    let effect = self
+                        .mixer
+                        .tracks
+                        .get_mut(track)
+                        .and_then(|t| t.effects.get_mut(slot))
+                        .and_then(Option::as_mut);
+                    if let Some(effect) = effect {
+                        effect.plugin_params.insert(param_id, value);
+                        self.mark_dirty();
+                    }
+                }
+                PluginEvent::Crashed { channel } => {
+                    let Some(channel) = self.state.channels.get(channel) else {
+                        continue;
+                    };
+                    let message = format!("Plugin on '{}' crashed, channel muted", channel.name);
+                    self.log_event(message, false);
+                }
+                PluginEvent::EffectCrashed { track, slot } => {
+                    let name = &self.mixer.track(TrackId(track)).name;
+                    let message = format!(
+                        "Effect plugin in '{}' slot {} crashed, passing audio through",
+                        name,
+                        slot + 1
+                    );
+                    self.log_event(message, false);
+                }
+                // Note output is routed to other channels by the audio thread
+                _ => {}
+            }
         }
+    }
 
-        // Accumulate time and advance steps as needed
-        self.transport.add_time(delta);
-        while self.transport.should_advance() {
-            self.transport.consume_step();
-            self.advance_step();
+    /// Send the song position to the audio thread when play state, step or
+    /// tempo changed; the audio thread advances it between steps
+    fn sync_plugin_transport(&mut self) {
+        let transport = self.transport.plugin_transport();
+        if self.transport.sent_to_plugins != Some(transport) {
+            self.audio.update_transport(transport);
+            self.transport.sent_to_plugins = Some(transport);
         }
     }
 
@@ -1305,6 +1619,7 @@ impl App {
     /// Stop notes that span the loop boundary (started before step 16 but end after)
     /// The `bar` parameter should be the bar that was playing BEFORE the loop occurred.
     fn stop_spanning_notes(&self, bar: usize) {
+        let at_frame = self.step_frame();
         // Get patterns to check based on play mode
         let patterns_to_check: Vec<&crate::sequencer::Pattern> =
             if self.transport.playback.is_playing_arrangement() {
@@ -1326,7 +1641,8 @@ impl App {
                     if let Some(slice) = channel.get_pattern(pattern.id) {
                         for note in &slice.notes {
                             if note.start_step + note.duration >= 16 {
-                                self.audio.plugin_note_off(channel_idx, note.pitch);
+                                self.audio
+                                    .plugin_note_off_at(channel_idx, note.pitch, at_frame);
                             }
                         }
                     }
@@ -1360,12 +1676,20 @@ impl App {
         self.play_step_from_patterns(patterns, self.transport.playback.step_or_zero());
     }
 
+    /// Engine frame the current step's events belong at, so they sound on
+    /// the audio clock rather than whenever the main loop got to them
+    fn step_frame(&self) -> Option<u64> {
+        let due = self.transport.step_due_at(Instant::now());
+        self.audio.schedule_frame(due)
+    }
+
     /// Play a step from the given patterns (unified playback logic)
     fn play_step_from_patterns<'a>(
         &self,
         patterns: impl Iterator<Item = &'a Pattern>,
         step: usize,
     ) {
+        let at_frame = self.step_frame();
         for pattern in patterns {
             for (channel_idx, channel) in self.channels.iter().enumerate() {
                 // Get the mixer track this channel routes to
@@ -1376,7 +1700,7 @@ impl App {
                     continue;
                 }
 
-                self.play_channel_step(channel_idx, channel, track_id, pattern, step);
+                self.play_channel_step(channel_idx, channel, track_id, pattern, step, at_frame);
             }
         }
     }
@@ -1389,6 +1713,7 @@ impl App {
         track_id: TrackId,
         pattern: &Pattern,
         step: usize,
+        at_frame: Option<u64>,
     ) {
         // Get volume from the mixer track
         let volume = self.mixer.track(track_id).volume;
@@ -1402,7 +1727,8 @@ impl App {
                 if slice.map(|s| s.get_step(step)).unwrap_or(false) {
                     if let Some(ref sample_path) = path {
                         let full_path = self.project.samples_path().join(sample_path);
-                        self.audio.play_sample(&full_path, volume, channel_idx);
+                        self.audio
+                            .play_sample_at(&full_path, volume, channel_idx, at_frame);
                     }
                 }
             }
@@ -1411,12 +1737,18 @@ impl App {
                 if let Some(slice) = slice {
                     for note in &slice.notes {
                         if note.start_step == step {
-                            self.audio
-                                .plugin_note_on(channel_idx, note.pitch, note.velocity);
+                            self.audio.plugin_note_on_at(
+                                channel_idx,
+                                note.pitch,
+                                note.velocity,
+                                note.expressions,
+                                at_frame,
+                            );
                         }
                         // Check for note-off events (notes that end at this step)
                         if note.start_step + note.duration == step {
-                            self.audio.plugin_note_off(channel_idx, note.pitch);
+                            self.audio
+                                .plugin_note_off_at(channel_idx, note.pitch, at_frame);
                         }
                     }
                 }
@@ -1560,54 +1892,130 @@ impl App {
         self.ui.projects_modal.show(Some(&current));
     }
 
-    /// Start the export flow (show filename prompt)
+    /// Open the export dialog for a full mix
     pub fn start_export(&mut self) {
-        let default_filename = format!("{}.wav", self.state.project.name);
-        self.ui.command_picker.start_export_input(&default_filename);
+        let name = self.state.project.name.clone();
+        self.ui.export_dialog.show(ExportMode::Mix, &name);
     }
 
-    /// Perform the actual export to WAV
-    pub fn do_export(&mut self, filename: &str) {
-        use crate::audio::offline::{render_offline, write_wav, RenderConfig};
+    /// Open the export dialog for per-track stems
+    pub fn start_export_stems(&mut self) {
+        let name = self.state.project.name.clone();
+        self.ui.export_dialog.show(ExportMode::Stems, &name);
+    }
 
-        let config = RenderConfig {
-            sample_rate: 44100,
-            bpm: self.transport.bpm,
-            steps_per_bar: 16,
-        };
+    /// Start the export configured in the export dialog on a worker thread
+    pub fn confirm_export(&mut self) {
+        let dialog = &self.ui.export_dialog;
+        let name = dialog.filename.value().trim().to_string();
+        let mode = dialog.mode;
+        let settings = dialog.settings;
+        let pre_master_fx = !dialog.stem_master_fx;
+        // The dialog shows why; keep it open so the choice can be fixed
+        if dialog.error().is_some() {
+            return;
+        }
+        self.ui.export_dialog.hide();
 
-        let samples_path = self.project.samples_path();
-        let plugins_path = self.project.plugins_path();
+        if name.is_empty() {
+            return;
+        }
+        self.start_export_job(mode, &name, settings, pre_master_fx);
+    }
 
-        self.log_event("exporting...", false);
+    /// Snapshot the project and render it to `name` (relative to the project folder)
+    pub fn start_export_job(
+        &mut self,
+        mode: ExportMode,
+        name: &str,
+        settings: ExportSettings,
+        pre_master_fx: bool,
+    ) {
+        if self.export_job.is_some() {
+            self.log_event("export already running", false);
+            return;
+        }
 
-        let samples = render_offline(
-            &self.state.channels,
-            &self.state.patterns,
-            &self.arrangement,
-            &self.mixer,
-            &samples_path,
-            &plugins_path,
-            &*self.plugin_loader,
-            &config,
+        self.capture_plugin_states();
+        let request = ExportRequest {
+            channels: self.state.channels.clone(),
+            patterns: self.state.patterns.clone(),
+            arrangement: self.arrangement.clone(),
+            mixer: self.mixer.clone(),
+            samples_path: self.project.samples_path(),
+            plugins_path: self.project.plugins_path(),
+            plugin_loader: Arc::clone(&self.plugin_loader),
+            bpm: self.transport.bpm,
+            settings,
+            mode,
+            pre_master_fx,
+            require_plugins: false,
+            output: self.project.path.join(name),
+        };
+
+        self.log_event(
+            match mode {
+                ExportMode::Mix => "exporting...",
+                ExportMode::Stems => "exporting stems...",
+            },
+            false,
         );
+        self.export_job = Some(ExportJob::spawn(request));
+    }
 
-        if samples.is_empty() {
-            self.log_event("nothing to export (arrangement is empty)", false);
-            return;
+    /// Ask the running export to stop
+    pub fn cancel_export(&mut self) {
+        if let Some(job) = &self.export_job {
+            if !job.is_cancelling() {
+                job.cancel();
+                self.log_event("cancelling export...", false);
+            }
         }
+    }
 
-        let output_path = self.project.path.join(filename);
-        match write_wav(&output_path, &samples, config.sample_rate) {
-            Ok(()) => {
-                self.log_event("export complete", false);
-            }
-            Err(_e) => {
-                self.log_event("export failed", false);
-            }
+    /// Stop the running export before quitting, waiting for its worker so
+    /// it isn't killed halfway through writing a file
+    pub fn abort_export(&mut self) {
+        if let Some(job) = self.export_job.take() {
+            job.cancel();
+            job.wait();
         }
     }
 
+    /// Log the result of the export job once its worker has finished
+    fn poll_export_job(&mut self) {
+        let Some(job) = self.export_job.as_mut() else {
+            return;
+        };
+        let Some(outcome) = job.try_finish() else {
+            return;
+        };
+        let job = self.export_job.take().expect("job checked above");
+
+        let secs = job.elapsed().as_secs_f32();
+        let output = job.output();
+        let shown = output.strip_prefix(&self.project.path).unwrap_or(output);
+
+        let message = match outcome {
+            ExportOutcome::Written(paths) => match job.mode() {
+                ExportMode::Mix => format!("exported {} ({:.1}s)", shown.display(), secs),
+                ExportMode::Stems => format!(
+                    "exported {} stems to {} ({:.1}s)",
+                    paths.len(),
+                    shown.display(),
+                    secs
+                ),
+            },
+            ExportOutcome::Empty => "nothing to export (arrangement is empty)".to_string(),
+            ExportOutcome::Cancelled => "export cancelled".to_string(),
+            ExportOutcome::MissingPlugins(plugins) => {
+                format!("export failed: plugin '{}' failed to load", plugins[0].0)
+            }
+            ExportOutcome::Failed(e) => format!("export failed: {}", e),
+        };
+        self.log_event(message, false);
+    }
+
     /// Hide the projects modal
     pub fn hide_projects_modal(&mut self) {
         self.ui.projects_modal.hide();
@@ -1650,6 +2058,9 @@ impl App {
         // Clear history for new project
         self.state.history = History::new();
 
+        // The old project's plugins must not save into the new one
+        self.state.plugin_instances = PluginInstances::new();
+
         // Mark as clean (freshly loaded)
         self.state.dirty = false;
 
@@ -1662,6 +2073,8 @@ impl App {
 
         // Sync all effects to audio
         self.sync_all_effects_to_audio();
+
+        self.log_legacy_plugin_params();
     }
 
     /// Get the current step index (0-15) from cursor column
@@ -1795,7 +2208,15 @@ impl App {
 
     /// Set a channel as a plugin channel and load the plugin
     /// Creates the channel at the specified slot if it doesn't exist (sparse)
-    pub fn set_channel_plugin(&mut self, slot: usize, plugin_path: String) {
+    ///
+    /// `plugin_id` picks a plugin from a multi-plugin bundle; the channel is
+    /// then named after that plugin rather than the bundle.
+    pub fn set_channel_plugin(
+        &mut self,
+        slot: usize,
+        plugin_path: String,
+        plugin_id: Option<String>,
+    ) {
         // Extract plugin name without extension for channel name
         let name = std::path::Path::new(&plugin_path)
             .file_stem()
@@ -1810,7 +2231,10 @@ impl App {
             channel.name = name;
             channel.source = ChannelSource::Plugin {
                 path: plugin_path.clone(),
+                plugin_id: plugin_id.clone(),
                 params: std::collections::HashMap::new(),
+                legacy_params: std::collections::HashMap::new(),
+                state: None,
             };
             // Capture mixer_track before dropping the mutable borrow
             let mixer_track = channel.mixer_track;
@@ -1820,7 +2244,10 @@ impl App {
         } else {
             // Create new channel with unique mixer track
             let mixer_track = self.find_available_mixer_track();
-            let channel = Channel::with_plugin_at_slot(&name, &plugin_path, slot, mixer_track);
+            let mut channel = Channel::with_plugin_at_slot(&name, &plugin_path, slot, mixer_track);
+            if let ChannelSource::Plugin { plugin_id: id, .. } = &mut channel.source {
+                id.clone_from(&plugin_id);
+            }
             self.channels.push(channel);
             let idx = self.channels.len() - 1;
             self.mixer.auto_assign_generator(idx);
@@ -1830,27 +2257,233 @@ impl App {
         };
 
         self.mark_dirty();
+        if let Some(info) = self.load_channel_plugin(channel_idx) {
+            if plugin_id.is_some() && !info.name.is_empty() {
+                self.channels[channel_idx].name = info.name;
+            }
+        }
+    }
+
+    /// Load and activate the plugin of the channel at `channel_idx` (with
+    /// its saved state) and hand it to the audio thread
+    ///
+    /// Returns the plugin's info if it loaded.
+    fn load_channel_plugin(&mut self, channel_idx: usize) -> Option<PluginInfo> {
+        let channel = &self.channels[channel_idx];
+        let plugin_path = channel.plugin_path()?.to_string();
+        let slot = channel.slot;
 
         // Load and activate the plugin using the loader trait
         let sample_rate = self.audio.sample_rate() as f64;
         let buffer_size = 512;
         let full_plugin_path = self.project.plugins_path().join(&plugin_path);
 
-        match self
-            .plugin_loader
-            .load_plugin(&full_plugin_path, sample_rate, buffer_size)
-        {
+        match self.plugin_loader.load_plugin(
+            &full_plugin_path,
+            channel.plugin_id(),
+            sample_rate,
+            buffer_size,
+            channel.plugin_state(),
+        ) {
             Ok(loaded) => {
-                // For newly assigned plugin, use default params
+                if let Some(e) = &loaded.state_error {
+                    let msg = format!("{}: saved state not restored: {}", plugin_path, e);
+                    self.log_event(msg, false);
+                }
                 let channel = &self.channels[channel_idx];
                 let init_state = self.build_plugin_init_state(channel_idx, channel);
                 self.audio
                     .send_plugin(channel_idx, loaded.processor, init_state);
+                self.plugin_instances.insert(
+                    PluginSlot::Channel(slot),
+                    &plugin_path,
+                    loaded.instance,
+                );
+                Some(loaded.info)
             }
             Err(e) => {
                 eprintln!("Failed to load plugin for channel {}: {}", channel_idx, e);
+                self.plugin_instances.remove(PluginSlot::Channel(slot));
+                None
+            }
+        }
+    }
+
+    /// Point the note output of the plugin channel at `slot` to the next
+    /// plugin channel (by slot), then back to nowhere
+    ///
+    /// The route reaches the audio thread with the next tick.
+    fn cycle_note_out(&mut self, slot: usize) {
+        let mut targets: Vec<usize> = self
+            .channels
+            .iter()
+            .filter(|c| c.slot != slot && c.plugin_path().is_some())
+            .map(|c| c.slot)
+            .collect();
+        targets.sort_unstable();
+        let Some(channel) = self
+            .state
+            .channels
+            .iter_mut()
+            .find(|c| c.slot == slot && c.plugin_path().is_some())
+        else {
+            return;
+        };
+        channel.note_out = match channel.note_out {
+            None => targets.first().copied(),
+            Some(current) => targets.into_iter().find(|&t| t > current),
+        };
+        self.mark_dirty();
+    }
+
+    /// Open the plugin editor for the plugin channel at `slot`
+    ///
+    /// The plugin is instantiated on the main thread to discover its
+    /// parameters; the editor keeps the instance to format values.
+    pub fn open_plugin_editor(&mut self, slot: usize) {
+        let Some(channel) = self.get_channel_at_slot(slot) else {
+            return;
+        };
+        let ChannelSource::Plugin {
+            path,
+            plugin_id,
+            params,
+            ..
+        } = &channel.source
+        else {
+            return;
+        };
+        let full_plugin_path = self.project.plugins_path().join(path);
+        let mut load_error = None;
+        let (plugin_id, editor_params, formatter, factory_presets) = match self
+            .plugin_loader
+            .inspect_plugin(&full_plugin_path, plugin_id.as_deref())
+        {
+            Ok(inspected) => (
+                inspected.info.id,
+                build_editor_params(inspected.info.params, params),
+                Some(inspected.formatter),
+                inspected.presets,
+            ),
+            Err(e) => {
+                load_error = Some(e.to_string());
+                (String::new(), Vec::new(), None, Vec::new())
             }
+        };
+        let name = channel.name.clone();
+        if let Some(e) = &load_error {
+            self.log_event(format!("{}: parameters not read: {}", name, e), false);
+        }
+
+        // Factory presets first, then the user's
+        let mut presets = factory_presets;
+        if !plugin_id.is_empty() {
+            presets.extend(self.plugin_preset_library.list(&plugin_id));
+        }
+        self.ui.plugin_preset_picker.open(presets);
+        self.ui.plugin_preset_picker.close();
+
+        self.ui
+            .plugin_editor
+            .open(slot, &plugin_id, &name, editor_params, formatter);
+        self.ui.plugin_editor.load_error = load_error;
+    }
+
+    /// Apply the preset selected in the plugin preset picker to the channel
+    /// open in the plugin editor
+    ///
+    /// Factory presets are loaded by the editor's plugin instance first and
+    /// applied as the parameters and state it reports afterwards. The
+    /// channel's plugin is then reloaded from the preset's state.
+    pub fn apply_selected_plugin_preset(&mut self) -> Result<(), String> {
+        let Some(preset) = self.ui.plugin_preset_picker.selected_preset().cloned() else {
+            return Ok(());
+        };
+        let snapshot = if preset.is_factory() {
+            self.ui.plugin_editor.load_preset(&preset)?
+        } else {
+            preset
+        };
+
+        let slot = self.ui.plugin_editor.channel_idx;
+        let Some(channel_idx) = self.channels.iter().position(|c| c.slot == slot) else {
+            return Ok(());
+        };
+        if let ChannelSource::Plugin { params, state, .. } = &mut self.channels[channel_idx].source
+        {
+            *params = snapshot.params.clone();
+            *state = snapshot.state.clone();
+        }
+        self.load_channel_plugin(channel_idx);
+
+        let editor = &mut self.ui.plugin_editor;
+        for (&param_id, &value) in &snapshot.params {
+            editor.set_param_value(param_id, value);
+        }
+        editor.preset_name = Some(snapshot.name);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Step to the previous or next preset of the plugin in the plugin
+    /// editor and apply it
+    ///
+    /// Before any preset was applied, next starts at the first preset and
+    /// previous at the last.
+    pub fn step_plugin_preset(&mut self, delta: isize) -> Result<(), String> {
+        let picker = &mut self.ui.plugin_preset_picker;
+        if picker.presets.is_empty() {
+            return Ok(());
+        }
+        if self.ui.plugin_editor.preset_name.is_some() {
+            picker.cycle(delta);
+        } else if delta > 0 {
+            picker.selected = 0;
+        } else {
+            picker.selected = picker.presets.len() - 1;
         }
+        self.apply_selected_plugin_preset()
+    }
+
+    /// Save the parameters and state of the channel open in the plugin
+    /// editor as a user preset named `name`
+    ///
+    /// Refreshes the plugin preset picker so the new preset shows up selected.
+    pub fn save_plugin_preset(&mut self, name: &str) -> Result<(), PresetError> {
+        let plugin_id = self.ui.plugin_editor.plugin_id.clone();
+        let slot = self.ui.plugin_editor.channel_idx;
+        let Some(channel) = self.channels.iter().find(|c| c.slot == slot) else {
+            return Ok(());
+        };
+        let Some(path) = channel.plugin_path().map(str::to_string) else {
+            return Ok(());
+        };
+        let params = channel.plugin_params().clone();
+        let loaded_state = channel.plugin_state().map(<[u8]>::to_vec);
+        // The running plugin knows its current state; fall back to the
+        // state the channel was loaded with
+        let saved = match self.plugin_instances.save(PluginSlot::Channel(slot), &path) {
+            Ok(saved) => saved,
+            Err(e) => {
+                self.log_event(format!("{}: state not saved: {}", path, e), false);
+                None
+            }
+        };
+        let state = saved.or(loaded_state);
+        let preset = PluginPreset::snapshot(name, &plugin_id, params, state);
+        self.plugin_preset_library.save(&preset)?;
+
+        let user_presets = self.plugin_preset_library.list(&plugin_id);
+        let picker = &mut self.ui.plugin_preset_picker;
+        picker.presets.retain(|p| p.is_factory());
+        picker.presets.extend(user_presets);
+        picker.selected = picker
+            .presets
+            .iter()
+            .position(|p| !p.is_factory() && p.name == preset.name)
+            .unwrap_or(0);
+        self.ui.plugin_editor.preset_name = Some(preset.name);
+        Ok(())
     }
 
     /// Start previewing a channel (called on key press)
@@ -1923,8 +2556,9 @@ impl App {
         }
     }
 
-    /// Save the project to disk
-    pub fn save_project(&self) {
+    /// Save the project to disk, with the current state of every running plugin
+    pub fn save_project(&mut self) {
+        self.capture_plugin_states();
         let project_file = ProjectFile::from_state(
             &self.project.name,
             self.transport.bpm,
@@ -1941,9 +2575,70 @@ impl App {
         }
     }
 
-    /// Update peak levels from audio thread (call every frame)
+    /// Ask every running plugin for its state and store it in its channel or
+    /// effect slot, so saving and exporting see what the plugins sound like now
+    ///
+    /// Slots whose plugin can't save (or isn't running) keep the state they
+    /// were loaded with; save failures go to the event log.
+    pub fn capture_plugin_states(&mut self) {
+        let AppState {
+            channels,
+            mixer,
+            plugin_instances,
+            ..
+        } = &mut self.state;
+        let mut failures = Vec::new();
+        let mut save =
+            |key: PluginSlot, path: &str, state: &mut Option<Vec<u8>>| match plugin_instances
+                .save(key, path)
+            {
+                Ok(Some(saved)) => *state = Some(saved),
+                Ok(None) => {}
+                Err(e) => failures.push(format!("{}: state not saved: {}", path, e)),
+            };
+        for channel in channels.iter_mut() {
+            let key = PluginSlot::Channel(channel.slot);
+            if let ChannelSource::Plugin { path, state, .. } = &mut channel.source {
+                save(key, path, state);
+            }
+        }
+        for (track_idx, track) in mixer.tracks.iter_mut().enumerate() {
+            for (slot_idx, slot) in track.effects.iter_mut().enumerate() {
+                let Some(slot) = slot else {
+                    continue;
+                };
+                let Some(path) = &slot.plugin_path else {
+                    continue;
+                };
+                let key = PluginSlot::Effect {
+                    track: track_idx,
+                    slot: slot_idx,
+                };
+                save(key, path, &mut slot.plugin_state);
+            }
+        }
+        for failure in failures {
+            self.log_event(failure, false);
+        }
+    }
+
+    /// Take over the plugins the audio engine loaded at startup
+    ///
+    /// Instances the app has loaded since replace the engine's, so those
+    /// slots keep the app's.
+    pub fn adopt_plugin_instances(&mut self, instances: PluginInstances) {
+        self.plugin_instances.adopt(instances);
+    }
+
+    /// Load plugins through `loader` from now on (e.g. the sandboxed one)
+    pub fn set_plugin_loader(&mut self, loader: Arc<dyn PluginLoader>) {
+        self.state.plugin_loader = loader;
+    }
+
+    /// Update peak levels and track latency from audio thread (call every frame)
     pub fn update_peak_levels(&mut self) {
         self.mixer.peak_levels = self.audio.get_peak_levels();
+        self.mixer.track_latency = self.audio.get_track_latencies();
     }
 
     // ============ Effect Management ============
@@ -2017,15 +2712,197 @@ impl App {
                 slot.set_param(param_id, value);
                 self.audio
                     .set_effect_param(track_idx, slot_idx, param_id, value);
+                if is_kernel_param(param_id) {
+                    self.sync_convolution_kernel(track_idx, slot_idx);
+                }
                 self.mark_dirty();
             }
         }
     }
 
+    /// Rebuild a convolution slot's kernel from its IR and shaping params and
+    /// send it to the audio thread (other slots are left alone)
+    pub(crate) fn sync_convolution_kernel(&self, track_idx: usize, slot_idx: usize) {
+        if let Some(slot) = &self.mixer.tracks[track_idx].effects[slot_idx] {
+            self.audio
+                .set_effect_convolution_kernel(track_idx, slot_idx, slot);
+        }
+    }
+
+    /// Point a convolution slot at an impulse response in the samples folder
+    pub fn set_effect_impulse_response(
+        &mut self,
+        track_idx: usize,
+        slot_idx: usize,
+        ir_path: String,
+    ) {
+        let samples_path = self.project.samples_path();
+        let Some(slot) = self.mixer.tracks[track_idx].effects[slot_idx].as_mut() else {
+            return;
+        };
+        slot.ir_path = Some(ir_path);
+        let loaded = slot.load_impulse_response(&samples_path);
+        self.sync_convolution_kernel(track_idx, slot_idx);
+        if let Err(e) = loaded {
+            self.log_event(format!("failed to load impulse response: {}", e), false);
+        }
+        self.mark_dirty();
+    }
+
+    /// Host the CLAP effect at `plugin_path` (relative to the plugins folder)
+    /// in a mixer insert slot, replacing whatever was there
+    ///
+    /// `plugin_id` picks a plugin from a multi-plugin bundle.
+    pub fn set_effect_plugin(
+        &mut self,
+        track_idx: usize,
+        slot_idx: usize,
+        plugin_path: String,
+        plugin_id: Option<String>,
+    ) {
+        self.mixer.tracks[track_idx].effects[slot_idx] = Some(EffectSlot {
+            plugin_id,
+            ..EffectSlot::plugin(plugin_path)
+        });
+        self.audio
+            .set_effect(track_idx, slot_idx, Some(EffectType::Plugin));
+        self.load_effect_plugin(track_idx, slot_idx);
+        self.mark_dirty();
+    }
+
+    /// Load a slot's CLAP effect on the main thread and hand it to the audio thread
+    ///
+    /// The slot keeps the passthrough installed by `set_effect` if loading
+    /// fails; the failure goes to the event log.
+    fn load_effect_plugin(&mut self, track_idx: usize, slot_idx: usize) {
+        let AppState {
+            mixer,
+            audio,
+            project,
+            plugin_loader,
+            plugin_instances,
+            ..
+        } = &mut self.state;
+        let Some(slot) = &mixer.tracks[track_idx].effects[slot_idx] else {
+            return;
+        };
+        let Some(plugin_path) = &slot.plugin_path else {
+            return;
+        };
+        let key = PluginSlot::Effect {
+            track: track_idx,
+            slot: slot_idx,
+        };
+        let sample_rate = audio.sample_rate() as f64;
+        let full_plugin_path = project.plugins_path().join(plugin_path);
+        let failure = match plugin_loader.load_plugin(
+            &full_plugin_path,
+            slot.plugin_id.as_deref(),
+            sample_rate,
+            512,
+            slot.plugin_state.as_deref(),
+        ) {
+            Ok(loaded) => {
+                audio.send_effect_plugin(
+                    track_idx,
+                    slot_idx,
+                    loaded.processor,
+                    &slot.plugin_params,
+                );
+                plugin_instances.insert(key, plugin_path, loaded.instance);
+                loaded
+                    .state_error
+                    .map(|e| format!("{}: saved state not restored: {}", plugin_path, e))
+            }
+            Err(e) => {
+                plugin_instances.remove(key);
+                Some(format!("{}: {}", plugin_path, e))
+            }
+        };
+        if let Some(msg) = failure {
+            self.log_event(msg, false);
+        }
+    }
+
+    /// Queue an effect slot's full state for the audio thread (flushed in `tick`)
+    pub(crate) fn queue_effect_slot_sync(&mut self, track_idx: usize, slot_idx: usize) {
+        let AppState {
+            audio_sync, mixer, ..
+        } = &mut self.state;
+        audio_sync.queue_effect_slot_state(
+            track_idx,
+            slot_idx,
+            mixer.tracks[track_idx].effects[slot_idx].as_ref(),
+        );
+    }
+
+    /// Set a CLAP effect parameter on a mixer insert slot
+    pub fn set_effect_plugin_param(
+        &mut self,
+        track_idx: usize,
+        slot_idx: usize,
+        param_id: u32,
+        value: f64,
+    ) {
+        if let Some(slot) = self.mixer.tracks[track_idx].effects[slot_idx].as_mut() {
+            slot.plugin_params.insert(param_id, value);
+            self.audio
+                .set_effect_plugin_param(track_idx, slot_idx, param_id, value);
+            self.mark_dirty();
+        }
+    }
+
+    /// Show the presets for an effect slot's type over the effect editor
+    pub fn open_effect_presets(&mut self, track_idx: usize, slot_idx: usize) {
+        if let Some(slot) = &self.mixer.tracks[track_idx].effects[slot_idx] {
+            let presets = self.preset_library.list(slot.effect_type);
+            self.ui.preset_picker.open(presets);
+        }
+    }
+
+    /// Apply the preset selected in the preset picker to an effect slot (undoable)
+    pub fn apply_selected_preset(&mut self, track_idx: usize, slot_idx: usize) {
+        let Some(preset) = self.ui.preset_picker.selected_preset() else {
+            return;
+        };
+        let params = preset.resolved_params();
+        self.dispatch(crate::command::AppCommand::ApplyEffectPreset {
+            track: track_idx,
+            slot: slot_idx,
+            params,
+        });
+    }
+
+    /// Save an effect slot's current parameters as a user preset named `name`
+    ///
+    /// Refreshes the preset picker so the new preset shows up selected.
+    pub fn save_effect_preset(
+        &mut self,
+        track_idx: usize,
+        slot_idx: usize,
+        name: &str,
+    ) -> Result<(), PresetError> {
+        let Some(slot) = &self.mixer.tracks[track_idx].effects[slot_idx] else {
+            return Ok(());
+        };
+        let preset = EffectPreset::from_slot(name, slot);
+        self.preset_library.save(&preset)?;
+
+        let presets = self.preset_library.list(preset.effect_type);
+        let picker = &mut self.ui.preset_picker;
+        picker.selected = presets
+            .iter()
+            .position(|p| !p.factory && p.name == preset.name)
+            .unwrap_or(0);
+        picker.presets = presets;
+        Ok(())
+    }
+
     /// Sync all effects for a track to the audio thread
     #[allow(dead_code)]
-    pub fn sync_effects_to_audio(&self, track_idx: usize) {
+    pub fn sync_effects_to_audio(&mut self, track_idx: usize) {
         for slot_idx in 0..EFFECT_SLOTS {
+            let mut is_plugin = false;
             if let Some(ref slot) = self.mixer.tracks[track_idx].effects[slot_idx] {
                 self.audio
                     .set_effect(track_idx, slot_idx, Some(slot.effect_type));
@@ -2033,14 +2910,22 @@ impl App {
                     self.audio
                         .set_effect_param(track_idx, slot_idx, *param_id, *value);
                 }
+                if slot.impulse_response.is_some() {
+                    self.audio
+                        .set_effect_convolution_kernel(track_idx, slot_idx, slot);
+                }
+                is_plugin = slot.effect_type == EffectType::Plugin;
                 self.audio
                     .set_effect_enabled(track_idx, slot_idx, !slot.bypassed);
             }
+            if is_plugin {
+                self.load_effect_plugin(track_idx, slot_idx);
+            }
         }
     }
 
     /// Sync all effects from all tracks to the audio thread (called on project load)
-    pub fn sync_all_effects_to_audio(&self) {
+    pub fn sync_all_effects_to_audio(&mut self) {
         for track_idx in 0..crate::mixer::NUM_TRACKS {
             self.sync_effects_to_audio(track_idx);
         }
@@ -2318,6 +3203,7 @@ impl PlaylistContext for App {
 mod tests {
     use super::*;
     use crate::audio::{AudioCommand, AudioHandle};
+    use crate::command::AppCommand;
     use crate::mixer::TrackId;
     use crossbeam_channel::Receiver;
     use tempfile::TempDir;
@@ -3165,6 +4051,48 @@ mod tests {
         assert_eq!(slice.notes.len(), 0, "Notes should be removed after undo");
     }
 
+    #[test]
+    fn test_dispatch_set_note_expression_records_to_history() {
+        let (mut app, _temp) = create_test_app();
+        app.set_channel_sample(0, "kick.wav".to_string());
+        app.patterns.push(Pattern::new(0, 16));
+
+        use crate::command::AppCommand;
+        use crate::sequencer::{Note, NoteExpression};
+        let note = Note::new(60, 0, 4);
+        let note_id = note.id.clone();
+        app.dispatch(AppCommand::AddNote {
+            channel: 0,
+            pattern: 0,
+            note,
+        });
+        app.history = History::new();
+
+        app.dispatch(AppCommand::SetNoteExpression {
+            channel: 0,
+            pattern: 0,
+            note_id,
+            expression: NoteExpression::PitchBend,
+            value: 2.0,
+        });
+        let bend = |app: &mut App| {
+            app.channels[0].get_or_create_pattern(0, 16).notes[0]
+                .expressions
+                .pitch_bend
+        };
+        assert_eq!(bend(&mut app), 2.0);
+        assert!(app.history.can_undo());
+
+        let mut history = std::mem::take(&mut app.history);
+        history.undo(&mut app);
+        app.history = history;
+        assert_eq!(
+            bend(&mut app),
+            0.0,
+            "Undo should restore the previous value"
+        );
+    }
+
     // ========================================================================
     // Destructive operations undo tests
     // ========================================================================
@@ -3347,9 +4275,13 @@ mod tests {
             slot: 0,
             source: ChannelSource::Plugin {
                 path: "test.clap".to_string(),
+                plugin_id: None,
                 params: HashMap::new(),
+                legacy_params: HashMap::new(),
+                state: None,
             },
             mixer_track: 1,
+            note_out: None,
             pattern_data,
         };
         app.state.channels = vec![channel];
@@ -3380,7 +4312,8 @@ mod tests {
                 cmd,
                 AudioCommand::PluginNoteOff {
                     channel: 0,
-                    note: 60
+                    note: 60,
+                    ..
                 }
             )
         });
@@ -3782,4 +4715,355 @@ mod tests {
         let slice1 = app.channels[0].get_pattern(1).expect("P1 should exist");
         assert!(slice1.get_step(4), "Pattern 1 step should be preserved");
     }
+
+    #[test]
+    fn test_save_and_apply_effect_preset_is_undoable() {
+        use crate::effects::EffectParamId;
+
+        let (mut app, temp) = create_test_app();
+        app.preset_library =
+            PresetLibrary::new(temp.path().join("factory"), temp.path().join("user"));
+        app.mixer.tracks[1].effects[0] = Some(EffectSlot::new(EffectType::Reverb));
+        let slot = |app: &App| app.mixer.tracks[1].effects[0].clone().unwrap();
+
+        // Save the current settings, then move away from them
+        app.mixer.tracks[1].effects[0]
+            .as_mut()
+            .unwrap()
+            .set_param(EffectParamId::ReverbMix, 0.6);
+        app.open_effect_presets(1, 0);
+        app.save_effect_preset(1, 0, "Wet").unwrap();
+        assert_eq!(app.ui.preset_picker.presets.len(), 1);
+        assert_eq!(app.ui.preset_picker.selected_preset().unwrap().name, "Wet");
+        app.mixer.tracks[1].effects[0]
+            .as_mut()
+            .unwrap()
+            .set_param(EffectParamId::ReverbMix, 0.1);
+
+        app.history = History::new();
+        app.apply_selected_preset(1, 0);
+        assert_eq!(slot(&app).get_param(EffectParamId::ReverbMix), 0.6);
+        assert!(app.history.can_undo());
+
+        let mut history = std::mem::take(&mut app.history);
+        history.undo(&mut app);
+        app.history = history;
+        assert_eq!(slot(&app).get_param(EffectParamId::ReverbMix), 0.1);
+    }
+
+    #[test]
+    fn test_effect_plugin_load_failure_is_logged() {
+        use crate::plugin_host::MockPluginLoader;
+
+        let (mut app, _temp) = create_test_app();
+        app.plugin_loader = Arc::new(MockPluginLoader::failing("no such bundle"));
+        app.set_effect_plugin(1, 0, "Verb.clap".to_string(), None);
+
+        let entry = app.event_log().entries_recent_first().next().unwrap();
+        assert_eq!(
+            entry.description,
+            "Verb.clap: Failed to load plugin: no such bundle"
+        );
+        assert!(app.mixer.tracks[1].effects[0].is_some(), "slot is kept");
+    }
+
+    #[test]
+    fn test_plugin_editor_shows_inspect_failure() {
+        use crate::plugin_host::MockPluginLoader;
+
+        let (mut app, _temp) = create_test_app();
+        app.plugin_loader = Arc::new(MockPluginLoader::failing("bad bundle"));
+        app.state.channels = vec![Channel::with_plugin_at_slot("Lead", "Lead.clap", 3, 3)];
+
+        app.open_plugin_editor(3);
+        let editor = &app.ui.plugin_editor;
+        assert!(editor.visible);
+        assert_eq!(
+            editor.load_error.as_deref(),
+            Some("Failed to load plugin: bad bundle")
+        );
+        let entry = app.event_log().entries_recent_first().next().unwrap();
+        assert_eq!(
+            entry.description,
+            "Lead: parameters not read: Failed to load plugin: bad bundle"
+        );
+    }
+
+    #[test]
+    fn test_plugin_editor_uses_discovered_params() {
+        use crate::plugin_host::{MockPluginLoader, PluginParam};
+
+        let (mut app, _temp) = create_test_app();
+        let cutoff = PluginParam {
+            id: 4242,
+            name: "Cutoff".to_string(),
+            module: "Filter".to_string(),
+            value: 0.5,
+            min: 0.0,
+            max: 1.0,
+            default: 0.5,
+            stepped: false,
+        };
+        let mode = PluginParam {
+            id: 7,
+            name: "Mode".to_string(),
+            module: String::new(),
+            value: 0.0,
+            min: 0.0,
+            max: 2.0,
+            default: 0.0,
+            stepped: true,
+        };
+        app.plugin_loader = Arc::new(MockPluginLoader {
+            params: vec![cutoff, mode],
+            ..MockPluginLoader::new()
+        });
+        let mut channel = Channel::with_plugin_at_slot("Lead", "Lead.clap", 3, 3);
+        channel.plugin_params_mut().unwrap().insert(4242, 0.8);
+        app.state.channels = vec![channel];
+
+        app.open_plugin_editor(3);
+        let editor = &mut app.ui.plugin_editor;
+        assert!(editor.visible);
+        assert!(!editor.has_envelope());
+        assert_eq!(editor.params.len(), 2);
+        assert_eq!(
+            editor.params[0].value, 0.8,
+            "stored value overrides the plugin's"
+        );
+        assert_eq!(
+            editor.value_text(0),
+            "0.8 u",
+            "values are formatted by the plugin"
+        );
+
+        // Edits are stored by CLAP id
+        editor.select_next();
+        let key = crossterm::event::KeyEvent::new(
+            crossterm::event::KeyCode::Char('l'),
+            crossterm::event::KeyModifiers::NONE,
+        );
+        crate::input::handle_key(key, &mut app);
+        assert_eq!(app.channels[0].plugin_params().get(&7), Some(&1.0));
+        assert_eq!(app.channels[0].plugin_params().get(&4242), Some(&0.8));
+    }
+
+    #[test]
+    fn test_plugin_presets_apply_and_save() {
+        use crate::plugin_host::{MockPluginLoader, PresetLocation};
+        use std::collections::HashMap;
+
+        let (mut app, temp) = create_test_app();
+        app.plugin_preset_library = PluginPresetLibrary::new(temp.path().join("plugin-presets"));
+        let location = PresetLocation::Plugin {
+            load_key: Some("init".to_string()),
+        };
+        app.plugin_loader = Arc::new(MockPluginLoader {
+            presets: vec![PluginPreset::factory("Init", "mock.Lead", location)],
+            ..MockPluginLoader::new()
+        });
+        let mut channel = Channel::with_plugin_at_slot("Lead", "Lead.clap", 3, 3);
+        channel.plugin_params_mut().unwrap().insert(9, 0.5);
+        app.state.channels = vec![channel];
+
+        // Save the current sound as a user preset; it's listed after the
+        // factory ones
+        app.open_plugin_editor(3);
+        assert_eq!(app.ui.plugin_editor.plugin_id, "mock.Lead");
+        assert!(!app.ui.plugin_preset_picker.visible);
+        app.save_plugin_preset("Mine").unwrap();
+        let names: Vec<_> = app
+            .ui
+            .plugin_preset_picker
+            .presets
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Init", "Mine"]);
+        assert_eq!(app.plugin_preset_library.list("mock.Lead").len(), 1);
+
+        // A factory preset is loaded by the plugin and stored as its snapshot
+        app.step_plugin_preset(1).unwrap();
+        assert_eq!(app.ui.plugin_editor.preset_name.as_deref(), Some("Init"));
+        assert_eq!(app.channels[0].plugin_params(), &HashMap::from([(0, 1.0)]));
+        assert_eq!(app.channels[0].plugin_state(), Some(&b"Init"[..]));
+
+        // Stepping on brings back the user preset
+        app.step_plugin_preset(1).unwrap();
+        assert_eq!(app.ui.plugin_editor.preset_name.as_deref(), Some("Mine"));
+        assert_eq!(app.channels[0].plugin_params(), &HashMap::from([(9, 0.5)]));
+        assert_eq!(app.channels[0].plugin_state(), None);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn test_save_project_stores_plugin_state() {
+        use crate::plugin_host::StateSaver;
+
+        struct Saver(Vec<u8>);
+        impl StateSaver for Saver {
+            fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+                Ok(Some(self.0.clone()))
+            }
+        }
+
+        let (mut app, _temp) = create_test_app();
+        app.state.channels = vec![
+            Channel::with_plugin_at_slot("Lead", "Lead.clap", 3, 3),
+            Channel::with_plugin_at_slot("Pad", "Pad.clap", 4, 4),
+        ];
+        app.mixer.tracks[2].effects[1] = Some(EffectSlot::plugin("Verb.clap"));
+        app.mixer.tracks[2].effects[1]
+            .as_mut()
+            .unwrap()
+            .plugin_state = Some(vec![9]);
+
+        app.plugin_instances.insert(
+            PluginSlot::Channel(3),
+            "Lead.clap",
+            Box::new(Saver(vec![1, 2])),
+        );
+        // A stale instance of another plugin must not overwrite the slot's state
+        let effect = PluginSlot::Effect { track: 2, slot: 1 };
+        app.plugin_instances
+            .insert(effect, "Delay.clap", Box::new(Saver(vec![5])));
+        app.save_project();
+
+        let saved = project::load_project(&app.project.path).unwrap();
+        assert_eq!(saved.channels[0].plugin_state(), Some(&[1u8, 2][..]));
+        assert_eq!(saved.channels[1].plugin_state(), None);
+        let mixer = saved.mixer.unwrap();
+        assert_eq!(
+            mixer.tracks[2].effects[1].as_ref().unwrap().plugin_state,
+            Some(vec![9])
+        );
+
+        app.plugin_instances
+            .insert(effect, "Verb.clap", Box::new(Saver(vec![6, 7])));
+        app.capture_plugin_states();
+        assert_eq!(
+            app.mixer.tracks[2].effects[1]
+                .as_ref()
+                .unwrap()
+                .plugin_state,
+            Some(vec![6, 7])
+        );
+    }
+
+    #[test]
+    fn test_tick_sends_plugin_transport_on_change() {
+        let (mut app, _temp, rx) = create_test_app_with_audio_rx();
+        let transports = |rx: &Receiver<AudioCommand>| -> Vec<PluginTransport> {
+            rx.try_iter()
+                .filter_map(|cmd| match cmd {
+                    AudioCommand::UpdateTransport(t) => Some(t),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        app.tick(Duration::ZERO);
+        assert_eq!(transports(&rx), vec![PluginTransport::stopped(140.0)]);
+        app.tick(Duration::ZERO);
+        assert!(
+            transports(&rx).is_empty(),
+            "unchanged transport isn't resent"
+        );
+
+        app.transport.playback.play_arrangement_from(2);
+        let step = app.transport.step_duration();
+        app.tick(step * 2);
+        let sent = transports(&rx);
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].playing);
+        assert_eq!(sent[0].position_beats, 8.5, "bar 2, step 2");
+        assert_eq!(sent[0].loop_beats, Some((0.0, 64.0)));
+    }
+
+    #[test]
+    fn test_step_due_at_accounts_for_late_ticks() {
+        let mut transport = TransportState::new(120.0);
+        let now = Instant::now();
+        assert_eq!(transport.step_due_at(now), now);
+
+        // A tick 10ms past the step boundary consumes the step late by 10ms
+        transport.add_time(transport.step_duration() + Duration::from_millis(10));
+        transport.consume_step();
+        assert_eq!(transport.step_due_at(now), now - Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_tick_applies_parameter_changes_made_by_plugins() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test-project");
+        std::fs::create_dir_all(&project_path).unwrap();
+        crate::project::save_project(
+            &project_path,
+            &crate::project::ProjectFile::new("test-project"),
+        )
+        .unwrap();
+        let (audio, _rx, plugin_events) = AudioHandle::testable_with_plugin_events();
+        let mut app = App::new(project_path.to_str().unwrap(), audio);
+
+        app.state.channels = vec![Channel::with_plugin_at_slot("synth", "synth.clap", 3, 1)];
+        app.mixer.tracks[2].effects[0] = Some(EffectSlot::plugin("verb.clap"));
+        plugin_events
+            .send(PluginEvent::ParamChanged {
+                channel: 0,
+                param_id: 9,
+                value: 0.25,
+            })
+            .unwrap();
+        plugin_events
+            .send(PluginEvent::EffectParamChanged {
+                track: 2,
+                slot: 0,
+                param_id: 4,
+                value: -6.0,
+            })
+            .unwrap();
+        app.tick(Duration::ZERO);
+
+        assert_eq!(app.channels[0].plugin_params().get(&9), Some(&0.25));
+        let effect = app.mixer.tracks[2].effects[0].as_ref().unwrap();
+        assert_eq!(effect.plugin_params.get(&4), Some(&-6.0));
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn test_cycle_note_out_steps_through_plugin_channels() {
+        let (mut app, _temp) = create_test_app();
+        app.state.channels = vec![
+            Channel::with_plugin_at_slot("arp", "arp.clap", 0, 1),
+            Channel::new_at_slot("drums", 1, 2),
+            Channel::with_plugin_at_slot("bass", "bass.clap", 4, 3),
+            Channel::with_plugin_at_slot("lead", "lead.clap", 2, 4),
+        ];
+
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            app.dispatch(AppCommand::CycleNoteOut(0));
+            seen.push(app.channels[0].note_out);
+        }
+        assert_eq!(seen, vec![Some(2), Some(4), None]);
+
+        // Sampler channels have no note output
+        app.dispatch(AppCommand::CycleNoteOut(1));
+        assert_eq!(app.channels[1].note_out, None);
+    }
+
+    #[test]
+    fn test_quit_key_cancels_running_export() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let (mut app, _temp) = create_test_app();
+        app.start_export_job(ExportMode::Mix, "out.wav", ExportSettings::default(), false);
+        assert!(app.export_job.is_some());
+
+        // Other keys are still swallowed while the export runs
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(!crate::input::handle_key(key('j'), &mut app));
+        assert!(crate::input::handle_key(key('q'), &mut app));
+        assert!(app.export_job.is_none());
+    }
 }
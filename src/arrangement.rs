@@ -170,4 +170,29 @@ impl Arrangement {
             })
             .collect()
     }
+
+    /// Copy of the arrangement limited to bars `start..end`, moved to start at bar 0
+    ///
+    /// Placements that straddle either edge are shortened to fit.
+    pub fn crop_bars(&self, start: usize, end: usize) -> Arrangement {
+        let placements = self
+            .placements
+            .iter()
+            .filter_map(|p| {
+                let from = p.start_bar.max(start);
+                let to = (p.start_bar + p.length).min(end);
+                (from < to).then(|| PatternPlacement {
+                    start_bar: from - start,
+                    length: to - from,
+                    ..p.clone()
+                })
+            })
+            .collect();
+
+        Arrangement {
+            placements,
+            muted_patterns: self.muted_patterns.clone(),
+            soloed_patterns: self.soloed_patterns.clone(),
+        }
+    }
 }
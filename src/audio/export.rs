@@ -0,0 +1,635 @@
+//! Export settings and file writing
+//!
+//! Turns rendered f32 audio into files on disk: WAV (16/24-bit integer or
+//! 32-bit float) or FLAC (16/24-bit), with optional TPDF dither when reducing
+//! to integer samples and optional peak or loudness (LUFS) normalization.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use super::flac::write_flac;
+use super::offline::Stem;
+
+/// Sample rates offered for export
+pub const EXPORT_SAMPLE_RATES: [u32; 4] = [44100, 48000, 88200, 96000];
+
+/// Maximum tail lengths offered for export, in seconds (0 = cut at the last bar)
+pub const EXPORT_TAIL_SECS: [f32; 5] = [0.0, 2.0, 5.0, 10.0, 30.0];
+
+/// Whether to export the full mix or one file per mixer track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportMode {
+    #[default]
+    Mix,
+    Stems,
+}
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Wav,
+    Flac,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Wav, ExportFormat::Flac];
+
+    /// File extension (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "wav",
+            ExportFormat::Flac => "flac",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "WAV",
+            ExportFormat::Flac => "FLAC",
+        }
+    }
+
+    /// Whether the format can store the given bit depth
+    pub fn supports(&self, bit_depth: BitDepth) -> bool {
+        match self {
+            ExportFormat::Wav => true,
+            ExportFormat::Flac => bit_depth != BitDepth::Float32,
+        }
+    }
+}
+
+/// Output sample format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    #[default]
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    pub const ALL: [BitDepth; 3] = [BitDepth::Int16, BitDepth::Int24, BitDepth::Float32];
+
+    pub fn bits(&self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        *self == BitDepth::Float32
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BitDepth::Int16 => "16-bit",
+            BitDepth::Int24 => "24-bit",
+            BitDepth::Float32 => "32-bit float",
+        }
+    }
+}
+
+/// Level normalization applied before writing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    #[default]
+    Off,
+    /// Scale so the highest sample peak hits -1 dBFS
+    PeakMinus1,
+    /// Scale so the highest sample peak hits -0.1 dBFS
+    PeakMinus01,
+    /// Integrated loudness of -14 LUFS (streaming services)
+    Lufs14,
+    /// Integrated loudness of -16 LUFS (podcasts)
+    Lufs16,
+    /// Integrated loudness of -23 LUFS (EBU R128 broadcast)
+    Lufs23,
+}
+
+impl Normalization {
+    pub const ALL: [Normalization; 6] = [
+        Normalization::Off,
+        Normalization::PeakMinus1,
+        Normalization::PeakMinus01,
+        Normalization::Lufs14,
+        Normalization::Lufs16,
+        Normalization::Lufs23,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Normalization::Off => "Off",
+            Normalization::PeakMinus1 => "Peak -1 dBFS",
+            Normalization::PeakMinus01 => "Peak -0.1 dBFS",
+            Normalization::Lufs14 => "-14 LUFS",
+            Normalization::Lufs16 => "-16 LUFS",
+            Normalization::Lufs23 => "-23 LUFS",
+        }
+    }
+}
+
+/// Everything that controls how audio is rendered and written to disk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportSettings {
+    pub format: ExportFormat,
+    pub bit_depth: BitDepth,
+    pub sample_rate: u32,
+    /// Apply TPDF dither when writing integer samples
+    pub dither: bool,
+    pub normalization: Normalization,
+    /// Longest effect/release tail rendered after the last bar, in seconds
+    pub max_tail_secs: f32,
+    /// Send note-offs to plugins for notes still held at the end
+    pub release_notes: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Wav,
+            bit_depth: BitDepth::Int16,
+            sample_rate: 44100,
+            dither: true,
+            normalization: Normalization::Off,
+            max_tail_secs: 10.0,
+            release_notes: true,
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Check the format can store the chosen bit depth
+    pub fn validate(&self) -> Result<(), ExportError> {
+        if self.format.supports(self.bit_depth) {
+            Ok(())
+        } else {
+            Err(ExportError::UnsupportedBitDepth {
+                format: self.format.label(),
+                bit_depth: self.bit_depth.label(),
+            })
+        }
+    }
+}
+
+/// Export errors
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("WAV error: {0}")]
+    Wav(#[from] hound::Error),
+    #[error("export thread panicked")]
+    Panicked,
+    #[error("{format} does not support {bit_depth} samples")]
+    UnsupportedBitDepth {
+        format: &'static str,
+        bit_depth: &'static str,
+    },
+}
+
+/// Write interleaved stereo samples to `path` using the given settings
+///
+/// Normalization is not applied here; see [`normalize`] and [`normalize_stems`].
+pub fn write_audio(
+    path: &Path,
+    samples: &[f32],
+    settings: &ExportSettings,
+) -> Result<(), ExportError> {
+    settings.validate()?;
+    match settings.format {
+        ExportFormat::Wav => write_wav(path, samples, settings),
+        ExportFormat::Flac => {
+            let bit_depth = settings.bit_depth;
+            let ints = quantize(samples, bit_depth.bits(), settings.dither);
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_flac(
+                &mut writer,
+                &ints,
+                2,
+                settings.sample_rate,
+                bit_depth.bits() as u32,
+            )?;
+            Ok(())
+        }
+    }
+}
+
+fn write_wav(path: &Path, samples: &[f32], settings: &ExportSettings) -> Result<(), ExportError> {
+    let spec = WavSpec {
+        channels: 2,
+        sample_rate: settings.sample_rate,
+        bits_per_sample: settings.bit_depth.bits(),
+        sample_format: if settings.bit_depth.is_float() {
+            SampleFormat::Float
+        } else {
+            SampleFormat::Int
+        },
+    };
+
+    let file = File::create(path)?;
+    let mut wav_writer = WavWriter::new(BufWriter::new(file), spec)?;
+
+    if settings.bit_depth.is_float() {
+        for &sample in samples {
+            wav_writer.write_sample(sample)?;
+        }
+    } else {
+        for sample in quantize(samples, settings.bit_depth.bits(), settings.dither) {
+            wav_writer.write_sample(sample)?;
+        }
+    }
+
+    wav_writer.finalize()?;
+    Ok(())
+}
+
+/// Convert f32 samples to `bits`-bit integers, clamping to full scale
+///
+/// With `dither`, triangular (TPDF) noise of +/-1 LSB is added before
+/// rounding so the quantization error is decorrelated from the signal.
+pub fn quantize(samples: &[f32], bits: u16, dither: bool) -> Vec<i32> {
+    let max = ((1i64 << (bits - 1)) - 1) as f64;
+    let min = -max - 1.0;
+    let mut rng = Xorshift32::new(0x9E37_79B9);
+
+    samples
+        .iter()
+        .map(|&s| {
+            let mut v = s as f64 * max;
+            if dither {
+                v += rng.next_unit() - rng.next_unit();
+            }
+            v.round().clamp(min, max) as i32
+        })
+        .collect()
+}
+
+/// Apply normalization to a single interleaved stereo buffer
+pub fn normalize(samples: &mut [f32], sample_rate: u32, normalization: Normalization) {
+    let gain = normalization_gain(samples, sample_rate, normalization);
+    if gain != 1.0 {
+        samples.iter_mut().for_each(|s| *s *= gain);
+    }
+}
+
+/// Apply one common normalization gain to a set of stems
+///
+/// The gain is measured on the sum of the stems, so the stems keep their
+/// balance and still add up to the normalized mix.
+pub fn normalize_stems(stems: &mut [Stem], sample_rate: u32, normalization: Normalization) {
+    if normalization == Normalization::Off {
+        return;
+    }
+
+    let len = stems.iter().map(|s| s.samples.len()).max().unwrap_or(0);
+    let mut sum = vec![0.0f32; len];
+    for stem in stems.iter() {
+        for (acc, s) in sum.iter_mut().zip(&stem.samples) {
+            *acc += s;
+        }
+    }
+
+    let gain = normalization_gain(&sum, sample_rate, normalization);
+    for stem in stems.iter_mut() {
+        stem.samples.iter_mut().for_each(|s| *s *= gain);
+    }
+}
+
+/// Linear gain that brings `samples` to the normalization target
+///
+/// Loudness targets are capped so the result never peaks above 0 dBFS.
+/// Silent input is left alone.
+pub fn normalization_gain(samples: &[f32], sample_rate: u32, normalization: Normalization) -> f32 {
+    let peak = samples.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+    if peak <= 0.0 {
+        return 1.0;
+    }
+
+    let target_peak = |dbfs: f32| db_to_gain(dbfs) / peak;
+    let target_lufs = |lufs: f32| match integrated_loudness(samples, sample_rate) {
+        Some(loudness) => db_to_gain(lufs - loudness).min(1.0 / peak),
+        None => 1.0,
+    };
+
+    match normalization {
+        Normalization::Off => 1.0,
+        Normalization::PeakMinus1 => target_peak(-1.0),
+        Normalization::PeakMinus01 => target_peak(-0.1),
+        Normalization::Lufs14 => target_lufs(-14.0),
+        Normalization::Lufs16 => target_lufs(-16.0),
+        Normalization::Lufs23 => target_lufs(-23.0),
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Integrated loudness of interleaved stereo audio in LUFS (ITU-R BS.1770-4)
+///
+/// Returns `None` when the audio is shorter than one 400 ms gating block or
+/// entirely below the -70 LUFS absolute gate.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let frames = samples.len() / 2;
+    let block = (sample_rate as usize * 4) / 10; // 400 ms
+    let hop = block / 4; // 75% overlap
+    if frames < block || hop == 0 {
+        return None;
+    }
+
+    // K-weight each channel, then accumulate squared samples per 100 ms hop
+    let mut shelf = [Biquad::k_shelf(sample_rate), Biquad::k_shelf(sample_rate)];
+    let mut high_pass = [
+        Biquad::k_high_pass(sample_rate),
+        Biquad::k_high_pass(sample_rate),
+    ];
+    let hops = frames / hop;
+    let mut hop_energy = vec![0.0f64; hops];
+    for (i, frame) in samples.chunks_exact(2).take(hops * hop).enumerate() {
+        let mut energy = 0.0;
+        for ch in 0..2 {
+            let y = high_pass[ch].process(shelf[ch].process(frame[ch] as f64));
+            energy += y * y;
+        }
+        hop_energy[i / hop] += energy;
+    }
+
+    // Mean square of each 400 ms block (four consecutive hops)
+    let blocks: Vec<f64> = hop_energy
+        .windows(4)
+        .map(|w| w.iter().sum::<f64>() / block as f64)
+        .collect();
+
+    let loudness = |ms: f64| -0.691 + 10.0 * ms.log10();
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = blocks
+            .iter()
+            .copied()
+            .filter(|&ms| ms > 0.0 && loudness(ms) > threshold)
+            .collect();
+        if gated.is_empty() {
+            None
+        } else {
+            Some(gated.iter().sum::<f64>() / gated.len() as f64)
+        }
+    };
+
+    let absolute = gated_mean(-70.0)?;
+    let relative = gated_mean(loudness(absolute) - 10.0)?;
+    Some(loudness(relative) as f32)
+}
+
+/// Direct form I biquad used for K-weighting
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    /// Stage 1: high shelf modelling the acoustic effect of the head
+    fn k_shelf(sample_rate: u32) -> Self {
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+
+        let k = (std::f64::consts::PI * f0 / sample_rate as f64).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+
+        Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    /// Stage 2: RLB high-pass
+    fn k_high_pass(sample_rate: u32) -> Self {
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+
+        let k = (std::f64::consts::PI * f0 / sample_rate as f64).tan();
+        let a0 = 1.0 + k / q + k * k;
+
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// Small deterministic PRNG for dither noise
+struct Xorshift32(u32);
+
+impl Xorshift32 {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Uniform value in [0, 1)
+    fn next_unit(&mut self) -> f64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x as f64 / (u32::MAX as f64 + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sine(freq: f32, amplitude: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        let frames = (seconds * sample_rate as f32) as usize;
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let s = (2.0 * std::f32::consts::PI * freq * t).sin() * amplitude;
+                [s, s]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantize_clamps_to_full_scale() {
+        let ints = quantize(&[2.0, -2.0, 0.0], 16, false);
+        assert_eq!(ints, vec![i16::MAX as i32, i16::MIN as i32, 0]);
+
+        let ints = quantize(&[1.0, -1.0], 24, false);
+        assert_eq!(ints, vec![(1 << 23) - 1, -(1 << 23) + 1]);
+    }
+
+    #[test]
+    fn test_dither_stays_within_one_lsb() {
+        let input = vec![0.25f32; 10_000];
+        let plain = quantize(&input, 16, false);
+        let dithered = quantize(&input, 16, true);
+
+        assert!(
+            dithered.iter().any(|&s| s != plain[0]),
+            "dither added no noise"
+        );
+        for &s in &dithered {
+            assert!(
+                (s - plain[0]).abs() <= 1,
+                "dither error {} too large",
+                s - plain[0]
+            );
+        }
+
+        // Dither is zero-mean, so the average stays on the original value
+        let mean = dithered.iter().map(|&s| s as f64).sum::<f64>() / dithered.len() as f64;
+        assert!((mean - 0.25 * i16::MAX as f64).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_loudness_of_full_scale_sine_is_zero_lufs() {
+        // BS.1770: a 0 dBFS 1 kHz sine in both channels reads 0 LUFS
+        let samples = sine(1000.0, 1.0, 2.0, 48000);
+        let lufs = integrated_loudness(&samples, 48000).unwrap();
+        assert!(lufs.abs() < 0.3, "got {} LUFS", lufs);
+
+        let quieter = sine(1000.0, 0.1, 2.0, 44100);
+        let lufs = integrated_loudness(&quieter, 44100).unwrap();
+        assert!((lufs + 20.0).abs() < 0.3, "got {} LUFS", lufs);
+    }
+
+    #[test]
+    fn test_loudness_of_silence_is_none() {
+        assert_eq!(integrated_loudness(&vec![0.0; 96000], 48000), None);
+        assert_eq!(integrated_loudness(&[0.5; 100], 48000), None);
+    }
+
+    #[test]
+    fn test_peak_normalization() {
+        let mut samples = sine(440.0, 0.25, 0.5, 44100);
+        normalize(&mut samples, 44100, Normalization::PeakMinus1);
+
+        let peak = samples.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+        assert!((peak - db_to_gain(-1.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_lufs_normalization_never_clips() {
+        // Reaching -14 LUFS from a sine would need +14 dB on a 0 dBFS signal
+        let mut loud = sine(1000.0, 1.0, 1.0, 48000);
+        normalize(&mut loud, 48000, Normalization::Lufs14);
+        let peak = loud.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+        assert!(peak <= 1.0 + 1e-6);
+
+        let mut quiet = sine(1000.0, 0.01, 1.0, 48000);
+        normalize(&mut quiet, 48000, Normalization::Lufs23);
+        let lufs = integrated_loudness(&quiet, 48000).unwrap();
+        assert!((lufs + 23.0).abs() < 0.3, "got {} LUFS", lufs);
+    }
+
+    #[test]
+    fn test_normalize_stems_uses_common_gain() {
+        let mut stems = vec![
+            Stem {
+                track: 1,
+                name: "A".to_string(),
+                samples: vec![0.25, 0.25, -0.25, -0.25],
+            },
+            Stem {
+                track: 2,
+                name: "B".to_string(),
+                samples: vec![0.25, 0.25, -0.25, -0.25],
+            },
+        ];
+        normalize_stems(&mut stems, 44100, Normalization::PeakMinus01);
+
+        // Sum peaked at 0.5, so each stem gets the same gain towards -0.1 dBFS
+        let expected = 0.25 * db_to_gain(-0.1) / 0.5;
+        for stem in &stems {
+            assert!((stem.samples[0] - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_write_audio_formats() {
+        let dir = TempDir::new().unwrap();
+        let samples = sine(440.0, 0.5, 0.1, 48000);
+
+        for (format, bit_depth) in [
+            (ExportFormat::Wav, BitDepth::Int16),
+            (ExportFormat::Wav, BitDepth::Int24),
+            (ExportFormat::Wav, BitDepth::Float32),
+            (ExportFormat::Flac, BitDepth::Int24),
+        ] {
+            let settings = ExportSettings {
+                format,
+                bit_depth,
+                sample_rate: 48000,
+                ..Default::default()
+            };
+            let path = dir
+                .path()
+                .join(format!("out-{}.{}", bit_depth.bits(), format.extension()));
+            write_audio(&path, &samples, &settings).unwrap();
+
+            if format == ExportFormat::Wav {
+                let reader = hound::WavReader::open(&path).unwrap();
+                let spec = reader.spec();
+                assert_eq!(spec.sample_rate, 48000);
+                assert_eq!(spec.bits_per_sample, bit_depth.bits());
+                assert_eq!(reader.len() as usize, samples.len());
+            } else {
+                let bytes = std::fs::read(&path).unwrap();
+                assert_eq!(&bytes[..4], b"fLaC");
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_audio_rejects_flac_float() {
+        let dir = TempDir::new().unwrap();
+        let settings = ExportSettings {
+            format: ExportFormat::Flac,
+            bit_depth: BitDepth::Float32,
+            ..Default::default()
+        };
+        let path = dir.path().join("out.flac");
+
+        let err = write_audio(&path, &[0.0; 4], &settings).unwrap_err();
+        assert!(matches!(err, ExportError::UnsupportedBitDepth { .. }));
+        assert_eq!(
+            err.to_string(),
+            "FLAC does not support 32-bit float samples"
+        );
+        assert!(!path.exists());
+    }
+}
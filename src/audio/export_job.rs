@@ -0,0 +1,356 @@
+//! Background export
+//!
+//! Renders and writes an export on a worker thread so the TUI keeps drawing.
+//! The UI reads [`ExportJob::progress`] for its progress bar and polls
+//! [`ExportJob::try_finish`] once per tick for the result.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::export::{
+    normalize, normalize_stems, write_audio, ExportError, ExportMode, ExportSettings,
+};
+use super::offline::{render_offline, render_stems, write_stems, RenderConfig, RenderControl};
+use crate::arrangement::Arrangement;
+use crate::mixer::Mixer;
+use crate::plugin_host::PluginLoader;
+use crate::sequencer::{Channel, Pattern};
+
+/// Everything needed to render an export, owned so it can cross threads
+pub struct ExportRequest {
+    pub channels: Vec<Channel>,
+    pub patterns: Vec<Pattern>,
+    pub arrangement: Arrangement,
+    pub mixer: Mixer,
+    pub samples_path: PathBuf,
+    pub plugins_path: PathBuf,
+    pub plugin_loader: Arc<dyn PluginLoader>,
+    pub bpm: f64,
+    pub settings: ExportSettings,
+    pub mode: ExportMode,
+    /// For stems: skip the master effect chain and fader
+    pub pre_master_fx: bool,
+    /// Write nothing if a plugin fails to load, instead of leaving it out
+    pub require_plugins: bool,
+    /// Output file (mix) or folder (stems)
+    pub output: PathBuf,
+}
+
+/// How an export ended
+#[derive(Debug)]
+pub enum ExportOutcome {
+    /// Files were written (one for a mix, one per stem)
+    Written(Vec<PathBuf>),
+    /// The arrangement is empty, nothing was written
+    Empty,
+    /// Cancelled before anything was written
+    Cancelled,
+    /// Plugins failed to load (path relative to the plugins folder, with the
+    /// error) and the request requires them, nothing was written
+    MissingPlugins(Vec<(String, String)>),
+    Failed(ExportError),
+}
+
+/// Render and write an export on the current thread
+pub fn run_export(request: &ExportRequest, control: &RenderControl) -> ExportOutcome {
+    let settings = &request.settings;
+    if let Err(e) = settings.validate() {
+        return ExportOutcome::Failed(e);
+    }
+    let config = RenderConfig {
+        sample_rate: settings.sample_rate,
+        bpm: request.bpm,
+        steps_per_bar: 16,
+        max_tail_secs: settings.max_tail_secs,
+        release_notes: settings.release_notes,
+        require_plugins: request.require_plugins,
+    };
+
+    match request.mode {
+        ExportMode::Mix => {
+            let mut samples = render_offline(
+                &request.channels,
+                &request.patterns,
+                &request.arrangement,
+                &request.mixer,
+                &request.samples_path,
+                &request.plugins_path,
+                &*request.plugin_loader,
+                &config,
+                control,
+            );
+            if control.is_cancelled() {
+                return ExportOutcome::Cancelled;
+            }
+            if let Some(outcome) = missing_plugins(request, control) {
+                return outcome;
+            }
+            if samples.is_empty() {
+                return ExportOutcome::Empty;
+            }
+
+            normalize(&mut samples, config.sample_rate, settings.normalization);
+            match write_audio(&request.output, &samples, settings) {
+                Ok(()) => ExportOutcome::Written(vec![request.output.clone()]),
+                Err(e) => ExportOutcome::Failed(e),
+            }
+        }
+        ExportMode::Stems => {
+            let mut stems = render_stems(
+                &request.channels,
+                &request.patterns,
+                &request.arrangement,
+                &request.mixer,
+                &request.samples_path,
+                &request.plugins_path,
+                &*request.plugin_loader,
+                &config,
+                request.pre_master_fx,
+                control,
+            );
+            if control.is_cancelled() {
+                return ExportOutcome::Cancelled;
+            }
+            if let Some(outcome) = missing_plugins(request, control) {
+                return outcome;
+            }
+            if stems.iter().all(|s| s.samples.is_empty()) {
+                return ExportOutcome::Empty;
+            }
+
+            normalize_stems(&mut stems, config.sample_rate, settings.normalization);
+            match write_stems(&request.output, &stems, settings) {
+                Ok(paths) => ExportOutcome::Written(paths),
+                Err(e) => ExportOutcome::Failed(e),
+            }
+        }
+    }
+}
+
+/// The outcome for a render that left out plugins the request requires
+fn missing_plugins(request: &ExportRequest, control: &RenderControl) -> Option<ExportOutcome> {
+    let failed = control.failed_plugins();
+    (request.require_plugins && !failed.is_empty()).then_some(ExportOutcome::MissingPlugins(failed))
+}
+
+/// An export running on a worker thread
+pub struct ExportJob {
+    control: Arc<RenderControl>,
+    handle: Option<JoinHandle<ExportOutcome>>,
+    started: Instant,
+    mode: ExportMode,
+    output: PathBuf,
+}
+
+impl ExportJob {
+    /// Start rendering `request` on a new thread
+    pub fn spawn(request: ExportRequest) -> Self {
+        let control = Arc::new(RenderControl::new());
+        let mode = request.mode;
+        let output = request.output.clone();
+
+        let worker_control = Arc::clone(&control);
+        let handle = std::thread::Builder::new()
+            .name("export".to_string())
+            .spawn(move || run_export(&request, &worker_control))
+            .expect("failed to spawn export thread");
+
+        Self {
+            control,
+            handle: Some(handle),
+            started: Instant::now(),
+            mode,
+            output,
+        }
+    }
+
+    /// Bars rendered so far and total bars
+    pub fn progress(&self) -> (usize, usize) {
+        self.control.progress()
+    }
+
+    /// Ask the worker to stop; the outcome will be [`ExportOutcome::Cancelled`]
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.control.is_cancelled()
+    }
+
+    /// Time since the job started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn mode(&self) -> ExportMode {
+        self.mode
+    }
+
+    /// Output file (mix) or folder (stems)
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// The outcome if the worker has finished, without blocking
+    pub fn try_finish(&mut self) -> Option<ExportOutcome> {
+        if self.handle.as_ref().is_some_and(|h| h.is_finished()) {
+            Some(self.join())
+        } else {
+            None
+        }
+    }
+
+    /// Block until the worker finishes
+    pub fn wait(mut self) -> ExportOutcome {
+        self.join()
+    }
+
+    fn join(&mut self) -> ExportOutcome {
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(outcome)) => outcome,
+            Some(Err(_)) | None => ExportOutcome::Failed(ExportError::Panicked),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrangement::PatternPlacement;
+    use crate::plugin_host::MockPluginLoader;
+    use tempfile::TempDir;
+
+    fn request(dir: &Path, bars: usize, mode: ExportMode) -> ExportRequest {
+        let mut arrangement = Arrangement::new();
+        if bars > 0 {
+            arrangement.placements.push(PatternPlacement {
+                id: "p1".to_string(),
+                pattern_id: 0,
+                start_bar: 0,
+                length: bars,
+            });
+        }
+
+        ExportRequest {
+            channels: vec![Channel::new_at_slot("a", 0, 1)],
+            patterns: vec![Pattern {
+                id: 0,
+                name: "Test".to_string(),
+                length: 16,
+            }],
+            arrangement,
+            mixer: Mixer::new(),
+            samples_path: dir.to_path_buf(),
+            plugins_path: dir.to_path_buf(),
+            plugin_loader: Arc::new(MockPluginLoader::new()),
+            bpm: 240.0,
+            settings: ExportSettings::default(),
+            mode,
+            pre_master_fx: false,
+            require_plugins: false,
+            output: dir.join(match mode {
+                ExportMode::Mix => "out.wav",
+                ExportMode::Stems => "stems",
+            }),
+        }
+    }
+
+    #[test]
+    fn test_export_job_writes_mix() {
+        let dir = TempDir::new().unwrap();
+        let job = ExportJob::spawn(request(dir.path(), 2, ExportMode::Mix));
+
+        match job.wait() {
+            ExportOutcome::Written(paths) => {
+                assert_eq!(paths, vec![dir.path().join("out.wav")]);
+                assert!(paths[0].exists());
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_export_job_writes_stems() {
+        let dir = TempDir::new().unwrap();
+        let job = ExportJob::spawn(request(dir.path(), 1, ExportMode::Stems));
+
+        match job.wait() {
+            ExportOutcome::Written(paths) => {
+                assert_eq!(paths.len(), 1);
+                assert!(paths[0].starts_with(dir.path().join("stems")));
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_export_job_empty_arrangement() {
+        let dir = TempDir::new().unwrap();
+        let job = ExportJob::spawn(request(dir.path(), 0, ExportMode::Mix));
+        assert!(matches!(job.wait(), ExportOutcome::Empty));
+    }
+
+    #[test]
+    fn test_run_export_cancelled_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let req = request(dir.path(), 4, ExportMode::Mix);
+        let control = RenderControl::new();
+        control.cancel();
+
+        assert!(matches!(
+            run_export(&req, &control),
+            ExportOutcome::Cancelled
+        ));
+        assert!(!req.output.exists());
+    }
+
+    #[test]
+    fn test_run_export_missing_plugin_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let mut req = request(dir.path(), 1, ExportMode::Mix);
+        req.channels
+            .push(Channel::with_plugin_at_slot("synth", "synth.clap", 1, 2));
+        req.require_plugins = true;
+
+        match run_export(&req, &RenderControl::new()) {
+            ExportOutcome::MissingPlugins(plugins) => {
+                assert_eq!(
+                    plugins,
+                    vec![("synth.clap".to_string(), "file not found".to_string())]
+                );
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(!req.output.exists());
+    }
+
+    #[test]
+    fn test_run_export_stems_missing_plugin_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let mut req = request(dir.path(), 1, ExportMode::Stems);
+        req.channels
+            .push(Channel::with_plugin_at_slot("synth", "synth.clap", 1, 2));
+        req.require_plugins = true;
+
+        match run_export(&req, &RenderControl::new()) {
+            ExportOutcome::MissingPlugins(plugins) => {
+                assert_eq!(plugins[0].0, "synth.clap");
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(!req.output.exists());
+    }
+
+    #[test]
+    fn test_progress_reaches_total() {
+        let dir = TempDir::new().unwrap();
+        let req = request(dir.path(), 3, ExportMode::Mix);
+        let control = RenderControl::new();
+
+        run_export(&req, &control);
+        assert_eq!(control.progress(), (3, 3));
+    }
+}
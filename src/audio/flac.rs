@@ -0,0 +1,555 @@
+//! Minimal FLAC encoder for offline export
+//!
+//! Encodes interleaved integer PCM into a FLAC stream using fixed linear
+//! predictors (orders 0-4) with partitioned Rice coding. This is not as small
+//! as the reference encoder's LPC output, but it is lossless, fast, and needs
+//! no external dependencies.
+
+use std::io::{self, Write};
+
+/// Samples per channel in each FLAC frame
+const BLOCK_SIZE: usize = 4096;
+
+/// Highest fixed predictor order defined by the format
+const MAX_FIXED_ORDER: usize = 4;
+
+/// Highest Rice partition order we try
+const MAX_PARTITION_ORDER: u32 = 8;
+
+/// Encode interleaved integer samples as a FLAC stream.
+///
+/// `samples` are interleaved (L, R, L, R, ...) and must already fit in
+/// `bits_per_sample` bits (16 or 24).
+pub fn write_flac<W: Write>(
+    out: &mut W,
+    samples: &[i32],
+    channels: usize,
+    sample_rate: u32,
+    bits_per_sample: u32,
+) -> io::Result<()> {
+    if channels == 0 || channels > 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "FLAC supports 1-8 channels",
+        ));
+    }
+    if !matches!(bits_per_sample, 16 | 24) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "FLAC export supports 16 or 24 bits per sample",
+        ));
+    }
+
+    let total_frames = samples.len() / channels;
+
+    // Encode all frames first so STREAMINFO can record real frame sizes
+    let mut frames: Vec<Vec<u8>> = Vec::new();
+    let mut channel_buf: Vec<Vec<i64>> = vec![Vec::with_capacity(BLOCK_SIZE); channels];
+
+    for (frame_number, start) in (0..total_frames).step_by(BLOCK_SIZE).enumerate() {
+        let block_len = BLOCK_SIZE.min(total_frames - start);
+        for (ch, buf) in channel_buf.iter_mut().enumerate() {
+            buf.clear();
+            buf.extend(
+                (start..start + block_len).map(|frame| samples[frame * channels + ch] as i64),
+            );
+        }
+        frames.push(encode_frame(
+            &channel_buf,
+            frame_number as u64,
+            bits_per_sample,
+        ));
+    }
+
+    let min_frame = frames.iter().map(Vec::len).min().unwrap_or(0);
+    let max_frame = frames.iter().map(Vec::len).max().unwrap_or(0);
+
+    out.write_all(b"fLaC")?;
+    out.write_all(&stream_info(
+        sample_rate,
+        channels as u32,
+        bits_per_sample,
+        total_frames as u64,
+        min_frame as u32,
+        max_frame as u32,
+    ))?;
+    for frame in &frames {
+        out.write_all(frame)?;
+    }
+
+    Ok(())
+}
+
+/// Build the STREAMINFO metadata block (marked as the last metadata block)
+fn stream_info(
+    sample_rate: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    total_samples: u64,
+    min_frame: u32,
+    max_frame: u32,
+) -> Vec<u8> {
+    let mut w = BitWriter::new();
+
+    // Metadata block header: last-block flag, type 0 (STREAMINFO), length 34
+    w.write(1, 1);
+    w.write(0, 7);
+    w.write(34, 24);
+
+    w.write(BLOCK_SIZE as u64, 16); // min block size
+    w.write(BLOCK_SIZE as u64, 16); // max block size
+    w.write(min_frame as u64, 24);
+    w.write(max_frame as u64, 24);
+    w.write(sample_rate as u64, 20);
+    w.write((channels - 1) as u64, 3);
+    w.write((bits_per_sample - 1) as u64, 5);
+    w.write(total_samples, 36);
+
+    // MD5 signature left as zero ("not computed"), which decoders accept
+    for _ in 0..16 {
+        w.write(0, 8);
+    }
+
+    w.into_bytes()
+}
+
+/// Encode one frame (all channels of one block)
+fn encode_frame(channels: &[Vec<i64>], frame_number: u64, bits_per_sample: u32) -> Vec<u8> {
+    let block_len = channels[0].len();
+    let mut w = BitWriter::new();
+
+    // Frame header
+    w.write(0b11_1111_1111_1110, 14); // sync code
+    w.write(0, 1); // reserved
+    w.write(0, 1); // fixed block size stream
+
+    let block_size_code = if block_len == BLOCK_SIZE {
+        12 // 256 * 2^(12-8) = 4096
+    } else {
+        7 // 16-bit (block size - 1) follows the frame number
+    };
+    w.write(block_size_code, 4);
+    w.write(0, 4); // sample rate: taken from STREAMINFO
+    w.write((channels.len() - 1) as u64, 4); // independent channels
+    let sample_size_code = match bits_per_sample {
+        16 => 0b100,
+        _ => 0b110, // 24 bits
+    };
+    w.write(sample_size_code, 3);
+    w.write(0, 1); // reserved
+
+    write_utf8_number(&mut w, frame_number);
+    if block_size_code == 7 {
+        w.write((block_len - 1) as u64, 16);
+    }
+
+    let header_crc = crc8(w.bytes());
+    w.write(header_crc as u64, 8);
+
+    // Subframes
+    for samples in channels {
+        encode_subframe(&mut w, samples, bits_per_sample);
+    }
+
+    // Frame footer: zero-pad to byte boundary, then CRC-16 of the whole frame
+    w.align();
+    let frame_crc = crc16(w.bytes());
+    w.write(frame_crc as u64, 16);
+
+    w.into_bytes()
+}
+
+/// Encode a channel as the cheapest of CONSTANT, FIXED(0-4) or VERBATIM
+fn encode_subframe(w: &mut BitWriter, samples: &[i64], bps: u32) {
+    if samples.iter().all(|&s| s == samples[0]) {
+        w.write(0, 1); // zero padding bit
+        w.write(0b000000, 6); // CONSTANT
+        w.write(0, 1); // no wasted bits
+        w.write_signed(samples[0], bps);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bps as u64;
+
+    // Find the cheapest fixed predictor
+    let mut best: Option<(usize, RicePlan, Vec<i64>)> = None;
+    for order in 0..=MAX_FIXED_ORDER.min(samples.len().saturating_sub(1)) {
+        let residual = fixed_residual(samples, order);
+        let plan = plan_rice(&residual, samples.len(), order);
+        let bits = order as u64 * bps as u64 + plan.bits;
+        let better = match &best {
+            Some((best_order, best_plan, _)) => {
+                bits < *best_order as u64 * bps as u64 + best_plan.bits
+            }
+            None => true,
+        };
+        if better {
+            best = Some((order, plan, residual));
+        }
+    }
+
+    match best {
+        Some((order, plan, residual))
+            if (order as u64 * bps as u64 + plan.bits) < verbatim_bits =>
+        {
+            w.write(0, 1);
+            w.write(0b001000 | order as u64, 6); // FIXED, predictor order
+            w.write(0, 1);
+            for &s in &samples[..order] {
+                w.write_signed(s, bps);
+            }
+            write_residual(w, &residual, samples.len(), order, &plan);
+        }
+        _ => {
+            w.write(0, 1);
+            w.write(0b000001, 6); // VERBATIM
+            w.write(0, 1);
+            for &s in samples {
+                w.write_signed(s, bps);
+            }
+        }
+    }
+}
+
+/// Residual of a fixed polynomial predictor (excludes the warm-up samples)
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+/// Chosen Rice partitioning for a residual
+struct RicePlan {
+    /// Partition order (2^order partitions)
+    partition_order: u32,
+    /// Rice parameter per partition
+    params: Vec<u32>,
+    /// Whether 5-bit parameters (RICE2) are needed
+    rice2: bool,
+    /// Total residual section size in bits (including headers)
+    bits: u64,
+}
+
+/// Pick the partition order and per-partition Rice parameters
+fn plan_rice(residual: &[i64], block_len: usize, predictor_order: usize) -> RicePlan {
+    let mut best: Option<RicePlan> = None;
+
+    for partition_order in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << partition_order;
+        if block_len.trailing_zeros() < partition_order {
+            break;
+        }
+        let partition_len = block_len >> partition_order;
+        if partition_len <= predictor_order {
+            break;
+        }
+
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 2 + 4; // coding method + partition order
+        let mut offset = 0;
+        for p in 0..partitions {
+            let len = if p == 0 {
+                partition_len - predictor_order
+            } else {
+                partition_len
+            };
+            let (param, cost) = best_rice_param(&residual[offset..offset + len]);
+            params.push(param);
+            bits += cost;
+            offset += len;
+        }
+
+        let rice2 = params.iter().any(|&k| k > 14);
+        bits += partitions as u64 * if rice2 { 5 } else { 4 };
+
+        if best.as_ref().map(|b| bits < b.bits).unwrap_or(true) {
+            best = Some(RicePlan {
+                partition_order,
+                params,
+                rice2,
+                bits,
+            });
+        }
+    }
+
+    best.expect("partition order 0 is always valid")
+}
+
+/// Cheapest Rice parameter for a partition, and its cost in bits (excluding header)
+fn best_rice_param(residual: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residual.iter().map(|&r| zigzag(r)).collect();
+    let mut best = (0u32, u64::MAX);
+    for k in 0..=30u32 {
+        let bits: u64 = folded.iter().map(|&u| (u >> k) + 1 + k as u64).sum();
+        if bits < best.1 {
+            best = (k, bits);
+        }
+    }
+    best
+}
+
+/// Write a residual section according to a Rice plan
+fn write_residual(
+    w: &mut BitWriter,
+    residual: &[i64],
+    block_len: usize,
+    predictor_order: usize,
+    plan: &RicePlan,
+) {
+    let param_bits = if plan.rice2 { 5 } else { 4 };
+    w.write(if plan.rice2 { 1 } else { 0 }, 2);
+    w.write(plan.partition_order as u64, 4);
+
+    // The first partition is short by the predictor's warm-up samples
+    let partition_len = block_len >> plan.partition_order;
+    let mut offset = 0;
+    for (p, &k) in plan.params.iter().enumerate() {
+        let len = if p == 0 {
+            partition_len - predictor_order
+        } else {
+            partition_len
+        };
+        w.write(k as u64, param_bits);
+        for &r in &residual[offset..offset + len] {
+            let u = zigzag(r);
+            w.write_unary(u >> k);
+            w.write(u & ((1u64 << k) - 1), k);
+        }
+        offset += len;
+    }
+}
+
+/// Map signed residuals onto unsigned values (0, -1, 1, -2, ... -> 0, 1, 2, 3, ...)
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+/// Write a frame number using FLAC's extended UTF-8 coding
+fn write_utf8_number(w: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        w.write(n, 8);
+        return;
+    }
+
+    // Number of continuation bytes needed (each carries 6 bits)
+    let mut extra = 1;
+    while extra < 6 && n >= 1u64 << (6 - extra + 6 * extra) {
+        extra += 1;
+    }
+
+    let prefix = (0xFF00u64 >> (extra + 1)) & 0xFF;
+    w.write(prefix | (n >> (6 * extra)), 8);
+    for i in (0..extra).rev() {
+        w.write(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+/// CRC-8, polynomial x^8 + x^2 + x + 1
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-16, polynomial x^16 + x^15 + x^2 + 1
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// MSB-first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Pending bits not yet flushed to `bytes`
+    acc: u64,
+    /// Number of valid bits in `acc`
+    acc_bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            acc: 0,
+            acc_bits: 0,
+        }
+    }
+
+    /// Write the low `bits` bits of `value`
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1);
+            self.acc_bits += 1;
+            if self.acc_bits == 8 {
+                self.bytes.push(self.acc as u8);
+                self.acc = 0;
+                self.acc_bits = 0;
+            }
+        }
+    }
+
+    /// Write a two's complement value in `bits` bits
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        let mask = if bits == 64 {
+            u64::MAX
+        } else {
+            (1u64 << bits) - 1
+        };
+        self.write(value as u64 & mask, bits);
+    }
+
+    /// Write `n` zero bits followed by a one
+    fn write_unary(&mut self, n: u64) {
+        for _ in 0..n {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    /// Pad with zero bits up to the next byte boundary
+    fn align(&mut self) {
+        if self.acc_bits > 0 {
+            self.write(0, 8 - self.acc_bits);
+        }
+    }
+
+    /// Bytes written so far (only complete bytes)
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    /// Decode a FLAC byte stream back to interleaved i32 samples
+    fn decode(bytes: Vec<u8>, bits: u32) -> Vec<i32> {
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .expect("probe flac");
+        let mut format = probed.format;
+        let track = format.default_track().expect("track").clone();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .expect("decoder");
+
+        let mut out = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let decoded = decoder.decode(&packet).expect("decode packet");
+            let mut buf = SampleBuffer::<i32>::new(decoded.capacity() as u64, *decoded.spec());
+            buf.copy_interleaved_ref(decoded);
+            // Symphonia scales integer samples up to the full i32 range
+            out.extend(buf.samples().iter().map(|&s| s >> (32 - bits)));
+        }
+        out
+    }
+
+    fn test_signal(frames: usize, amplitude: f64) -> Vec<i32> {
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f64 / 44100.0;
+                let l = (2.0 * std::f64::consts::PI * 220.0 * t).sin() * amplitude;
+                let r = (2.0 * std::f64::consts::PI * 331.0 * t).sin() * amplitude * 0.5;
+                [l as i32, r as i32]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flac_roundtrip_16_bit() {
+        // Odd length exercises the short final block
+        let samples = test_signal(10_000, 30000.0);
+        let mut bytes = Vec::new();
+        write_flac(&mut bytes, &samples, 2, 44100, 16).unwrap();
+
+        assert_eq!(&bytes[..4], b"fLaC");
+        assert_eq!(decode(bytes, 16), samples);
+    }
+
+    #[test]
+    fn test_flac_roundtrip_24_bit() {
+        let samples = test_signal(5000, 8_000_000.0);
+        let mut bytes = Vec::new();
+        write_flac(&mut bytes, &samples, 2, 48000, 24).unwrap();
+
+        assert_eq!(decode(bytes, 24), samples);
+    }
+
+    #[test]
+    fn test_flac_compresses_silence() {
+        let samples = vec![0i32; 44100 * 2];
+        let mut bytes = Vec::new();
+        write_flac(&mut bytes, &samples, 2, 44100, 16).unwrap();
+
+        // Constant subframes: a few bytes per frame instead of 16 KB
+        assert!(
+            bytes.len() < 1000,
+            "silence encoded to {} bytes",
+            bytes.len()
+        );
+        assert_eq!(decode(bytes, 16), samples);
+    }
+
+    #[test]
+    fn test_utf8_frame_numbers() {
+        for (n, expected) in [
+            (0u64, vec![0x00]),
+            (0x7F, vec![0x7F]),
+            (0x80, vec![0xC2, 0x80]),
+            (0x7FF, vec![0xDF, 0xBF]),
+            (0x800, vec![0xE0, 0xA0, 0x80]),
+        ] {
+            let mut w = BitWriter::new();
+            write_utf8_number(&mut w, n);
+            assert_eq!(w.into_bytes(), expected, "frame number {}", n);
+        }
+    }
+}
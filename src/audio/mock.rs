@@ -3,13 +3,17 @@
 //! Provides a MockAudioHandle that captures commands instead of sending them
 //! to a real audio engine. This enables testing App behavior without audio hardware.
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use super::{AudioCommand, AudioMixerState, PeakLevelsBuffer, PluginInitState, WaveformBuffer};
-use crate::effects::{EffectParamId, EffectType};
+use crate::effects::{EffectParamId, EffectSlot, EffectType};
 use crate::mixer::{StereoLevels, NUM_TRACKS};
-use crate::plugin_host::ActivePluginProcessor;
+use crate::plugin_host::transport::PluginTransport;
+use crate::plugin_host::{ActivePluginProcessor, PluginEvent};
+use crate::sequencer::NoteExpressions;
 
 /// A mock audio handle that captures commands for testing
 ///
@@ -90,11 +94,26 @@ impl MockAudioHandle {
         self.commands.lock().unwrap().push(cmd);
     }
 
+    pub fn schedule_frame(&self, _instant: Instant) -> Option<u64> {
+        None
+    }
+
     pub fn play_sample(&self, path: &Path, volume: f32, generator_idx: usize) {
+        self.play_sample_at(path, volume, generator_idx, None);
+    }
+
+    pub fn play_sample_at(
+        &self,
+        path: &Path,
+        volume: f32,
+        generator_idx: usize,
+        at_frame: Option<u64>,
+    ) {
         self.push_command(AudioCommand::PlaySample {
             path: path.to_path_buf(),
             volume,
             generator_idx,
+            at_frame,
         });
     }
 
@@ -133,15 +152,36 @@ impl MockAudioHandle {
     }
 
     pub fn plugin_note_on(&self, channel: usize, note: u8, velocity: f32) {
+        self.plugin_note_on_at(channel, note, velocity, NoteExpressions::default(), None);
+    }
+
+    pub fn plugin_note_on_at(
+        &self,
+        channel: usize,
+        note: u8,
+        velocity: f32,
+        expressions: NoteExpressions,
+        at_frame: Option<u64>,
+    ) {
         self.push_command(AudioCommand::PluginNoteOn {
             channel,
             note,
             velocity,
+            expressions,
+            at_frame,
         });
     }
 
     pub fn plugin_note_off(&self, channel: usize, note: u8) {
-        self.push_command(AudioCommand::PluginNoteOff { channel, note });
+        self.plugin_note_off_at(channel, note, None);
+    }
+
+    pub fn plugin_note_off_at(&self, channel: usize, note: u8, at_frame: Option<u64>) {
+        self.push_command(AudioCommand::PluginNoteOff {
+            channel,
+            note,
+            at_frame,
+        });
     }
 
     pub fn plugin_set_param(&self, channel: usize, param_id: u32, value: f64) {
@@ -166,6 +206,16 @@ impl MockAudioHandle {
         // Tests that need to verify plugin sending should use a different approach
     }
 
+    pub fn send_effect_plugin(
+        &self,
+        _track: usize,
+        _slot: usize,
+        _processor: ActivePluginProcessor,
+        _params: &HashMap<u32, f64>,
+    ) {
+        // Not captured, see send_plugin
+    }
+
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
@@ -190,6 +240,10 @@ impl MockAudioHandle {
         &self.peak_levels
     }
 
+    pub fn get_track_latencies(&self) -> [usize; NUM_TRACKS] {
+        [0; NUM_TRACKS]
+    }
+
     pub fn set_effect(&self, track: usize, slot: usize, effect_type: Option<EffectType>) {
         self.push_command(AudioCommand::SetEffect {
             track,
@@ -215,9 +269,43 @@ impl MockAudioHandle {
         });
     }
 
+    pub fn set_effect_convolution_kernel(&self, track: usize, slot: usize, effect: &EffectSlot) {
+        if effect.effect_type != EffectType::Convolution {
+            return;
+        }
+        self.push_command(AudioCommand::SetEffectConvolutionKernel {
+            track,
+            slot,
+            kernel: effect.convolution_kernel(self.sample_rate as f32),
+        });
+    }
+
+    pub fn set_effect_plugin_param(&self, track: usize, slot: usize, param_id: u32, value: f64) {
+        self.push_command(AudioCommand::SetEffectPluginParam {
+            track,
+            slot,
+            param_id,
+            value,
+        });
+    }
+
     pub fn update_tempo(&self, bpm: f64) {
         self.push_command(AudioCommand::UpdateTempo(bpm));
     }
+
+    pub fn update_transport(&self, transport: PluginTransport) {
+        self.push_command(AudioCommand::UpdateTransport(transport));
+    }
+
+    pub fn set_note_route(&self, channel: usize, target: Option<usize>) {
+        self.push_command(AudioCommand::SetNoteRoute { channel, target });
+    }
+
+    pub fn free_spent_kernels(&self) {}
+
+    pub fn poll_plugin_events(&self) -> Vec<PluginEvent> {
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
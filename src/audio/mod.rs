@@ -7,6 +7,10 @@
 //! - Master and per-channel volume control
 //! - Per-track mixing with routing (FL Studio-style mixer)
 
+pub mod export;
+pub mod export_job;
+pub mod flac;
+pub mod latency;
 pub mod mock;
 pub mod offline;
 
@@ -16,18 +20,26 @@ use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{SampleFormat, Stream, StreamConfig};
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use rodio::{Decoder, Source};
 
+use self::latency::CompensationDelay;
+use crate::effects::convolution::ConvolutionKernel;
+use crate::effects::gate::GateState;
+use crate::effects::plugin::PluginEffect;
 use crate::effects::{create_effect, Effect, EffectParamId, EffectSlot, EffectType, EFFECT_SLOTS};
 use crate::mixer::{Mixer, StereoLevels, TrackId, NUM_TRACKS};
+use crate::plugin_host::state::{PluginInstances, PluginSlot};
+use crate::plugin_host::transport::PluginTransport;
 use crate::plugin_host::{
-    params::build_init_params, ActivePluginProcessor, ParamChange, PluginLoader,
+    params::build_init_params, ActivePluginProcessor, ParamChange, PluginEvent, PluginLoader,
+    StateSaver,
 };
-use crate::sequencer::{Channel, ChannelSource};
+use crate::sequencer::{note_routes, Channel, ChannelSource, NoteExpressions};
 
 /// Project setup data for configuring the audio engine at creation time
 pub struct ProjectSetup<'a> {
@@ -56,6 +68,53 @@ pub type WaveformBuffer = Arc<Mutex<Vec<f32>>>;
 /// Shared peak levels buffer (updated by audio thread, read by UI)
 pub type PeakLevelsBuffer = Arc<Mutex<[StereoLevels; NUM_TRACKS]>>;
 
+/// Shared gain reduction per effect slot in dB (updated by audio thread, read by UI)
+pub type GainReductionBuffer = Arc<Mutex<[[f32; EFFECT_SLOTS]; NUM_TRACKS]>>;
+
+/// Shared gate state per effect slot (updated by audio thread, read by UI)
+pub type GateStateBuffer = Arc<Mutex<[[Option<GateState>; EFFECT_SLOTS]; NUM_TRACKS]>>;
+
+/// Shared latency per track in frames (updated by audio thread, read by UI)
+pub type LatencyBuffer = Arc<Mutex<[usize; NUM_TRACKS]>>;
+
+/// How far ahead of the audio clock sequenced events are scheduled. Must
+/// cover the gap between audio callbacks (the output buffer length) plus
+/// main-thread jitter, or events land late at the start of a block.
+const SCHEDULE_AHEAD: Duration = Duration::from_millis(30);
+
+/// Replaced convolution kernels the audio thread can hand back before it has
+/// to free one itself (the main thread empties the queue every tick)
+const SPENT_KERNEL_CAPACITY: usize = 8;
+
+/// Where the audio thread was at a point in time, so the main thread can
+/// turn wall-clock times into engine frames
+#[derive(Debug, Clone, Copy)]
+pub struct AudioClock {
+    /// Engine frame the last audio callback started rendering
+    pub frame: u64,
+    /// When that callback ran (None until the stream has started)
+    pub at: Option<Instant>,
+}
+
+impl AudioClock {
+    /// Engine frame for `instant` plus the scheduling lookahead, or None if
+    /// the stream hasn't run yet
+    pub fn schedule_frame(&self, instant: Instant, sample_rate: u32) -> Option<u64> {
+        let at = self.at?;
+        let since = instant.checked_duration_since(at).unwrap_or_default() + SCHEDULE_AHEAD;
+        let before = at.checked_duration_since(instant).unwrap_or_default();
+        let frames = |d: Duration| (d.as_secs_f64() * sample_rate as f64).round() as u64;
+        Some((self.frame + frames(since)).saturating_sub(frames(before)))
+    }
+}
+
+/// Audio clock shared between the audio callback and handles
+pub type AudioClockBuffer = Arc<Mutex<AudioClock>>;
+
+fn new_clock() -> AudioClockBuffer {
+    Arc::new(Mutex::new(AudioClock { frame: 0, at: None }))
+}
+
 /// Minimal mixer state for audio thread (no strings, no UI state)
 /// Sent atomically from main thread when mixer config changes
 #[derive(Debug, Clone)]
@@ -168,6 +227,8 @@ pub enum AudioCommand {
         path: PathBuf,
         volume: f32,
         generator_idx: usize,
+        /// Engine frame to start at (None = next block)
+        at_frame: Option<u64>,
     },
     /// Preview a sample (exclusive - stops previous preview)
     /// If route_to_master is true, audio goes directly to master (for browser previews)
@@ -189,9 +250,18 @@ pub enum AudioCommand {
         channel: usize,
         note: u8,
         velocity: f32,
+        /// Per-note expressions from the piano roll
+        expressions: NoteExpressions,
+        /// Engine frame the note starts at (None = next block)
+        at_frame: Option<u64>,
     },
     /// Send note off to a plugin channel
-    PluginNoteOff { channel: usize, note: u8 },
+    PluginNoteOff {
+        channel: usize,
+        note: u8,
+        /// Engine frame the note ends at (None = next block)
+        at_frame: Option<u64>,
+    },
     /// Set a plugin parameter value
     PluginSetParam {
         channel: usize,
@@ -223,8 +293,28 @@ pub enum AudioCommand {
         slot: usize,
         enabled: bool,
     },
+    /// Swap in a convolution effect's kernel, built on the main thread
+    SetEffectConvolutionKernel {
+        track: usize,
+        slot: usize,
+        kernel: Option<Box<ConvolutionKernel>>,
+    },
+    /// Set a plugin effect parameter by CLAP id
+    SetEffectPluginParam {
+        track: usize,
+        slot: usize,
+        param_id: u32,
+        value: f64,
+    },
     /// Update tempo for tempo-synced effects
     UpdateTempo(f64),
+    /// Song position and play state for plugins
+    UpdateTransport(PluginTransport),
+    /// Feed a plugin channel's note output to another channel (None = off)
+    SetNoteRoute {
+        channel: usize,
+        target: Option<usize>,
+    },
 }
 
 /// A loaded sample as raw audio data
@@ -252,19 +342,26 @@ struct Voice {
     generator_idx: usize,
     /// Whether to route directly to master (bypasses generator routing)
     route_to_master: bool,
+    /// Frames of silence before the sample starts (for scheduled playback)
+    start_delay: usize,
 }
 
 /// A MIDI note event pending for a plugin
 #[allow(dead_code)]
 struct PluginNoteEvent {
+    /// Engine frame the event belongs to
+    frame: u64,
     note: u8,
     velocity: f32,
     is_note_on: bool,
+    expressions: NoteExpressions,
 }
 
 /// A parameter change event pending for a plugin
 #[derive(Clone)]
 struct PluginParamEvent {
+    /// Engine frame the event belongs to
+    frame: u64,
     param_id: u32,
     value: f64,
 }
@@ -275,17 +372,38 @@ pub struct PluginInitState {
     pub params: Vec<(u32, f64)>, // (param_id, value) pairs
 }
 
+/// A plugin handed from the main thread to the audio thread
+pub enum PluginInstall {
+    /// Instrument plugin driving a channel
+    Channel {
+        channel: usize,
+        processor: Box<ActivePluginProcessor>,
+        init_state: PluginInitState,
+    },
+    /// Audio effect plugin for a mixer insert slot (already wrapped, with
+    /// its saved parameters queued)
+    Effect {
+        track: usize,
+        slot: usize,
+        effect: Box<dyn Effect>,
+    },
+}
+
 /// A plugin channel with processor and pending events
 #[allow(dead_code)]
 struct PluginChannel {
     processor: ActivePluginProcessor,
     pending_notes: Vec<PluginNoteEvent>,
     pending_params: Vec<PluginParamEvent>,
+    /// Keys with a note-on that hasn't been matched by a note-off yet
+    held_notes: Vec<u8>,
     /// Per-frame output buffers
     output_left: Vec<f32>,
     output_right: Vec<f32>,
     /// Channel volume (0.0-1.0)
     volume: f32,
+    /// Holds the output back to line up with slower instruments on its track
+    delay: CompensationDelay,
 }
 
 // ============================================================================
@@ -306,6 +424,12 @@ pub(crate) struct MixingEngine {
     track_effects: Vec<[Option<Box<dyn Effect>>; EFFECT_SLOTS]>,
     /// Effect bypass state per track/slot (true = bypassed)
     effect_bypassed: [[bool; EFFECT_SLOTS]; NUM_TRACKS],
+    /// Pre-insert copies of tracks that key a sidechained effect
+    sidechain_buffers: Vec<TrackBuffer>,
+    /// Gain reduction reported by each effect slot in the last block (dB)
+    gain_reduction: [[f32; EFFECT_SLOTS]; NUM_TRACKS],
+    /// Gate state reported by each effect slot in the last block
+    gate_states: [[Option<GateState>; EFFECT_SLOTS]; NUM_TRACKS],
     /// Mixer state (volumes, pans, mutes)
     mixer_state: AudioMixerState,
     /// Generator-to-track routing (generator_idx -> track_idx)
@@ -316,47 +440,92 @@ pub(crate) struct MixingEngine {
     sample_rate: u32,
     /// Current tempo in BPM (for tempo-synced effects)
     tempo_bpm: f64,
+    /// Song position at the start of the next block (for plugins)
+    transport: PluginTransport,
+    /// Song position the last processed block started at
+    block_transport: PluginTransport,
+    /// Frames processed so far; scheduled events are placed on this clock
+    frame_clock: u64,
+    /// Channel each plugin channel's note output feeds (by channel index)
+    note_routes: Vec<Option<usize>>,
+    /// Parameter changes and notes plugins sent out in the last block
+    plugin_events: Vec<PluginEvent>,
+    /// Total latency of each track at the master: instruments plus inserts
+    track_latency: [usize; NUM_TRACKS],
+    /// Holds back sample voices on tracks that also carry a slower plugin
+    input_delays: Vec<CompensationDelay>,
+    /// Holds back each track to line up with the slowest one at the master
+    track_delays: Vec<CompensationDelay>,
 }
 
 #[allow(dead_code)]
 impl MixingEngine {
     /// Create a new mixing engine
     pub fn new(sample_rate: u32) -> Self {
-        let track_buffers: Vec<TrackBuffer> = (0..NUM_TRACKS)
-            .map(|_| TrackBuffer {
-                left: vec![0.0; MAX_TRACK_BUFFER_SIZE],
-                right: vec![0.0; MAX_TRACK_BUFFER_SIZE],
-            })
-            .collect();
+        let new_buffers = || -> Vec<TrackBuffer> {
+            (0..NUM_TRACKS)
+                .map(|_| TrackBuffer {
+                    left: vec![0.0; MAX_TRACK_BUFFER_SIZE],
+                    right: vec![0.0; MAX_TRACK_BUFFER_SIZE],
+                })
+                .collect()
+        };
 
         let track_effects: Vec<[Option<Box<dyn Effect>>; EFFECT_SLOTS]> = (0..NUM_TRACKS)
             .map(|_| std::array::from_fn(|_| None))
             .collect();
 
         Self {
-            track_buffers,
+            track_buffers: new_buffers(),
             voices: Vec::with_capacity(MAX_VOICES),
             plugin_channels: Vec::new(),
             track_effects,
             effect_bypassed: [[false; EFFECT_SLOTS]; NUM_TRACKS],
+            sidechain_buffers: new_buffers(),
+            gain_reduction: [[0.0; EFFECT_SLOTS]; NUM_TRACKS],
+            gate_states: [[None; EFFECT_SLOTS]; NUM_TRACKS],
             mixer_state: AudioMixerState::default(),
             generator_tracks: [1; MAX_GENERATORS],
             master_volume: 1.0,
             sample_rate,
             tempo_bpm: 120.0,
+            transport: PluginTransport::default(),
+            block_transport: PluginTransport::default(),
+            frame_clock: 0,
+            note_routes: Vec::new(),
+            plugin_events: Vec::new(),
+            track_latency: [0; NUM_TRACKS],
+            input_delays: (0..NUM_TRACKS).map(|_| Default::default()).collect(),
+            track_delays: (0..NUM_TRACKS).map(|_| Default::default()).collect(),
         }
     }
 
     /// Process one block of audio, returns reference to master buffer (track 0)
     pub fn process_block(&mut self, num_frames: usize) -> &TrackBuffer {
+        self.plugin_events.clear();
+        self.update_latencies();
         self.clear_track_buffers(num_frames);
         self.render_voices_to_tracks(num_frames);
         self.process_plugins_to_tracks(num_frames);
         self.process_track_effects(num_frames);
+        self.compensate_track_latency(num_frames);
         self.sum_tracks_to_master(num_frames);
+        self.block_transport = self.transport;
+        self.transport.advance(num_frames, self.sample_rate);
+        self.frame_clock += num_frames as u64;
         &self.track_buffers[0]
     }
 
+    /// Frame the next processed block starts at
+    pub fn frame_clock(&self) -> u64 {
+        self.frame_clock
+    }
+
+    /// Parameter changes and notes plugins sent out in the last block
+    pub fn plugin_events(&self) -> &[PluginEvent] {
+        &self.plugin_events
+    }
+
     /// Get the master buffer directly (for reading output)
     pub fn master_buffer(&self) -> &TrackBuffer {
         &self.track_buffers[0]
@@ -367,6 +536,78 @@ impl MixingEngine {
         &self.track_buffers[track]
     }
 
+    /// Gain reduction per track/slot from the last processed block (dB)
+    pub fn gain_reduction(&self) -> &[[f32; EFFECT_SLOTS]; NUM_TRACKS] {
+        &self.gain_reduction
+    }
+
+    /// Gate state per track/slot from the last processed block
+    pub fn gate_states(&self) -> &[[Option<GateState>; EFFECT_SLOTS]; NUM_TRACKS] {
+        &self.gate_states
+    }
+
+    /// Latency per track in frames as of the last processed block: the
+    /// slowest instrument plugin feeding it plus its active inserts
+    pub fn track_latencies(&self) -> &[usize; NUM_TRACKS] {
+        &self.track_latency
+    }
+
+    /// Frames by which the master output trails the sequenced events, i.e.
+    /// the latency of the slowest track. Offline renders drop this many
+    /// frames from the start so the export lines up with the arrangement.
+    pub fn output_latency(&self) -> usize {
+        let (_, tracks) = self.measure_latencies();
+        tracks.into_iter().max().unwrap_or(0)
+    }
+
+    /// Instrument and total latency per track for the loaded plugins and
+    /// effects. Sends and track-to-track routing aren't rendered by the
+    /// engine, so every track's only path is straight into the master.
+    fn measure_latencies(&self) -> ([usize; NUM_TRACKS], [usize; NUM_TRACKS]) {
+        let mut inputs = [0; NUM_TRACKS];
+        for (channel_idx, plugin_ch) in self.plugin_channels.iter().enumerate() {
+            let Some(plugin_ch) = plugin_ch else { continue };
+            let track = self.generator_tracks.get(channel_idx).copied().unwrap_or(1);
+            if track < NUM_TRACKS {
+                let latency = plugin_ch.processor.latency_frames() as usize;
+                inputs[track] = inputs[track].max(latency);
+            }
+        }
+
+        let tracks = std::array::from_fn(|track| {
+            let inserts: usize = self.track_effects[track]
+                .iter()
+                .zip(&self.effect_bypassed[track])
+                .filter(|(_, &bypassed)| !bypassed)
+                .filter_map(|(effect, _)| effect.as_ref())
+                .map(|effect| effect.latency_frames())
+                .sum();
+            inputs[track] + inserts
+        });
+        (inputs, tracks)
+    }
+
+    /// Refresh the latency figures and retune the compensation delays
+    fn update_latencies(&mut self) {
+        let (inputs, tracks) = self.measure_latencies();
+        self.track_latency = tracks;
+
+        let slowest = tracks.iter().copied().max().unwrap_or(0);
+        for (delay, latency) in self.track_delays.iter_mut().zip(tracks) {
+            delay.set_delay(slowest - latency);
+        }
+        for (delay, latency) in self.input_delays.iter_mut().zip(inputs) {
+            delay.set_delay(latency);
+        }
+        for (channel_idx, plugin_ch) in self.plugin_channels.iter_mut().enumerate() {
+            let Some(plugin_ch) = plugin_ch else { continue };
+            let track = self.generator_tracks.get(channel_idx).copied().unwrap_or(1);
+            let own = plugin_ch.processor.latency_frames() as usize;
+            let target = inputs.get(track).copied().unwrap_or(own);
+            plugin_ch.delay.set_delay(target.saturating_sub(own));
+        }
+    }
+
     // ========================================================================
     // Voice Management
     // ========================================================================
@@ -378,6 +619,25 @@ impl MixingEngine {
         volume: f32,
         generator_idx: usize,
         route_to_master: bool,
+    ) {
+        self.add_voice_at(
+            sample,
+            volume,
+            generator_idx,
+            route_to_master,
+            self.frame_clock,
+        );
+    }
+
+    /// Add a voice that starts playing at engine frame `frame`
+    /// (immediately if that frame has already passed)
+    pub fn add_voice_at(
+        &mut self,
+        sample: SampleData,
+        volume: f32,
+        generator_idx: usize,
+        route_to_master: bool,
+        frame: u64,
     ) {
         if self.voices.len() >= MAX_VOICES {
             self.voices.remove(0);
@@ -389,6 +649,7 @@ impl MixingEngine {
             is_preview: false,
             generator_idx,
             route_to_master,
+            start_delay: frame.saturating_sub(self.frame_clock) as usize,
         });
     }
 
@@ -407,6 +668,7 @@ impl MixingEngine {
             is_preview: true,
             generator_idx,
             route_to_master,
+            start_delay: 0,
         });
     }
 
@@ -443,29 +705,128 @@ impl MixingEngine {
             processor,
             pending_notes: Vec::new(),
             pending_params: Vec::new(),
+            held_notes: Vec::new(),
             output_left: Vec::new(),
             output_right: Vec::new(),
             volume,
+            delay: CompensationDelay::default(),
         });
     }
 
     /// Send a note to a plugin channel
-    pub fn send_plugin_note(&mut self, channel: usize, note: u8, velocity: f32, is_note_on: bool) {
+    pub fn send_plugin_note(
+        &mut self,
+        channel: usize,
+        note: u8,
+        velocity: f32,
+        is_note_on: bool,
+        expressions: NoteExpressions,
+    ) {
+        let frame = self.frame_clock;
+        self.schedule_plugin_note(channel, note, velocity, is_note_on, expressions, frame);
+    }
+
+    /// Send a note to a plugin channel at engine frame `frame`
+    /// (at the start of the next block if that frame has already passed)
+    pub fn schedule_plugin_note(
+        &mut self,
+        channel: usize,
+        note: u8,
+        velocity: f32,
+        is_note_on: bool,
+        expressions: NoteExpressions,
+        frame: u64,
+    ) {
         if let Some(Some(plugin_ch)) = self.plugin_channels.get_mut(channel) {
             plugin_ch.pending_notes.push(PluginNoteEvent {
+                frame,
                 note,
                 velocity,
                 is_note_on,
+                expressions,
             });
+            if is_note_on {
+                plugin_ch.held_notes.push(note);
+            } else if let Some(pos) = plugin_ch.held_notes.iter().position(|&n| n == note) {
+                plugin_ch.held_notes.swap_remove(pos);
+            }
+        }
+    }
+
+    /// Send note-offs for every plugin note that is still held
+    pub fn release_plugin_notes(&mut self) {
+        let frame = self.frame_clock;
+        for plugin_ch in self.plugin_channels.iter_mut().flatten() {
+            for note in plugin_ch.held_notes.drain(..) {
+                plugin_ch.pending_notes.push(PluginNoteEvent {
+                    frame,
+                    note,
+                    velocity: 0.0,
+                    is_note_on: false,
+                    expressions: NoteExpressions::default(),
+                });
+            }
         }
     }
 
+    /// Feed the notes `channel`'s plugin sends out to the plugin on `target`
+    /// (None to stop). Notes reach a channel processed later in the same
+    /// block at their exact frame, otherwise at the start of the next one.
+    pub fn set_note_route(&mut self, channel: usize, target: Option<usize>) {
+        if self.note_routes.len() <= channel {
+            self.note_routes.resize(channel + 1, None);
+        }
+        self.note_routes[channel] = target.filter(|&t| t != channel);
+    }
+
+    /// Drop plugin note-ons scheduled for a later block, so stopping
+    /// playback doesn't leave notes starting after their note-offs went out
+    pub fn cancel_scheduled_notes(&mut self) {
+        let next_block = self.frame_clock;
+        for plugin_ch in self.plugin_channels.iter_mut().flatten() {
+            plugin_ch
+                .pending_notes
+                .retain(|e| !e.is_note_on || e.frame <= next_block);
+        }
+    }
+
+    /// Have every installed plugin (instruments and effects) wait out slow
+    /// blocks instead of dropping them, for offline renders
+    pub fn set_offline(&mut self) {
+        for plugin_ch in self.plugin_channels.iter_mut().flatten() {
+            plugin_ch.processor.set_offline();
+        }
+        for effect in self.track_effects.iter_mut().flatten().flatten() {
+            effect.set_offline();
+        }
+    }
+
+    /// Longest tail reported by any installed plugin, in frames
+    ///
+    /// `u32::MAX` means at least one plugin has an infinite tail.
+    pub fn plugin_tail_frames(&mut self) -> u32 {
+        self.plugin_channels
+            .iter_mut()
+            .flatten()
+            .map(|plugin_ch| plugin_ch.processor.tail_frames())
+            .max()
+            .unwrap_or(0)
+    }
+
     /// Send a parameter change to a plugin channel
     pub fn send_plugin_param(&mut self, channel: usize, param_id: u32, value: f64) {
+        self.schedule_plugin_param(channel, param_id, value, self.frame_clock);
+    }
+
+    /// Set a plugin parameter at engine frame `frame`
+    /// (at the start of the next block if that frame has already passed)
+    pub fn schedule_plugin_param(&mut self, channel: usize, param_id: u32, value: f64, frame: u64) {
         if let Some(Some(plugin_ch)) = self.plugin_channels.get_mut(channel) {
-            plugin_ch
-                .pending_params
-                .push(PluginParamEvent { param_id, value });
+            plugin_ch.pending_params.push(PluginParamEvent {
+                frame,
+                param_id,
+                value,
+            });
         }
     }
 
@@ -535,9 +896,43 @@ impl MixingEngine {
         }
     }
 
+    /// Swap in an effect's convolution kernel (convolution only)
+    ///
+    /// Returns the kernel that is no longer used (the replaced one, or
+    /// `kernel` if the slot can't take it).
+    pub fn set_effect_convolution_kernel(
+        &mut self,
+        track: usize,
+        slot: usize,
+        kernel: Option<Box<ConvolutionKernel>>,
+    ) -> Option<Box<ConvolutionKernel>> {
+        if track < NUM_TRACKS && slot < EFFECT_SLOTS {
+            if let Some(effect) = &mut self.track_effects[track][slot] {
+                return effect.set_convolution_kernel(kernel);
+            }
+        }
+        kernel
+    }
+
+    /// Set a plugin effect parameter by CLAP id
+    pub fn set_effect_plugin_param(
+        &mut self,
+        track: usize,
+        slot: usize,
+        param_id: u32,
+        value: f64,
+    ) {
+        if track < NUM_TRACKS && slot < EFFECT_SLOTS {
+            if let Some(effect) = &mut self.track_effects[track][slot] {
+                effect.set_plugin_param(param_id, value);
+            }
+        }
+    }
+
     /// Set tempo (for tempo-synced effects)
     pub fn set_tempo(&mut self, bpm: f64) {
         self.tempo_bpm = bpm;
+        self.transport.tempo = bpm;
         for track_effects in &mut self.track_effects {
             for effect in track_effects.iter_mut().flatten() {
                 effect.set_tempo(bpm);
@@ -545,6 +940,22 @@ impl MixingEngine {
         }
     }
 
+    /// Set the song position plugins see from the next block on
+    pub fn set_transport(&mut self, transport: PluginTransport) {
+        self.transport = transport;
+    }
+
+    /// Song position at the start of the next block
+    pub fn transport(&self) -> &PluginTransport {
+        &self.transport
+    }
+
+    /// Song position the last processed block started at, for effects run
+    /// on its output outside the engine
+    pub fn block_transport(&self) -> &PluginTransport {
+        &self.block_transport
+    }
+
     // ========================================================================
     // Private Mixing Methods
     // ========================================================================
@@ -565,10 +976,14 @@ impl MixingEngine {
     #[allow(clippy::type_complexity)]
     fn render_voices_to_tracks(&mut self, num_frames: usize) {
         // Collect voice outputs first to avoid borrow issues
-        // (target_track, samples, finished)
-        let mut voice_outputs: Vec<(usize, Vec<(f32, f32)>, bool)> = Vec::new();
+        // (target_track, first frame, samples, finished)
+        let mut voice_outputs: Vec<(usize, usize, Vec<(f32, f32)>, bool)> = Vec::new();
+
+        for voice in self.voices.iter_mut() {
+            // Scheduled voices stay silent until their start frame
+            let offset = voice.start_delay.min(num_frames);
+            voice.start_delay -= offset;
 
-        for voice in self.voices.iter() {
             let sample_data = &voice.sample.data;
             let voice_channels = voice.sample.channels as usize;
             let voice_volume = voice.volume;
@@ -582,11 +997,11 @@ impl MixingEngine {
             };
 
             let resample_ratio = sample_rate as f32 / self.sample_rate as f32;
-            let mut samples = Vec::with_capacity(num_frames);
+            let mut samples = Vec::with_capacity(num_frames - offset);
             let mut finished = false;
             let mut pos = voice.position;
 
-            for _ in 0..num_frames {
+            for _ in offset..num_frames {
                 let src_frame = (pos as f32 * resample_ratio) as usize;
 
                 if src_frame * voice_channels >= sample_data.len() {
@@ -613,16 +1028,17 @@ impl MixingEngine {
                 pos += 1;
             }
 
-            voice_outputs.push((target_track, samples, finished));
+            voice_outputs.push((target_track, offset, samples, finished));
         }
 
         // Apply to track buffers and update voice state
         let mut voices_to_remove = Vec::new();
 
-        for (voice_idx, (target_track, samples, finished)) in voice_outputs.into_iter().enumerate()
+        for (voice_idx, (target_track, offset, samples, finished)) in
+            voice_outputs.into_iter().enumerate()
         {
             if target_track < NUM_TRACKS {
-                for (frame, (left, right)) in samples.iter().enumerate() {
+                for (frame, (left, right)) in (offset..).zip(samples.iter()) {
                     self.track_buffers[target_track].left[frame] += left;
                     self.track_buffers[target_track].right[frame] += right;
                 }
@@ -647,8 +1063,15 @@ impl MixingEngine {
             return;
         }
 
-        for (channel_idx, plugin_opt) in self.plugin_channels.iter_mut().enumerate() {
-            let Some(plugin_ch) = plugin_opt else {
+        // Sample voices have no latency; hold them back to the slowest
+        // instrument sharing their track
+        for (buf, delay) in self.track_buffers.iter_mut().zip(&mut self.input_delays) {
+            delay.process(&mut buf.left[..num_frames], &mut buf.right[..num_frames]);
+        }
+
+        let block_start = self.frame_clock;
+        for channel_idx in 0..self.plugin_channels.len() {
+            let Some(plugin_ch) = self.plugin_channels[channel_idx].as_mut() else {
                 continue;
             };
 
@@ -664,34 +1087,58 @@ impl MixingEngine {
                 plugin_ch.output_right[i] = 0.0;
             }
 
-            // Convert pending notes to MidiNote format
-            let notes: Vec<MidiNote> = plugin_ch
-                .pending_notes
-                .drain(..)
-                .map(|e| MidiNote {
+            // Events due in this block are delivered at their frame within
+            // it (late ones at its start); later ones stay pending
+            let block_end = block_start + num_frames as u64;
+            let offset = |frame: u64| frame.saturating_sub(block_start) as u32;
+
+            // Convert due notes to MidiNote format
+            let mut notes: Vec<MidiNote> = Vec::new();
+            plugin_ch.pending_notes.retain(|e| {
+                if e.frame >= block_end {
+                    return true;
+                }
+                notes.push(MidiNote {
+                    time: offset(e.frame),
                     note: e.note,
                     velocity: e.velocity,
                     is_note_on: e.is_note_on,
-                })
-                .collect();
+                    expressions: e.expressions,
+                });
+                false
+            });
 
-            // Convert pending params to ParamChange format
-            let params: Vec<ParamChange> = plugin_ch
-                .pending_params
-                .drain(..)
-                .map(|e| ParamChange {
+            // Convert due params to ParamChange format
+            let mut params: Vec<ParamChange> = Vec::new();
+            plugin_ch.pending_params.retain(|e| {
+                if e.frame >= block_end {
+                    return true;
+                }
+                params.push(ParamChange {
+                    time: offset(e.frame),
                     param_id: e.param_id,
                     value: e.value,
-                })
-                .collect();
+                });
+                false
+            });
 
             // Process audio through the plugin
             plugin_ch.processor.process(
                 &notes,
                 &params,
+                &self.transport,
                 &mut plugin_ch.output_left[..num_frames],
                 &mut plugin_ch.output_right[..num_frames],
             );
+            plugin_ch.delay.process(
+                &mut plugin_ch.output_left[..num_frames],
+                &mut plugin_ch.output_right[..num_frames],
+            );
+            if plugin_ch.processor.take_crash() {
+                self.plugin_events.push(PluginEvent::Crashed {
+                    channel: channel_idx,
+                });
+            }
 
             // Get target track for this plugin's generator
             let target_track = self.generator_tracks.get(channel_idx).copied().unwrap_or(1);
@@ -706,6 +1153,42 @@ impl MixingEngine {
                     self.track_buffers[target_track].right[frame] += right;
                 }
             }
+
+            // Report what the plugin sent out and feed its notes onwards
+            for change in plugin_ch.processor.output_params() {
+                self.plugin_events.push(PluginEvent::ParamChanged {
+                    channel: channel_idx,
+                    param_id: change.param_id,
+                    value: change.value,
+                });
+            }
+            let route = self.note_routes.get(channel_idx).copied().flatten();
+            // By index, as scheduling a routed note needs the engine mutably
+            for i in 0..plugin_ch.processor.output_notes().len() {
+                let Some(note) = self.plugin_channels[channel_idx]
+                    .as_ref()
+                    .map(|ch| ch.processor.output_notes()[i])
+                else {
+                    break;
+                };
+                self.plugin_events.push(PluginEvent::NoteOut {
+                    channel: channel_idx,
+                    note: note.note,
+                    velocity: note.velocity,
+                    is_note_on: note.is_note_on,
+                });
+                if let Some(target) = route {
+                    let frame = block_start + note.time as u64;
+                    self.schedule_plugin_note(
+                        target,
+                        note.note,
+                        note.velocity,
+                        note.is_note_on,
+                        note.expressions,
+                        frame,
+                    );
+                }
+            }
         }
     }
 
@@ -714,21 +1197,83 @@ impl MixingEngine {
             return;
         }
 
+        // Snapshot sidechain sources before any inserts run, so a key is the
+        // source track's raw signal regardless of track order (and even if muted)
+        let mut keyed = [false; NUM_TRACKS];
+        for (effects, bypassed) in self.track_effects.iter().zip(&self.effect_bypassed) {
+            for (effect, &bypassed) in effects.iter().zip(bypassed) {
+                if let Some(source) = effect.as_ref().and_then(|e| e.sidechain_source()) {
+                    if source < NUM_TRACKS && !bypassed {
+                        keyed[source] = true;
+                    }
+                }
+            }
+        }
+        for (source, _) in keyed.iter().enumerate().filter(|(_, &k)| k) {
+            let (src, dst) = (
+                &self.track_buffers[source],
+                &mut self.sidechain_buffers[source],
+            );
+            dst.left[..num_frames].copy_from_slice(&src.left[..num_frames]);
+            dst.right[..num_frames].copy_from_slice(&src.right[..num_frames]);
+        }
+
         for track_idx in 0..NUM_TRACKS {
             for slot_idx in 0..EFFECT_SLOTS {
+                self.gain_reduction[track_idx][slot_idx] = 0.0;
+                self.gate_states[track_idx][slot_idx] = None;
                 if self.effect_bypassed[track_idx][slot_idx] {
                     continue;
                 }
 
                 if let Some(mut effect) = self.track_effects[track_idx][slot_idx].take() {
+                    effect.set_transport(&self.transport);
                     let buf = &mut self.track_buffers[track_idx];
-                    effect.process(&mut buf.left[..num_frames], &mut buf.right[..num_frames]);
+                    match effect.sidechain_source().filter(|&s| s < NUM_TRACKS) {
+                        Some(source) => {
+                            let key = &self.sidechain_buffers[source];
+                            effect.process_with_sidechain(
+                                &mut buf.left[..num_frames],
+                                &mut buf.right[..num_frames],
+                                &key.left[..num_frames],
+                                &key.right[..num_frames],
+                            );
+                        }
+                        None => {
+                            effect
+                                .process(&mut buf.left[..num_frames], &mut buf.right[..num_frames]);
+                        }
+                    }
+                    self.gain_reduction[track_idx][slot_idx] = effect.gain_reduction_db();
+                    self.gate_states[track_idx][slot_idx] = effect.gate_state();
+                    if effect.take_crash() {
+                        self.plugin_events.push(PluginEvent::EffectCrashed {
+                            track: track_idx,
+                            slot: slot_idx,
+                        });
+                    }
+                    for change in effect.plugin_param_changes() {
+                        self.plugin_events.push(PluginEvent::EffectParamChanged {
+                            track: track_idx,
+                            slot: slot_idx,
+                            param_id: change.param_id,
+                            value: change.value,
+                        });
+                    }
                     self.track_effects[track_idx][slot_idx] = Some(effect);
                 }
             }
         }
     }
 
+    /// Delay every track (including what feeds the master directly) so they
+    /// all arrive at the master as late as the slowest one
+    fn compensate_track_latency(&mut self, num_frames: usize) {
+        for (buf, delay) in self.track_buffers.iter_mut().zip(&mut self.track_delays) {
+            delay.process(&mut buf.left[..num_frames], &mut buf.right[..num_frames]);
+        }
+    }
+
     fn sum_tracks_to_master(&mut self, num_frames: usize) {
         // Sum tracks 1-15 to master (track 0)
         for track_idx in 1..NUM_TRACKS {
@@ -791,7 +1336,10 @@ pub(crate) fn build_mixer_state(mixer: &Mixer) -> AudioMixerState {
 /// Configure a MixingEngine with all project state.
 ///
 /// This is the shared setup function used by both real-time playback and offline export.
-/// It loads plugins, sets up effects, and configures mixer routing.
+/// It loads plugins (restoring their saved state), sets up effects, and configures
+/// mixer routing. Returns the main-thread handles of the loaded plugins, which must
+/// outlive the engine's use of them and are needed to save their state again,
+/// along with the plugins that failed to load.
 pub(crate) fn setup_engine(
     engine: &mut MixingEngine,
     channels: &[Channel],
@@ -800,7 +1348,9 @@ pub(crate) fn setup_engine(
     plugin_loader: &dyn PluginLoader,
     sample_rate: u32,
     bpm: f64,
-) {
+) -> EngineSetup {
+    let mut setup = EngineSetup::default();
+
     // Set mixer state (volumes, pans, mutes)
     engine.set_mixer_state(build_mixer_state(mixer));
     engine.set_master_volume(1.0);
@@ -811,18 +1361,41 @@ pub(crate) fn setup_engine(
         engine.set_generator_track(idx, channel.mixer_track);
     }
 
+    // Feed plugin note output to the channels it is routed to
+    for (idx, target) in note_routes(channels).into_iter().enumerate() {
+        engine.set_note_route(idx, target);
+    }
+
     // Load and install plugins
     for (idx, channel) in channels.iter().enumerate() {
-        if let ChannelSource::Plugin { path, params } = &channel.source {
+        if let ChannelSource::Plugin {
+            path,
+            plugin_id,
+            params,
+            state,
+            ..
+        } = &channel.source
+        {
             let plugin_path = plugins_path.join(path);
-            if let Ok(loaded) = plugin_loader.load_plugin(&plugin_path, sample_rate as f64, 512) {
-                let volume = mixer.track(TrackId(channel.mixer_track)).volume;
-                engine.install_plugin(idx, loaded.processor, volume);
-
-                // Apply saved parameters (convert from PluginParamId -> clap_id)
-                for (clap_id, value) in build_init_params(params) {
-                    engine.send_plugin_param(idx, clap_id, value);
+            match plugin_loader.load_plugin(
+                &plugin_path,
+                plugin_id.as_deref(),
+                sample_rate as f64,
+                512,
+                state.as_deref(),
+            ) {
+                Ok(loaded) => {
+                    let volume = mixer.track(TrackId(channel.mixer_track)).volume;
+                    engine.install_plugin(idx, loaded.processor, volume);
+                    let slot = PluginSlot::Channel(channel.slot);
+                    setup.instances.insert(slot, path, loaded.instance);
+
+                    // Apply saved parameters
+                    for (clap_id, value) in build_init_params(params) {
+                        engine.send_plugin_param(idx, clap_id, value);
+                    }
                 }
+                Err(e) => setup.add_failure(path, &plugin_path, e.to_string()),
             }
         }
     }
@@ -834,12 +1407,91 @@ pub(crate) fn setup_engine(
 
         for (slot_idx, slot) in track.effects.iter().enumerate() {
             if let Some(slot) = slot {
-                let effect = create_effect(slot, sample_rate as f32, bpm);
+                let (effect, instance) =
+                    build_effect(slot, plugins_path, plugin_loader, sample_rate, bpm);
+                if let Some(path) = &slot.plugin_path {
+                    match instance {
+                        Ok(Some(instance)) => {
+                            let key = PluginSlot::Effect {
+                                track: track_idx,
+                                slot: slot_idx,
+                            };
+                            setup.instances.insert(key, path, instance);
+                        }
+                        Ok(None) => {}
+                        Err(e) => setup.add_failure(path, &plugins_path.join(path), e),
+                    }
+                }
                 engine.set_effect(track_idx, slot_idx, Some(effect));
                 engine.set_effect_enabled(track_idx, slot_idx, !slot.bypassed);
             }
         }
     }
+
+    setup
+}
+
+/// What [`setup_engine`] loaded into the engine
+#[derive(Default)]
+pub(crate) struct EngineSetup {
+    /// Main-thread handles of the loaded plugins
+    pub instances: PluginInstances,
+    /// Plugins that failed to load: path relative to the plugins folder,
+    /// with the error. Each path is listed once, however often it's used.
+    pub failed_plugins: Vec<(String, String)>,
+}
+
+impl EngineSetup {
+    fn add_failure(&mut self, path: &str, full_path: &Path, error: String) {
+        if self.failed_plugins.iter().any(|(p, _)| p == path) {
+            return;
+        }
+        let error = if full_path.exists() {
+            error
+        } else {
+            "file not found".to_string()
+        };
+        self.failed_plugins.push((path.to_string(), error));
+    }
+}
+
+/// Main-thread handle of a CLAP effect (None for built-in effects), or the
+/// error it failed to load with
+pub(crate) type EffectInstance = Result<Option<Box<dyn StateSaver>>, String>;
+
+/// Create the processor for an effect slot, loading CLAP effects through the
+/// plugin loader with the slot's saved state.
+///
+/// A plugin that fails to load leaves a passthrough in the slot so the rest
+/// of the chain still plays, and its error is returned alongside the effect.
+/// For a loaded plugin, its main-thread handle is returned instead.
+pub(crate) fn build_effect(
+    slot: &EffectSlot,
+    plugins_path: &Path,
+    plugin_loader: &dyn PluginLoader,
+    sample_rate: u32,
+    bpm: f64,
+) -> (Box<dyn Effect>, EffectInstance) {
+    if let (EffectType::Plugin, Some(path)) = (slot.effect_type, &slot.plugin_path) {
+        let plugin_path = plugins_path.join(path);
+        return match plugin_loader.load_plugin(
+            &plugin_path,
+            slot.plugin_id.as_deref(),
+            sample_rate as f64,
+            512,
+            slot.plugin_state.as_deref(),
+        ) {
+            Ok(loaded) => {
+                let effect = PluginEffect::new(loaded.processor, &slot.plugin_params);
+                (Box::new(effect), Ok(Some(loaded.instance)))
+            }
+            Err(e) => (
+                create_effect(slot, sample_rate as f32, bpm),
+                Err(e.to_string()),
+            ),
+        };
+    }
+    (create_effect(slot, sample_rate as f32, bpm), Ok(None))
 }
 
 /// Shared state between audio thread and main thread
@@ -850,38 +1502,81 @@ struct AudioState {
     sample_cache: HashMap<PathBuf, SampleData>,
     /// Command receiver
     rx: Receiver<AudioCommand>,
-    /// Receiver for plugin processors from main thread (channel, processor, initial state)
+    /// Receiver for plugin processors from main thread
     #[allow(dead_code)]
-    plugin_rx: Receiver<(usize, ActivePluginProcessor, PluginInitState)>,
+    plugin_rx: Receiver<PluginInstall>,
     /// Waveform buffer for visualization (shared with UI)
     waveform_buffer: WaveformBuffer,
     /// Write position in waveform buffer
     waveform_write_pos: usize,
     /// Peak levels buffer (shared with UI for meter visualization)
     peak_levels: PeakLevelsBuffer,
+    /// Gain reduction buffer (shared with UI for effect editor meters)
+    gain_reduction: GainReductionBuffer,
+    /// Gate state buffer (shared with UI for effect editor indicators)
+    gate_states: GateStateBuffer,
+    /// Track latency buffer (shared with UI for the mixer)
+    track_latency: LatencyBuffer,
+    /// Audio clock (shared with handles for scheduling)
+    clock: AudioClockBuffer,
+    /// Sender for events plugins sent out (to the main thread)
+    plugin_event_tx: Sender<PluginEvent>,
+    /// Replaced convolution kernels, sent back to be freed on the main thread
+    spent_kernel_tx: Sender<Box<ConvolutionKernel>>,
 }
 
 /// Handle for sending commands to the audio engine
 #[derive(Clone)]
 pub struct AudioHandle {
     tx: Sender<AudioCommand>,
-    plugin_tx: Sender<(usize, ActivePluginProcessor, PluginInitState)>,
+    plugin_tx: Sender<PluginInstall>,
     sample_rate: u32,
     /// Shared waveform buffer for visualization
     waveform_buffer: WaveformBuffer,
     /// Shared peak levels buffer for mixer meters
     peak_levels: PeakLevelsBuffer,
+    /// Shared gain reduction buffer for compressor meters
+    gain_reduction: GainReductionBuffer,
+    /// Shared gate state buffer for gate indicators
+    gate_states: GateStateBuffer,
+    /// Shared track latency buffer for the mixer
+    track_latency: LatencyBuffer,
+    /// Shared audio clock for scheduling
+    clock: AudioClockBuffer,
+    /// Events plugins sent out, forwarded by the audio thread
+    plugin_event_rx: Receiver<PluginEvent>,
+    /// Convolution kernels the audio thread is done with
+    spent_kernel_rx: Receiver<Box<ConvolutionKernel>>,
 }
 
 #[allow(dead_code)]
 impl AudioHandle {
+    /// Engine frame an event happening at `instant` should be scheduled at,
+    /// or None to play it as soon as possible (e.g. before the stream runs)
+    pub fn schedule_frame(&self, instant: Instant) -> Option<u64> {
+        let clock = *self.clock.lock().ok()?;
+        clock.schedule_frame(instant, self.sample_rate)
+    }
+
     /// Play a sample at the given volume (polyphonic)
     /// generator_idx is used for routing to the correct mixer track
     pub fn play_sample(&self, path: &Path, volume: f32, generator_idx: usize) {
+        self.play_sample_at(path, volume, generator_idx, None);
+    }
+
+    /// Play a sample starting at engine frame `at_frame`
+    pub fn play_sample_at(
+        &self,
+        path: &Path,
+        volume: f32,
+        generator_idx: usize,
+        at_frame: Option<u64>,
+    ) {
         let _ = self.tx.send(AudioCommand::PlaySample {
             path: path.to_path_buf(),
             volume,
             generator_idx,
+            at_frame,
         });
     }
 
@@ -927,16 +1622,40 @@ impl AudioHandle {
 
     /// Send note on to a plugin channel
     pub fn plugin_note_on(&self, channel: usize, note: u8, velocity: f32) {
+        self.plugin_note_on_at(channel, note, velocity, NoteExpressions::default(), None);
+    }
+
+    /// Send note on with per-note expressions to a plugin channel at
+    /// engine frame `at_frame`
+    pub fn plugin_note_on_at(
+        &self,
+        channel: usize,
+        note: u8,
+        velocity: f32,
+        expressions: NoteExpressions,
+        at_frame: Option<u64>,
+    ) {
         let _ = self.tx.send(AudioCommand::PluginNoteOn {
             channel,
             note,
             velocity,
+            expressions,
+            at_frame,
         });
     }
 
     /// Send note off to a plugin channel
     pub fn plugin_note_off(&self, channel: usize, note: u8) {
-        let _ = self.tx.send(AudioCommand::PluginNoteOff { channel, note });
+        self.plugin_note_off_at(channel, note, None);
+    }
+
+    /// Send note off to a plugin channel at engine frame `at_frame`
+    pub fn plugin_note_off_at(&self, channel: usize, note: u8, at_frame: Option<u64>) {
+        let _ = self.tx.send(AudioCommand::PluginNoteOff {
+            channel,
+            note,
+            at_frame,
+        });
     }
 
     /// Set a plugin parameter value
@@ -962,7 +1681,27 @@ impl AudioHandle {
         processor: ActivePluginProcessor,
         init_state: PluginInitState,
     ) {
-        let _ = self.plugin_tx.send((channel, processor, init_state));
+        let _ = self.plugin_tx.send(PluginInstall::Channel {
+            channel,
+            processor: Box::new(processor),
+            init_state,
+        });
+    }
+
+    /// Send an activated effect plugin to the audio thread for a mixer insert slot
+    pub fn send_effect_plugin(
+        &self,
+        track: usize,
+        slot: usize,
+        processor: ActivePluginProcessor,
+        params: &HashMap<u32, f64>,
+    ) {
+        let effect = Box::new(PluginEffect::new(processor, params));
+        let _ = self.plugin_tx.send(PluginInstall::Effect {
+            track,
+            slot,
+            effect,
+        });
     }
 
     /// Get the output sample rate
@@ -1001,6 +1740,30 @@ impl AudioHandle {
         &self.peak_levels
     }
 
+    /// Get the current gain reduction of an effect slot in dB (for UI meters)
+    pub fn get_gain_reduction(&self, track: usize, slot: usize) -> f32 {
+        match self.gain_reduction.lock() {
+            Ok(gr) if track < NUM_TRACKS && slot < EFFECT_SLOTS => gr[track][slot],
+            _ => 0.0,
+        }
+    }
+
+    /// Get the current state of a gate effect slot (None for other effects)
+    pub fn get_gate_state(&self, track: usize, slot: usize) -> Option<GateState> {
+        match self.gate_states.lock() {
+            Ok(states) if track < NUM_TRACKS && slot < EFFECT_SLOTS => states[track][slot],
+            _ => None,
+        }
+    }
+
+    /// Latency of each mixer track in frames (instrument plugins plus inserts)
+    pub fn get_track_latencies(&self) -> [usize; NUM_TRACKS] {
+        match self.track_latency.lock() {
+            Ok(latency) => *latency,
+            Err(_) => [0; NUM_TRACKS],
+        }
+    }
+
     /// Set or remove an effect on a mixer track slot
     pub fn set_effect(&self, track: usize, slot: usize, effect_type: Option<EffectType>) {
         let _ = self.tx.send(AudioCommand::SetEffect {
@@ -1029,11 +1792,57 @@ impl AudioHandle {
         });
     }
 
+    /// Build the kernel for a convolution slot's IR and shaping params and
+    /// send it to the audio thread, which only swaps it in
+    ///
+    /// Call whenever the IR or a shaping param changes. Does nothing for
+    /// other effect types.
+    pub fn set_effect_convolution_kernel(&self, track: usize, slot: usize, effect: &EffectSlot) {
+        if effect.effect_type != EffectType::Convolution {
+            return;
+        }
+        let _ = self.tx.send(AudioCommand::SetEffectConvolutionKernel {
+            track,
+            slot,
+            kernel: effect.convolution_kernel(self.sample_rate as f32),
+        });
+    }
+
+    /// Set a plugin effect parameter by CLAP id
+    pub fn set_effect_plugin_param(&self, track: usize, slot: usize, param_id: u32, value: f64) {
+        let _ = self.tx.send(AudioCommand::SetEffectPluginParam {
+            track,
+            slot,
+            param_id,
+            value,
+        });
+    }
+
     /// Update tempo for tempo-synced effects
     pub fn update_tempo(&self, bpm: f64) {
         let _ = self.tx.send(AudioCommand::UpdateTempo(bpm));
     }
 
+    /// Update the song position and play state plugins follow
+    pub fn update_transport(&self, transport: PluginTransport) {
+        let _ = self.tx.send(AudioCommand::UpdateTransport(transport));
+    }
+
+    /// Feed the notes a plugin channel sends out to another channel's plugin
+    pub fn set_note_route(&self, channel: usize, target: Option<usize>) {
+        let _ = self.tx.send(AudioCommand::SetNoteRoute { channel, target });
+    }
+
+    /// Parameter changes and notes plugins sent out since the last poll
+    pub fn poll_plugin_events(&self) -> Vec<PluginEvent> {
+        self.plugin_event_rx.try_iter().collect()
+    }
+
+    /// Free the convolution kernels the audio thread swapped out
+    pub fn free_spent_kernels(&self) {
+        self.spent_kernel_rx.try_iter().for_each(drop);
+    }
+
     /// Create a dummy AudioHandle for testing (no actual audio processing)
     ///
     /// Commands sent to this handle are simply dropped. This is useful for
@@ -1043,13 +1852,21 @@ impl AudioHandle {
         // Create channels that will just drop messages (no receiver)
         let (tx, _rx) = unbounded();
         let (plugin_tx, _plugin_rx) = unbounded();
+        let (_plugin_event_tx, plugin_event_rx) = unbounded();
+        let (_spent_kernel_tx, spent_kernel_rx) = bounded(SPENT_KERNEL_CAPACITY);
 
         Self {
             tx,
             plugin_tx,
+            plugin_event_rx,
+            spent_kernel_rx,
             sample_rate: 44100,
             waveform_buffer: Arc::new(Mutex::new(vec![0.0; WAVEFORM_BUFFER_SIZE])),
             peak_levels: Arc::new(Mutex::new([StereoLevels::default(); NUM_TRACKS])),
+            gain_reduction: Arc::new(Mutex::new([[0.0; EFFECT_SLOTS]; NUM_TRACKS])),
+            gate_states: Arc::new(Mutex::new([[None; EFFECT_SLOTS]; NUM_TRACKS])),
+            track_latency: Arc::new(Mutex::new([0; NUM_TRACKS])),
+            clock: new_clock(),
         }
     }
 
@@ -1058,17 +1875,33 @@ impl AudioHandle {
     /// Use this when you need to verify specific audio commands are sent.
     #[cfg(test)]
     pub fn testable() -> (Self, Receiver<AudioCommand>) {
+        let (handle, rx, _plugin_event_tx) = Self::testable_with_plugin_events();
+        (handle, rx)
+    }
+
+    /// Like [`AudioHandle::testable`], also returning a sender to fake
+    /// events coming back from plugins
+    #[cfg(test)]
+    pub fn testable_with_plugin_events() -> (Self, Receiver<AudioCommand>, Sender<PluginEvent>) {
         let (tx, rx) = unbounded();
         let (plugin_tx, _plugin_rx) = unbounded();
+        let (plugin_event_tx, plugin_event_rx) = unbounded();
+        let (_spent_kernel_tx, spent_kernel_rx) = bounded(SPENT_KERNEL_CAPACITY);
 
         let handle = Self {
             tx,
             plugin_tx,
+            plugin_event_rx,
+            spent_kernel_rx,
             sample_rate: 44100,
             waveform_buffer: Arc::new(Mutex::new(vec![0.0; WAVEFORM_BUFFER_SIZE])),
             peak_levels: Arc::new(Mutex::new([StereoLevels::default(); NUM_TRACKS])),
+            gain_reduction: Arc::new(Mutex::new([[0.0; EFFECT_SLOTS]; NUM_TRACKS])),
+            gate_states: Arc::new(Mutex::new([[None; EFFECT_SLOTS]; NUM_TRACKS])),
+            track_latency: Arc::new(Mutex::new([0; NUM_TRACKS])),
+            clock: new_clock(),
         };
-        (handle, rx)
+        (handle, rx, plugin_event_tx)
     }
 }
 
@@ -1079,6 +1912,9 @@ pub struct AudioEngine {
     state: Arc<Mutex<AudioState>>,
     #[allow(dead_code)] // Will be used for plugin hosting
     sample_rate: Arc<AtomicU32>,
+    /// Main-thread handles of the plugins loaded from the project setup,
+    /// until the app takes them over
+    plugin_instances: PluginInstances,
 }
 
 impl AudioEngine {
@@ -1101,6 +1937,8 @@ impl AudioEngine {
 
         let (tx, rx) = unbounded();
         let (plugin_tx, plugin_rx) = unbounded();
+        let (plugin_event_tx, plugin_event_rx) = unbounded();
+        let (spent_kernel_tx, spent_kernel_rx) = bounded(SPENT_KERNEL_CAPACITY);
 
         // Create shared waveform buffer for visualization
         let waveform_buffer: WaveformBuffer = Arc::new(Mutex::new(vec![0.0; WAVEFORM_BUFFER_SIZE]));
@@ -1109,12 +1947,26 @@ impl AudioEngine {
         let peak_levels: PeakLevelsBuffer =
             Arc::new(Mutex::new([StereoLevels::default(); NUM_TRACKS]));
 
+        // Create shared gain reduction buffer for compressor meters
+        let gain_reduction: GainReductionBuffer =
+            Arc::new(Mutex::new([[0.0; EFFECT_SLOTS]; NUM_TRACKS]));
+
+        // Create shared gate state buffer for gate indicators
+        let gate_states: GateStateBuffer = Arc::new(Mutex::new([[None; EFFECT_SLOTS]; NUM_TRACKS]));
+
+        // Create shared track latency buffer for the mixer
+        let track_latency: LatencyBuffer = Arc::new(Mutex::new([0; NUM_TRACKS]));
+
+        // Create shared audio clock for scheduling sequenced events
+        let clock = new_clock();
+
         // Create and configure the mixing engine
         let mut engine = MixingEngine::new(sample_rate);
 
         // If project setup is provided, configure engine with plugins/effects/mixer
+        let mut plugin_instances = PluginInstances::new();
         if let Some(setup) = project_setup {
-            setup_engine(
+            plugin_instances = setup_engine(
                 &mut engine,
                 setup.channels,
                 setup.mixer,
@@ -1122,7 +1974,8 @@ impl AudioEngine {
                 setup.plugin_loader,
                 sample_rate,
                 setup.bpm,
-            );
+            )
+            .instances;
         }
 
         let state = Arc::new(Mutex::new(AudioState {
@@ -1133,6 +1986,12 @@ impl AudioEngine {
             waveform_buffer: waveform_buffer.clone(),
             waveform_write_pos: 0,
             peak_levels: peak_levels.clone(),
+            gain_reduction: gain_reduction.clone(),
+            gate_states: gate_states.clone(),
+            track_latency: track_latency.clone(),
+            clock: clock.clone(),
+            plugin_event_tx,
+            spent_kernel_tx,
         }));
 
         let sample_rate_atomic = Arc::new(AtomicU32::new(sample_rate));
@@ -1156,6 +2015,7 @@ impl AudioEngine {
             _stream: stream,
             state,
             sample_rate: sample_rate_atomic,
+            plugin_instances,
         };
 
         let handle = AudioHandle {
@@ -1164,11 +2024,23 @@ impl AudioEngine {
             sample_rate,
             waveform_buffer,
             peak_levels,
+            gain_reduction,
+            gate_states,
+            track_latency,
+            clock,
+            plugin_event_rx,
+            spent_kernel_rx,
         };
 
         Ok((engine, handle))
     }
 
+    /// Hand over the plugins loaded from the project setup so their state
+    /// can be saved with the project
+    pub fn take_plugin_instances(&mut self) -> PluginInstances {
+        std::mem::take(&mut self.plugin_instances)
+    }
+
     fn build_stream<T: cpal::SizedSample + cpal::FromSample<f32> + cpal::Sample>(
         device: &cpal::Device,
         config: &StreamConfig,
@@ -1208,6 +2080,12 @@ impl AudioEngine {
             return;
         };
 
+        // Publish where the engine is, so the main thread can schedule
+        if let Ok(mut clock) = state.clock.try_lock() {
+            clock.frame = state.engine.frame_clock();
+            clock.at = Some(Instant::now());
+        }
+
         // Process commands (non-blocking)
         Self::process_commands_internal(&mut state);
 
@@ -1219,6 +2097,11 @@ impl AudioEngine {
         // Delegate mixing to the engine
         state.engine.process_block(num_frames);
 
+        // Pass on what plugins sent out (parameter changes, note output)
+        for event in state.engine.plugin_events() {
+            let _ = state.plugin_event_tx.send(event.clone());
+        }
+
         // Calculate peak levels for all tracks (need to access track buffers)
         let mut peak_levels = [StereoLevels::default(); NUM_TRACKS];
         for (track_idx, peak) in peak_levels.iter_mut().enumerate() {
@@ -1239,6 +2122,15 @@ impl AudioEngine {
         if let Ok(mut shared_peaks) = state.peak_levels.try_lock() {
             *shared_peaks = peak_levels;
         }
+        if let Ok(mut shared_gr) = state.gain_reduction.try_lock() {
+            *shared_gr = *state.engine.gain_reduction();
+        }
+        if let Ok(mut shared_gates) = state.gate_states.try_lock() {
+            *shared_gates = *state.engine.gate_states();
+        }
+        if let Ok(mut shared_latency) = state.track_latency.try_lock() {
+            *shared_latency = *state.engine.track_latencies();
+        }
 
         // Output master track to DAC
         let master = state.engine.master_buffer();
@@ -1273,15 +2165,32 @@ impl AudioEngine {
 
     fn receive_plugins(state: &mut AudioState) {
         // Receive new plugin processors from main thread
-        while let Ok((channel, processor, init_state)) = state.plugin_rx.try_recv() {
-            // Install plugin in the mixing engine
-            state
-                .engine
-                .install_plugin(channel, processor, init_state.volume);
-
-            // Apply initial parameters
-            for (param_id, value) in init_state.params {
-                state.engine.send_plugin_param(channel, param_id, value);
+        while let Ok(install) = state.plugin_rx.try_recv() {
+            match install {
+                PluginInstall::Channel {
+                    channel,
+                    processor,
+                    init_state,
+                } => {
+                    // Install plugin in the mixing engine
+                    state
+                        .engine
+                        .install_plugin(channel, *processor, init_state.volume);
+
+                    // Apply initial parameters
+                    for (param_id, value) in init_state.params {
+                        state.engine.send_plugin_param(channel, param_id, value);
+                    }
+                }
+                PluginInstall::Effect {
+                    track,
+                    slot,
+                    effect,
+                } => {
+                    // Replaces the passthrough installed by SetEffect, so the
+                    // slot keeps its bypass state
+                    state.engine.set_effect(track, slot, Some(effect));
+                }
             }
         }
     }
@@ -1293,8 +2202,17 @@ impl AudioEngine {
                     path,
                     volume,
                     generator_idx,
+                    at_frame,
                 } => {
-                    Self::play_sample_internal(state, &path, volume, false, generator_idx, false);
+                    Self::play_sample_internal(
+                        state,
+                        &path,
+                        volume,
+                        false,
+                        generator_idx,
+                        false,
+                        at_frame,
+                    );
                 }
                 AudioCommand::PreviewSample {
                     path,
@@ -1310,6 +2228,7 @@ impl AudioEngine {
                         true,
                         generator_idx,
                         route_to_master,
+                        None,
                     );
                 }
                 AudioCommand::StopPreview => {
@@ -1317,6 +2236,7 @@ impl AudioEngine {
                 }
                 AudioCommand::StopAll => {
                     state.engine.stop_all_voices();
+                    state.engine.cancel_scheduled_notes();
                 }
                 AudioCommand::SetMasterVolume(vol) => {
                     state.engine.set_master_volume(vol.clamp(0.0, 1.0));
@@ -1328,11 +2248,33 @@ impl AudioEngine {
                     channel,
                     note,
                     velocity,
+                    expressions,
+                    at_frame,
                 } => {
-                    state.engine.send_plugin_note(channel, note, velocity, true);
+                    let frame = at_frame.unwrap_or(state.engine.frame_clock());
+                    state.engine.schedule_plugin_note(
+                        channel,
+                        note,
+                        velocity,
+                        true,
+                        expressions,
+                        frame,
+                    );
                 }
-                AudioCommand::PluginNoteOff { channel, note } => {
-                    state.engine.send_plugin_note(channel, note, 0.0, false);
+                AudioCommand::PluginNoteOff {
+                    channel,
+                    note,
+                    at_frame,
+                } => {
+                    let frame = at_frame.unwrap_or(state.engine.frame_clock());
+                    state.engine.schedule_plugin_note(
+                        channel,
+                        note,
+                        0.0,
+                        false,
+                        NoteExpressions::default(),
+                        frame,
+                    );
                 }
                 AudioCommand::PluginSetParam {
                     channel,
@@ -1379,9 +2321,38 @@ impl AudioEngine {
                 } => {
                     state.engine.set_effect_enabled(track, slot, enabled);
                 }
+                AudioCommand::SetEffectConvolutionKernel {
+                    track,
+                    slot,
+                    kernel,
+                } => {
+                    let spent = state
+                        .engine
+                        .set_effect_convolution_kernel(track, slot, kernel);
+                    // Freed here only if the main thread has fallen behind
+                    if let Some(spent) = spent {
+                        let _ = state.spent_kernel_tx.try_send(spent);
+                    }
+                }
+                AudioCommand::SetEffectPluginParam {
+                    track,
+                    slot,
+                    param_id,
+                    value,
+                } => {
+                    state
+                        .engine
+                        .set_effect_plugin_param(track, slot, param_id, value);
+                }
                 AudioCommand::UpdateTempo(bpm) => {
                     state.engine.set_tempo(bpm);
                 }
+                AudioCommand::UpdateTransport(transport) => {
+                    state.engine.set_transport(transport);
+                }
+                AudioCommand::SetNoteRoute { channel, target } => {
+                    state.engine.set_note_route(channel, target);
+                }
             }
         }
     }
@@ -1393,6 +2364,7 @@ impl AudioEngine {
         is_preview: bool,
         generator_idx: usize,
         route_to_master: bool,
+        at_frame: Option<u64>,
     ) {
         // Load sample if not cached
         if !state.sample_cache.contains_key(path) {
@@ -1406,9 +2378,10 @@ impl AudioEngine {
                     .engine
                     .add_preview_voice(sample, generator_idx, route_to_master);
             } else {
+                let frame = at_frame.unwrap_or(state.engine.frame_clock());
                 state
                     .engine
-                    .add_voice(sample, volume, generator_idx, route_to_master);
+                    .add_voice_at(sample, volume, generator_idx, route_to_master, frame);
             }
         }
     }
@@ -1693,6 +2666,54 @@ mod mixing_engine_tests {
         assert_eq!(engine.voice_count(), 0);
     }
 
+    #[test]
+    fn test_scheduled_voice_starts_at_its_frame() {
+        let mut engine = MixingEngine::new(44100);
+        engine.process_block(64);
+        assert_eq!(engine.frame_clock(), 64);
+
+        // Due 80 frames in: silent for the next block, then starts 16 frames
+        // into the one after
+        engine.add_voice_at(make_test_sample(1000, 0.5), 1.0, 0, true, 64 + 80);
+        engine.process_block(64);
+        assert!(engine.master_buffer().left[..64].iter().all(|&s| s == 0.0));
+
+        engine.process_block(64);
+        let master = &engine.master_buffer().left[..64];
+        assert!(master[..16].iter().all(|&s| s == 0.0));
+        assert!(master[16..].iter().all(|&s| s != 0.0));
+
+        // A frame that has already passed plays from the start of the block
+        engine.stop_all_voices();
+        engine.add_voice_at(make_test_sample(1000, 0.5), 1.0, 0, true, 0);
+        engine.process_block(64);
+        assert_ne!(engine.master_buffer().left[0], 0.0);
+    }
+
+    #[test]
+    fn test_clock_maps_instants_to_frames_ahead() {
+        let at = Instant::now();
+        let clock = AudioClock {
+            frame: 1000,
+            at: Some(at),
+        };
+        let ahead = (SCHEDULE_AHEAD.as_secs_f64() * 1000.0).round() as u64;
+
+        assert_eq!(clock.schedule_frame(at, 1000), Some(1000 + ahead));
+        assert_eq!(
+            clock.schedule_frame(at + Duration::from_millis(10), 1000),
+            Some(1010 + ahead)
+        );
+        assert_eq!(
+            clock.schedule_frame(at - Duration::from_millis(10), 1000),
+            Some(990 + ahead)
+        );
+        assert_eq!(
+            AudioClock { frame: 0, at: None }.schedule_frame(at, 1000),
+            None
+        );
+    }
+
     #[test]
     fn test_stop_all_voices() {
         let mut engine = MixingEngine::new(44100);
@@ -1723,4 +2744,79 @@ mod mixing_engine_tests {
         engine.add_preview_voice(sample, 0, true);
         assert_eq!(engine.voice_count(), 1);
     }
+
+    #[test]
+    fn test_sidechain_compressor_ducks_keyed_track() {
+        use crate::effects::compressor::CompressorEffect;
+
+        let mut engine = MixingEngine::new(44100);
+        // Generator 0 (kick) -> track 1, generator 1 (pad) -> track 2
+        engine.set_generator_track(0, 1);
+        engine.set_generator_track(1, 2);
+
+        let mut comp = CompressorEffect::new(44100.0);
+        comp.set_param(EffectParamId::CompressorSidechain, 1.0);
+        comp.set_param(EffectParamId::CompressorAttack, 0.1);
+        engine.set_effect(2, 0, Some(Box::new(comp)));
+
+        engine.add_voice(make_test_sample(4096, 0.05), 1.0, 1, false);
+        engine.process_block(512);
+        let undisturbed = engine.track_buffer(2).left[511];
+        assert!((undisturbed - 0.05).abs() < 1e-4);
+        assert_eq!(engine.gain_reduction()[2][0], 0.0);
+
+        // The kick on track 1 keys the compressor on track 2
+        engine.add_voice(make_test_sample(4096, 1.0), 1.0, 0, false);
+        engine.process_block(512);
+        assert!(engine.track_buffer(2).left[511] < undisturbed * 0.5);
+        assert!(engine.gain_reduction()[2][0] > 6.0);
+        // The key track itself is untouched
+        assert!((engine.track_buffer(1).left[511] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gate_state_reported_per_slot() {
+        use crate::effects::gate::{GateEffect, GateState};
+
+        let mut engine = MixingEngine::new(44100);
+        engine.set_generator_track(0, 1);
+        engine.set_effect(1, 2, Some(Box::new(GateEffect::new(44100.0))));
+
+        engine.process_block(512);
+        assert_eq!(engine.gate_states()[1][2], Some(GateState::Closed));
+        assert_eq!(engine.gate_states()[1][0], None);
+
+        engine.add_voice(make_test_sample(4096, 0.5), 1.0, 0, false);
+        engine.process_block(512);
+        assert_eq!(engine.gate_states()[1][2], Some(GateState::Open));
+    }
+    #[test]
+    fn test_latency_compensation_aligns_tracks() {
+        use crate::effects::distortion::DistortionEffect;
+        use crate::effects::oversampling::OversampleFactor;
+
+        // Track 1 runs through a fully dry, 4x oversampled distortion, which
+        // only delays; track 2 has no inserts and must be held back to match
+        let mut distortion = DistortionEffect::new(44100.0);
+        distortion.set_param(EffectParamId::DistortionMix, 0.0);
+        distortion.set_param(EffectParamId::DistortionOversampling, 2.0);
+        let latency = OversampleFactor::X4.latency();
+
+        let mut engine = MixingEngine::new(44100);
+        engine.set_generator_track(0, 1);
+        engine.set_generator_track(1, 2);
+        engine.set_effect(1, 0, Some(Box::new(distortion)));
+        assert_eq!(engine.output_latency(), latency);
+
+        engine.add_voice(make_test_sample(4096, 0.5), 1.0, 0, false);
+        engine.add_voice(make_test_sample(4096, 0.5), 1.0, 1, false);
+        engine.process_block(512);
+
+        assert_eq!(engine.track_latencies()[1], latency);
+        assert_eq!(engine.track_latencies()[2], 0);
+        let (delayed, held_back) = (engine.track_buffer(1), engine.track_buffer(2));
+        assert_eq!(&delayed.left[..512], &held_back.left[..512]);
+        assert!(held_back.left[..latency].iter().all(|&s| s == 0.0));
+        assert_eq!(held_back.left[latency], 0.5);
+    }
 }
@@ -1,28 +1,47 @@
-//! Offline audio rendering for WAV export
+//! Offline audio rendering for export
 //!
 //! Uses the same MixingEngine as real-time playback to ensure
 //! exported audio is identical to what is heard during playback.
 
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufWriter;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use hound::{SampleFormat, WavSpec, WavWriter};
 use rodio::{Decoder, Source};
 
-use super::{setup_engine, MixingEngine, SampleData};
+use super::export::{write_audio, ExportError, ExportSettings};
+use super::{
+    build_effect, build_mixer_state, setup_engine, AudioMixerState, MixingEngine, SampleData,
+};
 use crate::arrangement::Arrangement;
-use crate::mixer::{Mixer, TrackId};
+use crate::effects::Effect;
+use crate::mixer::{Mixer, TrackId, MASTER_TRACK, NUM_TRACKS};
+use crate::plugin_host::transport::PluginTransport;
 use crate::plugin_host::PluginLoader;
-use crate::sequencer::{Channel, ChannelSource, Pattern};
+use crate::sequencer::{Channel, ChannelSource, NoteExpressions, Pattern};
+
+/// Peak level below which the master counts as silent in the tail (-90 dBFS)
+const TAIL_SILENCE_THRESHOLD: f32 = 3.162e-5;
+
+/// How long the master must stay silent before the tail is considered over.
+/// Long enough to bridge the gap between echoes of a slow delay.
+const TAIL_SILENCE_HOLD_SECS: f32 = 2.0;
 
 /// Configuration for offline rendering
+#[derive(Debug, Clone)]
 pub struct RenderConfig {
     pub sample_rate: u32,
     pub bpm: f64,
     pub steps_per_bar: usize,
+    /// Longest tail to render after the last bar, in seconds (0 = stop at the last bar)
+    pub max_tail_secs: f32,
+    /// Send note-offs for plugin notes still held when the arrangement ends
+    pub release_notes: bool,
+    /// Render nothing if a plugin fails to load, instead of leaving it out
+    pub require_plugins: bool,
 }
 
 impl Default for RenderConfig {
@@ -31,10 +50,61 @@ impl Default for RenderConfig {
             sample_rate: 44100,
             bpm: 140.0,
             steps_per_bar: 16,
+            max_tail_secs: 10.0,
+            release_notes: true,
+            require_plugins: false,
         }
     }
 }
 
+/// Progress reporting and cancellation for a render running on another thread
+#[derive(Debug, Default)]
+pub struct RenderControl {
+    bars_done: AtomicUsize,
+    bars_total: AtomicUsize,
+    cancelled: AtomicBool,
+    failed_plugins: Mutex<Vec<(String, String)>>,
+}
+
+impl RenderControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bars rendered so far and total bars in the arrangement
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.bars_done.load(Ordering::Relaxed),
+            self.bars_total.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Ask the render to stop at the next block boundary
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Plugins the render couldn't load (path relative to the plugins
+    /// folder, with the error)
+    pub fn failed_plugins(&self) -> Vec<(String, String)> {
+        self.failed_plugins.lock().unwrap().clone()
+    }
+}
+
+/// A single mixer track rendered to its own audio buffer
+pub struct Stem {
+    /// Mixer track index the stem was taken from
+    pub track: usize,
+    /// Track name (from `MixerTrack::name`)
+    pub name: String,
+    /// Interleaved stereo f32 samples (L, R, L, R, ...)
+    pub samples: Vec<f32>,
+}
+
 /// Render arrangement to stereo audio samples
 ///
 /// Returns interleaved stereo f32 samples (L, R, L, R, ...)
@@ -48,11 +118,178 @@ pub fn render_offline(
     plugins_path: &Path,
     plugin_loader: &dyn PluginLoader,
     config: &RenderConfig,
+    control: &RenderControl,
 ) -> Vec<f32> {
+    let mut output = Vec::new();
+
+    let frames = render_blocks(
+        channels,
+        patterns,
+        arrangement,
+        mixer,
+        samples_path,
+        plugins_path,
+        plugin_loader,
+        config,
+        control,
+        |engine, frames| {
+            let master = engine.master_buffer();
+
+            // Append interleaved stereo to output
+            for i in frames {
+                output.push(master.left[i]);
+                output.push(master.right[i]);
+            }
+        },
+    );
+
+    output.truncate(frames * 2);
+    output
+}
+
+/// Render every mixer track the master hears to its own stem in a single pass
+///
+/// Stems are taken post-fader (track volume and pan applied) from the tracks
+/// the master sums, with the same mute and solo rules, so a muted track, or
+/// one silenced by another track's solo, gets no stem. Unless
+/// `pre_master_fx` is set, each stem is also run through its own copy of the
+/// master effect chain and master fader so the stems sum back to the mix.
+#[allow(clippy::too_many_arguments)]
+pub fn render_stems(
+    channels: &[Channel],
+    patterns: &[Pattern],
+    arrangement: &Arrangement,
+    mixer: &Mixer,
+    samples_path: &Path,
+    plugins_path: &Path,
+    plugin_loader: &dyn PluginLoader,
+    config: &RenderConfig,
+    pre_master_fx: bool,
+    control: &RenderControl,
+) -> Vec<Stem> {
+    let mixer_state = build_mixer_state(mixer);
+    let tracks = stem_tracks(channels, mixer, &mixer_state);
+    let (master_pan_left, master_pan_right) = mixer_state.pan_gains(MASTER_TRACK);
+    let master_gain = mixer_state.track_volumes[MASTER_TRACK];
+
+    let mut stems: Vec<Stem> = tracks
+        .iter()
+        .map(|&track| Stem {
+            track,
+            name: mixer.track(TrackId(track)).name.clone(),
+            samples: Vec::new(),
+        })
+        .collect();
+
+    // One master chain per stem so effect state doesn't bleed between stems
+    let mut master_chains: Vec<Vec<Box<dyn Effect>>> = tracks
+        .iter()
+        .map(|_| {
+            if pre_master_fx {
+                Vec::new()
+            } else {
+                master_effect_chain(mixer, plugins_path, plugin_loader, config)
+            }
+        })
+        .collect();
+
+    // Each stem's copy of the master chain delays it on top of the engine's
+    // own latency; drop that much from its start as well
+    let mut chain_skip: Vec<usize> = master_chains
+        .iter()
+        .map(|chain| chain.iter().map(|effect| effect.latency_frames()).sum())
+        .collect();
+
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+
+    let frames = render_blocks(
+        channels,
+        patterns,
+        arrangement,
+        mixer,
+        samples_path,
+        plugins_path,
+        plugin_loader,
+        config,
+        control,
+        |engine, frames| {
+            let stems = stems
+                .iter_mut()
+                .zip(&mut master_chains)
+                .zip(&mut chain_skip);
+            for ((stem, chain), skip) in stems {
+                let track = stem.track;
+                let buf = engine.track_buffer(track);
+                let gain = mixer_state.track_volumes[track];
+                let (pan_left, pan_right) = mixer_state.pan_gains(track);
+
+                left.clear();
+                right.clear();
+                left.extend(buf.left[frames.clone()].iter().map(|s| s * gain * pan_left));
+                right.extend(
+                    buf.right[frames.clone()]
+                        .iter()
+                        .map(|s| s * gain * pan_right),
+                );
+
+                if !pre_master_fx {
+                    for effect in chain.iter_mut() {
+                        effect.set_transport(engine.block_transport());
+                        effect.process(&mut left, &mut right);
+                    }
+                    for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                        *l *= master_gain * master_pan_left;
+                        *r *= master_gain * master_pan_right;
+                    }
+                }
+
+                let skipped = (*skip).min(left.len());
+                *skip -= skipped;
+                for (l, r) in left.iter().zip(right.iter()).skip(skipped) {
+                    stem.samples.push(*l);
+                    stem.samples.push(*r);
+                }
+            }
+        },
+    );
+
+    for stem in &mut stems {
+        stem.samples.resize(frames * 2, 0.0);
+    }
+    stems
+}
+
+/// Drive the mixing engine through the whole arrangement, then its tail.
+///
+/// `on_block` is called after every processed block with the engine and the
+/// range of frames in its buffers to keep, so callers can pull whichever
+/// buffers they need. The first [`MixingEngine::output_latency`] frames are
+/// withheld (and as many rendered past the end) so plugin and effect latency
+/// doesn't shift the export against the arrangement.
+///
+/// After the last bar, rendering continues until the master has been silent
+/// for [`TAIL_SILENCE_HOLD_SECS`] (but never shorter than the longest tail a
+/// plugin reports) or `config.max_tail_secs` is reached. Returns the number of
+/// frames worth keeping: the arrangement plus the audible part of the tail.
+#[allow(clippy::too_many_arguments)]
+fn render_blocks(
+    channels: &[Channel],
+    patterns: &[Pattern],
+    arrangement: &Arrangement,
+    mixer: &Mixer,
+    samples_path: &Path,
+    plugins_path: &Path,
+    plugin_loader: &dyn PluginLoader,
+    config: &RenderConfig,
+    control: &RenderControl,
+    mut on_block: impl FnMut(&MixingEngine, Range<usize>),
+) -> usize {
     let mut engine = MixingEngine::new(config.sample_rate);
 
-    // Use shared setup function (loads plugins, effects, sets mixer state)
-    setup_engine(
+    // Use shared setup function (loads plugins, effects, sets mixer state).
+    // The plugins' main-thread handles stay alive until the render is done.
+    let setup = setup_engine(
         &mut engine,
         channels,
         mixer,
@@ -61,29 +298,60 @@ pub fn render_offline(
         config.sample_rate,
         config.bpm,
     );
+    engine.set_offline();
+    let any_failed = !setup.failed_plugins.is_empty();
+    *control.failed_plugins.lock().unwrap() = setup.failed_plugins;
+    if any_failed && config.require_plugins {
+        return 0;
+    }
 
     // Load samples into cache (for sampler channels)
     let sample_cache = load_samples(channels, samples_path);
 
     // Calculate total length from arrangement
     if arrangement.placements.is_empty() {
-        return Vec::new();
+        return 0;
     }
     let total_bars = get_last_bar(arrangement);
     let total_steps = total_bars * config.steps_per_bar;
+    control.bars_total.store(total_bars, Ordering::Relaxed);
 
     // Calculate samples per step
     let samples_per_beat = (60.0 / config.bpm * config.sample_rate as f64) as usize;
     let beats_per_step = 4.0 / config.steps_per_bar as f64; // Assuming 4/4 time
     let samples_per_step = (samples_per_beat as f64 * beats_per_step) as usize;
 
-    let mut output = Vec::new();
     let block_size = 512;
+    let mut song_frames: usize = 0;
+
+    let latency = engine.output_latency();
+    let mut to_skip = latency;
+    let mut deliver = |engine: &MixingEngine, frames: usize| {
+        let skip = to_skip.min(frames);
+        to_skip -= skip;
+        if skip < frames {
+            on_block(engine, skip..frames);
+        }
+    };
 
     for step in 0..total_steps {
         let bar = step / config.steps_per_bar;
         let step_in_bar = step % config.steps_per_bar;
 
+        if control.is_cancelled() {
+            return song_frames.saturating_sub(latency);
+        }
+        control.bars_done.store(bar, Ordering::Relaxed);
+
+        // Line plugins' transport up with the step; it runs on through the
+        // step's blocks (and the tail) from here
+        engine.set_transport(PluginTransport {
+            playing: true,
+            tempo: config.bpm,
+            position_beats: step as f64 * beats_per_step,
+            loop_beats: None,
+        });
+
         // Trigger notes for this step
         trigger_step(
             &mut engine,
@@ -102,41 +370,158 @@ pub fn render_offline(
         while samples_remaining > 0 {
             let frames = samples_remaining.min(block_size);
             engine.process_block(frames);
-            let master = engine.master_buffer();
+            deliver(&engine, frames);
+            samples_remaining -= frames;
+            song_frames += frames;
+        }
+    }
+    control.bars_done.store(total_bars, Ordering::Relaxed);
 
-            // Append interleaved stereo to output
-            for i in 0..frames {
-                output.push(master.left[i].clamp(-1.0, 1.0));
-                output.push(master.right[i].clamp(-1.0, 1.0));
+    if config.release_notes {
+        engine.release_plugin_notes();
+    }
+
+    let sample_rate = config.sample_rate as f32;
+    // The tail is measured from the end of the delayed song, which is still
+    // `latency` frames out when the last step has been rendered
+    let max_tail = (config.max_tail_secs.max(0.0) * sample_rate) as usize + latency;
+    let min_tail = (engine.plugin_tail_frames() as usize).min(max_tail - latency) + latency;
+    let hold = (TAIL_SILENCE_HOLD_SECS * sample_rate) as usize;
+
+    let mut keep_frames = song_frames + latency;
+    let mut tail = 0;
+    let mut silent_run = 0;
+    while tail < max_tail && !control.is_cancelled() {
+        let frames = block_size.min(max_tail - tail);
+        engine.process_block(frames);
+        deliver(&engine, frames);
+
+        let master = engine.master_buffer();
+        let last_audible = (0..frames).rev().find(|&i| {
+            master.left[i].abs() > TAIL_SILENCE_THRESHOLD
+                || master.right[i].abs() > TAIL_SILENCE_THRESHOLD
+        });
+        match last_audible {
+            Some(i) => {
+                keep_frames = keep_frames.max(song_frames + tail + i + 1);
+                silent_run = frames - i - 1;
             }
-            samples_remaining -= frames;
+            None => silent_run += frames,
+        }
+
+        tail += frames;
+        if tail >= min_tail && silent_run >= hold {
+            break;
         }
     }
 
-    output
+    keep_frames.min(song_frames + tail).saturating_sub(latency)
 }
 
-/// Write rendered audio to WAV file
-pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<(), hound::Error> {
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate,
-        bits_per_sample: 16,
-        sample_format: SampleFormat::Int,
-    };
+/// Tracks the master sums that can carry sound, in order: every audible
+/// (not muted or soloed away) track with a channel routed to it or an active
+/// insert effect, which may make sound of its own
+fn stem_tracks(channels: &[Channel], mixer: &Mixer, mixer_state: &AudioMixerState) -> Vec<usize> {
+    (0..NUM_TRACKS)
+        .filter(|&track| track != MASTER_TRACK && !mixer_state.track_mutes[track])
+        .filter(|&track| {
+            let effects = &mixer.track(TrackId(track)).effects;
+            channels.iter().any(|c| c.mixer_track == track)
+                || effects.iter().flatten().any(|slot| !slot.bypassed)
+        })
+        .collect()
+}
+
+/// Build a fresh instance of the master track's (non-bypassed) effect chain
+fn master_effect_chain(
+    mixer: &Mixer,
+    plugins_path: &Path,
+    plugin_loader: &dyn PluginLoader,
+    config: &RenderConfig,
+) -> Vec<Box<dyn Effect>> {
+    mixer
+        .track(TrackId::MASTER)
+        .effects
+        .iter()
+        .flatten()
+        .filter(|slot| !slot.bypassed)
+        .map(|slot| {
+            let (effect, _instance) = build_effect(
+                slot,
+                plugins_path,
+                plugin_loader,
+                config.sample_rate,
+                config.bpm,
+            );
+            effect
+        })
+        .collect()
+}
 
-    let file = File::create(path)?;
-    let writer = BufWriter::new(file);
-    let mut wav_writer = WavWriter::new(writer, spec)?;
+/// Write stems as individual files into `dir` (created if missing)
+///
+/// Returns the paths of the written files.
+pub fn write_stems(
+    dir: &Path,
+    stems: &[Stem],
+    settings: &ExportSettings,
+) -> Result<Vec<PathBuf>, ExportError> {
+    std::fs::create_dir_all(dir)?;
 
-    for &sample in samples {
-        // Convert f32 (-1.0 to 1.0) to i16
-        let sample_i16 = (sample * i16::MAX as f32) as i16;
-        wav_writer.write_sample(sample_i16)?;
+    let mut paths = Vec::with_capacity(stems.len());
+    for stem in stems {
+        let path = dir.join(stem_file_name(stem, settings.format.extension()));
+        write_audio(&path, &stem.samples, settings)?;
+        paths.push(path);
     }
+    Ok(paths)
+}
+
+/// File name for a stem: track number plus the track name made filesystem-safe
+///
+/// The track number keeps names unique when several tracks share a name.
+pub fn stem_file_name(stem: &Stem, extension: &str) -> String {
+    let name: String = stem
+        .name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
 
-    wav_writer.finalize()?;
-    Ok(())
+    if name.is_empty() {
+        format!("{:02}.{}", stem.track, extension)
+    } else {
+        format!("{:02} {}.{}", stem.track, name, extension)
+    }
+}
+
+/// Check that every sample the channels use can be loaded, returning the
+/// paths (relative to the samples folder) of those that can't
+///
+/// A render silently skips samples it can't load, so headless renders call
+/// this first to fail loudly instead of writing a file with parts missing.
+/// Plugins are checked by the render itself (see [`RenderConfig::require_plugins`]).
+pub fn find_missing_samples(channels: &[Channel], samples_path: &Path) -> Vec<String> {
+    let mut missing: Vec<String> = Vec::new();
+
+    for channel in channels {
+        if let ChannelSource::Sampler { path: Some(path) } = &channel.source {
+            if missing.contains(path) {
+                continue;
+            }
+            if load_sample(&samples_path.join(path)).is_none() {
+                missing.push(path.clone());
+            }
+        }
+    }
+
+    missing
 }
 
 fn get_last_bar(arrangement: &Arrangement) -> usize {
@@ -257,11 +642,23 @@ fn trigger_pattern_step(
                 if let Some(slice) = slice {
                     for note in &slice.notes {
                         if note.start_step == step {
-                            engine.send_plugin_note(channel_idx, note.pitch, note.velocity, true);
+                            engine.send_plugin_note(
+                                channel_idx,
+                                note.pitch,
+                                note.velocity,
+                                true,
+                                note.expressions,
+                            );
                         }
                         // Check for note-off events
                         if note.start_step + note.duration == step {
-                            engine.send_plugin_note(channel_idx, note.pitch, 0.0, false);
+                            engine.send_plugin_note(
+                                channel_idx,
+                                note.pitch,
+                                0.0,
+                                false,
+                                NoteExpressions::default(),
+                            );
                         }
                     }
                 }
@@ -275,6 +672,7 @@ mod tests {
     use super::*;
     use crate::arrangement::PatternPlacement;
     use crate::plugin_host::MockPluginLoader;
+    use hound::{SampleFormat, WavSpec, WavWriter};
 
     #[test]
     fn test_render_empty_arrangement() {
@@ -296,6 +694,7 @@ mod tests {
             plugins_path,
             &plugin_loader,
             &config,
+            &RenderControl::new(),
         );
 
         // Empty arrangement should produce no audio
@@ -329,6 +728,7 @@ mod tests {
             sample_rate: 44100,
             bpm: 120.0, // 0.5 sec per beat, 2 sec per bar (4 beats)
             steps_per_bar: 16,
+            ..Default::default()
         };
 
         let samples = render_offline(
@@ -340,6 +740,7 @@ mod tests {
             plugins_path,
             &plugin_loader,
             &config,
+            &RenderControl::new(),
         );
 
         // At 120 BPM: 1 beat = 0.5 sec, 1 bar = 4 beats = 2 sec
@@ -386,6 +787,7 @@ mod tests {
             sample_rate: 44100,
             bpm: 120.0,
             steps_per_bar: 16,
+            ..Default::default()
         };
 
         let samples = render_offline(
@@ -397,6 +799,7 @@ mod tests {
             plugins_path,
             &plugin_loader,
             &config,
+            &RenderControl::new(),
         );
 
         // 4 bars at 120 BPM = 8 sec
@@ -408,4 +811,395 @@ mod tests {
             samples.len()
         );
     }
+
+    /// Write a short mono WAV of constant value for sampler tests
+    fn write_test_sample(path: &Path, value: f32) {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(path, spec).unwrap();
+        for _ in 0..4410 {
+            writer
+                .write_sample((value * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_stem_tracks_follow_the_master_mix() {
+        use crate::effects::{EffectSlot, EffectType};
+
+        let channels = vec![
+            Channel::new_at_slot("a", 0, 3),
+            Channel::new_at_slot("b", 1, 1),
+            Channel::new_at_slot("c", 2, 3),
+            Channel::new_at_slot("d", 3, 0),
+            Channel::new_at_slot("e", 4, 6),
+        ];
+        let mut mixer = Mixer::new();
+        mixer.track_mut(TrackId(6)).muted = true;
+        mixer.track_mut(TrackId(8)).effects[0] = Some(EffectSlot::new(EffectType::Reverb));
+        let mut bypassed = EffectSlot::new(EffectType::Delay);
+        bypassed.bypassed = true;
+        mixer.track_mut(TrackId(9)).effects[0] = Some(bypassed);
+        let tracks = |mixer: &Mixer| stem_tracks(&channels, mixer, &build_mixer_state(mixer));
+        assert_eq!(tracks(&mixer), vec![1, 3, 8]);
+
+        mixer.toggle_solo(TrackId(3));
+        assert_eq!(tracks(&mixer), vec![3]);
+    }
+
+    #[test]
+    fn test_stem_file_name_sanitizes_track_name() {
+        let stem = Stem {
+            track: 4,
+            name: "Bass/Sub: 1".to_string(),
+            samples: Vec::new(),
+        };
+        assert_eq!(stem_file_name(&stem, "wav"), "04 Bass_Sub_ 1.wav");
+
+        let unnamed = Stem {
+            track: 12,
+            name: "  ".to_string(),
+            samples: Vec::new(),
+        };
+        assert_eq!(stem_file_name(&unnamed, "flac"), "12.flac");
+    }
+
+    #[test]
+    fn test_render_stems_routes_audio_to_its_track() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_test_sample(&dir.path().join("kick.wav"), 0.5);
+
+        let mut kick = Channel::with_sample_at_slot("kick", "kick.wav", 0, 2);
+        kick.get_or_create_pattern(0, 16).set_step(0, true);
+        let hat = Channel::with_sample_at_slot("hat", "missing.wav", 1, 5);
+        let channels = vec![kick, hat];
+
+        let patterns = vec![Pattern {
+            id: 0,
+            name: "Test".to_string(),
+            length: 16,
+        }];
+        let mut arrangement = Arrangement::new();
+        arrangement.placements.push(PatternPlacement {
+            id: "p1".to_string(),
+            pattern_id: 0,
+            start_bar: 0,
+            length: 1,
+        });
+
+        let mut mixer = Mixer::new();
+        mixer.track_mut(TrackId(2)).name = "Kick".to_string();
+
+        let config = RenderConfig {
+            sample_rate: 44100,
+            bpm: 120.0,
+            steps_per_bar: 16,
+            ..Default::default()
+        };
+        let plugin_loader = MockPluginLoader::new();
+
+        let stems = render_stems(
+            &channels,
+            &patterns,
+            &arrangement,
+            &mixer,
+            dir.path(),
+            Path::new("/tmp"),
+            &plugin_loader,
+            &config,
+            true,
+            &RenderControl::new(),
+        );
+
+        assert_eq!(stems.len(), 2);
+        assert_eq!(stems[0].track, 2);
+        assert_eq!(stems[0].name, "Kick");
+        assert_eq!(stems[1].track, 5);
+
+        // Stems cover the same length as the full mix
+        let mix = render_offline(
+            &channels,
+            &patterns,
+            &arrangement,
+            &mixer,
+            dir.path(),
+            Path::new("/tmp"),
+            &plugin_loader,
+            &config,
+            &RenderControl::new(),
+        );
+        assert_eq!(stems[0].samples.len(), mix.len());
+        assert_eq!(stems[1].samples.len(), mix.len());
+
+        let peak = |s: &[f32]| s.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        assert!(
+            peak(&stems[0].samples) > 0.01,
+            "kick stem should have audio"
+        );
+        assert!(peak(&stems[1].samples) < 1e-6, "hat stem should be silent");
+    }
+
+    #[test]
+    fn test_render_stems_apply_solo_like_the_mix() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_test_sample(&dir.path().join("kick.wav"), 0.5);
+
+        let mut kick = Channel::with_sample_at_slot("kick", "kick.wav", 0, 2);
+        kick.get_or_create_pattern(0, 16).set_step(0, true);
+        let mut snare = Channel::with_sample_at_slot("snare", "kick.wav", 1, 4);
+        snare.get_or_create_pattern(0, 16).set_step(4, true);
+        let channels = vec![kick, snare];
+
+        let patterns = vec![Pattern {
+            id: 0,
+            name: "Test".to_string(),
+            length: 16,
+        }];
+        let mut arrangement = Arrangement::new();
+        arrangement.placements.push(PatternPlacement {
+            id: "p1".to_string(),
+            pattern_id: 0,
+            start_bar: 0,
+            length: 1,
+        });
+
+        let mut mixer = Mixer::new();
+        mixer.toggle_solo(TrackId(4));
+
+        let config = RenderConfig {
+            bpm: 120.0,
+            ..Default::default()
+        };
+        let plugin_loader = MockPluginLoader::new();
+
+        let stems = render_stems(
+            &channels,
+            &patterns,
+            &arrangement,
+            &mixer,
+            dir.path(),
+            Path::new("/tmp"),
+            &plugin_loader,
+            &config,
+            false,
+            &RenderControl::new(),
+        );
+        assert_eq!(stems.len(), 1, "the soloed track is the only one heard");
+        assert_eq!(stems[0].track, 4);
+
+        // The stem is the whole mix
+        let mix = render_offline(
+            &channels,
+            &patterns,
+            &arrangement,
+            &mixer,
+            dir.path(),
+            Path::new("/tmp"),
+            &plugin_loader,
+            &config,
+            &RenderControl::new(),
+        );
+        assert_eq!(stems[0].samples.len(), mix.len());
+        for (stem, mix) in stems[0].samples.iter().zip(&mix) {
+            assert!((stem - mix).abs() < 1e-5);
+        }
+        assert!(mix.iter().any(|s| s.abs() > 0.01));
+    }
+
+    /// One bar with a single kick hit on `step`, routed to the master track
+    fn single_hit_project(dir: &Path, step: usize) -> (Vec<Channel>, Vec<Pattern>, Arrangement) {
+        write_test_sample(&dir.join("kick.wav"), 0.5);
+
+        let mut kick = Channel::with_sample_at_slot("kick", "kick.wav", 0, 1);
+        kick.get_or_create_pattern(0, 16).set_step(step, true);
+
+        let patterns = vec![Pattern {
+            id: 0,
+            name: "Test".to_string(),
+            length: 16,
+        }];
+        let mut arrangement = Arrangement::new();
+        arrangement.placements.push(PatternPlacement {
+            id: "p1".to_string(),
+            pattern_id: 0,
+            start_bar: 0,
+            length: 1,
+        });
+        (vec![kick], patterns, arrangement)
+    }
+
+    #[test]
+    fn test_render_tail_keeps_reverb_decay() {
+        use crate::effects::{EffectSlot, EffectType};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (channels, patterns, arrangement) = single_hit_project(dir.path(), 15);
+        let mut mixer = Mixer::new();
+        mixer.track_mut(TrackId(1)).effects[0] = Some(EffectSlot::new(EffectType::Reverb));
+        let plugin_loader = MockPluginLoader::new();
+
+        let render = |max_tail_secs: f32| {
+            let config = RenderConfig {
+                bpm: 120.0,
+                max_tail_secs,
+                ..Default::default()
+            };
+            render_offline(
+                &channels,
+                &patterns,
+                &arrangement,
+                &mixer,
+                dir.path(),
+                Path::new("/tmp"),
+                &plugin_loader,
+                &config,
+                &RenderControl::new(),
+            )
+        };
+
+        // 16 steps of 5512 frames at 120 BPM
+        let bar_frames = 16 * 5512;
+        assert_eq!(render(0.0).len(), bar_frames * 2);
+
+        let with_tail = render(5.0);
+        assert!(
+            with_tail.len() > bar_frames * 2,
+            "reverb tail should extend past the last bar"
+        );
+        assert!(with_tail.len() <= (bar_frames + 5 * 44100) * 2);
+    }
+
+    #[test]
+    fn test_render_tail_trims_trailing_silence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (channels, patterns, arrangement) = single_hit_project(dir.path(), 0);
+        let config = RenderConfig {
+            bpm: 120.0,
+            max_tail_secs: 10.0,
+            ..Default::default()
+        };
+
+        let samples = render_offline(
+            &channels,
+            &patterns,
+            &arrangement,
+            &Mixer::new(),
+            dir.path(),
+            Path::new("/tmp"),
+            &MockPluginLoader::new(),
+            &config,
+            &RenderControl::new(),
+        );
+
+        // The hit dies out inside the bar, so nothing is added after it
+        assert_eq!(samples.len(), 16 * 5512 * 2);
+    }
+
+    #[test]
+    fn test_find_missing_samples() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_test_sample(&dir.path().join("kick.wav"), 0.5);
+
+        let channels = vec![
+            Channel::with_sample_at_slot("kick", "kick.wav", 0, 1),
+            Channel::with_sample_at_slot("snare", "snare.wav", 1, 2),
+            Channel::with_sample_at_slot("snare 2", "snare.wav", 2, 3),
+            Channel::with_plugin_at_slot("synth", "synth.clap", 3, 4),
+        ];
+
+        let missing = find_missing_samples(&channels, dir.path());
+
+        assert_eq!(missing, vec!["snare.wav".to_string()]);
+    }
+
+    #[test]
+    fn test_render_records_failed_plugins() {
+        use crate::effects::EffectSlot;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (mut channels, patterns, arrangement) = single_hit_project(dir.path(), 0);
+        std::fs::write(dir.path().join("verb.clap"), b"").unwrap();
+        channels.push(Channel::with_plugin_at_slot("gone", "gone.clap", 1, 2));
+
+        let mut mixer = Mixer::new();
+        mixer.track_mut(TrackId(1)).effects[0] = Some(EffectSlot::plugin("verb.clap"));
+        // Used twice, listed once
+        mixer.track_mut(TrackId(5)).effects[0] = Some(EffectSlot::plugin("gone.clap"));
+
+        let render = |require_plugins| {
+            let config = RenderConfig {
+                bpm: 120.0,
+                require_plugins,
+                ..Default::default()
+            };
+            let control = RenderControl::new();
+            let samples = render_offline(
+                &channels,
+                &patterns,
+                &arrangement,
+                &mixer,
+                dir.path(),
+                dir.path(),
+                &MockPluginLoader::new(),
+                &config,
+                &control,
+            );
+            (samples, control.failed_plugins())
+        };
+
+        let (samples, failed) = render(false);
+        assert!(!samples.is_empty());
+        assert_eq!(failed.len(), 2);
+        assert_eq!(
+            failed[0],
+            ("gone.clap".to_string(), "file not found".to_string())
+        );
+        // The mock loader can't instantiate anything, so an existing file still fails
+        assert_eq!(failed[1].0, "verb.clap");
+
+        let (samples, failed) = render(true);
+        assert!(samples.is_empty());
+        assert_eq!(failed.len(), 2);
+    }
+
+    #[test]
+    fn test_unloadable_effect_plugin_passes_track_through() {
+        use crate::effects::EffectSlot;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (channels, patterns, arrangement) = single_hit_project(dir.path(), 0);
+        let plugin_loader = MockPluginLoader::new();
+        let config = RenderConfig {
+            bpm: 120.0,
+            ..Default::default()
+        };
+        let render = |mixer: &Mixer| {
+            render_offline(
+                &channels,
+                &patterns,
+                &arrangement,
+                mixer,
+                dir.path(),
+                dir.path(),
+                &plugin_loader,
+                &config,
+                &RenderControl::new(),
+            )
+        };
+
+        let dry = render(&Mixer::new());
+        let mut mixer = Mixer::new();
+        mixer.track_mut(TrackId(1)).effects[0] = Some(EffectSlot::plugin("gone.clap"));
+        mixer.track_mut(TrackId::MASTER).effects[0] = Some(EffectSlot::plugin("gone.clap"));
+        let wet = render(&mixer);
+
+        assert!(dry.iter().any(|s| s.abs() > 0.01));
+        assert_eq!(dry, wet);
+    }
 }
@@ -5,7 +5,7 @@
 //! call `mark_mixer_dirty()` and let `flush()` batch updates per frame.
 
 use crate::audio::{AudioHandle, AudioMixerState};
-use crate::effects::{EffectParamId, EffectType};
+use crate::effects::{EffectParamId, EffectSlot, EffectType};
 use crate::mixer::{Mixer, NUM_TRACKS};
 
 /// Dirty flags for audio sync batching
@@ -55,6 +55,8 @@ pub enum EffectChange {
         slot: usize,
         enabled: bool,
     },
+    /// Rebuild a convolution effect's kernel from the slot in the mixer
+    SetConvolutionKernel { track: usize, slot: usize },
 }
 
 /// Pending routing change
@@ -75,6 +77,10 @@ pub struct AudioSync {
     routing_changes: Vec<RoutingChange>,
     /// Pending effect changes
     effect_changes: Vec<EffectChange>,
+    /// Plugin slots to load once their effect changes are flushed
+    plugin_loads: Vec<(usize, usize)>,
+    /// Note routes last sent to the audio thread, by channel index
+    note_routes: Vec<Option<usize>>,
 }
 
 impl AudioSync {
@@ -136,6 +142,56 @@ impl AudioSync {
         });
     }
 
+    /// Queue everything needed to make the audio thread's slot match `effect`:
+    /// the effect itself, its parameters, impulse response and bypass state.
+    ///
+    /// Plugin slots can't be built here; they are returned by
+    /// [`take_plugin_loads`](Self::take_plugin_loads) after the next flush.
+    pub fn queue_effect_slot_state(
+        &mut self,
+        track: usize,
+        slot: usize,
+        effect: Option<&EffectSlot>,
+    ) {
+        self.queue_effect_slot(track, slot, effect.map(|e| e.effect_type));
+        let Some(effect) = effect else {
+            return;
+        };
+        for (&param_id, &value) in &effect.params {
+            self.queue_effect_param(track, slot, param_id, value);
+        }
+        if effect.impulse_response.is_some() {
+            self.effect_changes
+                .push(EffectChange::SetConvolutionKernel { track, slot });
+        }
+        if effect.effect_type == EffectType::Plugin && !self.plugin_loads.contains(&(track, slot)) {
+            self.plugin_loads.push((track, slot));
+        }
+        self.queue_effect_enabled(track, slot, !effect.bypassed);
+    }
+
+    /// Plugin slots queued by `queue_effect_slot_state`
+    ///
+    /// Call after [`flush`](Self::flush) so the loaded plugin replaces the
+    /// placeholder the flushed slot change installs.
+    pub fn take_plugin_loads(&mut self) -> Vec<(usize, usize)> {
+        std::mem::take(&mut self.plugin_loads)
+    }
+
+    /// Send the note routes that differ from the ones last sent
+    ///
+    /// Routes are addressed by channel index, which shifts when channels are
+    /// added or removed, so the app hands over the full set every frame.
+    pub fn sync_note_routes(&mut self, audio: &AudioHandle, routes: Vec<Option<usize>>) {
+        for channel in 0..routes.len().max(self.note_routes.len()) {
+            let route = routes.get(channel).copied().flatten();
+            if self.note_routes.get(channel).copied().flatten() != route {
+                audio.set_note_route(channel, route);
+            }
+        }
+        self.note_routes = routes;
+    }
+
     /// Flush all pending changes to the audio thread.
     /// Call this once per frame in App::tick().
     pub fn flush(&mut self, audio: &AudioHandle, mixer: &Mixer) {
@@ -182,6 +238,11 @@ impl AudioSync {
                     } => {
                         audio.set_effect_enabled(track, slot, enabled);
                     }
+                    EffectChange::SetConvolutionKernel { track, slot } => {
+                        if let Some(effect) = &mixer.tracks[track].effects[slot] {
+                            audio.set_effect_convolution_kernel(track, slot, effect);
+                        }
+                    }
                 }
             }
         }
@@ -214,6 +275,7 @@ impl AudioSync {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::AudioCommand;
 
     #[test]
     fn test_dirty_flags_default() {
@@ -275,4 +337,103 @@ mod tests {
         assert!(sync.dirty.effects);
         assert_eq!(sync.effect_changes.len(), 1);
     }
+
+    #[test]
+    fn test_queue_effect_slot_state() {
+        let mut sync = AudioSync::new();
+        let mut effect = EffectSlot::new(EffectType::Delay);
+        effect.bypassed = true;
+        sync.queue_effect_slot_state(2, 3, Some(&effect));
+
+        // Slot, every param, then bypass
+        assert_eq!(sync.effect_changes.len(), effect.params.len() + 2);
+        assert!(matches!(
+            sync.effect_changes[0],
+            EffectChange::SetSlot {
+                effect_type: Some(EffectType::Delay),
+                ..
+            }
+        ));
+        assert!(matches!(
+            sync.effect_changes.last(),
+            Some(EffectChange::SetEnabled { enabled: false, .. })
+        ));
+        assert!(sync.take_plugin_loads().is_empty());
+
+        sync.queue_effect_slot_state(2, 3, None);
+        assert!(matches!(
+            sync.effect_changes.last(),
+            Some(EffectChange::SetSlot {
+                effect_type: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_plugin_slot_state_queues_one_load() {
+        let mut sync = AudioSync::new();
+        let effect = EffectSlot::plugin("fx/comp.clap");
+        sync.queue_effect_slot_state(1, 0, Some(&effect));
+        sync.queue_effect_slot_state(1, 0, Some(&effect));
+        assert_eq!(sync.take_plugin_loads(), vec![(1, 0)]);
+        assert!(sync.take_plugin_loads().is_empty());
+    }
+
+    #[test]
+    fn test_flush_sends_built_convolution_kernel() {
+        use crate::effects::convolution::ImpulseResponse;
+        use crate::mixer::TrackId;
+        use std::sync::Arc;
+
+        let (audio, rx) = AudioHandle::testable();
+        let mut mixer = Mixer::new();
+        let mut effect = EffectSlot::new(EffectType::Convolution);
+        effect.impulse_response = Some(Arc::new(ImpulseResponse {
+            sample_rate: 44100,
+            channels: vec![vec![1.0, 0.5]],
+        }));
+        mixer.track_mut(TrackId(2)).effects[3] = Some(effect);
+
+        let mut sync = AudioSync::new();
+        sync.queue_effect_slot_state(2, 3, mixer.track(TrackId(2)).effects[3].as_ref());
+        sync.flush(&audio, &mixer);
+
+        let kernels: Vec<_> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                AudioCommand::SetEffectConvolutionKernel {
+                    track,
+                    slot,
+                    kernel,
+                } => Some((track, slot, kernel.is_some())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(kernels, vec![(2, 3, true)]);
+    }
+
+    #[test]
+    fn test_sync_note_routes_sends_only_changes() {
+        let (audio, rx) = AudioHandle::testable();
+        let routes =
+            |rx: &crossbeam_channel::Receiver<AudioCommand>| -> Vec<(usize, Option<usize>)> {
+                rx.try_iter()
+                    .filter_map(|cmd| match cmd {
+                        AudioCommand::SetNoteRoute { channel, target } => Some((channel, target)),
+                        _ => None,
+                    })
+                    .collect()
+            };
+        let mut sync = AudioSync::new();
+
+        sync.sync_note_routes(&audio, vec![None, Some(0), None]);
+        assert_eq!(routes(&rx), vec![(1, Some(0))]);
+        sync.sync_note_routes(&audio, vec![None, Some(0), None]);
+        assert!(routes(&rx).is_empty());
+
+        // A deleted channel shifts the indices; stale routes are cleared
+        sync.sync_note_routes(&audio, vec![Some(1)]);
+        assert_eq!(routes(&rx), vec![(0, Some(1)), (1, None)]);
+    }
 }
@@ -6,12 +6,15 @@
 //! - Sample preview with Space
 //! - Selection mode for assigning samples/plugins to channels
 //! - Toggle between Samples and Plugins mode with Tab
+//! - Plugins listed by category, by vendor, or as the project's plugin files
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use walkdir::WalkDir;
 
+use crate::plugin_host::{scan_plugin_directories, PluginCategory, ScannedBundle};
+
 /// Browser mode - what type of files to browse
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum BrowserMode {
@@ -41,13 +44,70 @@ impl BrowserMode {
     }
 }
 
+/// How plugins are listed in Plugins mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PluginGrouping {
+    /// Every scanned plugin under its category (instrument, effect, ...)
+    #[default]
+    Category,
+    /// Every scanned plugin under its vendor
+    Vendor,
+    /// The project's plugins folder as a file tree
+    Files,
+}
+
+impl PluginGrouping {
+    /// Cycle to the next grouping
+    pub fn next(self) -> Self {
+        match self {
+            PluginGrouping::Category => PluginGrouping::Vendor,
+            PluginGrouping::Vendor => PluginGrouping::Files,
+            PluginGrouping::Files => PluginGrouping::Category,
+        }
+    }
+
+    /// Get the display name
+    pub fn name(self) -> &'static str {
+        match self {
+            PluginGrouping::Category => "Category",
+            PluginGrouping::Vendor => "Vendor",
+            PluginGrouping::Files => "Files",
+        }
+    }
+}
+
+/// A plugin listed from the plugin scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginChoice {
+    /// Bundle the plugin lives in
+    pub bundle: PathBuf,
+    /// Plugin id, only for bundles that offer more than one plugin
+    pub id: Option<String>,
+}
+
+/// What a file picked in selection mode is assigned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionTarget {
+    /// A channel rack slot (sample or plugin)
+    Channel(usize),
+    /// A convolution reverb's impulse response
+    ImpulseResponse { track: usize, slot: usize },
+    /// A mixer insert slot hosting a CLAP effect
+    EffectPlugin { track: usize, slot: usize },
+}
+
 /// A file or directory entry in the browser
+///
+/// Group headers and plugins listed from the scan have made-up paths under
+/// the root, so they expand and collapse like folders and files.
 #[derive(Debug, Clone)]
 pub struct BrowserEntry {
     pub path: PathBuf,
     pub name: String,
     pub is_dir: bool,
     pub depth: usize,
+    /// The plugin this entry picks, when it isn't simply the file at `path`
+    pub plugin: Option<PluginChoice>,
 }
 
 /// Browser state
@@ -69,8 +129,12 @@ pub struct BrowserState {
     pub expanded: HashSet<PathBuf>,
     /// Whether we're in selection mode
     pub selection_mode: bool,
-    /// Which channel we're selecting for
-    pub target_channel: Option<usize>,
+    /// What we're selecting for
+    pub target: Option<SelectionTarget>,
+    /// How plugins are listed in Plugins mode
+    pub plugin_grouping: PluginGrouping,
+    /// Installed plugins, scanned the first time Plugins mode is shown
+    plugin_catalog: Option<Vec<ScannedBundle>>,
 }
 
 #[allow(dead_code)]
@@ -92,7 +156,9 @@ impl BrowserState {
             cursor: 0,
             expanded: HashSet::new(),
             selection_mode: false,
-            target_channel: None,
+            target: None,
+            plugin_grouping: PluginGrouping::default(),
+            plugin_catalog: None,
         };
         state.scan_directory();
         state.update_visible_entries();
@@ -109,6 +175,14 @@ impl BrowserState {
 
     /// Toggle between samples and plugins mode
     pub fn toggle_mode(&mut self) {
+        // Impulse responses only come from the samples folder, effect
+        // plugins only from the plugins folder
+        if matches!(
+            self.target,
+            Some(SelectionTarget::ImpulseResponse { .. } | SelectionTarget::EffectPlugin { .. })
+        ) {
+            return;
+        }
         self.mode.toggle();
         self.cursor = 0;
         self.expanded.clear();
@@ -116,10 +190,43 @@ impl BrowserState {
         self.update_visible_entries();
     }
 
+    /// Cycle how plugins are listed (Plugins mode only)
+    pub fn cycle_plugin_grouping(&mut self) {
+        if self.mode != BrowserMode::Plugins {
+            return;
+        }
+        self.plugin_grouping = self.plugin_grouping.next();
+        self.cursor = 0;
+        self.expanded.clear();
+        self.scan_directory();
+        self.update_visible_entries();
+    }
+
+    /// Use `catalog` as the installed plugins instead of scanning for them
+    pub fn set_plugin_catalog(&mut self, catalog: Vec<ScannedBundle>) {
+        self.plugin_catalog = Some(catalog);
+        if self.mode == BrowserMode::Plugins {
+            self.refresh();
+        }
+    }
+
+    /// Installed plugins, scanning the plugin directories and the project's
+    /// plugins folder on first use
+    fn plugin_catalog(&mut self) -> &[ScannedBundle] {
+        let plugins_path = self.plugins_path.clone();
+        self.plugin_catalog
+            .get_or_insert_with(|| scan_plugin_directories(&[plugins_path]))
+    }
+
     /// Scan the directory for files based on current mode
     pub fn scan_directory(&mut self) {
         self.all_entries.clear();
 
+        if self.mode == BrowserMode::Plugins && self.plugin_grouping != PluginGrouping::Files {
+            self.group_plugins();
+            return;
+        }
+
         let root = self.root_path().to_path_buf();
         if !root.exists() {
             return;
@@ -176,11 +283,94 @@ impl BrowserState {
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_default();
 
+                // A bundle offering several plugins opens like a folder
+                // listing them
+                let choices: Vec<_> = if self.mode == BrowserMode::Plugins && !is_dir {
+                    self.plugin_catalog()
+                        .iter()
+                        .find(|b| b.path == path && b.plugins.len() > 1)
+                        .map(|b| {
+                            b.plugins
+                                .iter()
+                                .map(|p| (p.id.clone(), p.name.clone()))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
+
                 self.all_entries.push(BrowserEntry {
-                    path,
+                    path: path.clone(),
                     name,
-                    is_dir,
+                    is_dir: is_dir || !choices.is_empty(),
                     depth,
+                    plugin: None,
+                });
+                for (id, name) in choices {
+                    self.all_entries.push(BrowserEntry {
+                        path: path.join(&id),
+                        name,
+                        is_dir: false,
+                        depth: depth + 1,
+                        plugin: Some(PluginChoice {
+                            bundle: path.clone(),
+                            id: Some(id),
+                        }),
+                    });
+                }
+            }
+        }
+    }
+
+    /// List every scanned plugin under a header for its category or vendor
+    fn group_plugins(&mut self) {
+        let root = self.plugins_path.clone();
+        let grouping = self.plugin_grouping;
+
+        // Categories keep their own order, vendors sort by name
+        let mut groups: BTreeMap<(Option<PluginCategory>, String), Vec<_>> = BTreeMap::new();
+        for bundle in self.plugin_catalog() {
+            let multi = bundle.plugins.len() > 1;
+            for plugin in &bundle.plugins {
+                let key = match grouping {
+                    PluginGrouping::Category => {
+                        let category = plugin.category();
+                        (Some(category), category.name().to_string())
+                    }
+                    _ if plugin.vendor.is_empty() => (None, "Unknown".to_string()),
+                    _ => (None, plugin.vendor.clone()),
+                };
+                let name = if plugin.name.is_empty() {
+                    plugin.id.clone()
+                } else {
+                    plugin.name.clone()
+                };
+                let choice = PluginChoice {
+                    bundle: bundle.path.clone(),
+                    id: multi.then(|| plugin.id.clone()),
+                };
+                groups.entry(key).or_default().push((name, choice));
+            }
+        }
+
+        for ((_, label), mut plugins) in groups {
+            plugins.sort_by_key(|(name, _)| name.to_lowercase());
+            let header = root.join(label.replace(std::path::MAIN_SEPARATOR, "-"));
+            self.all_entries.push(BrowserEntry {
+                path: header.clone(),
+                name: label,
+                is_dir: true,
+                depth: 0,
+                plugin: None,
+            });
+            for (idx, (name, choice)) in plugins.into_iter().enumerate() {
+                self.all_entries.push(BrowserEntry {
+                    path: header.join(idx.to_string()),
+                    name,
+                    is_dir: false,
+                    depth: 1,
+                    plugin: Some(choice),
                 });
             }
         }
@@ -302,22 +492,51 @@ impl BrowserState {
     /// Start sample selection mode for a channel
     pub fn start_selection(&mut self, channel_idx: usize) {
         self.selection_mode = true;
-        self.target_channel = Some(channel_idx);
+        self.target = Some(SelectionTarget::Channel(channel_idx));
+    }
+
+    /// Start selecting a plugin instrument for a channel
+    pub fn start_plugin_selection(&mut self, channel_idx: usize) {
+        if self.mode != BrowserMode::Plugins {
+            self.toggle_mode();
+        }
+        self.start_selection(channel_idx);
+    }
+
+    /// Start selecting an impulse response for a convolution effect slot
+    pub fn start_impulse_response_selection(&mut self, track: usize, slot: usize) {
+        if self.mode != BrowserMode::Samples {
+            self.toggle_mode();
+        }
+        self.selection_mode = true;
+        self.target = Some(SelectionTarget::ImpulseResponse { track, slot });
+    }
+
+    /// Start selecting a CLAP effect for a mixer insert slot
+    pub fn start_effect_plugin_selection(&mut self, track: usize, slot: usize) {
+        if self.mode != BrowserMode::Plugins {
+            self.toggle_mode();
+        }
+        self.selection_mode = true;
+        self.target = Some(SelectionTarget::EffectPlugin { track, slot });
     }
 
     /// Cancel sample selection mode
     pub fn cancel_selection(&mut self) {
         self.selection_mode = false;
-        self.target_channel = None;
+        self.target = None;
     }
 
-    /// Complete selection and return (channel_idx, relative_path)
-    pub fn complete_selection(&mut self) -> Option<(usize, String)> {
+    /// Complete selection and return (target, relative_path, plugin_id)
+    ///
+    /// Plugins outside the project's plugins folder keep their absolute
+    /// path. The plugin id is only set for bundles offering several plugins.
+    pub fn complete_selection(&mut self) -> Option<(SelectionTarget, String, Option<String>)> {
         if !self.selection_mode {
             return None;
         }
 
-        let channel_idx = self.target_channel?;
+        let target = self.target?;
         let entry = self.current_entry()?;
 
         if entry.is_dir {
@@ -325,19 +544,22 @@ impl BrowserState {
         }
 
         let root = self.root_path().to_path_buf();
+        let (path, plugin_id) = match &entry.plugin {
+            Some(choice) => (choice.bundle.clone(), choice.id.clone()),
+            None => (entry.path.clone(), None),
+        };
 
         // Get path relative to root
-        let relative_path = entry
-            .path
+        let relative_path = path
             .strip_prefix(&root)
-            .ok()?
+            .unwrap_or(&path)
             .to_string_lossy()
             .to_string();
 
         self.selection_mode = false;
-        self.target_channel = None;
+        self.target = None;
 
-        Some((channel_idx, relative_path))
+        Some((target, relative_path, plugin_id))
     }
 
     /// Get the currently selected file's full path (for plugin loading)
@@ -383,3 +605,115 @@ fn is_plugin_bundle(path: &Path) -> bool {
             .map(|ext| ext.to_lowercase() == "clap")
             .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::plugin_host::PluginDescription;
+
+    fn plugin(id: &str, vendor: &str, feature: &str) -> PluginDescription {
+        PluginDescription {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            vendor: vendor.to_string(),
+            version: "1.0".to_string(),
+            features: vec![feature.to_string()],
+        }
+    }
+
+    /// Browser over an empty project with a made-up plugin scan
+    fn browser_with_catalog(dir: &Path) -> BrowserState {
+        let samples = dir.join("samples");
+        std::fs::create_dir_all(&samples).unwrap();
+        std::fs::create_dir_all(dir.join("plugins")).unwrap();
+        std::fs::write(dir.join("plugins/multi.clap"), b"").unwrap();
+
+        let mut browser = BrowserState::new(samples);
+        browser.set_plugin_catalog(vec![
+            ScannedBundle {
+                path: dir.join("plugins/multi.clap"),
+                plugins: vec![
+                    plugin("acme.synth", "Acme", "instrument"),
+                    plugin("acme.delay", "Acme", "audio-effect"),
+                ],
+            },
+            ScannedBundle {
+                path: PathBuf::from("/usr/lib/clap/verb.clap"),
+                plugins: vec![plugin("other.verb", "", "audio-effect")],
+            },
+        ]);
+        browser.toggle_mode();
+        browser
+    }
+
+    fn names(browser: &BrowserState) -> Vec<&str> {
+        browser
+            .visible_entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_plugins_grouped_by_category_then_vendor() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut browser = browser_with_catalog(dir.path());
+        assert_eq!(names(&browser), vec!["Instruments", "Audio Effects"]);
+
+        browser.go_to_bottom();
+        browser.expand();
+        assert_eq!(
+            names(&browser),
+            vec!["Instruments", "Audio Effects", "ACME.DELAY", "OTHER.VERB"]
+        );
+
+        browser.cycle_plugin_grouping();
+        assert_eq!(browser.plugin_grouping, PluginGrouping::Vendor);
+        assert_eq!(names(&browser), vec!["Acme", "Unknown"]);
+    }
+
+    #[test]
+    fn test_selection_picks_plugin_from_multi_plugin_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut browser = browser_with_catalog(dir.path());
+        browser.start_selection(2);
+        browser.expand();
+        browser.move_down(1);
+
+        let (target, path, id) = browser.complete_selection().unwrap();
+        assert_eq!(target, SelectionTarget::Channel(2));
+        assert_eq!(path, "multi.clap");
+        assert_eq!(id.as_deref(), Some("acme.synth"));
+    }
+
+    #[test]
+    fn test_single_plugin_bundle_keeps_absolute_path_without_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut browser = browser_with_catalog(dir.path());
+        browser.start_effect_plugin_selection(1, 0);
+        browser.go_to_bottom();
+        browser.expand();
+        browser.go_to_bottom();
+
+        let (_, path, id) = browser.complete_selection().unwrap();
+        assert_eq!(path, "/usr/lib/clap/verb.clap");
+        assert_eq!(id, None);
+    }
+
+    #[test]
+    fn test_files_view_opens_multi_plugin_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut browser = browser_with_catalog(dir.path());
+        browser.cycle_plugin_grouping();
+        browser.cycle_plugin_grouping();
+        assert_eq!(browser.plugin_grouping, PluginGrouping::Files);
+        assert_eq!(names(&browser), vec!["multi.clap"]);
+
+        browser.expand();
+        assert_eq!(
+            names(&browser),
+            vec!["multi.clap", "ACME.SYNTH", "ACME.DELAY"]
+        );
+    }
+}
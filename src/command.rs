@@ -5,8 +5,10 @@
 //! - Testable input handlers
 //! - Event logging and debugging
 
-use crate::effects::{EffectParamId, EffectType};
-use crate::sequencer::{Channel, Note};
+use std::collections::HashMap;
+
+use crate::effects::{EffectParamId, EffectSlot, EffectType, EFFECT_SLOTS};
+use crate::sequencer::{Channel, Note, NoteExpression};
 
 /// Application commands representing all possible state mutations.
 ///
@@ -66,7 +68,11 @@ pub enum AppCommand {
     SetChannelSample { slot: usize, path: String },
 
     /// Set channel as plugin
-    SetChannelPlugin { slot: usize, path: String },
+    SetChannelPlugin {
+        slot: usize,
+        path: String,
+        plugin_id: Option<String>,
+    },
 
     /// Set channel's mixer track routing
     SetChannelRouting { slot: usize, track: usize },
@@ -77,6 +83,9 @@ pub enum AppCommand {
     /// Decrement channel's mixer track routing (with wrap)
     DecrementChannelRouting(usize),
 
+    /// Cycle which plugin channel a plugin channel's note output plays
+    CycleNoteOut(usize),
+
     // ========================================================================
     // Step grid (channel rack)
     // ========================================================================
@@ -149,6 +158,15 @@ pub enum AppCommand {
         positions: Vec<(u8, usize)>,
     },
 
+    /// Set a per-note expression (pitch bend, pressure, timbre, pan)
+    SetNoteExpression {
+        channel: usize,
+        pattern: usize,
+        note_id: String,
+        expression: NoteExpression,
+        value: f32,
+    },
+
     // ========================================================================
     // Playlist / Arrangement
     // ========================================================================
@@ -205,6 +223,29 @@ pub enum AppCommand {
 
     /// Toggle effect bypass
     ToggleEffectBypass { track: usize, slot: usize },
+
+    /// Replace all parameters of an effect with a preset's values
+    ApplyEffectPreset {
+        track: usize,
+        slot: usize,
+        params: HashMap<EffectParamId, f32>,
+    },
+
+    /// Paste a copied effect, parameters included, into a slot
+    PasteEffect {
+        track: usize,
+        slot: usize,
+        effect: EffectSlot,
+    },
+
+    /// Swap two slots of a track's effect chain
+    SwapEffects { track: usize, a: usize, b: usize },
+
+    /// Replace a track's whole effect chain with a copied one
+    PasteEffectChain {
+        track: usize,
+        chain: Box<[Option<EffectSlot>; EFFECT_SLOTS]>,
+    },
 }
 
 impl AppCommand {
@@ -240,6 +281,7 @@ impl AppCommand {
             AppCommand::SetChannelRouting { .. } => "set channel routing",
             AppCommand::IncrementChannelRouting(_) => "increment routing",
             AppCommand::DecrementChannelRouting(_) => "decrement routing",
+            AppCommand::CycleNoteOut(_) => "cycle note output",
             AppCommand::ToggleStep { .. } => "toggle step",
             AppCommand::SetSteps { .. } => "set steps",
             AppCommand::ClearSteps { .. } => "clear steps",
@@ -249,6 +291,7 @@ impl AppCommand {
             AppCommand::DeleteNote { .. } => "delete note",
             AppCommand::BatchAddNotes { .. } => "batch add notes",
             AppCommand::BatchDeleteNotes { .. } => "batch delete notes",
+            AppCommand::SetNoteExpression { .. } => "set note expression",
             AppCommand::PlacePattern { .. } => "place pattern",
             AppCommand::RemovePlacement { .. } => "remove placement",
             AppCommand::TogglePatternMute(_) => "toggle pattern mute",
@@ -262,6 +305,10 @@ impl AppCommand {
             AppCommand::RemoveEffect { .. } => "remove effect",
             AppCommand::SetEffectParam { .. } => "set effect param",
             AppCommand::ToggleEffectBypass { .. } => "toggle effect bypass",
+            AppCommand::ApplyEffectPreset { .. } => "apply effect preset",
+            AppCommand::PasteEffect { .. } => "paste effect",
+            AppCommand::SwapEffects { .. } => "swap effects",
+            AppCommand::PasteEffectChain { .. } => "paste effect chain",
         }
     }
 }
@@ -11,6 +11,7 @@ pub enum Command {
     // Projects
     OpenProjects,
     Export,
+    ExportStems,
 
     // Panels
     ToggleBrowser,
@@ -31,6 +32,7 @@ impl Command {
         match self {
             Command::OpenProjects => 'p',
             Command::Export => 'e',
+            Command::ExportStems => 's',
             Command::ToggleBrowser => 'b',
             Command::ToggleMixer => 'm',
             Command::ToggleEventLog => 'l',
@@ -44,7 +46,8 @@ impl Command {
     pub fn label(&self) -> &'static str {
         match self {
             Command::OpenProjects => "Open Projects",
-            Command::Export => "Export WAV",
+            Command::Export => "Export Audio",
+            Command::ExportStems => "Export Stems",
             Command::ToggleBrowser => "Toggle Browser",
             Command::ToggleMixer => "Toggle Mixer",
             Command::ToggleEventLog => "Toggle Event Log",
@@ -113,7 +116,6 @@ pub enum InputTarget {
     #[default]
     None,
     Tempo,
-    ExportWav,
 }
 
 /// Command picker state
@@ -133,7 +135,7 @@ impl CommandPicker {
         let groups = vec![
             CommandGroup {
                 name: "Projects",
-                commands: vec![Command::OpenProjects, Command::Export],
+                commands: vec![Command::OpenProjects, Command::Export, Command::ExportStems],
             },
             CommandGroup {
                 name: "Panels",
@@ -171,17 +173,6 @@ impl CommandPicker {
         };
     }
 
-    /// Start export input mode
-    pub fn start_export_input(&mut self, default_filename: &str) {
-        self.visible = false;
-        self.input = InputMode {
-            active: true,
-            prompt: "Export to:",
-            input: Input::new(default_filename.to_string()),
-            target: InputTarget::ExportWav,
-        };
-    }
-
     /// Cancel input mode
     pub fn cancel_input(&mut self) {
         self.input = InputMode::default();
@@ -196,15 +187,6 @@ impl CommandPicker {
         }
     }
 
-    /// Get the export filename value, if in export mode
-    pub fn get_export_filename(&self) -> Option<&str> {
-        if self.input.target == InputTarget::ExportWav {
-            Some(self.input.input.value())
-        } else {
-            None
-        }
-    }
-
     /// Show the picker
     pub fn show(&mut self) {
         self.visible = true;
@@ -4,6 +4,7 @@
 //! This reduces the god-object nature of App and groups related state.
 
 use crate::coords::AppCol;
+use crate::sequencer::NoteExpression;
 
 /// Channel rack cursor and viewport state
 #[derive(Debug, Clone)]
@@ -37,6 +38,8 @@ pub struct PianoRollCursor {
     pub viewport_top: u8,
     /// If Some, we're placing a note starting at this step
     pub placing_note: Option<usize>,
+    /// Per-note expression shown in the lane below the grid, if any
+    pub expression_lane: Option<NoteExpression>,
 }
 
 impl Default for PianoRollCursor {
@@ -46,6 +49,7 @@ impl Default for PianoRollCursor {
             step: 0,
             viewport_top: 72, // Around C5
             placing_note: None,
+            expression_lane: None,
         }
     }
 }
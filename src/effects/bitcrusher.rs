@@ -0,0 +1,159 @@
+//! Bitcrusher effect
+//!
+//! Reduces bit depth by quantizing to fewer levels and reduces the sample
+//! rate with a sample-and-hold. The aliasing this produces is the point, so
+//! unlike the other nonlinear effects it never runs oversampled.
+
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Bitcrusher effect
+pub struct BitcrusherEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Bit depth (fractional values blend smoothly between depths)
+    bits: f32,
+    /// Held sample rate in Hz
+    rate: f32,
+    /// Dry/wet mix (0.0 = dry, 1.0 = wet)
+    mix: f32,
+    /// Sample-and-hold phase; a new sample is taken each time it passes 1.0
+    phase: f32,
+    /// Currently held samples
+    hold_l: f32,
+    hold_r: f32,
+}
+
+impl BitcrusherEffect {
+    /// Create a new bitcrusher effect
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            bits: 8.0,
+            rate: 11025.0,
+            mix: 1.0,
+            phase: 1.0,
+            hold_l: 0.0,
+            hold_r: 0.0,
+        }
+    }
+
+    /// Quantize to the current bit depth (mid-tread, so silence stays silent)
+    #[inline]
+    fn quantize(x: f32, step: f32) -> f32 {
+        (x / step).round() * step
+    }
+}
+
+impl Effect for BitcrusherEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let step = 2.0 / self.bits.exp2();
+        let increment = (self.rate / self.sample_rate).min(1.0);
+        let mix = self.mix;
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            if self.phase >= 1.0 {
+                self.phase -= 1.0;
+                self.hold_l = Self::quantize(*l, step);
+                self.hold_r = Self::quantize(*r, step);
+            }
+            self.phase += increment;
+
+            *l = *l * (1.0 - mix) + self.hold_l * mix;
+            *r = *r * (1.0 - mix) + self.hold_r * mix;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        match id {
+            EffectParamId::BitcrusherBits => self.bits = value.clamp(1.0, 16.0),
+            EffectParamId::BitcrusherRate => self.rate = value.clamp(200.0, 48000.0),
+            EffectParamId::BitcrusherMix => self.mix = value.clamp(0.0, 1.0),
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::BitcrusherBits => self.bits,
+            EffectParamId::BitcrusherRate => self.rate,
+            EffectParamId::BitcrusherMix => self.mix,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 1.0;
+        self.hold_l = 0.0;
+        self.hold_r = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Bitcrusher doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Bitcrusher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_bitcrusher_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Bitcrusher);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_bitcrusher_default_params() {
+        let effect = create_test_effect(EffectType::Bitcrusher);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_bitcrusher_reset() {
+        let mut effect = create_test_effect(EffectType::Bitcrusher);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_quantizes_to_bit_depth() {
+        let mut fx = BitcrusherEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::BitcrusherBits, 2.0);
+        fx.set_param(EffectParamId::BitcrusherRate, 48000.0);
+        let mut left = generate_sine(441, 100.0, TEST_SAMPLE_RATE);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+
+        // Two bits over [-1, 1] gives steps of 0.5
+        for x in &left {
+            assert!(
+                ((x * 2.0).round() - x * 2.0).abs() < 1e-6,
+                "{} not on the grid",
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn test_rate_reduction_holds_samples() {
+        let mut fx = BitcrusherEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::BitcrusherBits, 16.0);
+        fx.set_param(EffectParamId::BitcrusherRate, TEST_SAMPLE_RATE / 4.0);
+        let mut left: Vec<f32> = (0..16).map(|i| i as f32 / 16.0).collect();
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+
+        for chunk in left.chunks(4) {
+            assert!(chunk.iter().all(|&x| x == chunk[0]), "{:?}", chunk);
+        }
+        assert_ne!(left[0], left[4]);
+    }
+}
@@ -0,0 +1,379 @@
+//! Compressor effect
+//!
+//! Feed-forward stereo-linked compressor with soft knee, peak or RMS
+//! detection and an optional sidechain key from another mixer track.
+
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Level (dB) treated as silence by the detector
+const DETECTOR_FLOOR_DB: f32 = -120.0;
+
+/// Averaging window for RMS detection (ms)
+const RMS_WINDOW_MS: f32 = 10.0;
+
+/// Sidechain choices: off, or the mixer track (1-15) that keys the compressor
+pub const SIDECHAIN_CHOICES: [&str; 16] = [
+    "Off", "Track 1", "Track 2", "Track 3", "Track 4", "Track 5", "Track 6", "Track 7", "Track 8",
+    "Track 9", "Track 10", "Track 11", "Track 12", "Track 13", "Track 14", "Track 15",
+];
+
+/// Level detection mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detection {
+    Peak,
+    Rms,
+}
+
+pub(crate) fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+pub(crate) fn linear_to_db(linear: f32) -> f32 {
+    if linear <= 0.0 {
+        DETECTOR_FLOOR_DB
+    } else {
+        (20.0 * linear.log10()).max(DETECTOR_FLOOR_DB)
+    }
+}
+
+/// One-pole smoothing coefficient for a time constant in ms
+pub(crate) fn time_coef(ms: f32, sample_rate: f32) -> f32 {
+    (-1.0 / (ms * 0.001 * sample_rate)).exp()
+}
+
+/// Compressor effect
+pub struct CompressorEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Threshold in dB
+    threshold_db: f32,
+    /// Compression ratio (N:1)
+    ratio: f32,
+    /// Attack time in ms
+    attack_ms: f32,
+    /// Release time in ms
+    release_ms: f32,
+    /// Knee width in dB (0 = hard knee)
+    knee_db: f32,
+    /// Makeup gain in dB
+    makeup_db: f32,
+    /// Peak or RMS detection
+    detection: Detection,
+    /// Mixer track keying the compressor (None = own signal)
+    sidechain: Option<usize>,
+    /// Attack smoothing coefficient
+    attack_coef: f32,
+    /// Release smoothing coefficient
+    release_coef: f32,
+    /// RMS averaging coefficient
+    rms_coef: f32,
+    /// Running mean square for RMS detection
+    mean_square: f32,
+    /// Smoothed gain reduction in dB (positive = reducing)
+    envelope_db: f32,
+    /// Largest gain reduction in the last processed block, for metering
+    block_reduction_db: f32,
+}
+
+impl CompressorEffect {
+    /// Create a new compressor effect
+    pub fn new(sample_rate: f32) -> Self {
+        let mut effect = Self {
+            sample_rate,
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            knee_db: 6.0,
+            makeup_db: 0.0,
+            detection: Detection::Peak,
+            sidechain: None,
+            attack_coef: 0.0,
+            release_coef: 0.0,
+            rms_coef: 0.0,
+            mean_square: 0.0,
+            envelope_db: 0.0,
+            block_reduction_db: 0.0,
+        };
+        effect.update_coefficients();
+        effect
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coef = time_coef(self.attack_ms, self.sample_rate);
+        self.release_coef = time_coef(self.release_ms, self.sample_rate);
+        self.rms_coef = time_coef(RMS_WINDOW_MS, self.sample_rate);
+    }
+
+    /// Static gain reduction (dB, positive) for an input level, with soft knee
+    fn static_reduction(&self, level_db: f32) -> f32 {
+        let over = level_db - self.threshold_db;
+        let slope = 1.0 - 1.0 / self.ratio;
+
+        if 2.0 * over <= -self.knee_db {
+            0.0
+        } else if 2.0 * over.abs() < self.knee_db {
+            let x = over + self.knee_db / 2.0;
+            slope * x * x / (2.0 * self.knee_db)
+        } else {
+            slope * over
+        }
+    }
+
+    /// Compress `left`/`right`, detecting on `key` if given or on the input itself
+    fn compress(&mut self, left: &mut [f32], right: &mut [f32], key: Option<(&[f32], &[f32])>) {
+        let makeup = db_to_linear(self.makeup_db);
+        let mut block_reduction: f32 = 0.0;
+
+        for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            let key_level = match key {
+                Some((key_left, key_right)) => key_left[i].abs().max(key_right[i].abs()),
+                None => l.abs().max(r.abs()),
+            };
+            let level_db = match self.detection {
+                Detection::Peak => linear_to_db(key_level),
+                Detection::Rms => {
+                    self.mean_square = self.rms_coef * self.mean_square
+                        + (1.0 - self.rms_coef) * key_level * key_level;
+                    linear_to_db(self.mean_square.sqrt())
+                }
+            };
+
+            let target = self.static_reduction(level_db);
+            let coef = if target > self.envelope_db {
+                self.attack_coef
+            } else {
+                self.release_coef
+            };
+            self.envelope_db = coef * self.envelope_db + (1.0 - coef) * target;
+            block_reduction = block_reduction.max(self.envelope_db);
+
+            let gain = db_to_linear(-self.envelope_db) * makeup;
+            *l *= gain;
+            *r *= gain;
+        }
+
+        self.block_reduction_db = block_reduction;
+    }
+}
+
+impl Effect for CompressorEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.compress(left, right, None);
+    }
+
+    fn process_with_sidechain(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        key_left: &[f32],
+        key_right: &[f32],
+    ) {
+        self.compress(left, right, Some((key_left, key_right)));
+    }
+
+    fn sidechain_source(&self) -> Option<usize> {
+        self.sidechain
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        self.block_reduction_db
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        match id {
+            EffectParamId::CompressorThreshold => {
+                self.threshold_db = value.clamp(-60.0, 0.0);
+            }
+            EffectParamId::CompressorRatio => {
+                self.ratio = value.clamp(1.0, 20.0);
+            }
+            EffectParamId::CompressorAttack => {
+                self.attack_ms = value.clamp(0.1, 100.0);
+                self.update_coefficients();
+            }
+            EffectParamId::CompressorRelease => {
+                self.release_ms = value.clamp(10.0, 1000.0);
+                self.update_coefficients();
+            }
+            EffectParamId::CompressorKnee => {
+                self.knee_db = value.clamp(0.0, 24.0);
+            }
+            EffectParamId::CompressorMakeup => {
+                self.makeup_db = value.clamp(0.0, 24.0);
+            }
+            EffectParamId::CompressorDetection => {
+                self.detection = if value >= 0.5 {
+                    Detection::Rms
+                } else {
+                    Detection::Peak
+                };
+            }
+            EffectParamId::CompressorSidechain => {
+                let track = (value.max(0.0) as usize).min(SIDECHAIN_CHOICES.len() - 1);
+                self.sidechain = (track > 0).then_some(track);
+            }
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::CompressorThreshold => self.threshold_db,
+            EffectParamId::CompressorRatio => self.ratio,
+            EffectParamId::CompressorAttack => self.attack_ms,
+            EffectParamId::CompressorRelease => self.release_ms,
+            EffectParamId::CompressorKnee => self.knee_db,
+            EffectParamId::CompressorMakeup => self.makeup_db,
+            EffectParamId::CompressorDetection => match self.detection {
+                Detection::Peak => 0.0,
+                Detection::Rms => 1.0,
+            },
+            EffectParamId::CompressorSidechain => self.sidechain.unwrap_or(0) as f32,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mean_square = 0.0;
+        self.envelope_db = 0.0;
+        self.block_reduction_db = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Compressor doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Compressor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_compressor_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Compressor);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_compressor_default_params() {
+        let effect = create_test_effect(EffectType::Compressor);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_compressor_reset() {
+        let mut effect = create_test_effect(EffectType::Compressor);
+        assert_reset_clears_state(effect.as_mut());
+        assert_eq!(effect.gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_quiet_signal_untouched() {
+        let mut fx = CompressorEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::CompressorKnee, 0.0);
+
+        // -40 dBFS is well below the -18 dB threshold
+        let mut left = generate_dc(4410, 0.01);
+        let mut right = generate_dc(4410, 0.01);
+        fx.process(&mut left, &mut right);
+
+        assert!((left[4409] - 0.01).abs() < 1e-6);
+        assert_eq!(fx.gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_loud_signal_settles_at_ratio() {
+        let mut fx = CompressorEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::CompressorThreshold, -20.0);
+        fx.set_param(EffectParamId::CompressorRatio, 4.0);
+        fx.set_param(EffectParamId::CompressorKnee, 0.0);
+
+        // 0 dBFS in, 20 dB over threshold -> 15 dB of reduction at 4:1
+        let mut left = generate_dc(TEST_SAMPLE_RATE as usize, 1.0);
+        let mut right = generate_dc(TEST_SAMPLE_RATE as usize, 1.0);
+        fx.process(&mut left, &mut right);
+
+        let out_db = linear_to_db(*left.last().unwrap());
+        assert!((out_db + 15.0).abs() < 0.1, "output {} dB", out_db);
+        assert!((fx.gain_reduction_db() - 15.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_makeup_gain() {
+        let mut fx = CompressorEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::CompressorMakeup, 6.0);
+
+        let mut left = generate_dc(64, 0.01);
+        let mut right = generate_dc(64, 0.01);
+        fx.process(&mut left, &mut right);
+
+        assert!((left[63] - 0.01 * db_to_linear(6.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_soft_knee_is_continuous() {
+        let mut fx = CompressorEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::CompressorKnee, 12.0);
+
+        // Reduction starts gently below threshold and meets the hard curve above the knee
+        assert_eq!(fx.static_reduction(-30.0), 0.0);
+        assert!(fx.static_reduction(-18.0) > 0.0);
+        let above = fx.static_reduction(-12.0);
+        assert!((above - 6.0 * 0.75).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_rms_detection_reacts_slower_to_peaks() {
+        let mut peak = CompressorEffect::new(TEST_SAMPLE_RATE);
+        let mut rms = CompressorEffect::new(TEST_SAMPLE_RATE);
+        rms.set_param(EffectParamId::CompressorDetection, 1.0);
+
+        // A short click: peak detection sees its full level, RMS averages it away
+        let mut signal = vec![0.0; 2048];
+        signal[..8].fill(1.0);
+        let (mut pl, mut pr) = (signal.clone(), signal.clone());
+        let (mut rl, mut rr) = (signal.clone(), signal);
+        peak.process(&mut pl, &mut pr);
+        rms.process(&mut rl, &mut rr);
+
+        assert!(peak.gain_reduction_db() > rms.gain_reduction_db());
+    }
+
+    #[test]
+    fn test_sidechain_key_ducks_signal() {
+        let mut fx = CompressorEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::CompressorSidechain, 3.0);
+        fx.set_param(EffectParamId::CompressorAttack, 0.1);
+        assert_eq!(fx.sidechain_source(), Some(3));
+
+        // Quiet pad, loud kick on the key: the pad gets ducked
+        let mut left = generate_dc(4410, 0.05);
+        let mut right = generate_dc(4410, 0.05);
+        let key = generate_dc(4410, 1.0);
+        fx.process_with_sidechain(&mut left, &mut right, &key, &key);
+
+        assert!(left[4409] < 0.05 * 0.5, "pad not ducked: {}", left[4409]);
+        assert!(fx.gain_reduction_db() > 6.0);
+
+        // Silent key: no reduction (releases back to unity)
+        let silent = vec![0.0; 44100];
+        let mut left = generate_dc(44100, 0.05);
+        let mut right = generate_dc(44100, 0.05);
+        fx.process_with_sidechain(&mut left, &mut right, &silent, &silent);
+        assert!((left[44099] - 0.05).abs() < 1e-4);
+
+        fx.set_param(EffectParamId::CompressorSidechain, 0.0);
+        assert_eq!(fx.sidechain_source(), None);
+    }
+}
@@ -0,0 +1,644 @@
+//! Convolution reverb effect
+//!
+//! Convolves the input with an impulse response recorded from a real space
+//! (or any WAV in the project's samples). Uses uniformly partitioned
+//! overlap-save FFT convolution: the IR is split into blocks of
+//! [`BLOCK_SIZE`] samples whose spectra are multiplied against a delay line of
+//! past input spectra, so the wet signal is only one block late however long
+//! the IR is. Pre-delay absorbs that block of latency when it is long enough.
+//!
+//! Shaping the IR and transforming its partitions is far too slow for the
+//! audio thread, so the convolvers are built into a [`ConvolutionKernel`] on
+//! the main thread whenever the IR or a shaping param changes, and the effect
+//! only swaps the finished kernel in. The kernel it replaces goes back to the
+//! main thread to be freed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::effects::fft::{Complex, Fft};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Samples per partition, which is also the wet path's latency
+pub const BLOCK_SIZE: usize = 256;
+/// Transform size for overlap-save (previous block plus current block)
+const FFT_SIZE: usize = BLOCK_SIZE * 2;
+/// Non-redundant bins of a real signal's spectrum
+const BINS: usize = BLOCK_SIZE + 1;
+/// Longest IR kept after loading and stretching, in seconds
+const MAX_IR_SECONDS: f32 = 10.0;
+/// Fade applied to the tail of a shortened IR, in ms
+const TRIM_FADE_MS: f32 = 10.0;
+
+/// Impulse response loading errors
+#[derive(Debug, thiserror::Error)]
+pub enum ImpulseResponseError {
+    #[error("WAV error: {0}")]
+    Wav(#[from] hound::Error),
+    #[error("Impulse response has no samples")]
+    Empty,
+}
+
+/// Impulse response decoded from a WAV file (mono or stereo)
+#[derive(Clone, PartialEq)]
+pub struct ImpulseResponse {
+    /// Sample rate the IR was recorded at
+    pub sample_rate: u32,
+    /// One or two channels of samples
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl fmt::Debug for ImpulseResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImpulseResponse")
+            .field("sample_rate", &self.sample_rate)
+            .field("channels", &self.channels.len())
+            .field("frames", &self.frames())
+            .finish()
+    }
+}
+
+impl ImpulseResponse {
+    /// Load a WAV impulse response, keeping at most the first two channels
+    pub fn load(path: &Path) -> Result<Self, ImpulseResponseError> {
+        let reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let channel_count = spec.channels.max(1) as usize;
+
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 * scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        let max_frames = (MAX_IR_SECONDS * spec.sample_rate as f32) as usize;
+        let channels: Vec<Vec<f32>> = (0..channel_count.min(2))
+            .map(|c| {
+                interleaved
+                    .iter()
+                    .skip(c)
+                    .step_by(channel_count)
+                    .take(max_frames)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        if channels[0].is_empty() {
+            return Err(ImpulseResponseError::Empty);
+        }
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            channels,
+        })
+    }
+
+    /// Length in sample frames
+    pub fn frames(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+}
+
+/// Per-channel partitioned convolver
+#[derive(Clone)]
+struct Convolver {
+    /// Spectrum of each IR partition
+    partitions: Vec<Vec<Complex>>,
+    /// Spectra of recent input frames, one per partition (ring buffer)
+    history: Vec<Vec<Complex>>,
+    /// Ring position of the newest input spectrum
+    history_pos: usize,
+    /// Previous block followed by the block being filled
+    input: Vec<f32>,
+    /// Wet output for the block being filled
+    output: Vec<f32>,
+    /// FFT work buffer
+    scratch: Vec<Complex>,
+    /// Spectral accumulator
+    acc: Vec<Complex>,
+}
+
+impl Convolver {
+    fn new(kernel: &[f32], fft: &Fft) -> Self {
+        let mut scratch = vec![Complex::ZERO; FFT_SIZE];
+        let partitions: Vec<Vec<Complex>> = kernel
+            .chunks(BLOCK_SIZE)
+            .map(|chunk| {
+                scratch.fill(Complex::ZERO);
+                for (s, &x) in scratch.iter_mut().zip(chunk) {
+                    *s = Complex::new(x, 0.0);
+                }
+                fft.forward(&mut scratch);
+                scratch[..BINS].to_vec()
+            })
+            .collect();
+        let count = partitions.len();
+
+        Self {
+            partitions,
+            history: vec![vec![Complex::ZERO; BINS]; count],
+            history_pos: 0,
+            input: vec![0.0; FFT_SIZE],
+            output: vec![0.0; BLOCK_SIZE],
+            scratch,
+            acc: vec![Complex::ZERO; BINS],
+        }
+    }
+
+    /// Convolve the block just filled and shift it into the history
+    fn process_block(&mut self, fft: &Fft) {
+        for (s, &x) in self.scratch.iter_mut().zip(&self.input) {
+            *s = Complex::new(x, 0.0);
+        }
+        fft.forward(&mut self.scratch);
+        self.history[self.history_pos].copy_from_slice(&self.scratch[..BINS]);
+
+        self.acc.fill(Complex::ZERO);
+        let count = self.partitions.len();
+        for (p, partition) in self.partitions.iter().enumerate() {
+            let frame = &self.history[(self.history_pos + count - p) % count];
+            for ((acc, &x), &h) in self.acc.iter_mut().zip(frame).zip(partition) {
+                *acc += x * h;
+            }
+        }
+        self.history_pos = (self.history_pos + 1) % count;
+
+        // Rebuild the full spectrum from its conjugate-symmetric half
+        self.scratch[..BINS].copy_from_slice(&self.acc);
+        for k in BINS..FFT_SIZE {
+            self.scratch[k] = self.acc[FFT_SIZE - k].conj();
+        }
+        fft.inverse(&mut self.scratch);
+
+        // Overlap-save: only the second half is free of circular wrap-around
+        for (out, s) in self.output.iter_mut().zip(&self.scratch[BLOCK_SIZE..]) {
+            *out = s.re;
+        }
+        self.input.copy_within(BLOCK_SIZE.., 0);
+    }
+
+    fn clear(&mut self) {
+        self.history
+            .iter_mut()
+            .for_each(|frame| frame.fill(Complex::ZERO));
+        self.history_pos = 0;
+        self.input.fill(0.0);
+        self.output.fill(0.0);
+    }
+}
+
+/// Whether changing `id` calls for a new [`ConvolutionKernel`]
+pub fn is_kernel_param(id: EffectParamId) -> bool {
+    matches!(
+        id,
+        EffectParamId::ConvolutionPreDelay
+            | EffectParamId::ConvolutionTrimStart
+            | EffectParamId::ConvolutionLength
+            | EffectParamId::ConvolutionStretch
+    )
+}
+
+/// Params that reshape the IR
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shaping {
+    /// Delay before the reverb in ms
+    predelay_ms: f32,
+    /// Time cut from the start of the IR in ms
+    trim_start_ms: f32,
+    /// Fraction of the remaining IR kept (0.01 - 1.0)
+    length: f32,
+    /// Time stretch factor (> 1.0 makes the space larger)
+    stretch: f32,
+}
+
+impl Default for Shaping {
+    fn default() -> Self {
+        Self {
+            predelay_ms: 0.0,
+            trim_start_ms: 0.0,
+            length: 1.0,
+            stretch: 1.0,
+        }
+    }
+}
+
+impl Shaping {
+    /// Set a shaping param, returning false if `id` isn't one
+    fn set(&mut self, id: EffectParamId, value: f32) -> bool {
+        match id {
+            EffectParamId::ConvolutionPreDelay => self.predelay_ms = value.clamp(0.0, 250.0),
+            EffectParamId::ConvolutionTrimStart => self.trim_start_ms = value.clamp(0.0, 500.0),
+            EffectParamId::ConvolutionLength => self.length = value.clamp(0.01, 1.0),
+            EffectParamId::ConvolutionStretch => self.stretch = value.clamp(0.5, 2.0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Trim, stretch and resample one IR channel to `sample_rate`
+    fn shape_channel(&self, ir: &ImpulseResponse, channel: usize, sample_rate: f32) -> Vec<f32> {
+        let src = &ir.channels[channel.min(ir.channels.len() - 1)];
+        let start = ((self.trim_start_ms / 1000.0 * ir.sample_rate as f32) as usize)
+            .min(src.len().saturating_sub(1));
+        let remaining = src.len() - start;
+        let keep = ((remaining as f32 * self.length).ceil() as usize).clamp(1, remaining);
+        let segment = &src[start..start + keep];
+
+        // Source samples consumed per output sample
+        let step = ir.sample_rate as f32 / (sample_rate * self.stretch);
+        let max_len = (MAX_IR_SECONDS * sample_rate) as usize;
+        let out_len = ((keep as f32 / step) as usize).clamp(1, max_len);
+        let mut kernel: Vec<f32> = (0..out_len)
+            .map(|n| {
+                let pos = n as f32 * step;
+                let i = pos as usize;
+                let frac = pos - i as f32;
+                let a = segment.get(i).copied().unwrap_or(0.0);
+                let b = segment.get(i + 1).copied().unwrap_or(0.0);
+                a + (b - a) * frac
+            })
+            .collect();
+
+        // A shortened IR ends abruptly; fade it out to avoid a click
+        if self.length < 1.0 {
+            let fade = ((TRIM_FADE_MS / 1000.0 * sample_rate) as usize).min(out_len / 2);
+            for (i, sample) in kernel[out_len - fade..].iter_mut().enumerate() {
+                *sample *= 1.0 - (i + 1) as f32 / fade as f32;
+            }
+        }
+        kernel
+    }
+}
+
+/// Left and right convolvers built from an IR and the shaping params, ready
+/// to swap into a [`ConvolutionEffect`]
+#[derive(Clone)]
+pub struct ConvolutionKernel {
+    convolvers: [Convolver; 2],
+}
+
+impl fmt::Debug for ConvolutionKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConvolutionKernel")
+            .field("partitions", &self.convolvers[0].partitions.len())
+            .finish()
+    }
+}
+
+impl ConvolutionKernel {
+    /// Shape `ir` with the shaping params among `params` and transform it for
+    /// a convolution running at `sample_rate`
+    pub fn new(
+        ir: &ImpulseResponse,
+        params: &HashMap<EffectParamId, f32>,
+        sample_rate: f32,
+    ) -> Self {
+        let mut shaping = Shaping::default();
+        for (&id, &value) in params {
+            shaping.set(id, value);
+        }
+        let mut kernels = [0, 1].map(|c| shaping.shape_channel(ir, c, sample_rate));
+
+        // Unit energy keeps wet level similar across IRs; one gain for both
+        // channels preserves the IR's stereo balance
+        let energy = kernels
+            .iter()
+            .map(|k| k.iter().map(|x| x * x).sum::<f32>())
+            .fold(0.0f32, f32::max);
+        let gain = if energy > 0.0 {
+            1.0 / energy.sqrt()
+        } else {
+            0.0
+        };
+
+        // The block of latency counts towards the pre-delay
+        let predelay =
+            ((shaping.predelay_ms / 1000.0 * sample_rate) as usize).saturating_sub(BLOCK_SIZE);
+
+        for kernel in kernels.iter_mut() {
+            kernel.iter_mut().for_each(|x| *x *= gain);
+            kernel.splice(0..0, std::iter::repeat_n(0.0, predelay));
+        }
+        let fft = Fft::new(FFT_SIZE);
+        Self {
+            convolvers: kernels.map(|kernel| Convolver::new(&kernel, &fft)),
+        }
+    }
+}
+
+/// Convolution reverb effect
+pub struct ConvolutionEffect {
+    /// Shaping params, as last set; they take effect with the next kernel
+    shaping: Shaping,
+    /// Dry/wet mix (0.0 = dry, 1.0 = wet)
+    mix: f32,
+    /// FFT plan shared by both channels
+    fft: Fft,
+    /// Convolvers for the current IR (passes audio through untouched without one)
+    kernel: Option<Box<ConvolutionKernel>>,
+    /// Position within the current block
+    block_pos: usize,
+}
+
+impl ConvolutionEffect {
+    /// Create a new convolution reverb without a kernel
+    pub fn new() -> Self {
+        Self {
+            shaping: Shaping::default(),
+            mix: 0.3,
+            fft: Fft::new(FFT_SIZE),
+            kernel: None,
+            block_pos: 0,
+        }
+    }
+}
+
+impl Default for ConvolutionEffect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for ConvolutionEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let Some(kernel) = self.kernel.as_deref_mut() else {
+            return;
+        };
+        let [conv_l, conv_r] = &mut kernel.convolvers;
+        let mix = self.mix;
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let pos = self.block_pos;
+            conv_l.input[BLOCK_SIZE + pos] = *l;
+            conv_r.input[BLOCK_SIZE + pos] = *r;
+            let wet_l = conv_l.output[pos];
+            let wet_r = conv_r.output[pos];
+
+            self.block_pos += 1;
+            if self.block_pos == BLOCK_SIZE {
+                self.block_pos = 0;
+                conv_l.process_block(&self.fft);
+                conv_r.process_block(&self.fft);
+            }
+
+            *l = *l * (1.0 - mix) + wet_l * mix;
+            *r = *r * (1.0 - mix) + wet_r * mix;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        if !self.shaping.set(id, value) && id == EffectParamId::ConvolutionMix {
+            self.mix = value.clamp(0.0, 1.0);
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::ConvolutionPreDelay => self.shaping.predelay_ms,
+            EffectParamId::ConvolutionTrimStart => self.shaping.trim_start_ms,
+            EffectParamId::ConvolutionLength => self.shaping.length,
+            EffectParamId::ConvolutionStretch => self.shaping.stretch,
+            EffectParamId::ConvolutionMix => self.mix,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.block_pos = 0;
+        if let Some(kernel) = &mut self.kernel {
+            kernel.convolvers.iter_mut().for_each(Convolver::clear);
+        }
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: f32) {
+        // The kernel is built for the engine rate by whoever sends it
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Convolution doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Convolution
+    }
+
+    fn set_convolution_kernel(
+        &mut self,
+        kernel: Option<Box<ConvolutionKernel>>,
+    ) -> Option<Box<ConvolutionKernel>> {
+        self.block_pos = 0;
+        std::mem::replace(&mut self.kernel, kernel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    fn mono_kernel(
+        samples: Vec<f32>,
+        shaping: &[(EffectParamId, f32)],
+    ) -> Option<Box<ConvolutionKernel>> {
+        let ir = ImpulseResponse {
+            sample_rate: TEST_SAMPLE_RATE as u32,
+            channels: vec![samples],
+        };
+        let params = shaping.iter().copied().collect();
+        Some(Box::new(ConvolutionKernel::new(
+            &ir,
+            &params,
+            TEST_SAMPLE_RATE,
+        )))
+    }
+
+    fn wet_only(ir: Vec<f32>, shaping: &[(EffectParamId, f32)]) -> ConvolutionEffect {
+        let mut fx = ConvolutionEffect::new();
+        fx.set_param(EffectParamId::ConvolutionMix, 1.0);
+        fx.set_convolution_kernel(mono_kernel(ir, shaping));
+        fx
+    }
+
+    fn impulse_response_of(fx: &mut ConvolutionEffect, samples: usize) -> Vec<f32> {
+        let mut left = generate_impulse(samples);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        left
+    }
+
+    #[test]
+    fn test_convolution_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Convolution);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+        effect.set_convolution_kernel(mono_kernel(generate_noise(2000, 5), &[]));
+        assert_effect_passes_silence(effect.as_mut(), 1024);
+    }
+
+    #[test]
+    fn test_convolution_default_params() {
+        let effect = create_test_effect(EffectType::Convolution);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_convolution_reset() {
+        let mut effect = create_test_effect(EffectType::Convolution);
+        effect.set_convolution_kernel(mono_kernel(generate_noise(2000, 5), &[]));
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_swapping_kernel_hands_back_the_old_one() {
+        let mut fx = ConvolutionEffect::new();
+        assert!(fx
+            .set_convolution_kernel(mono_kernel(vec![1.0], &[]))
+            .is_none());
+        let old = fx.set_convolution_kernel(mono_kernel(vec![0.5, 0.5], &[]));
+        assert!(old.is_some(), "the caller frees the replaced kernel");
+        assert!(fx.set_convolution_kernel(None).is_some());
+
+        let mut other = create_test_effect(EffectType::Delay);
+        assert!(
+            other
+                .set_convolution_kernel(mono_kernel(vec![1.0], &[]))
+                .is_some(),
+            "effects without a kernel hand it straight back"
+        );
+    }
+
+    #[test]
+    fn test_without_ir_passes_audio_through() {
+        let mut fx = ConvolutionEffect::new();
+        fx.set_param(EffectParamId::ConvolutionMix, 1.0);
+        let input = generate_sine(512, 440.0, TEST_SAMPLE_RATE);
+        let mut left = input.clone();
+        let mut right = input.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(left, input);
+    }
+
+    #[test]
+    fn test_dirac_ir_delays_by_one_block() {
+        let mut fx = wet_only(vec![1.0], &[]);
+        let out = impulse_response_of(&mut fx, 1024);
+        assert!((out[BLOCK_SIZE] - 1.0).abs() < 1e-4);
+        let rest: f32 = out
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != BLOCK_SIZE)
+            .map(|(_, x)| x.abs())
+            .sum();
+        assert!(rest < 1e-3, "energy outside the delayed impulse: {}", rest);
+    }
+
+    #[test]
+    fn test_matches_direct_convolution() {
+        // Long enough to span several partitions
+        let ir: Vec<f32> = generate_noise(700, 11)
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * (-(i as f32) / 200.0).exp())
+            .collect();
+        let gain = 1.0 / ir.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let input = generate_noise(3000, 42);
+
+        let mut fx = wet_only(ir.clone(), &[]);
+        let mut left = input.clone();
+        let mut right = input.clone();
+        fx.process(&mut left, &mut right);
+
+        for (t, &actual) in left[BLOCK_SIZE..].iter().enumerate() {
+            let expected: f32 = (0..ir.len().min(t + 1))
+                .map(|k| ir[k] * input[t - k])
+                .sum::<f32>()
+                * gain;
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "sample {}: {} vs {}",
+                t + BLOCK_SIZE,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_predelay_absorbs_block_latency() {
+        let mut fx = wet_only(vec![1.0], &[(EffectParamId::ConvolutionPreDelay, 20.0)]);
+        let out = impulse_response_of(&mut fx, 2048);
+        let expected = (0.02 * TEST_SAMPLE_RATE) as usize;
+        assert!((out[expected] - 1.0).abs() < 1e-4, "{}", out[expected]);
+    }
+
+    #[test]
+    fn test_stretch_scales_ir_time() {
+        let mut ir = vec![0.0; 101];
+        ir[0] = 1.0;
+        ir[100] = 1.0;
+        let mut fx = wet_only(ir, &[(EffectParamId::ConvolutionStretch, 2.0)]);
+        let out = impulse_response_of(&mut fx, 1024);
+        // The second spike moves from 100 to 200 samples after the first
+        let first = out[BLOCK_SIZE];
+        assert!(first > 0.1);
+        assert!((out[BLOCK_SIZE + 200] - first).abs() < 1e-3);
+        assert!(out[BLOCK_SIZE + 100].abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_trim_start_drops_early_part() {
+        let mut ir = vec![0.0; 441 * 2];
+        ir[0] = 1.0; // direct sound
+        ir[441 + 10] = 0.5; // reflection 10 samples past the 10 ms trim point
+        let mut fx = wet_only(ir, &[(EffectParamId::ConvolutionTrimStart, 10.0)]);
+        let out = impulse_response_of(&mut fx, 1024);
+        // Only the reflection survives, normalised to unit energy
+        assert!((out[BLOCK_SIZE + 10] - 1.0).abs() < 1e-3);
+        assert!(out[BLOCK_SIZE].abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_shaping_param_waits_for_next_kernel() {
+        let mut fx = wet_only(vec![1.0], &[]);
+        fx.set_param(EffectParamId::ConvolutionPreDelay, 20.0);
+        assert_eq!(fx.get_param(EffectParamId::ConvolutionPreDelay), 20.0);
+        let out = impulse_response_of(&mut fx, 1024);
+        assert!((out[BLOCK_SIZE] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_load_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ir.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for frame in 0..100 {
+            writer
+                .write_sample(if frame == 0 { 16384i16 } else { 0 })
+                .unwrap();
+            writer
+                .write_sample(if frame == 1 { -16384i16 } else { 0 })
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let ir = ImpulseResponse::load(&path).unwrap();
+        assert_eq!(ir.sample_rate, 48000);
+        assert_eq!(ir.channels.len(), 2);
+        assert_eq!(ir.frames(), 100);
+        assert_eq!(ir.channels[0][0], 0.5);
+        assert_eq!(ir.channels[1][1], -0.5);
+
+        assert!(ImpulseResponse::load(&dir.path().join("missing.wav")).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Waveshaping distortion effect
+//!
+//! Drives the signal into one of four transfer curves: soft clip (tanh), hard
+//! clip, foldback and an asymmetric tube curve that adds even harmonics. The
+//! shaper can run 2x or 4x oversampled to keep aliasing down at high drive.
+
+use crate::effects::oversampling::{OversampleFactor, Oversampler};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Bias applied before the tube curve to make it asymmetric
+const TUBE_BIAS: f32 = 0.3;
+/// DC blocker corner frequency in Hz
+const DC_BLOCK_HZ: f32 = 10.0;
+
+/// Transfer curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistortionMode {
+    SoftClip = 0,
+    HardClip = 1,
+    Foldback = 2,
+    Tube = 3,
+}
+
+impl From<f32> for DistortionMode {
+    fn from(value: f32) -> Self {
+        match value as u32 {
+            0 => DistortionMode::SoftClip,
+            1 => DistortionMode::HardClip,
+            2 => DistortionMode::Foldback,
+            _ => DistortionMode::Tube,
+        }
+    }
+}
+
+impl DistortionMode {
+    /// Apply the transfer curve to an already driven sample
+    #[inline]
+    pub fn shape(self, x: f32) -> f32 {
+        match self {
+            DistortionMode::SoftClip => x.tanh(),
+            DistortionMode::HardClip => x.clamp(-1.0, 1.0),
+            DistortionMode::Foldback => {
+                // Triangle wave of the input: reflects back off +/-1
+                let t = (x + 1.0).rem_euclid(4.0);
+                if t < 2.0 {
+                    t - 1.0
+                } else {
+                    3.0 - t
+                }
+            }
+            DistortionMode::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+        }
+    }
+}
+
+/// One-pole DC blocker (the tube curve's asymmetry leaves an offset)
+#[derive(Debug, Clone, Default)]
+struct DcBlocker {
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    #[inline]
+    fn process(&mut self, x: f32, pole: f32) -> f32 {
+        let y = x - self.x1 + pole * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Waveshaping distortion effect
+pub struct DistortionEffect {
+    /// DC blocker pole for the current sample rate
+    dc_pole: f32,
+    /// Input drive in dB
+    drive_db: f32,
+    /// Transfer curve
+    mode: DistortionMode,
+    /// Output level in dB
+    output_db: f32,
+    /// Dry/wet mix (0.0 = dry, 1.0 = wet)
+    mix: f32,
+    /// Left channel oversampler
+    os_l: Oversampler,
+    /// Right channel oversampler
+    os_r: Oversampler,
+    /// Left channel DC blocker
+    dc_l: DcBlocker,
+    /// Right channel DC blocker
+    dc_r: DcBlocker,
+}
+
+impl DistortionEffect {
+    /// Create a new distortion effect
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            dc_pole: Self::dc_pole(sample_rate),
+            drive_db: 12.0,
+            mode: DistortionMode::SoftClip,
+            output_db: -6.0,
+            mix: 1.0,
+            os_l: Oversampler::new(OversampleFactor::X2),
+            os_r: Oversampler::new(OversampleFactor::X2),
+            dc_l: DcBlocker::default(),
+            dc_r: DcBlocker::default(),
+        }
+    }
+
+    fn dc_pole(sample_rate: f32) -> f32 {
+        (-std::f32::consts::TAU * DC_BLOCK_HZ / sample_rate).exp()
+    }
+}
+
+impl Effect for DistortionEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let drive = 10.0f32.powf(self.drive_db / 20.0);
+        let output = 10.0f32.powf(self.output_db / 20.0);
+        let mode = self.mode;
+        let mix = self.mix;
+        let shape = |x: f32| mode.shape(x * drive);
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let (dry_l, wet_l) = self.os_l.process(*l, shape);
+            let (dry_r, wet_r) = self.os_r.process(*r, shape);
+            let wet_l = self.dc_l.process(wet_l, self.dc_pole) * output;
+            let wet_r = self.dc_r.process(wet_r, self.dc_pole) * output;
+
+            *l = dry_l * (1.0 - mix) + wet_l * mix;
+            *r = dry_r * (1.0 - mix) + wet_r * mix;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        match id {
+            EffectParamId::DistortionDrive => self.drive_db = value.clamp(0.0, 36.0),
+            EffectParamId::DistortionMode => self.mode = DistortionMode::from(value),
+            EffectParamId::DistortionOutput => self.output_db = value.clamp(-24.0, 6.0),
+            EffectParamId::DistortionMix => self.mix = value.clamp(0.0, 1.0),
+            EffectParamId::DistortionOversampling => {
+                let factor = OversampleFactor::from(value);
+                self.os_l.set_factor(factor);
+                self.os_r.set_factor(factor);
+            }
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::DistortionDrive => self.drive_db,
+            EffectParamId::DistortionMode => self.mode as u32 as f32,
+            EffectParamId::DistortionOutput => self.output_db,
+            EffectParamId::DistortionMix => self.mix,
+            EffectParamId::DistortionOversampling => self.os_l.factor() as u32 as f32,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.os_l.reset();
+        self.os_r.reset();
+        self.dc_l = DcBlocker::default();
+        self.dc_r = DcBlocker::default();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.dc_pole = Self::dc_pole(sample_rate);
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Distortion doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Distortion
+    }
+
+    fn latency_frames(&self) -> usize {
+        self.os_l.factor().latency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_distortion_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Distortion);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_distortion_default_params() {
+        let effect = create_test_effect(EffectType::Distortion);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_distortion_reset() {
+        let mut effect = create_test_effect(EffectType::Distortion);
+        effect.set_param(EffectParamId::DistortionMode, 3.0);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_curves() {
+        assert_eq!(DistortionMode::HardClip.shape(3.0), 1.0);
+        assert!((DistortionMode::SoftClip.shape(0.1) - 0.1).abs() < 0.001);
+        assert!((DistortionMode::Foldback.shape(1.5) - 0.5).abs() < 1e-6);
+        assert!((DistortionMode::Foldback.shape(-1.25) + 0.75).abs() < 1e-6);
+        assert_eq!(DistortionMode::Tube.shape(0.0), 0.0);
+        // Asymmetric: the negative half saturates later than the positive half
+        assert!(DistortionMode::Tube.shape(-1.0).abs() > DistortionMode::Tube.shape(1.0));
+    }
+
+    #[test]
+    fn test_all_modes_bounded_at_max_drive() {
+        for mode in 0..4 {
+            for factor in 0..3 {
+                let mut fx = DistortionEffect::new(TEST_SAMPLE_RATE);
+                fx.set_param(EffectParamId::DistortionMode, mode as f32);
+                fx.set_param(EffectParamId::DistortionOversampling, factor as f32);
+                fx.set_param(EffectParamId::DistortionDrive, 36.0);
+                fx.set_param(EffectParamId::DistortionOutput, 0.0);
+                let mut left = generate_sine(4410, 220.0, TEST_SAMPLE_RATE);
+                let mut right = left.clone();
+                fx.process(&mut left, &mut right);
+                let peak = calculate_peak(&left);
+                assert!(
+                    peak.is_finite() && peak < 2.5,
+                    "mode {} factor {} peak {}",
+                    mode,
+                    factor,
+                    peak
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_dry_mix_is_delay_compensated_input() {
+        let mut fx = DistortionEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::DistortionMix, 0.0);
+        fx.set_param(EffectParamId::DistortionOversampling, 2.0);
+        let input = generate_sine(1024, 440.0, TEST_SAMPLE_RATE);
+        let mut left = input.clone();
+        let mut right = input.clone();
+        fx.process(&mut left, &mut right);
+
+        let latency = OversampleFactor::X4.latency();
+        assert_eq!(&left[latency..], &input[..1024 - latency]);
+    }
+
+    #[test]
+    fn test_reports_oversampling_latency() {
+        let mut fx = DistortionEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::DistortionOversampling, 0.0);
+        assert_eq!(fx.latency_frames(), 0);
+        fx.set_param(EffectParamId::DistortionOversampling, 2.0);
+        assert_eq!(fx.latency_frames(), OversampleFactor::X4.latency());
+    }
+}
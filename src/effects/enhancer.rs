@@ -1,7 +1,10 @@
 //! Enhancer effect
 //!
 //! Adds warmth, presence, and punch through soft saturation and high-frequency excitation.
+//! The saturation stage can run oversampled; the rest of the chain then works
+//! on the latency-aligned dry signal.
 
+use crate::effects::oversampling::{OversampleFactor, Oversampler};
 use crate::effects::{Effect, EffectParamId, EffectType};
 
 /// One-pole lowpass filter for smoothing
@@ -42,6 +45,10 @@ pub struct EnhancerEffect {
     env_l: OnePole,
     /// Envelope follower for compression (right)
     env_r: OnePole,
+    /// Saturation oversampler (left)
+    os_l: Oversampler,
+    /// Saturation oversampler (right)
+    os_r: Oversampler,
 }
 
 impl EnhancerEffect {
@@ -55,6 +62,8 @@ impl EnhancerEffect {
             hp_r: OnePole::new(3000.0, sample_rate),
             env_l: OnePole::new(50.0, sample_rate),
             env_r: OnePole::new(50.0, sample_rate),
+            os_l: Oversampler::default(),
+            os_r: Oversampler::default(),
         }
     }
 
@@ -90,13 +99,12 @@ impl Effect for EnhancerEffect {
         self.hp_l.set_cutoff(exciter_freq, self.sample_rate);
         self.hp_r.set_cutoff(exciter_freq, self.sample_rate);
 
-        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
-            let dry_l = *l;
-            let dry_r = *r;
+        let drive = sat_drive * self.amount;
 
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
             // 1. Soft saturation for warmth
-            let sat_l = Self::saturate(dry_l, sat_drive * self.amount);
-            let sat_r = Self::saturate(dry_r, sat_drive * self.amount);
+            let (dry_l, sat_l) = self.os_l.process(*l, |x| Self::saturate(x, drive));
+            let (dry_r, sat_r) = self.os_r.process(*r, |x| Self::saturate(x, drive));
 
             // 2. High-frequency excitation
             // Extract highs, saturate them, add back
@@ -139,6 +147,11 @@ impl Effect for EnhancerEffect {
             EffectParamId::EnhancerMode => {
                 self.mode = (value as u32).min(3);
             }
+            EffectParamId::EnhancerOversampling => {
+                let factor = OversampleFactor::from(value);
+                self.os_l.set_factor(factor);
+                self.os_r.set_factor(factor);
+            }
             _ => {}
         }
     }
@@ -147,6 +160,7 @@ impl Effect for EnhancerEffect {
         match id {
             EffectParamId::EnhancerAmount => self.amount,
             EffectParamId::EnhancerMode => self.mode as f32,
+            EffectParamId::EnhancerOversampling => self.os_l.factor() as u32 as f32,
             _ => 0.0,
         }
     }
@@ -156,6 +170,8 @@ impl Effect for EnhancerEffect {
         self.hp_r.y1 = 0.0;
         self.env_l.y1 = 0.0;
         self.env_r.y1 = 0.0;
+        self.os_l.reset();
+        self.os_r.reset();
     }
 
     fn set_sample_rate(&mut self, sample_rate: f32) {
@@ -173,6 +189,10 @@ impl Effect for EnhancerEffect {
     fn effect_type(&self) -> EffectType {
         EffectType::Enhancer
     }
+
+    fn latency_frames(&self) -> usize {
+        self.os_l.factor().latency()
+    }
 }
 
 #[cfg(test)]
@@ -233,4 +253,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn enhancer_oversampled_saturation_stays_close_to_plain() {
+        let input: Vec<f32> = (0..4410)
+            .map(|i| 0.8 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / 44100.0).sin())
+            .collect();
+        let run = |factor: f32| {
+            let mut fx = EnhancerEffect::new(44100.0);
+            fx.set_param(EffectParamId::EnhancerAmount, 1.0);
+            fx.set_param(EffectParamId::EnhancerOversampling, factor);
+            let mut left = input.clone();
+            let mut right = input.clone();
+            fx.process(&mut left, &mut right);
+            left
+        };
+
+        let plain = run(0.0);
+        let oversampled = run(2.0);
+        let latency = OversampleFactor::X4.latency();
+        // A low tone barely aliases, so apart from the latency the output matches
+        let max_diff = plain[..4410 - latency]
+            .iter()
+            .zip(&oversampled[latency..])
+            .skip(441)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_diff < 0.02, "max difference {}", max_diff);
+    }
 }
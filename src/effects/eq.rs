@@ -0,0 +1,505 @@
+//! Multi-band parametric EQ effect
+//!
+//! Six independent biquad bands (RBJ cookbook), each switchable between
+//! bell, shelf, pass and notch shapes. The same band math drives the
+//! frequency-response curve drawn in the effect editor.
+
+use std::f32::consts::PI;
+
+use crate::effects::{Effect, EffectParamId, EffectSlot, EffectType};
+
+/// Number of EQ bands
+pub const EQ_BANDS: usize = 6;
+
+/// Band shape choices, in parameter order
+pub const BAND_KIND_CHOICES: [&str; 7] = [
+    "Off",
+    "Bell",
+    "Low Shelf",
+    "High Shelf",
+    "High Pass",
+    "Low Pass",
+    "Notch",
+];
+
+/// Parameter ids per band: [type, frequency, gain, Q]
+pub const BAND_PARAMS: [[EffectParamId; 4]; EQ_BANDS] = [
+    [
+        EffectParamId::EqBand1Type,
+        EffectParamId::EqBand1Freq,
+        EffectParamId::EqBand1Gain,
+        EffectParamId::EqBand1Q,
+    ],
+    [
+        EffectParamId::EqBand2Type,
+        EffectParamId::EqBand2Freq,
+        EffectParamId::EqBand2Gain,
+        EffectParamId::EqBand2Q,
+    ],
+    [
+        EffectParamId::EqBand3Type,
+        EffectParamId::EqBand3Freq,
+        EffectParamId::EqBand3Gain,
+        EffectParamId::EqBand3Q,
+    ],
+    [
+        EffectParamId::EqBand4Type,
+        EffectParamId::EqBand4Freq,
+        EffectParamId::EqBand4Gain,
+        EffectParamId::EqBand4Q,
+    ],
+    [
+        EffectParamId::EqBand5Type,
+        EffectParamId::EqBand5Freq,
+        EffectParamId::EqBand5Gain,
+        EffectParamId::EqBand5Q,
+    ],
+    [
+        EffectParamId::EqBand6Type,
+        EffectParamId::EqBand6Freq,
+        EffectParamId::EqBand6Gain,
+        EffectParamId::EqBand6Q,
+    ],
+];
+
+/// Default (shape, frequency) per band; all bands start at 0 dB
+pub const BAND_DEFAULTS: [(BandKind, f32); EQ_BANDS] = [
+    (BandKind::Off, 30.0),
+    (BandKind::LowShelf, 100.0),
+    (BandKind::Bell, 400.0),
+    (BandKind::Bell, 2500.0),
+    (BandKind::HighShelf, 8000.0),
+    (BandKind::Off, 18000.0),
+];
+
+/// Shape of an EQ band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandKind {
+    Off = 0,
+    Bell = 1,
+    LowShelf = 2,
+    HighShelf = 3,
+    HighPass = 4,
+    LowPass = 5,
+    Notch = 6,
+}
+
+impl From<f32> for BandKind {
+    fn from(value: f32) -> Self {
+        match value.max(0.0) as u32 {
+            0 => BandKind::Off,
+            1 => BandKind::Bell,
+            2 => BandKind::LowShelf,
+            3 => BandKind::HighShelf,
+            4 => BandKind::HighPass,
+            5 => BandKind::LowPass,
+            _ => BandKind::Notch,
+        }
+    }
+}
+
+/// Settings of one EQ band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub kind: BandKind,
+    /// Center/corner frequency in Hz
+    pub freq: f32,
+    /// Gain in dB (bell and shelves only)
+    pub gain_db: f32,
+    /// Bandwidth / resonance
+    pub q: f32,
+}
+
+impl EqBand {
+    /// Read band `index` from a saved effect slot
+    pub fn from_slot(slot: &EffectSlot, index: usize) -> Self {
+        let [kind, freq, gain, q] = BAND_PARAMS[index];
+        Self {
+            kind: BandKind::from(slot.get_param(kind)),
+            freq: slot.get_param(freq),
+            gain_db: slot.get_param(gain),
+            q: slot.get_param(q),
+        }
+    }
+}
+
+impl Default for EqBand {
+    fn default() -> Self {
+        Self {
+            kind: BandKind::Off,
+            freq: 1000.0,
+            gain_db: 0.0,
+            q: 0.71,
+        }
+    }
+}
+
+/// Normalized biquad coefficients (a0 = 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BiquadCoefs {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl BiquadCoefs {
+    const IDENTITY: Self = Self {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 0.0,
+    };
+
+    /// RBJ cookbook coefficients for a band
+    pub(crate) fn for_band(band: &EqBand, sample_rate: f32) -> Self {
+        if band.kind == BandKind::Off {
+            return Self::IDENTITY;
+        }
+
+        let freq = band.freq.clamp(10.0, sample_rate * 0.49);
+        let q = band.q.max(0.05);
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / (2.0 * q);
+        let a = 10.0_f32.powf(band.gain_db / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match band.kind {
+            BandKind::Off => unreachable!(),
+            BandKind::Bell => (
+                1.0 + alpha * a,
+                -2.0 * cos_w0,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos_w0,
+                1.0 - alpha / a,
+            ),
+            BandKind::LowShelf => {
+                let sq = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 + sq),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 - sq),
+                    (a + 1.0) + (a - 1.0) * cos_w0 + sq,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    (a + 1.0) + (a - 1.0) * cos_w0 - sq,
+                )
+            }
+            BandKind::HighShelf => {
+                let sq = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 + sq),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 - sq),
+                    (a + 1.0) - (a - 1.0) * cos_w0 + sq,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    (a + 1.0) - (a - 1.0) * cos_w0 - sq,
+                )
+            }
+            BandKind::HighPass => (
+                (1.0 + cos_w0) / 2.0,
+                -(1.0 + cos_w0),
+                (1.0 + cos_w0) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            BandKind::LowPass => (
+                (1.0 - cos_w0) / 2.0,
+                1.0 - cos_w0,
+                (1.0 - cos_w0) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            BandKind::Notch => (
+                1.0,
+                -2.0 * cos_w0,
+                1.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Magnitude response in dB at `freq`
+    pub(crate) fn magnitude_db(&self, freq: f32, sample_rate: f32) -> f32 {
+        let w = 2.0 * PI * freq / sample_rate;
+        let (s1, c1) = w.sin_cos();
+        let (s2, c2) = (2.0 * w).sin_cos();
+
+        let num_re = self.b0 + self.b1 * c1 + self.b2 * c2;
+        let num_im = -(self.b1 * s1 + self.b2 * s2);
+        let den_re = 1.0 + self.a1 * c1 + self.a2 * c2;
+        let den_im = -(self.a1 * s1 + self.a2 * s2);
+
+        let num = num_re * num_re + num_im * num_im;
+        let den = (den_re * den_re + den_im * den_im).max(1e-20);
+        10.0 * (num / den).max(1e-12).log10()
+    }
+}
+
+/// Transposed direct form II biquad state
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BiquadState {
+    z1: f32,
+    z2: f32,
+}
+
+impl BiquadState {
+    #[inline]
+    pub(crate) fn process(&mut self, input: f32, c: &BiquadCoefs) -> f32 {
+        let output = c.b0 * input + self.z1;
+        self.z1 = c.b1 * input - c.a1 * output + self.z2;
+        self.z2 = c.b2 * input - c.a2 * output;
+
+        if output.is_finite() {
+            output
+        } else {
+            *self = Self::default();
+            0.0
+        }
+    }
+}
+
+/// Combined magnitude response of all bands in dB at `freq`
+pub fn response_db(bands: &[EqBand], freq: f32, sample_rate: f32) -> f32 {
+    bands
+        .iter()
+        .filter(|b| b.kind != BandKind::Off)
+        .map(|b| BiquadCoefs::for_band(b, sample_rate).magnitude_db(freq, sample_rate))
+        .sum()
+}
+
+/// Multi-band parametric EQ effect
+pub struct EqEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Band settings
+    bands: [EqBand; EQ_BANDS],
+    /// Coefficients per band
+    coefs: [BiquadCoefs; EQ_BANDS],
+    /// Left channel state per band
+    state_l: [BiquadState; EQ_BANDS],
+    /// Right channel state per band
+    state_r: [BiquadState; EQ_BANDS],
+}
+
+impl EqEffect {
+    /// Create a new EQ with the default band layout, all flat
+    pub fn new(sample_rate: f32) -> Self {
+        let bands = BAND_DEFAULTS.map(|(kind, freq)| EqBand {
+            kind,
+            freq,
+            ..EqBand::default()
+        });
+        let mut effect = Self {
+            sample_rate,
+            bands,
+            coefs: [BiquadCoefs::IDENTITY; EQ_BANDS],
+            state_l: [BiquadState::default(); EQ_BANDS],
+            state_r: [BiquadState::default(); EQ_BANDS],
+        };
+        for band in 0..EQ_BANDS {
+            effect.update_band(band);
+        }
+        effect
+    }
+
+    fn update_band(&mut self, band: usize) {
+        self.coefs[band] = BiquadCoefs::for_band(&self.bands[band], self.sample_rate);
+    }
+
+    /// Find the band and field (0 = type, 1 = freq, 2 = gain, 3 = Q) of a param
+    fn locate(id: EffectParamId) -> Option<(usize, usize)> {
+        BAND_PARAMS
+            .iter()
+            .enumerate()
+            .find_map(|(band, ids)| ids.iter().position(|&p| p == id).map(|field| (band, field)))
+    }
+}
+
+impl Effect for EqEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for band in 0..EQ_BANDS {
+            if self.bands[band].kind == BandKind::Off {
+                continue;
+            }
+            let coefs = self.coefs[band];
+            let (state_l, state_r) = (&mut self.state_l[band], &mut self.state_r[band]);
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                *l = state_l.process(*l, &coefs);
+                *r = state_r.process(*r, &coefs);
+            }
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        let Some((band, field)) = Self::locate(id) else {
+            return;
+        };
+        let settings = &mut self.bands[band];
+        match field {
+            0 => {
+                let kind = BandKind::from(value);
+                if kind != settings.kind {
+                    // A new shape starts from a clean state to avoid a click
+                    self.state_l[band] = BiquadState::default();
+                    self.state_r[band] = BiquadState::default();
+                }
+                settings.kind = kind;
+            }
+            1 => settings.freq = value.clamp(20.0, 20000.0),
+            2 => settings.gain_db = value.clamp(-18.0, 18.0),
+            _ => settings.q = value.clamp(0.1, 10.0),
+        }
+        self.update_band(band);
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        let Some((band, field)) = Self::locate(id) else {
+            return 0.0;
+        };
+        let settings = &self.bands[band];
+        match field {
+            0 => settings.kind as u32 as f32,
+            1 => settings.freq,
+            2 => settings.gain_db,
+            _ => settings.q,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state_l = [BiquadState::default(); EQ_BANDS];
+        self.state_r = [BiquadState::default(); EQ_BANDS];
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for band in 0..EQ_BANDS {
+            self.update_band(band);
+        }
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // EQ doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Eq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    /// Steady-state gain (dB) of a sine through the EQ
+    fn measured_gain_db(fx: &mut EqEffect, freq: f32) -> f32 {
+        let n = TEST_SAMPLE_RATE as usize / 2;
+        let mut left = generate_sine(n, freq, TEST_SAMPLE_RATE);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        let out = calculate_rms(&left[n / 2..]);
+        20.0 * (out / std::f32::consts::FRAC_1_SQRT_2).log10()
+    }
+
+    #[test]
+    fn test_eq_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Eq);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_eq_default_params() {
+        let effect = create_test_effect(EffectType::Eq);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_eq_reset() {
+        let mut effect = create_test_effect(EffectType::Eq);
+        effect.set_param(EffectParamId::EqBand3Gain, 12.0);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_default_eq_is_flat() {
+        let mut fx = EqEffect::new(TEST_SAMPLE_RATE);
+        for freq in [50.0, 400.0, 5000.0] {
+            assert!(measured_gain_db(&mut fx, freq).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_bell_boosts_center_frequency() {
+        let mut fx = EqEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::EqBand3Freq, 1000.0);
+        fx.set_param(EffectParamId::EqBand3Gain, 6.0);
+        fx.set_param(EffectParamId::EqBand3Q, 1.0);
+
+        assert!((measured_gain_db(&mut fx, 1000.0) - 6.0).abs() < 0.3);
+        fx.reset();
+        assert!(measured_gain_db(&mut fx, 100.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_high_pass_cuts_lows() {
+        let mut fx = EqEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::EqBand1Type, BandKind::HighPass as u32 as f32);
+        fx.set_param(EffectParamId::EqBand1Freq, 500.0);
+
+        assert!(measured_gain_db(&mut fx, 50.0) < -30.0);
+        fx.reset();
+        assert!(measured_gain_db(&mut fx, 5000.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_response_matches_processing() {
+        let mut fx = EqEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::EqBand2Gain, -9.0);
+        fx.set_param(EffectParamId::EqBand5Gain, 4.0);
+
+        let mut slot = EffectSlot::new(EffectType::Eq);
+        slot.set_param(EffectParamId::EqBand2Gain, -9.0);
+        slot.set_param(EffectParamId::EqBand5Gain, 4.0);
+        let bands: Vec<EqBand> = (0..EQ_BANDS).map(|b| EqBand::from_slot(&slot, b)).collect();
+
+        for freq in [60.0, 1000.0, 12000.0] {
+            let predicted = response_db(&bands, freq, TEST_SAMPLE_RATE);
+            fx.reset();
+            let measured = measured_gain_db(&mut fx, freq);
+            assert!(
+                (predicted - measured).abs() < 0.5,
+                "{} Hz: predicted {} measured {}",
+                freq,
+                predicted,
+                measured
+            );
+        }
+    }
+
+    #[test]
+    fn test_notch_removes_frequency() {
+        let band = EqBand {
+            kind: BandKind::Notch,
+            freq: 1000.0,
+            gain_db: 0.0,
+            q: 2.0,
+        };
+        assert!(response_db(&[band], 1000.0, TEST_SAMPLE_RATE) < -40.0);
+        assert!(response_db(&[band], 100.0, TEST_SAMPLE_RATE).abs() < 0.5);
+    }
+}
@@ -0,0 +1,175 @@
+//! Minimal radix-2 FFT for block convolution
+//!
+//! Iterative in-place Cooley-Tukey on power-of-two sizes with precomputed
+//! twiddles and bit-reversal table, so transforms don't allocate.
+
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+/// Complex number
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl AddAssign for Complex {
+    fn add_assign(&mut self, rhs: Complex) {
+        self.re += rhs.re;
+        self.im += rhs.im;
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// FFT plan for one size
+#[derive(Debug, Clone)]
+pub struct Fft {
+    size: usize,
+    /// exp(-2 pi i k / size) for k in 0..size/2
+    twiddles: Vec<Complex>,
+    /// Bit-reversed index for each position
+    bit_reverse: Vec<usize>,
+}
+
+impl Fft {
+    /// Plan a transform of `size` points (must be a power of two)
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT size must be a power of two");
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -std::f64::consts::TAU * k as f64 / size as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+        let bits = size.trailing_zeros();
+        let bit_reverse = (0..size)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+        Self {
+            size,
+            twiddles,
+            bit_reverse,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Forward transform in place
+    pub fn forward(&self, buf: &mut [Complex]) {
+        debug_assert_eq!(buf.len(), self.size);
+        for i in 0..self.size {
+            let j = self.bit_reverse[i];
+            if i < j {
+                buf.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= self.size {
+            let half = len / 2;
+            let stride = self.size / len;
+            for start in (0..self.size).step_by(len) {
+                for k in 0..half {
+                    let a = buf[start + k];
+                    let b = buf[start + k + half] * self.twiddles[k * stride];
+                    buf[start + k] = a + b;
+                    buf[start + k + half] = a - b;
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    /// Inverse transform in place, scaled so `inverse(forward(x)) == x`
+    pub fn inverse(&self, buf: &mut [Complex]) {
+        buf.iter_mut().for_each(|c| *c = c.conj());
+        self.forward(buf);
+        let scale = 1.0 / self.size as f32;
+        buf.iter_mut()
+            .for_each(|c| *c = Complex::new(c.re * scale, -c.im * scale));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impulse_has_flat_spectrum() {
+        let fft = Fft::new(16);
+        let mut buf = vec![Complex::ZERO; 16];
+        buf[0] = Complex::new(1.0, 0.0);
+        fft.forward(&mut buf);
+        for bin in &buf {
+            assert!((bin.re - 1.0).abs() < 1e-6 && bin.im.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_sine_lands_in_its_bin() {
+        let fft = Fft::new(64);
+        let mut buf: Vec<Complex> = (0..64)
+            .map(|n| Complex::new((std::f32::consts::TAU * 4.0 * n as f32 / 64.0).cos(), 0.0))
+            .collect();
+        fft.forward(&mut buf);
+        assert!((buf[4].re - 32.0).abs() < 1e-3);
+        assert!((buf[60].re - 32.0).abs() < 1e-3);
+        assert!(buf[5].re.abs() < 1e-3 && buf[5].im.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fft = Fft::new(256);
+        let original: Vec<Complex> = (0..256)
+            .map(|n| Complex::new((n as f32 * 0.37).sin(), (n as f32 * 0.11).cos()))
+            .collect();
+        let mut buf = original.clone();
+        fft.forward(&mut buf);
+        fft.inverse(&mut buf);
+        for (a, b) in buf.iter().zip(&original) {
+            assert!((a.re - b.re).abs() < 1e-4 && (a.im - b.im).abs() < 1e-4);
+        }
+    }
+}
@@ -1,10 +1,25 @@
-//! Simple State Variable Filter (SVF) effect
+//! Resonant multimode filter effect
 //!
-//! A clean filter with low-pass, high-pass, and band-pass modes.
-//! No resonance - just smooth frequency cutoff.
+//! Two filter models share the same controls:
+//! - SVF: a trapezoidal (zero-delay feedback) state variable filter, clean and
+//!   stable under fast cutoff modulation
+//! - Ladder: four cascaded one-pole stages with saturated feedback, for a
+//!   fatter, self-oscillation-prone resonance
+//!
+//! Both offer low-pass, high-pass, band-pass, notch and peak responses at a
+//! 12 dB or 24 dB per octave slope.
 
 use crate::effects::{Effect, EffectParamId, EffectType};
 
+/// Lowest selectable Q
+pub const MIN_Q: f32 = 0.5;
+/// Highest selectable Q
+pub const MAX_Q: f32 = 20.0;
+/// Q of a Butterworth (maximally flat) response
+const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+/// Ladder feedback just short of self-oscillation
+const MAX_LADDER_FEEDBACK: f32 = 3.95;
+
 /// Filter mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)] // LowPass/HighPass/BandPass are standard filter terminology
@@ -12,6 +27,8 @@ pub enum FilterMode {
     LowPass = 0,
     HighPass = 1,
     BandPass = 2,
+    Notch = 3,
+    Peak = 4,
 }
 
 impl From<f32> for FilterMode {
@@ -19,7 +36,103 @@ impl From<f32> for FilterMode {
         match value as u32 {
             0 => FilterMode::LowPass,
             1 => FilterMode::HighPass,
-            _ => FilterMode::BandPass,
+            2 => FilterMode::BandPass,
+            3 => FilterMode::Notch,
+            _ => FilterMode::Peak,
+        }
+    }
+}
+
+/// Filter slope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterSlope {
+    /// 12 dB per octave (two poles)
+    Db12 = 0,
+    /// 24 dB per octave (four poles)
+    Db24 = 1,
+}
+
+impl From<f32> for FilterSlope {
+    fn from(value: f32) -> Self {
+        match value as u32 {
+            0 => FilterSlope::Db12,
+            _ => FilterSlope::Db24,
+        }
+    }
+}
+
+/// Filter circuit model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterModel {
+    Svf = 0,
+    Ladder = 1,
+}
+
+impl From<f32> for FilterModel {
+    fn from(value: f32) -> Self {
+        match value as u32 {
+            0 => FilterModel::Svf,
+            _ => FilterModel::Ladder,
+        }
+    }
+}
+
+/// Map the deprecated 0.0-1.0 resonance amount onto a Q value
+///
+/// 0.0 gives the flat Butterworth response the old resonance-free filter had,
+/// 1.0 gives the maximum Q, with an exponential curve in between.
+pub fn q_from_legacy_resonance(resonance: f32) -> f32 {
+    let r = resonance.clamp(0.0, 1.0);
+    (BUTTERWORTH_Q * (MAX_Q / BUTTERWORTH_Q).powf(r)).clamp(MIN_Q, MAX_Q)
+}
+
+/// Coefficients for one trapezoidal SVF stage
+#[derive(Debug, Clone, Copy, Default)]
+struct SvfCoefs {
+    /// Damping, 1 / Q
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+}
+
+impl SvfCoefs {
+    fn new(g: f32, q: f32) -> Self {
+        let k = 1.0 / q;
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        Self { k, a1, a2, a3 }
+    }
+}
+
+/// State of one trapezoidal SVF stage
+#[derive(Debug, Clone, Copy, Default)]
+struct SvfStage {
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl SvfStage {
+    /// Run one sample and return the selected response
+    #[inline]
+    fn process(&mut self, input: f32, c: &SvfCoefs, mode: FilterMode) -> f32 {
+        let v3 = input - self.ic2eq;
+        let v1 = c.a1 * self.ic1eq + c.a2 * v3;
+        let v2 = self.ic2eq + c.a2 * self.ic1eq + c.a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let low = v2;
+        let band = v1;
+        let high = input - c.k * band - low;
+        match mode {
+            FilterMode::LowPass => low,
+            FilterMode::HighPass => high,
+            // Scaled by k for unity gain at the centre frequency
+            FilterMode::BandPass => c.k * band,
+            FilterMode::Notch => low + high,
+            FilterMode::Peak => low - high,
         }
     }
 }
@@ -27,26 +140,47 @@ impl From<f32> for FilterMode {
 /// Per-channel filter state
 #[derive(Debug, Clone, Default)]
 struct FilterState {
-    /// Low-pass output
-    low: f32,
-    /// Band-pass output
-    band: f32,
+    /// SVF stages (the second only runs at 24 dB)
+    svf: [SvfStage; 2],
+    /// Ladder one-pole stage outputs
+    ladder: [f32; 4],
 }
 
-/// Simple State Variable Filter effect (no resonance)
+impl FilterState {
+    fn is_finite(&self) -> bool {
+        self.svf
+            .iter()
+            .all(|s| s.ic1eq.is_finite() && s.ic2eq.is_finite())
+            && self.ladder.iter().all(|s| s.is_finite())
+    }
+}
+
+/// Resonant multimode filter effect
 pub struct FilterEffect {
     /// Sample rate in Hz
     sample_rate: f32,
     /// Cutoff frequency in Hz
     cutoff: f32,
+    /// Resonance as Q
+    q: f32,
     /// Filter mode
     mode: FilterMode,
+    /// Filter slope
+    slope: FilterSlope,
+    /// Filter circuit model
+    model: FilterModel,
     /// Left channel state
     state_l: FilterState,
     /// Right channel state
     state_r: FilterState,
-    /// Precomputed coefficient: 2 * sin(pi * cutoff / sample_rate)
-    g: f32,
+    /// Resonant SVF stage
+    svf_main: SvfCoefs,
+    /// Flat second SVF stage for the 24 dB slope
+    svf_second: SvfCoefs,
+    /// Ladder one-pole coefficient: 1 - exp(-2 * pi * cutoff / sample_rate)
+    ladder_g: f32,
+    /// Ladder feedback amount derived from Q
+    ladder_feedback: f32,
 }
 
 impl FilterEffect {
@@ -55,10 +189,16 @@ impl FilterEffect {
         let mut effect = Self {
             sample_rate,
             cutoff: 1000.0,
+            q: BUTTERWORTH_Q,
             mode: FilterMode::LowPass,
+            slope: FilterSlope::Db12,
+            model: FilterModel::Svf,
             state_l: FilterState::default(),
             state_r: FilterState::default(),
-            g: 0.0,
+            svf_main: SvfCoefs::default(),
+            svf_second: SvfCoefs::default(),
+            ladder_g: 0.0,
+            ladder_feedback: 0.0,
         };
         effect.update_coefficients();
         effect
@@ -68,41 +208,72 @@ impl FilterEffect {
     fn update_coefficients(&mut self) {
         // Clamp cutoff to valid range (up to ~20kHz at 44.1kHz)
         let cutoff = self.cutoff.clamp(20.0, self.sample_rate * 0.45);
-
-        // Simple SVF coefficient: g = 2 * sin(pi * fc / fs)
-        // With no resonance (k=sqrt(2) for Butterworth response), filter is always stable
         let omega = std::f32::consts::PI * cutoff / self.sample_rate;
-        self.g = (2.0 * omega.sin()).min(1.0);
+
+        // Prewarped integrator gain keeps the cutoff exact up to Nyquist
+        let g = omega.tan();
+        self.svf_main = SvfCoefs::new(g, self.q);
+        self.svf_second = SvfCoefs::new(g, BUTTERWORTH_Q);
+
+        self.ladder_g = 1.0 - (-2.0 * omega).exp();
+        // Q at Butterworth means no feedback, rising towards self-oscillation
+        self.ladder_feedback =
+            (4.0 * (1.0 - BUTTERWORTH_Q / self.q)).clamp(0.0, MAX_LADDER_FEEDBACK);
     }
 
-    /// Process a single sample through the SVF
+    /// Process a single sample through the SVF model
     #[inline]
-    fn process_sample(input: f32, state: &mut FilterState, g: f32, mode: FilterMode) -> f32 {
-        // Check for corrupted state and reset if needed
-        if !state.band.is_finite() {
-            state.band = 0.0;
-        }
-        if !state.low.is_finite() {
-            state.low = 0.0;
+    fn process_svf(&self, input: f32, state: &mut FilterState) -> f32 {
+        let out = state.svf[0].process(input, &self.svf_main, self.mode);
+        match self.slope {
+            FilterSlope::Db12 => out,
+            FilterSlope::Db24 => state.svf[1].process(out, &self.svf_second, self.mode),
         }
+    }
 
-        // Butterworth SVF (k = sqrt(2) ≈ 1.414 for flat passband)
-        const K: f32 = 1.414;
-
-        // Standard Chamberlin SVF
-        let high = input - K * state.band - state.low;
-        let band = state.band + g * high;
-        let low = state.low + g * band;
-
-        // Update state
-        state.band = band;
-        state.low = low;
-
-        // Select output based on mode
-        let output = match mode {
+    /// Process a single sample through the ladder model
+    #[inline]
+    fn process_ladder(&self, input: f32, state: &mut FilterState) -> f32 {
+        let g = self.ladder_g;
+        let s = &mut state.ladder;
+
+        // Saturating the feedback path keeps high resonance bounded
+        let y0 = input - self.ladder_feedback * s[3].tanh();
+        s[0] += g * (y0 - s[0]);
+        s[1] += g * (s[0] - s[1]);
+        s[2] += g * (s[1] - s[2]);
+        s[3] += g * (s[2] - s[3]);
+        let [y1, y2, y3, y4] = *s;
+
+        // Tap mixing turns the low-pass cascade into the other responses
+        let (low, high, band) = match self.slope {
+            FilterSlope::Db12 => (y2, y0 - 2.0 * y1 + y2, 2.0 * (y1 - y2)),
+            FilterSlope::Db24 => (
+                y4,
+                y0 - 4.0 * y1 + 6.0 * y2 - 4.0 * y3 + y4,
+                4.0 * (y2 - 2.0 * y3 + y4),
+            ),
+        };
+        match self.mode {
             FilterMode::LowPass => low,
             FilterMode::HighPass => high,
             FilterMode::BandPass => band,
+            FilterMode::Notch => low + high,
+            FilterMode::Peak => low - high,
+        }
+    }
+
+    /// Process a single sample through the selected model
+    #[inline]
+    fn process_sample(&self, input: f32, state: &mut FilterState) -> f32 {
+        // Check for corrupted state and reset if needed
+        if !state.is_finite() {
+            *state = FilterState::default();
+        }
+
+        let output = match self.model {
+            FilterModel::Svf => self.process_svf(input, state),
+            FilterModel::Ladder => self.process_ladder(input, state),
         };
 
         if output.is_finite() {
@@ -115,13 +286,16 @@ impl FilterEffect {
 
 impl Effect for FilterEffect {
     fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
-        let g = self.g;
-        let mode = self.mode;
+        let mut state_l = std::mem::take(&mut self.state_l);
+        let mut state_r = std::mem::take(&mut self.state_r);
 
         for (l, r) in left.iter_mut().zip(right.iter_mut()) {
-            *l = Self::process_sample(*l, &mut self.state_l, g, mode);
-            *r = Self::process_sample(*r, &mut self.state_r, g, mode);
+            *l = self.process_sample(*l, &mut state_l);
+            *r = self.process_sample(*r, &mut state_r);
         }
+
+        self.state_l = state_l;
+        self.state_r = state_r;
     }
 
     fn set_param(&mut self, id: EffectParamId, value: f32) {
@@ -133,7 +307,21 @@ impl Effect for FilterEffect {
             EffectParamId::FilterMode => {
                 self.mode = FilterMode::from(value);
             }
-            _ => {} // Ignore non-filter parameters (resonance removed)
+            EffectParamId::FilterQ => {
+                self.q = value.clamp(MIN_Q, MAX_Q);
+                self.update_coefficients();
+            }
+            EffectParamId::FilterSlope => {
+                self.slope = FilterSlope::from(value);
+            }
+            EffectParamId::FilterModel => {
+                self.model = FilterModel::from(value);
+            }
+            EffectParamId::FilterResonance => {
+                self.q = q_from_legacy_resonance(value);
+                self.update_coefficients();
+            }
+            _ => {} // Ignore non-filter parameters
         }
     }
 
@@ -141,6 +329,9 @@ impl Effect for FilterEffect {
         match id {
             EffectParamId::FilterCutoff => self.cutoff,
             EffectParamId::FilterMode => self.mode as u32 as f32,
+            EffectParamId::FilterQ => self.q,
+            EffectParamId::FilterSlope => self.slope as u32 as f32,
+            EffectParamId::FilterModel => self.model as u32 as f32,
             _ => 0.0,
         }
     }
@@ -167,6 +358,7 @@ impl Effect for FilterEffect {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::effects::test_helpers::*;
 
     #[test]
     fn filter_produces_finite_output_at_max_cutoff() {
@@ -339,4 +531,112 @@ mod tests {
             max_delta
         );
     }
+
+    /// Steady-state RMS of a sine through a configured filter
+    fn sine_rms_through(filter: &mut FilterEffect, frequency: f32) -> f32 {
+        let mut left = generate_sine(8820, frequency, TEST_SAMPLE_RATE);
+        let mut right = left.clone();
+        filter.process(&mut left, &mut right);
+        calculate_rms(&left[4410..])
+    }
+
+    fn configured(model: f32, mode: f32, slope: f32, q: f32) -> FilterEffect {
+        let mut filter = FilterEffect::new(TEST_SAMPLE_RATE);
+        filter.set_param(EffectParamId::FilterCutoff, 1000.0);
+        filter.set_param(EffectParamId::FilterModel, model);
+        filter.set_param(EffectParamId::FilterMode, mode);
+        filter.set_param(EffectParamId::FilterSlope, slope);
+        filter.set_param(EffectParamId::FilterQ, q);
+        filter
+    }
+
+    #[test]
+    fn test_ladder_reset() {
+        let mut effect = create_test_effect(EffectType::Filter);
+        effect.set_param(EffectParamId::FilterModel, 1.0);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_resonance_boosts_cutoff_frequency() {
+        for model in [0.0, 1.0] {
+            let flat = sine_rms_through(&mut configured(model, 0.0, 0.0, BUTTERWORTH_Q), 1000.0);
+            let resonant = sine_rms_through(&mut configured(model, 0.0, 0.0, 8.0), 1000.0);
+            assert!(
+                resonant > flat * 2.0,
+                "model {} resonance should boost the cutoff (flat {}, resonant {})",
+                model,
+                flat,
+                resonant
+            );
+        }
+    }
+
+    #[test]
+    fn test_24db_slope_is_steeper() {
+        for model in [0.0, 1.0] {
+            let gentle = sine_rms_through(&mut configured(model, 0.0, 0.0, BUTTERWORTH_Q), 8000.0);
+            let steep = sine_rms_through(&mut configured(model, 0.0, 1.0, BUTTERWORTH_Q), 8000.0);
+            assert!(
+                steep < gentle * 0.25,
+                "model {} 24 dB should cut harder (12 dB {}, 24 dB {})",
+                model,
+                gentle,
+                steep
+            );
+        }
+    }
+
+    #[test]
+    fn test_notch_removes_cutoff_and_keeps_the_rest() {
+        let at_cutoff = sine_rms_through(&mut configured(0.0, 3.0, 0.0, 2.0), 1000.0);
+        let away = sine_rms_through(&mut configured(0.0, 3.0, 0.0, 2.0), 100.0);
+        assert!(at_cutoff < 0.05, "notch left {} at the cutoff", at_cutoff);
+        assert!(
+            away > 0.6,
+            "notch removed too much away from cutoff: {}",
+            away
+        );
+    }
+
+    #[test]
+    fn test_peak_emphasises_cutoff() {
+        let at_cutoff = sine_rms_through(&mut configured(0.0, 4.0, 0.0, 4.0), 1000.0);
+        let away = sine_rms_through(&mut configured(0.0, 4.0, 0.0, 4.0), 100.0);
+        assert!(
+            at_cutoff > away * 2.0,
+            "peak should emphasise the cutoff (at {}, away {})",
+            at_cutoff,
+            away
+        );
+    }
+
+    #[test]
+    fn test_ladder_stays_bounded_at_max_resonance() {
+        for mode in 0..5 {
+            let mut filter = configured(1.0, mode as f32, 1.0, MAX_Q);
+            filter.set_param(EffectParamId::FilterCutoff, 15000.0);
+            let mut left = generate_noise(44100, 7);
+            let mut right = left.clone();
+            filter.process(&mut left, &mut right);
+            let peak = calculate_peak(&left);
+            assert!(
+                peak.is_finite() && peak < 50.0,
+                "mode {} ran away: {}",
+                mode,
+                peak
+            );
+        }
+    }
+
+    #[test]
+    fn test_legacy_resonance_maps_onto_q() {
+        assert!((q_from_legacy_resonance(0.0) - BUTTERWORTH_Q).abs() < 1e-6);
+        assert!((q_from_legacy_resonance(1.0) - MAX_Q).abs() < 1e-3);
+        assert!(q_from_legacy_resonance(0.5) > q_from_legacy_resonance(0.25));
+
+        let mut filter = FilterEffect::new(TEST_SAMPLE_RATE);
+        filter.set_param(EffectParamId::FilterResonance, 1.0);
+        assert!((filter.get_param(EffectParamId::FilterQ) - MAX_Q).abs() < 1e-3);
+    }
 }
@@ -0,0 +1,174 @@
+//! Stereo flanger effect
+//!
+//! A very short modulated delay with feedback. Negative feedback gives the
+//! hollow, odd-harmonic comb; positive feedback the classic jet sweep.
+
+use crate::effects::modulation::{FractionalDelayLine, Lfo, ModulationParams};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Delay at the bottom of the sweep in ms
+const BASE_DELAY_MS: f32 = 0.5;
+/// Sweep width at full depth in ms
+const MAX_SWEEP_MS: f32 = 5.0;
+
+/// Stereo flanger effect
+pub struct FlangerEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Tempo in BPM (for synced rates)
+    bpm: f64,
+    /// Rate, depth, feedback, spread and mix
+    params: ModulationParams,
+    /// Sweep LFO
+    lfo: Lfo,
+    /// Left channel delay line
+    delay_l: FractionalDelayLine,
+    /// Right channel delay line
+    delay_r: FractionalDelayLine,
+}
+
+impl FlangerEffect {
+    /// Create a new flanger effect
+    pub fn new(sample_rate: f32, bpm: f64) -> Self {
+        let mut effect = Self {
+            sample_rate,
+            bpm,
+            params: ModulationParams::new(EffectType::Flanger),
+            lfo: Lfo::default(),
+            delay_l: Self::delay_line(sample_rate),
+            delay_r: Self::delay_line(sample_rate),
+        };
+        effect.update_lfo();
+        effect
+    }
+
+    fn delay_line(sample_rate: f32) -> FractionalDelayLine {
+        let max_ms = BASE_DELAY_MS + MAX_SWEEP_MS + 1.0;
+        FractionalDelayLine::new((max_ms / 1000.0 * sample_rate) as usize + 2)
+    }
+
+    fn update_lfo(&mut self) {
+        self.lfo
+            .set_frequency(self.params.lfo_hz(self.bpm), self.sample_rate);
+    }
+
+    /// Delay in samples for an LFO value
+    #[inline]
+    fn delay_samples(&self, lfo: f32) -> f32 {
+        let sweep = MAX_SWEEP_MS * self.params.depth * (0.5 + 0.5 * lfo);
+        (BASE_DELAY_MS + sweep) / 1000.0 * self.sample_rate
+    }
+}
+
+impl Effect for FlangerEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let spread = self.params.spread_cycles();
+        let feedback = self.params.feedback;
+        let mix = self.params.mix;
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let wet_l = self.delay_l.read(self.delay_samples(self.lfo.value(0.0)));
+            let wet_r = self
+                .delay_r
+                .read(self.delay_samples(self.lfo.value(spread)));
+            self.lfo.advance();
+
+            self.delay_l.write(*l + wet_l * feedback);
+            self.delay_r.write(*r + wet_r * feedback);
+
+            *l = *l * (1.0 - mix) + wet_l * mix;
+            *r = *r * (1.0 - mix) + wet_r * mix;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        if self.params.set(id, value) {
+            self.update_lfo();
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        self.params.get(id)
+    }
+
+    fn reset(&mut self) {
+        self.delay_l.clear();
+        self.delay_r.clear();
+        self.lfo.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.delay_l = Self::delay_line(sample_rate);
+        self.delay_r = Self::delay_line(sample_rate);
+        self.update_lfo();
+    }
+
+    fn set_tempo(&mut self, bpm: f64) {
+        self.bpm = bpm;
+        self.update_lfo();
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Flanger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_flanger_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Flanger);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_flanger_default_params() {
+        let effect = create_test_effect(EffectType::Flanger);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_flanger_reset() {
+        let mut effect = create_test_effect(EffectType::Flanger);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_feedback_repeats_impulse() {
+        let mut fx = FlangerEffect::new(TEST_SAMPLE_RATE, TEST_BPM);
+        fx.set_param(EffectParamId::ModDepth, 0.0);
+        fx.set_param(EffectParamId::ModMix, 1.0);
+        fx.set_param(EffectParamId::ModFeedback, -0.8);
+
+        let mut left = generate_impulse(256);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+
+        // 0.5 ms at 44.1 kHz, read one sample after the write
+        let period = (BASE_DELAY_MS / 1000.0 * TEST_SAMPLE_RATE) as usize + 1;
+        assert!(
+            (left[period] - 1.0).abs() < 0.1,
+            "first echo {}",
+            left[period]
+        );
+        assert!(
+            (left[2 * period] + 0.8).abs() < 0.1,
+            "second echo should be inverted: {}",
+            left[2 * period]
+        );
+    }
+
+    #[test]
+    fn test_max_feedback_stays_bounded() {
+        let mut fx = FlangerEffect::new(TEST_SAMPLE_RATE, TEST_BPM);
+        fx.set_param(EffectParamId::ModFeedback, 0.95);
+        let mut left = generate_noise(44100, 3);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert!(calculate_peak(&left) < 25.0);
+    }
+}
@@ -0,0 +1,372 @@
+//! Noise gate effect
+//!
+//! Stereo-linked gate with separate open and close thresholds (hysteresis),
+//! a hold stage so the gate doesn't chatter on decaying tails, and a range
+//! control that sets how far a closed gate attenuates. The detector can be
+//! keyed from another mixer track, e.g. to gate a bass line with the kick.
+
+use crate::effects::compressor::{db_to_linear, linear_to_db, time_coef, SIDECHAIN_CHOICES};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Release time of the level detector (ms), long enough to ride over
+/// zero crossings of low notes
+const DETECTOR_RELEASE_MS: f32 = 10.0;
+
+/// Gate state, shown in the effect editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GateState {
+    /// Signal above the open threshold, gate passing audio
+    Open,
+    /// Signal fell below the close threshold, holding open before release
+    Hold,
+    /// Gate releasing towards (or at) full range attenuation
+    #[default]
+    Closed,
+}
+
+impl GateState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GateState::Open => "Open",
+            GateState::Hold => "Hold",
+            GateState::Closed => "Closed",
+        }
+    }
+}
+
+/// Noise gate effect
+pub struct GateEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Level (dB) at which the gate opens
+    threshold_db: f32,
+    /// How far below the threshold (dB) the level must fall to close
+    hysteresis_db: f32,
+    /// Attack time in ms
+    attack_ms: f32,
+    /// Hold time in ms
+    hold_ms: f32,
+    /// Release time in ms
+    release_ms: f32,
+    /// Attenuation of a closed gate in dB
+    range_db: f32,
+    /// Mixer track keying the gate (None = own signal)
+    sidechain: Option<usize>,
+    /// Attack smoothing coefficient
+    attack_coef: f32,
+    /// Release smoothing coefficient
+    release_coef: f32,
+    /// Detector release coefficient
+    detector_coef: f32,
+    /// Peak level follower
+    detector: f32,
+    /// Current gain (linear, 1.0 = open)
+    gain: f32,
+    /// Samples left in the hold stage
+    hold_remaining: usize,
+    /// Current state
+    state: GateState,
+    /// Largest attenuation in the last processed block, for metering
+    block_reduction_db: f32,
+}
+
+impl GateEffect {
+    /// Create a new noise gate effect
+    pub fn new(sample_rate: f32) -> Self {
+        let mut effect = Self {
+            sample_rate,
+            threshold_db: -40.0,
+            hysteresis_db: 4.0,
+            attack_ms: 0.5,
+            hold_ms: 20.0,
+            release_ms: 100.0,
+            range_db: 80.0,
+            sidechain: None,
+            attack_coef: 0.0,
+            release_coef: 0.0,
+            detector_coef: 0.0,
+            detector: 0.0,
+            gain: db_to_linear(-80.0),
+            hold_remaining: 0,
+            state: GateState::Closed,
+            block_reduction_db: 0.0,
+        };
+        effect.update_coefficients();
+        effect
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coef = time_coef(self.attack_ms, self.sample_rate);
+        self.release_coef = time_coef(self.release_ms, self.sample_rate);
+        self.detector_coef = time_coef(DETECTOR_RELEASE_MS, self.sample_rate);
+    }
+
+    fn hold_samples(&self) -> usize {
+        (self.hold_ms * 0.001 * self.sample_rate) as usize
+    }
+
+    /// Advance the state machine for one detector level
+    fn update_state(&mut self, level_db: f32) {
+        let open_db = self.threshold_db;
+        let close_db = self.threshold_db - self.hysteresis_db;
+
+        self.state = match self.state {
+            GateState::Closed if level_db >= open_db => GateState::Open,
+            GateState::Closed => GateState::Closed,
+            GateState::Open | GateState::Hold if level_db >= close_db => GateState::Open,
+            GateState::Open => {
+                self.hold_remaining = self.hold_samples();
+                GateState::Hold
+            }
+            GateState::Hold => {
+                if self.hold_remaining == 0 {
+                    GateState::Closed
+                } else {
+                    self.hold_remaining -= 1;
+                    GateState::Hold
+                }
+            }
+        };
+    }
+
+    /// Gate `left`/`right`, detecting on `key` if given or on the input itself
+    fn gate(&mut self, left: &mut [f32], right: &mut [f32], key: Option<(&[f32], &[f32])>) {
+        let floor = db_to_linear(-self.range_db);
+        let mut min_gain: f32 = 1.0;
+
+        for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            let key_level = match key {
+                Some((key_left, key_right)) => key_left[i].abs().max(key_right[i].abs()),
+                None => l.abs().max(r.abs()),
+            };
+            self.detector = key_level.max(self.detector * self.detector_coef);
+            self.update_state(linear_to_db(self.detector));
+
+            let (target, coef) = match self.state {
+                GateState::Open | GateState::Hold => (1.0, self.attack_coef),
+                GateState::Closed => (floor, self.release_coef),
+            };
+            self.gain = coef * self.gain + (1.0 - coef) * target;
+            min_gain = min_gain.min(self.gain);
+
+            *l *= self.gain;
+            *r *= self.gain;
+        }
+
+        self.block_reduction_db = -linear_to_db(min_gain);
+    }
+
+    /// Current state of the gate
+    pub fn state(&self) -> GateState {
+        self.state
+    }
+}
+
+impl Effect for GateEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.gate(left, right, None);
+    }
+
+    fn process_with_sidechain(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        key_left: &[f32],
+        key_right: &[f32],
+    ) {
+        self.gate(left, right, Some((key_left, key_right)));
+    }
+
+    fn sidechain_source(&self) -> Option<usize> {
+        self.sidechain
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        self.block_reduction_db
+    }
+
+    fn gate_state(&self) -> Option<GateState> {
+        Some(self.state)
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        match id {
+            EffectParamId::GateThreshold => self.threshold_db = value.clamp(-80.0, 0.0),
+            EffectParamId::GateHysteresis => self.hysteresis_db = value.clamp(0.0, 12.0),
+            EffectParamId::GateAttack => {
+                self.attack_ms = value.clamp(0.01, 50.0);
+                self.update_coefficients();
+            }
+            EffectParamId::GateHold => self.hold_ms = value.clamp(0.0, 500.0),
+            EffectParamId::GateRelease => {
+                self.release_ms = value.clamp(5.0, 2000.0);
+                self.update_coefficients();
+            }
+            EffectParamId::GateRange => self.range_db = value.clamp(0.0, 80.0),
+            EffectParamId::GateSidechain => {
+                let track = (value.max(0.0) as usize).min(SIDECHAIN_CHOICES.len() - 1);
+                self.sidechain = (track > 0).then_some(track);
+            }
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::GateThreshold => self.threshold_db,
+            EffectParamId::GateHysteresis => self.hysteresis_db,
+            EffectParamId::GateAttack => self.attack_ms,
+            EffectParamId::GateHold => self.hold_ms,
+            EffectParamId::GateRelease => self.release_ms,
+            EffectParamId::GateRange => self.range_db,
+            EffectParamId::GateSidechain => self.sidechain.unwrap_or(0) as f32,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.detector = 0.0;
+        self.gain = db_to_linear(-self.range_db);
+        self.hold_remaining = 0;
+        self.state = GateState::Closed;
+        self.block_reduction_db = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Gate doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Gate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_gate_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Gate);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_gate_default_params() {
+        let effect = create_test_effect(EffectType::Gate);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_gate_reset() {
+        let mut effect = create_test_effect(EffectType::Gate);
+        assert_reset_clears_state(effect.as_mut());
+        assert_eq!(effect.gate_state(), Some(GateState::Closed));
+    }
+
+    #[test]
+    fn test_loud_signal_opens_gate() {
+        let mut fx = GateEffect::new(TEST_SAMPLE_RATE);
+        let mut left = generate_sine(4410, 220.0, TEST_SAMPLE_RATE);
+        let mut right = left.clone();
+        let input = left.clone();
+        fx.process(&mut left, &mut right);
+
+        assert_eq!(fx.state(), GateState::Open);
+        let tail = calculate_rms(&left[2205..]) / calculate_rms(&input[2205..]);
+        assert!((tail - 1.0).abs() < 0.01, "open gate gain {}", tail);
+    }
+
+    #[test]
+    fn test_quiet_signal_attenuated_by_range() {
+        let mut fx = GateEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::GateRange, 20.0);
+        fx.reset();
+
+        // -60 dBFS noise floor, below the -40 dB threshold
+        let mut left = generate_dc(4410, 0.001);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+
+        assert_eq!(fx.state(), GateState::Closed);
+        assert!((left[4409] - 0.0001).abs() < 1e-6, "{}", left[4409]);
+        assert!((fx.gain_reduction_db() - 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_hold_then_release() {
+        let mut fx = GateEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::GateHold, 50.0);
+        fx.set_param(EffectParamId::GateRelease, 5.0);
+
+        let mut left = generate_dc(441, 0.5);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(fx.state(), GateState::Open);
+
+        // 60 ms of silence: the detector has decayed, but the gate is holding
+        let mut left = vec![0.0; 2646];
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(fx.state(), GateState::Hold);
+
+        // Past the hold time: closed
+        let mut left = vec![0.0; 4410];
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(fx.state(), GateState::Closed);
+    }
+
+    #[test]
+    fn test_hysteresis_keeps_gate_open_between_thresholds() {
+        let mut fx = GateEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::GateThreshold, -20.0);
+        fx.set_param(EffectParamId::GateHysteresis, 10.0);
+        fx.set_param(EffectParamId::GateHold, 0.0);
+
+        // -26 dB: below open threshold, above close threshold (-30 dB)
+        let between = db_to_linear(-26.0);
+        let mut left = generate_dc(4410, between);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(
+            fx.state(),
+            GateState::Closed,
+            "must not open below threshold"
+        );
+
+        let mut left = generate_dc(441, 0.5);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        let mut left = generate_dc(4410, between);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        assert_eq!(
+            fx.state(),
+            GateState::Open,
+            "must stay open above close threshold"
+        );
+    }
+
+    #[test]
+    fn test_sidechain_key_opens_gate() {
+        let mut fx = GateEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::GateSidechain, 2.0);
+        assert_eq!(fx.sidechain_source(), Some(2));
+
+        // Quiet bass below threshold, loud kick on the key
+        let mut left = generate_dc(4410, 0.005);
+        let mut right = left.clone();
+        let key = generate_dc(4410, 1.0);
+        fx.process_with_sidechain(&mut left, &mut right, &key, &key);
+
+        assert_eq!(fx.state(), GateState::Open);
+        assert!((left[4409] - 0.005).abs() < 1e-5);
+    }
+}
@@ -6,23 +6,44 @@
 // Allow dead code during initial implementation - will be used by audio thread
 #![allow(dead_code)]
 
+pub mod bitcrusher;
+pub mod chorus;
+pub mod compressor;
+pub mod convolution;
 pub mod delay;
+pub mod distortion;
 pub mod enhancer;
+pub mod eq;
+pub mod fft;
 pub mod filter;
+pub mod flanger;
+pub mod gate;
+pub mod modulation;
+pub mod oversampling;
+pub mod phaser;
+pub mod plugin;
+pub mod preset;
 pub mod reverb;
 pub mod test_helpers;
+pub mod transient;
 
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use crate::plugin_host::transport::PluginTransport;
+use crate::plugin_host::ParamChange;
+use convolution::{ConvolutionKernel, ImpulseResponse, ImpulseResponseError};
+
 /// Number of effect slots per mixer track
 pub const EFFECT_SLOTS: usize = 8;
 
 /// Effect types available in the DAW
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EffectType {
-    /// Simple state-variable filter (LP/HP/BP)
+    /// Resonant multimode filter (SVF or ladder)
     Filter,
     /// Tempo-synced delay with feedback
     Delay,
@@ -30,6 +51,28 @@ pub enum EffectType {
     Reverb,
     /// Enhancer - saturation, excitation, compression
     Enhancer,
+    /// Compressor with optional sidechain input
+    Compressor,
+    /// Multi-band parametric EQ
+    Eq,
+    /// Modulated-delay chorus
+    Chorus,
+    /// Short modulated delay with feedback
+    Flanger,
+    /// Swept all-pass phaser
+    Phaser,
+    /// Waveshaping distortion (soft/hard clip, foldback, tube)
+    Distortion,
+    /// Bit depth and sample rate reducer
+    Bitcrusher,
+    /// Convolution reverb using a WAV impulse response
+    Convolution,
+    /// Noise gate with hysteresis, hold and optional sidechain input
+    Gate,
+    /// Attack and sustain emphasis
+    TransientShaper,
+    /// CLAP audio effect loaded from the plugins directory
+    Plugin,
 }
 
 impl EffectType {
@@ -40,6 +83,17 @@ impl EffectType {
             EffectType::Delay => "Delay",
             EffectType::Reverb => "Reverb",
             EffectType::Enhancer => "Enhancer",
+            EffectType::Compressor => "Compressor",
+            EffectType::Eq => "EQ",
+            EffectType::Chorus => "Chorus",
+            EffectType::Flanger => "Flanger",
+            EffectType::Phaser => "Phaser",
+            EffectType::Distortion => "Distortion",
+            EffectType::Bitcrusher => "Bitcrusher",
+            EffectType::Convolution => "Convolution",
+            EffectType::Gate => "Noise Gate",
+            EffectType::TransientShaper => "Transient Shaper",
+            EffectType::Plugin => "CLAP Plugin",
         }
     }
 
@@ -50,6 +104,17 @@ impl EffectType {
             EffectType::Delay,
             EffectType::Reverb,
             EffectType::Enhancer,
+            EffectType::Compressor,
+            EffectType::Eq,
+            EffectType::Chorus,
+            EffectType::Flanger,
+            EffectType::Phaser,
+            EffectType::Distortion,
+            EffectType::Bitcrusher,
+            EffectType::Convolution,
+            EffectType::Gate,
+            EffectType::TransientShaper,
+            EffectType::Plugin,
         ]
     }
 }
@@ -60,8 +125,11 @@ pub enum EffectParamId {
     // Filter parameters
     FilterCutoff,
     FilterMode,
-    /// Deprecated: kept for backward compatibility with saved projects
+    /// Deprecated: 0.0-1.0 resonance from old projects, mapped onto `FilterQ` on load
     FilterResonance,
+    FilterQ,
+    FilterSlope,
+    FilterModel,
 
     // Delay parameters
     DelayTime,
@@ -78,6 +146,85 @@ pub enum EffectParamId {
     // Enhancer parameters
     EnhancerAmount,
     EnhancerMode,
+    EnhancerOversampling,
+
+    // Compressor parameters
+    CompressorThreshold,
+    CompressorRatio,
+    CompressorAttack,
+    CompressorRelease,
+    CompressorKnee,
+    CompressorMakeup,
+    CompressorDetection,
+    CompressorSidechain,
+
+    // EQ parameters (type, frequency, gain and Q per band)
+    EqBand1Type,
+    EqBand1Freq,
+    EqBand1Gain,
+    EqBand1Q,
+    EqBand2Type,
+    EqBand2Freq,
+    EqBand2Gain,
+    EqBand2Q,
+    EqBand3Type,
+    EqBand3Freq,
+    EqBand3Gain,
+    EqBand3Q,
+    EqBand4Type,
+    EqBand4Freq,
+    EqBand4Gain,
+    EqBand4Q,
+    EqBand5Type,
+    EqBand5Freq,
+    EqBand5Gain,
+    EqBand5Q,
+    EqBand6Type,
+    EqBand6Freq,
+    EqBand6Gain,
+    EqBand6Q,
+
+    // Modulation parameters (shared by chorus, flanger and phaser)
+    ModRate,
+    ModSync,
+    ModDivision,
+    ModDepth,
+    ModFeedback,
+    ModSpread,
+    ModMix,
+
+    // Distortion parameters
+    DistortionDrive,
+    DistortionMode,
+    DistortionOutput,
+    DistortionMix,
+    DistortionOversampling,
+
+    // Bitcrusher parameters
+    BitcrusherBits,
+    BitcrusherRate,
+    BitcrusherMix,
+
+    // Convolution reverb parameters
+    ConvolutionPreDelay,
+    ConvolutionTrimStart,
+    ConvolutionLength,
+    ConvolutionStretch,
+    ConvolutionMix,
+
+    // Noise gate parameters
+    GateThreshold,
+    GateHysteresis,
+    GateAttack,
+    GateHold,
+    GateRelease,
+    GateRange,
+    GateSidechain,
+
+    // Transient shaper parameters
+    TransientAttack,
+    TransientSustain,
+    TransientOutput,
 }
 
 impl EffectParamId {
@@ -87,6 +234,9 @@ impl EffectParamId {
             EffectParamId::FilterCutoff => "Cutoff",
             EffectParamId::FilterMode => "Mode",
             EffectParamId::FilterResonance => "Resonance", // Deprecated but kept for compat
+            EffectParamId::FilterQ => "Q",
+            EffectParamId::FilterSlope => "Slope",
+            EffectParamId::FilterModel => "Model",
             EffectParamId::DelayTime => "Time",
             EffectParamId::DelayFeedback => "Feedback",
             EffectParamId::DelayMix => "Mix",
@@ -97,6 +247,69 @@ impl EffectParamId {
             EffectParamId::ReverbMix => "Mix",
             EffectParamId::EnhancerAmount => "Amount",
             EffectParamId::EnhancerMode => "Mode",
+            EffectParamId::EnhancerOversampling => "Oversample",
+            EffectParamId::CompressorThreshold => "Threshold",
+            EffectParamId::CompressorRatio => "Ratio",
+            EffectParamId::CompressorAttack => "Attack",
+            EffectParamId::CompressorRelease => "Release",
+            EffectParamId::CompressorKnee => "Knee",
+            EffectParamId::CompressorMakeup => "Makeup",
+            EffectParamId::CompressorDetection => "Detection",
+            EffectParamId::CompressorSidechain => "Sidechain",
+            EffectParamId::EqBand1Type => "B1 Type",
+            EffectParamId::EqBand1Freq => "B1 Freq",
+            EffectParamId::EqBand1Gain => "B1 Gain",
+            EffectParamId::EqBand1Q => "B1 Q",
+            EffectParamId::EqBand2Type => "B2 Type",
+            EffectParamId::EqBand2Freq => "B2 Freq",
+            EffectParamId::EqBand2Gain => "B2 Gain",
+            EffectParamId::EqBand2Q => "B2 Q",
+            EffectParamId::EqBand3Type => "B3 Type",
+            EffectParamId::EqBand3Freq => "B3 Freq",
+            EffectParamId::EqBand3Gain => "B3 Gain",
+            EffectParamId::EqBand3Q => "B3 Q",
+            EffectParamId::EqBand4Type => "B4 Type",
+            EffectParamId::EqBand4Freq => "B4 Freq",
+            EffectParamId::EqBand4Gain => "B4 Gain",
+            EffectParamId::EqBand4Q => "B4 Q",
+            EffectParamId::EqBand5Type => "B5 Type",
+            EffectParamId::EqBand5Freq => "B5 Freq",
+            EffectParamId::EqBand5Gain => "B5 Gain",
+            EffectParamId::EqBand5Q => "B5 Q",
+            EffectParamId::EqBand6Type => "B6 Type",
+            EffectParamId::EqBand6Freq => "B6 Freq",
+            EffectParamId::EqBand6Gain => "B6 Gain",
+            EffectParamId::EqBand6Q => "B6 Q",
+            EffectParamId::ModRate => "Rate",
+            EffectParamId::ModSync => "Sync",
+            EffectParamId::ModDivision => "Sync Rate",
+            EffectParamId::ModDepth => "Depth",
+            EffectParamId::ModFeedback => "Feedback",
+            EffectParamId::ModSpread => "Spread",
+            EffectParamId::ModMix => "Mix",
+            EffectParamId::DistortionDrive => "Drive",
+            EffectParamId::DistortionMode => "Curve",
+            EffectParamId::DistortionOutput => "Output",
+            EffectParamId::DistortionMix => "Mix",
+            EffectParamId::DistortionOversampling => "Oversample",
+            EffectParamId::BitcrusherBits => "Bits",
+            EffectParamId::BitcrusherRate => "Rate",
+            EffectParamId::BitcrusherMix => "Mix",
+            EffectParamId::ConvolutionPreDelay => "Pre-Delay",
+            EffectParamId::ConvolutionTrimStart => "Trim",
+            EffectParamId::ConvolutionLength => "Length",
+            EffectParamId::ConvolutionStretch => "Stretch",
+            EffectParamId::ConvolutionMix => "Mix",
+            EffectParamId::GateThreshold => "Threshold",
+            EffectParamId::GateHysteresis => "Hysteresis",
+            EffectParamId::GateAttack => "Attack",
+            EffectParamId::GateHold => "Hold",
+            EffectParamId::GateRelease => "Release",
+            EffectParamId::GateRange => "Range",
+            EffectParamId::GateSidechain => "Sidechain",
+            EffectParamId::TransientAttack => "Attack",
+            EffectParamId::TransientSustain => "Sustain",
+            EffectParamId::TransientOutput => "Output",
         }
     }
 }
@@ -165,6 +378,30 @@ pub struct EffectSlot {
     pub bypassed: bool,
     /// Parameter values (param_id -> value)
     pub params: HashMap<EffectParamId, f32>,
+    /// Impulse response file relative to the project's samples directory
+    /// (convolution only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ir_path: Option<String>,
+    /// Impulse response decoded from `ir_path`
+    #[serde(skip)]
+    pub impulse_response: Option<Arc<ImpulseResponse>>,
+    /// CLAP bundle relative to the project's plugins directory, or absolute
+    /// for installed plugins (plugin only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_path: Option<String>,
+    /// Plugin picked from a multi-plugin bundle (the first one if None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    /// Saved plugin parameter values (CLAP param id -> value)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub plugin_params: HashMap<u32, f64>,
+    /// Opaque plugin state from the CLAP state extension (plugin only)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::plugin_host::state::base64_blob"
+    )]
+    pub plugin_state: Option<Vec<u8>>,
 }
 
 impl EffectSlot {
@@ -175,6 +412,32 @@ impl EffectSlot {
             effect_type,
             bypassed: false,
             params,
+            ir_path: None,
+            impulse_response: None,
+            plugin_path: None,
+            plugin_id: None,
+            plugin_params: HashMap::new(),
+            plugin_state: None,
+        }
+    }
+
+    /// Create a slot hosting the CLAP effect at `plugin_path`
+    pub fn plugin(plugin_path: impl Into<String>) -> Self {
+        Self {
+            plugin_path: Some(plugin_path.into()),
+            ..Self::new(EffectType::Plugin)
+        }
+    }
+
+    /// Display name for the slot: the plugin's file stem for CLAP effects,
+    /// the effect type name otherwise
+    pub fn display_name(&self) -> &str {
+        match (&self.effect_type, &self.plugin_path) {
+            (EffectType::Plugin, Some(path)) => Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(path),
+            _ => self.effect_type.name(),
         }
     }
 
@@ -187,6 +450,55 @@ impl EffectSlot {
     pub fn set_param(&mut self, id: EffectParamId, value: f32) {
         self.params.insert(id, value);
     }
+
+    /// Decode the impulse response named by `ir_path` from the samples directory
+    ///
+    /// Clears any previously loaded IR first, so on error the slot is left
+    /// without one but keeps its path.
+    pub fn load_impulse_response(
+        &mut self,
+        samples_dir: &Path,
+    ) -> Result<(), ImpulseResponseError> {
+        self.impulse_response = None;
+        if let Some(path) = &self.ir_path {
+            let ir = ImpulseResponse::load(&samples_dir.join(path))?;
+            self.impulse_response = Some(Arc::new(ir));
+        }
+        Ok(())
+    }
+
+    /// Build the kernel for a convolution slot's IR and shaping params
+    ///
+    /// None for a slot without an IR or for any other effect type. This is
+    /// the slow part of convolution, so call it off the audio thread.
+    pub fn convolution_kernel(&self, sample_rate: f32) -> Option<Box<ConvolutionKernel>> {
+        if self.effect_type != EffectType::Convolution {
+            return None;
+        }
+        let ir = self.impulse_response.as_ref()?;
+        Some(Box::new(ConvolutionKernel::new(
+            ir,
+            &self.params,
+            sample_rate,
+        )))
+    }
+
+    /// Bring a slot saved by an older version up to the current parameter set
+    ///
+    /// Deprecated parameters are mapped onto their replacements and any
+    /// parameter added since the project was saved gets its default.
+    pub fn upgrade_legacy_params(&mut self) {
+        if let Some(resonance) = self.params.remove(&EffectParamId::FilterResonance) {
+            if self.effect_type == EffectType::Filter {
+                self.params
+                    .entry(EffectParamId::FilterQ)
+                    .or_insert(filter::q_from_legacy_resonance(resonance));
+            }
+        }
+        for def in get_param_defs(self.effect_type) {
+            self.params.entry(def.id).or_insert(def.default);
+        }
+    }
 }
 
 /// Get default parameter values for an effect type
@@ -215,10 +527,38 @@ pub fn get_param_defs(effect_type: EffectType) -> Vec<EffectParamDef> {
             EffectParamDef {
                 id: EffectParamId::FilterMode,
                 min: 0.0,
-                max: 2.0,
+                max: 4.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &["LP", "HP", "BP", "Notch", "Peak"],
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::FilterQ,
+                min: filter::MIN_Q,
+                max: filter::MAX_Q,
+                default: 0.707,
+                display: ParamDisplay::Continuous {
+                    unit: "",
+                    decimals: 2,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::FilterSlope,
+                min: 0.0,
+                max: 1.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &["12dB", "24dB"],
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::FilterModel,
+                min: 0.0,
+                max: 1.0,
                 default: 0.0,
                 display: ParamDisplay::Discrete {
-                    choices: &["LP", "HP", "BP"],
+                    choices: &["SVF", "Ladder"],
                 },
             },
         ],
@@ -324,10 +664,469 @@ pub fn get_param_defs(effect_type: EffectType) -> Vec<EffectParamDef> {
                     choices: &["Warm", "Bright", "Punch", "Loud"],
                 },
             },
+            EffectParamDef {
+                id: EffectParamId::EnhancerOversampling,
+                min: 0.0,
+                max: 2.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &oversampling::OVERSAMPLING_CHOICES,
+                },
+            },
         ],
+        EffectType::Compressor => vec![
+            EffectParamDef {
+                id: EffectParamId::CompressorThreshold,
+                min: -60.0,
+                max: 0.0,
+                default: -18.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorRatio,
+                min: 1.0,
+                max: 20.0,
+                default: 4.0,
+                display: ParamDisplay::Continuous {
+                    unit: ":1",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorAttack,
+                min: 0.1,
+                max: 100.0,
+                default: 10.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorRelease,
+                min: 10.0,
+                max: 1000.0,
+                default: 100.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorKnee,
+                min: 0.0,
+                max: 24.0,
+                default: 6.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorMakeup,
+                min: 0.0,
+                max: 24.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorDetection,
+                min: 0.0,
+                max: 1.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &["Peak", "RMS"],
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::CompressorSidechain,
+                min: 0.0,
+                max: 15.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &compressor::SIDECHAIN_CHOICES,
+                },
+            },
+        ],
+        EffectType::Eq => eq::BAND_PARAMS
+            .iter()
+            .zip(eq::BAND_DEFAULTS)
+            .flat_map(|(&[kind, freq, gain, q], (default_kind, default_freq))| {
+                [
+                    EffectParamDef {
+                        id: kind,
+                        min: 0.0,
+                        max: (eq::BAND_KIND_CHOICES.len() - 1) as f32,
+                        default: default_kind as u32 as f32,
+                        display: ParamDisplay::Discrete {
+                            choices: &eq::BAND_KIND_CHOICES,
+                        },
+                    },
+                    EffectParamDef {
+                        id: freq,
+                        min: 20.0,
+                        max: 20000.0,
+                        default: default_freq,
+                        display: ParamDisplay::Continuous {
+                            unit: "Hz",
+                            decimals: 0,
+                        },
+                    },
+                    EffectParamDef {
+                        id: gain,
+                        min: -18.0,
+                        max: 18.0,
+                        default: 0.0,
+                        display: ParamDisplay::Continuous {
+                            unit: "dB",
+                            decimals: 1,
+                        },
+                    },
+                    EffectParamDef {
+                        id: q,
+                        min: 0.1,
+                        max: 10.0,
+                        default: 0.71,
+                        display: ParamDisplay::Continuous {
+                            unit: "",
+                            decimals: 2,
+                        },
+                    },
+                ]
+            })
+            .collect(),
+        EffectType::Chorus => modulation_param_defs(0.8, (0.0, 0.7), 0.0, 90.0),
+        EffectType::Flanger => modulation_param_defs(0.25, (-0.95, 0.95), 0.5, 30.0),
+        EffectType::Phaser => modulation_param_defs(0.5, (0.0, 0.9), 0.5, 90.0),
+        EffectType::Distortion => vec![
+            EffectParamDef {
+                id: EffectParamId::DistortionDrive,
+                min: 0.0,
+                max: 36.0,
+                default: 12.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::DistortionMode,
+                min: 0.0,
+                max: 3.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &["Soft", "Hard", "Fold", "Tube"],
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::DistortionOutput,
+                min: -24.0,
+                max: 6.0,
+                default: -6.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::DistortionMix,
+                min: 0.0,
+                max: 1.0,
+                default: 1.0,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::DistortionOversampling,
+                min: 0.0,
+                max: 2.0,
+                default: 1.0,
+                display: ParamDisplay::Discrete {
+                    choices: &oversampling::OVERSAMPLING_CHOICES,
+                },
+            },
+        ],
+        EffectType::Bitcrusher => vec![
+            EffectParamDef {
+                id: EffectParamId::BitcrusherBits,
+                min: 1.0,
+                max: 16.0,
+                default: 8.0,
+                display: ParamDisplay::Continuous {
+                    unit: " bit",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::BitcrusherRate,
+                min: 200.0,
+                max: 48000.0,
+                default: 11025.0,
+                display: ParamDisplay::Continuous {
+                    unit: "Hz",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::BitcrusherMix,
+                min: 0.0,
+                max: 1.0,
+                default: 1.0,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+        ],
+        EffectType::Convolution => vec![
+            EffectParamDef {
+                id: EffectParamId::ConvolutionPreDelay,
+                min: 0.0,
+                max: 250.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::ConvolutionTrimStart,
+                min: 0.0,
+                max: 500.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::ConvolutionLength,
+                min: 0.01,
+                max: 1.0,
+                default: 1.0,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::ConvolutionStretch,
+                min: 0.5,
+                max: 2.0,
+                default: 1.0,
+                display: ParamDisplay::Continuous {
+                    unit: "x",
+                    decimals: 2,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::ConvolutionMix,
+                min: 0.0,
+                max: 1.0,
+                default: 0.3,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+        ],
+        EffectType::Gate => vec![
+            EffectParamDef {
+                id: EffectParamId::GateThreshold,
+                min: -80.0,
+                max: 0.0,
+                default: -40.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateHysteresis,
+                min: 0.0,
+                max: 12.0,
+                default: 4.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateAttack,
+                min: 0.01,
+                max: 50.0,
+                default: 0.5,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 2,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateHold,
+                min: 0.0,
+                max: 500.0,
+                default: 20.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateRelease,
+                min: 5.0,
+                max: 2000.0,
+                default: 100.0,
+                display: ParamDisplay::Continuous {
+                    unit: "ms",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateRange,
+                min: 0.0,
+                max: 80.0,
+                default: 80.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::GateSidechain,
+                min: 0.0,
+                max: 15.0,
+                default: 0.0,
+                display: ParamDisplay::Discrete {
+                    choices: &compressor::SIDECHAIN_CHOICES,
+                },
+            },
+        ],
+        EffectType::TransientShaper => vec![
+            EffectParamDef {
+                id: EffectParamId::TransientAttack,
+                min: -1.0,
+                max: 1.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::TransientSustain,
+                min: -1.0,
+                max: 1.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "%",
+                    decimals: 0,
+                },
+            },
+            EffectParamDef {
+                id: EffectParamId::TransientOutput,
+                min: -12.0,
+                max: 12.0,
+                default: 0.0,
+                display: ParamDisplay::Continuous {
+                    unit: "dB",
+                    decimals: 1,
+                },
+            },
+        ],
+        // Plugin parameters are discovered from the plugin, not declared here
+        EffectType::Plugin => vec![],
     }
 }
 
+/// Parameter definitions shared by the modulation effects
+///
+/// Only the rate, feedback and spread defaults and the feedback range differ.
+fn modulation_param_defs(
+    rate_hz: f32,
+    (feedback_min, feedback_max): (f32, f32),
+    feedback: f32,
+    spread_deg: f32,
+) -> Vec<EffectParamDef> {
+    vec![
+        EffectParamDef {
+            id: EffectParamId::ModRate,
+            min: 0.01,
+            max: 10.0,
+            default: rate_hz,
+            display: ParamDisplay::Continuous {
+                unit: "Hz",
+                decimals: 2,
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModSync,
+            min: 0.0,
+            max: 1.0,
+            default: 0.0,
+            display: ParamDisplay::Discrete {
+                choices: &["Off", "On"],
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModDivision,
+            min: 0.0,
+            max: (modulation::SYNC_DIVISION_CHOICES.len() - 1) as f32,
+            default: 4.0,
+            display: ParamDisplay::Discrete {
+                choices: &modulation::SYNC_DIVISION_CHOICES,
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModDepth,
+            min: 0.0,
+            max: 1.0,
+            default: 0.5,
+            display: ParamDisplay::Continuous {
+                unit: "%",
+                decimals: 0,
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModFeedback,
+            min: feedback_min,
+            max: feedback_max,
+            default: feedback,
+            display: ParamDisplay::Continuous {
+                unit: "%",
+                decimals: 0,
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModSpread,
+            min: 0.0,
+            max: 180.0,
+            default: spread_deg,
+            display: ParamDisplay::Continuous {
+                unit: "°",
+                decimals: 0,
+            },
+        },
+        EffectParamDef {
+            id: EffectParamId::ModMix,
+            min: 0.0,
+            max: 1.0,
+            default: 0.5,
+            display: ParamDisplay::Continuous {
+                unit: "%",
+                decimals: 0,
+            },
+        },
+    ]
+}
+
 /// Trait for audio effect processors (implemented by filter, delay, etc.)
 ///
 /// Effects must be Send to work in the audio thread.
@@ -352,6 +1151,74 @@ pub trait Effect: Send {
 
     /// Get the effect type
     fn effect_type(&self) -> EffectType;
+
+    /// Mixer track whose signal keys this effect, for sidechained dynamics
+    fn sidechain_source(&self) -> Option<usize> {
+        None
+    }
+
+    /// Process with a key signal taken from another mixer track
+    ///
+    /// Only called when [`Effect::sidechain_source`] returns a track; effects
+    /// without a sidechain input keep the default, which ignores the key.
+    fn process_with_sidechain(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        _key_left: &[f32],
+        _key_right: &[f32],
+    ) {
+        self.process(left, right);
+    }
+
+    /// Gain reduction applied during the last block in dB (dynamics effects only)
+    fn gain_reduction_db(&self) -> f32 {
+        0.0
+    }
+
+    /// Open/hold/closed state after the last block (gates only)
+    fn gate_state(&self) -> Option<gate::GateState> {
+        None
+    }
+
+    /// Swap in a kernel built off the audio thread (convolution only)
+    ///
+    /// Returns the kernel it replaced, or `kernel` itself for effects that
+    /// don't convolve, so the caller decides where it gets freed.
+    fn set_convolution_kernel(
+        &mut self,
+        kernel: Option<Box<ConvolutionKernel>>,
+    ) -> Option<Box<ConvolutionKernel>> {
+        kernel
+    }
+
+    /// Set a parameter by CLAP id (plugin effects only; others ignore it)
+    fn set_plugin_param(&mut self, _param_id: u32, _value: f64) {}
+
+    /// Song position for the next block (plugin effects only; others ignore it)
+    fn set_transport(&mut self, _transport: &PluginTransport) {}
+
+    /// Parameter changes the effect made itself during the last block, by
+    /// CLAP id (plugin effects only; others report none)
+    fn plugin_param_changes(&self) -> &[ParamChange] {
+        &[]
+    }
+
+    /// Delay the effect adds to the signal, in frames. The engine delays the
+    /// other tracks by the difference so the mix stays phase-aligned.
+    fn latency_frames(&self) -> usize {
+        0
+    }
+
+    /// True once after the effect's sandboxed plugin crashed (plugin
+    /// effects only; others never crash this way)
+    fn take_crash(&mut self) -> bool {
+        false
+    }
+
+    /// Wait for the plugin however long each block takes, for offline
+    /// renders (plugin effects only; others ignore it)
+    fn set_offline(&mut self) {}
 }
 
 /// Create a new effect processor from an EffectSlot
@@ -385,5 +1252,79 @@ pub fn create_effect(slot: &EffectSlot, sample_rate: f32, bpm: f64) -> Box<dyn E
             }
             Box::new(effect)
         }
+        EffectType::Compressor => {
+            let mut effect = compressor::CompressorEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Eq => {
+            let mut effect = eq::EqEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Chorus => {
+            let mut effect = chorus::ChorusEffect::new(sample_rate, bpm);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Flanger => {
+            let mut effect = flanger::FlangerEffect::new(sample_rate, bpm);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Phaser => {
+            let mut effect = phaser::PhaserEffect::new(sample_rate, bpm);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Distortion => {
+            let mut effect = distortion::DistortionEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Bitcrusher => {
+            let mut effect = bitcrusher::BitcrusherEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::Convolution => {
+            let mut effect = convolution::ConvolutionEffect::new();
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            effect.set_convolution_kernel(slot.convolution_kernel(sample_rate));
+            Box::new(effect)
+        }
+        EffectType::Gate => {
+            let mut effect = gate::GateEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        EffectType::TransientShaper => {
+            let mut effect = transient::TransientEffect::new(sample_rate);
+            for (id, value) in &slot.params {
+                effect.set_param(*id, *value);
+            }
+            Box::new(effect)
+        }
+        // The plugin itself has to be loaded through a PluginLoader, which
+        // replaces this passthrough once the processor is ready
+        EffectType::Plugin => Box::new(plugin::PluginEffect::missing()),
     }
 }
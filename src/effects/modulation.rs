@@ -0,0 +1,224 @@
+//! Shared building blocks for the time-modulated effects
+//!
+//! Chorus, flanger and phaser all sweep something with a sine LFO and expose
+//! the same controls: rate (free in Hz or synced to a note division), depth,
+//! feedback, stereo spread and mix. This module holds the LFO, the parameter
+//! set they share and the fractional delay line used by chorus and flanger.
+
+use crate::effects::{get_param_defs, EffectParamId, EffectType};
+
+/// Synced LFO cycle lengths in beats
+pub const SYNC_DIVISIONS: [f64; 8] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+
+/// Labels for [`SYNC_DIVISIONS`]
+pub const SYNC_DIVISION_CHOICES: [&str; 8] = ["1/16", "1/8", "1/4", "1/2", "1", "2", "4", "8"];
+
+/// Sine LFO with a per-channel phase offset
+#[derive(Debug, Clone, Default)]
+pub struct Lfo {
+    /// Current phase, 0.0 - 1.0
+    phase: f32,
+    /// Phase increment per sample
+    increment: f32,
+}
+
+impl Lfo {
+    /// Set the LFO frequency
+    pub fn set_frequency(&mut self, hz: f32, sample_rate: f32) {
+        self.increment = hz / sample_rate;
+    }
+
+    /// LFO value (-1.0 to 1.0) at the current phase plus an offset in cycles
+    #[inline]
+    pub fn value(&self, offset: f32) -> f32 {
+        (std::f32::consts::TAU * (self.phase + offset)).sin()
+    }
+
+    /// Move to the next sample
+    #[inline]
+    pub fn advance(&mut self) {
+        self.phase = (self.phase + self.increment).fract();
+    }
+
+    /// Restart the cycle
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+/// Controls shared by all modulation effects
+#[derive(Debug, Clone)]
+pub struct ModulationParams {
+    /// Free-running rate in Hz
+    pub rate_hz: f32,
+    /// Follow the project tempo instead of `rate_hz`
+    pub sync: bool,
+    /// Index into [`SYNC_DIVISIONS`]
+    pub division: usize,
+    /// Sweep depth (0.0 - 1.0)
+    pub depth: f32,
+    /// Feedback amount (range depends on the effect)
+    pub feedback: f32,
+    /// Phase offset between left and right LFOs in degrees
+    pub spread_deg: f32,
+    /// Dry/wet mix (0.0 = dry, 1.0 = wet)
+    pub mix: f32,
+    /// Feedback limits from the effect's parameter definitions
+    feedback_range: (f32, f32),
+}
+
+impl ModulationParams {
+    /// Parameters at the defaults declared for an effect type
+    pub fn new(effect_type: EffectType) -> Self {
+        let mut params = Self {
+            rate_hz: 1.0,
+            sync: false,
+            division: 2,
+            depth: 0.5,
+            feedback: 0.0,
+            spread_deg: 0.0,
+            mix: 0.5,
+            feedback_range: (0.0, 0.0),
+        };
+        for def in get_param_defs(effect_type) {
+            if def.id == EffectParamId::ModFeedback {
+                params.feedback_range = (def.min, def.max);
+            }
+            params.set(def.id, def.default);
+        }
+        params
+    }
+
+    /// Apply a parameter; returns false for ids that aren't modulation params
+    pub fn set(&mut self, id: EffectParamId, value: f32) -> bool {
+        match id {
+            EffectParamId::ModRate => self.rate_hz = value.clamp(0.01, 10.0),
+            EffectParamId::ModSync => self.sync = value >= 0.5,
+            EffectParamId::ModDivision => {
+                self.division = (value as usize).min(SYNC_DIVISIONS.len() - 1)
+            }
+            EffectParamId::ModDepth => self.depth = value.clamp(0.0, 1.0),
+            EffectParamId::ModFeedback => {
+                self.feedback = value.clamp(self.feedback_range.0, self.feedback_range.1)
+            }
+            EffectParamId::ModSpread => self.spread_deg = value.clamp(0.0, 180.0),
+            EffectParamId::ModMix => self.mix = value.clamp(0.0, 1.0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Current value of a modulation param
+    pub fn get(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::ModRate => self.rate_hz,
+            EffectParamId::ModSync => {
+                if self.sync {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            EffectParamId::ModDivision => self.division as f32,
+            EffectParamId::ModDepth => self.depth,
+            EffectParamId::ModFeedback => self.feedback,
+            EffectParamId::ModSpread => self.spread_deg,
+            EffectParamId::ModMix => self.mix,
+            _ => 0.0,
+        }
+    }
+
+    /// LFO frequency in Hz at the given tempo
+    pub fn lfo_hz(&self, bpm: f64) -> f32 {
+        if self.sync {
+            let beats_per_second = bpm / 60.0;
+            (beats_per_second / SYNC_DIVISIONS[self.division]) as f32
+        } else {
+            self.rate_hz
+        }
+    }
+
+    /// Right-channel LFO offset in cycles
+    pub fn spread_cycles(&self) -> f32 {
+        self.spread_deg / 360.0
+    }
+}
+
+/// Delay line read at fractional positions with linear interpolation
+pub struct FractionalDelayLine {
+    buffer: Vec<f32>,
+    write_pos: usize,
+}
+
+impl FractionalDelayLine {
+    /// Create a delay line holding up to `max_samples` of history
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            buffer: vec![0.0; max_samples.max(2)],
+            write_pos: 0,
+        }
+    }
+
+    /// Read `delay` samples behind the last write (0.0 is the last write)
+    #[inline]
+    pub fn read(&self, delay: f32) -> f32 {
+        let size = self.buffer.len();
+        let delay = delay.clamp(0.0, (size - 2) as f32);
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = self.buffer[(self.write_pos + size - whole) % size];
+        let b = self.buffer[(self.write_pos + size - whole - 1) % size];
+        a + (b - a) * frac
+    }
+
+    /// Push the next sample
+    #[inline]
+    pub fn write(&mut self, sample: f32) {
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+        self.buffer[self.write_pos] = sample;
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synced_rate_follows_tempo() {
+        let mut params = ModulationParams::new(EffectType::Chorus);
+        params.set(EffectParamId::ModSync, 1.0);
+        params.set(EffectParamId::ModDivision, 2.0); // one cycle per beat
+        assert!((params.lfo_hz(120.0) - 2.0).abs() < 1e-6);
+        assert!((params.lfo_hz(60.0) - 1.0).abs() < 1e-6);
+
+        params.set(EffectParamId::ModSync, 0.0);
+        params.set(EffectParamId::ModRate, 3.0);
+        assert_eq!(params.lfo_hz(120.0), 3.0);
+    }
+
+    #[test]
+    fn test_lfo_completes_a_cycle() {
+        let mut lfo = Lfo::default();
+        lfo.set_frequency(1.0, 100.0);
+        for _ in 0..25 {
+            lfo.advance();
+        }
+        assert!((lfo.value(0.0) - 1.0).abs() < 1e-4);
+        assert!((lfo.value(0.5) + 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fractional_read_interpolates() {
+        let mut line = FractionalDelayLine::new(16);
+        line.write(0.0);
+        line.write(1.0);
+        assert_eq!(line.read(0.0), 1.0);
+        assert_eq!(line.read(1.0), 0.0);
+        assert!((line.read(0.25) - 0.75).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,192 @@
+//! Stereo phaser effect
+//!
+//! Six first-order all-pass stages whose break frequency is swept by the LFO
+//! on a log scale. Mixing the phase-shifted signal with the dry one carves
+//! moving notches; feedback deepens them.
+
+use crate::effects::modulation::{Lfo, ModulationParams};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Number of all-pass stages (three notches)
+const STAGES: usize = 6;
+/// Break frequency at the bottom of the sweep in Hz
+const MIN_FREQ: f32 = 200.0;
+/// Sweep width at full depth in octaves
+const MAX_SWEEP_OCTAVES: f32 = 5.0;
+
+/// Per-channel all-pass chain
+#[derive(Debug, Clone, Default)]
+struct PhaserState {
+    /// One-sample memory of each all-pass stage
+    stages: [f32; STAGES],
+    /// Last chain output, fed back into the input
+    last: f32,
+}
+
+impl PhaserState {
+    #[inline]
+    fn process(&mut self, input: f32, coef: f32, feedback: f32) -> f32 {
+        let mut x = input + self.last * feedback;
+        for z in self.stages.iter_mut() {
+            let y = coef * x + *z;
+            *z = x - coef * y;
+            x = y;
+        }
+        self.last = if x.is_finite() { x } else { 0.0 };
+        self.last
+    }
+}
+
+/// Stereo phaser effect
+pub struct PhaserEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Tempo in BPM (for synced rates)
+    bpm: f64,
+    /// Rate, depth, feedback, spread and mix
+    params: ModulationParams,
+    /// Sweep LFO
+    lfo: Lfo,
+    /// Left channel state
+    state_l: PhaserState,
+    /// Right channel state
+    state_r: PhaserState,
+}
+
+impl PhaserEffect {
+    /// Create a new phaser effect
+    pub fn new(sample_rate: f32, bpm: f64) -> Self {
+        let mut effect = Self {
+            sample_rate,
+            bpm,
+            params: ModulationParams::new(EffectType::Phaser),
+            lfo: Lfo::default(),
+            state_l: PhaserState::default(),
+            state_r: PhaserState::default(),
+        };
+        effect.update_lfo();
+        effect
+    }
+
+    fn update_lfo(&mut self) {
+        self.lfo
+            .set_frequency(self.params.lfo_hz(self.bpm), self.sample_rate);
+    }
+
+    /// All-pass coefficient for an LFO value
+    #[inline]
+    fn coefficient(&self, lfo: f32) -> f32 {
+        let octaves = MAX_SWEEP_OCTAVES * self.params.depth * (0.5 + 0.5 * lfo);
+        let freq = (MIN_FREQ * octaves.exp2()).min(self.sample_rate * 0.45);
+        let t = (std::f32::consts::PI * freq / self.sample_rate).tan();
+        (t - 1.0) / (t + 1.0)
+    }
+}
+
+impl Effect for PhaserEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let spread = self.params.spread_cycles();
+        let feedback = self.params.feedback;
+        let mix = self.params.mix;
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let coef_l = self.coefficient(self.lfo.value(0.0));
+            let coef_r = self.coefficient(self.lfo.value(spread));
+            self.lfo.advance();
+
+            let wet_l = self.state_l.process(*l, coef_l, feedback);
+            let wet_r = self.state_r.process(*r, coef_r, feedback);
+
+            *l = *l * (1.0 - mix) + wet_l * mix;
+            *r = *r * (1.0 - mix) + wet_r * mix;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        if self.params.set(id, value) {
+            self.update_lfo();
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        self.params.get(id)
+    }
+
+    fn reset(&mut self) {
+        self.state_l = PhaserState::default();
+        self.state_r = PhaserState::default();
+        self.lfo.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_lfo();
+    }
+
+    fn set_tempo(&mut self, bpm: f64) {
+        self.bpm = bpm;
+        self.update_lfo();
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Phaser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_phaser_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Phaser);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_phaser_default_params() {
+        let effect = create_test_effect(EffectType::Phaser);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_phaser_reset() {
+        let mut effect = create_test_effect(EffectType::Phaser);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_wet_signal_is_allpass() {
+        let mut fx = PhaserEffect::new(TEST_SAMPLE_RATE, TEST_BPM);
+        fx.set_param(EffectParamId::ModMix, 1.0);
+        fx.set_param(EffectParamId::ModFeedback, 0.0);
+        let mut left = generate_sine(8820, 1000.0, TEST_SAMPLE_RATE);
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        let rms = calculate_rms(&left[4410..]);
+        assert!(
+            (rms - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.02,
+            "rms {}",
+            rms
+        );
+    }
+
+    #[test]
+    fn test_half_mix_notches_break_frequency() {
+        // With no depth the chain sits still at MIN_FREQ, where six stages
+        // shift by 540 degrees and the wet signal cancels the dry one
+        let notch_rms = |freq: f32| {
+            let mut fx = PhaserEffect::new(TEST_SAMPLE_RATE, TEST_BPM);
+            fx.set_param(EffectParamId::ModDepth, 0.0);
+            fx.set_param(EffectParamId::ModFeedback, 0.0);
+            fx.set_param(EffectParamId::ModMix, 0.5);
+            let mut left = generate_sine(8820, freq, TEST_SAMPLE_RATE);
+            let mut right = left.clone();
+            fx.process(&mut left, &mut right);
+            calculate_rms(&left[4410..])
+        };
+        assert!(notch_rms(MIN_FREQ) < 0.02);
+        assert!(notch_rms(400.0) > 0.2);
+    }
+}
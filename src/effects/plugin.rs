@@ -0,0 +1,161 @@
+//! CLAP audio effect hosted in a mixer insert slot
+//!
+//! Wraps an [`ActivePluginProcessor`] so it can sit in a track's effect chain
+//! like any built-in effect: the track buffer is fed to the plugin's input
+//! port and replaced by its output. Parameters are CLAP ids rather than
+//! [`EffectParamId`]s, so changes arrive through [`Effect::set_plugin_param`]
+//! and are handed to the plugin with the next block.
+//!
+//! A slot whose plugin could not be loaded holds an effect without a
+//! processor, which passes audio through untouched.
+
+use std::collections::HashMap;
+
+use crate::effects::{Effect, EffectParamId, EffectType};
+use crate::plugin_host::transport::PluginTransport;
+use crate::plugin_host::{ActivePluginProcessor, ParamChange};
+
+/// Parameter changes queued before the audio thread has to grow the buffer
+const PENDING_PARAMS_CAPACITY: usize = 64;
+
+/// Plugin-backed insert effect
+pub struct PluginEffect {
+    /// Activated plugin, or None when the plugin is missing
+    processor: Option<ActivePluginProcessor>,
+    /// Parameter changes to send with the next process call
+    pending_params: Vec<ParamChange>,
+    /// Song position at the start of the next block
+    transport: PluginTransport,
+}
+
+impl PluginEffect {
+    /// Wrap an activated plugin, queueing its saved parameter values
+    pub fn new(processor: ActivePluginProcessor, params: &HashMap<u32, f64>) -> Self {
+        let mut effect = Self {
+            processor: Some(processor),
+            pending_params: Vec::with_capacity(PENDING_PARAMS_CAPACITY.max(params.len())),
+            transport: PluginTransport::default(),
+        };
+        for (&param_id, &value) in params {
+            effect.set_plugin_param(param_id, value);
+        }
+        effect
+    }
+
+    /// Placeholder for a slot whose plugin is not (yet) loaded
+    pub fn missing() -> Self {
+        Self {
+            processor: None,
+            pending_params: Vec::new(),
+            transport: PluginTransport::default(),
+        }
+    }
+
+    /// Whether a plugin processor is attached
+    pub fn is_loaded(&self) -> bool {
+        self.processor.is_some()
+    }
+}
+
+impl Effect for PluginEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        if let Some(processor) = &mut self.processor {
+            processor.process_effect(&self.pending_params, &self.transport, left, right);
+            self.pending_params.clear();
+        }
+    }
+
+    fn set_param(&mut self, _id: EffectParamId, _value: f32) {
+        // Plugin parameters are addressed by CLAP id, see set_plugin_param
+    }
+
+    fn get_param(&self, _id: EffectParamId) -> f32 {
+        0.0
+    }
+
+    fn reset(&mut self) {
+        // The plugin owns its state; nothing to clear on the host side
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: f32) {
+        // The plugin is activated at the engine sample rate when loaded
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Tempo reaches plugins through the transport, see set_transport
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::Plugin
+    }
+
+    fn set_plugin_param(&mut self, param_id: u32, value: f64) {
+        if self.processor.is_some() {
+            self.pending_params.push(ParamChange {
+                time: 0,
+                param_id,
+                value,
+            });
+        }
+    }
+
+    fn set_transport(&mut self, transport: &PluginTransport) {
+        self.transport = *transport;
+    }
+
+    fn plugin_param_changes(&self) -> &[ParamChange] {
+        match &self.processor {
+            Some(processor) => processor.output_params(),
+            None => &[],
+        }
+    }
+
+    fn latency_frames(&self) -> usize {
+        self.processor
+            .as_ref()
+            .map_or(0, |processor| processor.latency_frames() as usize)
+    }
+
+    fn take_crash(&mut self) -> bool {
+        self.processor
+            .as_mut()
+            .is_some_and(|processor| processor.take_crash())
+    }
+
+    fn set_offline(&mut self) {
+        if let Some(processor) = &mut self.processor {
+            processor.set_offline();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    #[test]
+    fn test_plugin_passes_silence() {
+        let mut effect = create_test_effect(EffectType::Plugin);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_missing_plugin_passes_audio_through() {
+        let mut effect = create_test_effect(EffectType::Plugin);
+        let input = generate_sine(512, 440.0, TEST_SAMPLE_RATE);
+        let mut left = input.clone();
+        let mut right = input.clone();
+        effect.process(&mut left, &mut right);
+        assert_eq!(left, input);
+        assert_eq!(right, input);
+    }
+
+    #[test]
+    fn test_missing_plugin_drops_param_changes() {
+        let mut effect = PluginEffect::missing();
+        effect.set_plugin_param(3, 0.5);
+        assert!(!effect.is_loaded());
+        assert!(effect.pending_params.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Effect presets - named parameter sets stored as JSON
+//!
+//! Presets are kept one file per preset, grouped by effect type:
+//! `<dir>/<EffectType>/<name>.json`. Factory presets ship in the templates
+//! directory and are read-only; presets saved by the user go to the config
+//! directory. A preset only needs the parameters it cares about - anything
+//! it leaves out (or that was added to the effect later) gets its default.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{get_default_params, get_param_defs, EffectParamId, EffectSlot, EffectType};
+
+/// Error type for preset operations
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid preset file: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Preset name is empty")]
+    EmptyName,
+
+    #[error("{0} has no parameters to store")]
+    Unsupported(&'static str),
+}
+
+/// A named set of parameter values for one effect type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectPreset {
+    /// Display name
+    pub name: String,
+    /// Effect the preset is for
+    pub effect_type: EffectType,
+    /// Parameter values (param_id -> value)
+    pub params: HashMap<EffectParamId, f32>,
+    /// Whether the preset ships with the app (read-only)
+    #[serde(skip)]
+    pub factory: bool,
+}
+
+impl EffectPreset {
+    /// Capture a slot's current parameters under `name`
+    pub fn from_slot(name: &str, slot: &EffectSlot) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            effect_type: slot.effect_type,
+            params: slot.params.clone(),
+            factory: false,
+        }
+    }
+
+    /// Full parameter set for a slot: defaults overlaid with the preset's
+    /// values, clamped to each parameter's range. Parameters the effect
+    /// doesn't have are dropped.
+    pub fn resolved_params(&self) -> HashMap<EffectParamId, f32> {
+        let mut params = get_default_params(self.effect_type);
+        for def in get_param_defs(self.effect_type) {
+            if let Some(&value) = self.params.get(&def.id) {
+                params.insert(def.id, value.clamp(def.min, def.max));
+            }
+        }
+        params
+    }
+}
+
+/// Factory and user preset directories
+#[derive(Debug, Clone)]
+pub struct PresetLibrary {
+    /// Read-only presets bundled with the templates
+    factory_dir: PathBuf,
+    /// Presets saved by the user
+    user_dir: PathBuf,
+}
+
+impl Default for PresetLibrary {
+    fn default() -> Self {
+        Self::new(
+            crate::templates::factory_presets_dir(),
+            crate::templates::user_presets_dir(),
+        )
+    }
+}
+
+impl PresetLibrary {
+    /// Create a library over the given factory and user directories
+    pub fn new(factory_dir: impl Into<PathBuf>, user_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            factory_dir: factory_dir.into(),
+            user_dir: user_dir.into(),
+        }
+    }
+
+    /// All presets for an effect type: factory presets first, then the
+    /// user's, each sorted by name. Unreadable files are skipped.
+    pub fn list(&self, effect_type: EffectType) -> Vec<EffectPreset> {
+        let mut presets = read_presets(&self.factory_dir, effect_type, true);
+        presets.extend(read_presets(&self.user_dir, effect_type, false));
+        presets
+    }
+
+    /// Save a preset to the user directory, overwriting one with the same name
+    ///
+    /// Returns the path of the written file.
+    pub fn save(&self, preset: &EffectPreset) -> Result<PathBuf, PresetError> {
+        if preset.effect_type == EffectType::Plugin {
+            return Err(PresetError::Unsupported(preset.effect_type.name()));
+        }
+        let file_name = preset_file_name(&preset.name).ok_or(PresetError::EmptyName)?;
+        let dir = self.user_dir.join(type_dir(preset.effect_type));
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(file_name);
+        let json = serde_json::to_string_pretty(preset)?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+/// Directory name for an effect type's presets (its serialized name)
+fn type_dir(effect_type: EffectType) -> String {
+    format!("{:?}", effect_type)
+}
+
+/// File name for a preset, with anything that isn't safe in a path replaced
+pub(crate) fn preset_file_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(format!("{}.json", safe))
+}
+
+/// Read the presets for one effect type from `root`, sorted by name
+fn read_presets(root: &Path, effect_type: EffectType, factory: bool) -> Vec<EffectPreset> {
+    let Ok(entries) = fs::read_dir(root.join(type_dir(effect_type))) else {
+        return Vec::new();
+    };
+    let mut presets: Vec<EffectPreset> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .filter_map(|json| serde_json::from_str::<EffectPreset>(&json).ok())
+        .filter(|p| p.effect_type == effect_type)
+        .map(|p| EffectPreset { factory, ..p })
+        .collect();
+    presets.sort_by_key(|p| p.name.to_lowercase());
+    presets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn library() -> (PresetLibrary, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let library = PresetLibrary::new(dir.path().join("factory"), dir.path().join("user"));
+        (library, dir)
+    }
+
+    #[test]
+    fn test_save_and_list_round_trip() {
+        let (library, _dir) = library();
+        let mut slot = EffectSlot::new(EffectType::Reverb);
+        slot.set_param(EffectParamId::ReverbRoomSize, 0.9);
+        let preset = EffectPreset::from_slot("  Big Hall ", &slot);
+
+        let path = library.save(&preset).unwrap();
+        assert!(path.ends_with("Reverb/Big Hall.json"));
+
+        let presets = library.list(EffectType::Reverb);
+        assert_eq!(presets, vec![preset]);
+        assert_eq!(presets[0].name, "Big Hall");
+        assert!(library.list(EffectType::Delay).is_empty());
+    }
+
+    #[test]
+    fn test_factory_presets_listed_first() {
+        let (library, dir) = library();
+        let factory = dir.path().join("factory").join("Delay");
+        fs::create_dir_all(&factory).unwrap();
+        fs::write(
+            factory.join("Slapback.json"),
+            r#"{"name":"Slapback","effect_type":"Delay","params":{"DelayMix":0.3}}"#,
+        )
+        .unwrap();
+        fs::write(factory.join("broken.json"), "not json").unwrap();
+        library
+            .save(&EffectPreset::from_slot(
+                "Ambient",
+                &EffectSlot::new(EffectType::Delay),
+            ))
+            .unwrap();
+
+        let presets = library.list(EffectType::Delay);
+        let names: Vec<_> = presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Slapback", "Ambient"]);
+        assert!(presets[0].factory);
+        assert!(!presets[1].factory);
+    }
+
+    #[test]
+    fn test_resolved_params_fill_defaults_and_clamp() {
+        let mut params = HashMap::new();
+        params.insert(EffectParamId::ReverbRoomSize, 5.0);
+        params.insert(EffectParamId::DelayMix, 0.5);
+        let preset = EffectPreset {
+            name: "Odd".to_string(),
+            effect_type: EffectType::Reverb,
+            params,
+            factory: false,
+        };
+
+        let resolved = preset.resolved_params();
+        let defaults = get_default_params(EffectType::Reverb);
+        assert_eq!(resolved.len(), defaults.len());
+        assert!(!resolved.contains_key(&EffectParamId::DelayMix));
+        let room = get_param_defs(EffectType::Reverb)
+            .into_iter()
+            .find(|d| d.id == EffectParamId::ReverbRoomSize)
+            .unwrap();
+        assert_eq!(resolved[&EffectParamId::ReverbRoomSize], room.max);
+    }
+
+    #[test]
+    fn test_save_rejects_empty_name_and_sanitizes() {
+        let (library, _dir) = library();
+        let slot = EffectSlot::new(EffectType::Eq);
+        assert!(matches!(
+            library.save(&EffectPreset::from_slot("   ", &slot)),
+            Err(PresetError::EmptyName)
+        ));
+        let path = library
+            .save(&EffectPreset::from_slot("../Vocal: air", &slot))
+            .unwrap();
+        assert_eq!(path.file_name().unwrap(), "___Vocal_ air.json");
+        assert_eq!(library.list(EffectType::Eq)[0].name, "../Vocal: air");
+    }
+
+    #[test]
+    fn test_bundled_factory_presets_parse() {
+        let library = PresetLibrary::new("templates/presets", "/nonexistent");
+        for &effect_type in EffectType::all() {
+            let dir = Path::new("templates/presets").join(type_dir(effect_type));
+            let files = fs::read_dir(&dir).map(|d| d.count()).unwrap_or(0);
+            assert_eq!(
+                library.list(effect_type).len(),
+                files,
+                "every file in {:?} should be a valid preset",
+                dir
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Transient shaper effect
+//!
+//! Level-independent attack and sustain control. Two pairs of envelope
+//! followers run on the stereo-linked input: a fast-attack follower racing
+//! ahead of a slow-attack one marks the onset of a hit, and a slow-release
+//! follower lagging behind a fast-release one marks its tail. Their
+//! differences (in dB) are scaled by the attack and sustain amounts to boost
+//! or cut each part, so the effect works the same on quiet and loud hits.
+
+use crate::effects::compressor::{db_to_linear, linear_to_db, time_coef};
+use crate::effects::{Effect, EffectParamId, EffectType};
+
+/// Fast follower attack (ms)
+const FAST_ATTACK_MS: f32 = 0.5;
+/// Slow follower attack (ms), roughly the length of a drum transient
+const SLOW_ATTACK_MS: f32 = 20.0;
+/// Release of the followers that track the body of a hit (ms)
+const FAST_RELEASE_MS: f32 = 20.0;
+/// Release of the follower that tracks the tail (ms)
+const SLOW_RELEASE_MS: f32 = 300.0;
+/// Largest boost or cut applied (dB)
+const MAX_GAIN_DB: f32 = 24.0;
+
+/// Peak envelope follower with separate attack and release
+#[derive(Debug, Clone, Default)]
+struct Follower {
+    attack_coef: f32,
+    release_coef: f32,
+    envelope: f32,
+}
+
+impl Follower {
+    fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        self.attack_coef = time_coef(attack_ms, sample_rate);
+        self.release_coef = time_coef(release_ms, sample_rate);
+    }
+
+    #[inline]
+    fn process(&mut self, level: f32) -> f32 {
+        let coef = if level > self.envelope {
+            self.attack_coef
+        } else {
+            self.release_coef
+        };
+        self.envelope = coef * self.envelope + (1.0 - coef) * level;
+        self.envelope
+    }
+}
+
+/// Transient shaper effect
+pub struct TransientEffect {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// Attack emphasis (-1.0 = soften, 1.0 = sharpen)
+    attack: f32,
+    /// Sustain emphasis (-1.0 = shorten, 1.0 = lengthen)
+    sustain: f32,
+    /// Output gain in dB
+    output_db: f32,
+    /// Fast attack, fast release
+    fast: Follower,
+    /// Slow attack, fast release
+    slow_attack: Follower,
+    /// Fast attack, slow release
+    slow_release: Follower,
+}
+
+impl TransientEffect {
+    /// Create a new transient shaper effect
+    pub fn new(sample_rate: f32) -> Self {
+        let mut effect = Self {
+            sample_rate,
+            attack: 0.0,
+            sustain: 0.0,
+            output_db: 0.0,
+            fast: Follower::default(),
+            slow_attack: Follower::default(),
+            slow_release: Follower::default(),
+        };
+        effect.update_coefficients();
+        effect
+    }
+
+    fn update_coefficients(&mut self) {
+        let sr = self.sample_rate;
+        self.fast.set_times(FAST_ATTACK_MS, FAST_RELEASE_MS, sr);
+        self.slow_attack
+            .set_times(SLOW_ATTACK_MS, FAST_RELEASE_MS, sr);
+        self.slow_release
+            .set_times(FAST_ATTACK_MS, SLOW_RELEASE_MS, sr);
+    }
+}
+
+impl Effect for TransientEffect {
+    fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let output = db_to_linear(self.output_db);
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let level = l.abs().max(r.abs());
+            let fast_db = linear_to_db(self.fast.process(level));
+            let slow_attack_db = linear_to_db(self.slow_attack.process(level));
+            let slow_release_db = linear_to_db(self.slow_release.process(level));
+
+            // Positive while a hit is rising / while it is dying away
+            let onset_db = (fast_db - slow_attack_db).max(0.0);
+            let tail_db = (slow_release_db - fast_db).max(0.0);
+
+            let gain_db =
+                (self.attack * onset_db + self.sustain * tail_db).clamp(-MAX_GAIN_DB, MAX_GAIN_DB);
+            let gain = db_to_linear(gain_db) * output;
+            *l *= gain;
+            *r *= gain;
+        }
+    }
+
+    fn set_param(&mut self, id: EffectParamId, value: f32) {
+        match id {
+            EffectParamId::TransientAttack => self.attack = value.clamp(-1.0, 1.0),
+            EffectParamId::TransientSustain => self.sustain = value.clamp(-1.0, 1.0),
+            EffectParamId::TransientOutput => self.output_db = value.clamp(-12.0, 12.0),
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, id: EffectParamId) -> f32 {
+        match id {
+            EffectParamId::TransientAttack => self.attack,
+            EffectParamId::TransientSustain => self.sustain,
+            EffectParamId::TransientOutput => self.output_db,
+            _ => 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.fast.envelope = 0.0;
+        self.slow_attack.envelope = 0.0;
+        self.slow_release.envelope = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    fn set_tempo(&mut self, _bpm: f64) {
+        // Transient shaper doesn't use tempo
+    }
+
+    fn effect_type(&self) -> EffectType {
+        EffectType::TransientShaper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::test_helpers::*;
+
+    /// A drum-like hit: 200 Hz tone with an instant onset and exponential decay
+    fn hit() -> Vec<f32> {
+        generate_sine(8820, 200.0, TEST_SAMPLE_RATE)
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * 0.5 * (-(i as f32) / 2205.0).exp())
+            .collect()
+    }
+
+    fn shaped(attack: f32, sustain: f32) -> Vec<f32> {
+        let mut fx = TransientEffect::new(TEST_SAMPLE_RATE);
+        fx.set_param(EffectParamId::TransientAttack, attack);
+        fx.set_param(EffectParamId::TransientSustain, sustain);
+        let mut left = hit();
+        let mut right = left.clone();
+        fx.process(&mut left, &mut right);
+        left
+    }
+
+    #[test]
+    fn test_transient_passes_silence() {
+        let mut effect = create_test_effect(EffectType::TransientShaper);
+        assert_effect_passes_silence(effect.as_mut(), 256);
+    }
+
+    #[test]
+    fn test_transient_default_params() {
+        let effect = create_test_effect(EffectType::TransientShaper);
+        assert_params_in_range(effect.as_ref());
+    }
+
+    #[test]
+    fn test_transient_reset() {
+        let mut effect = create_test_effect(EffectType::TransientShaper);
+        assert_reset_clears_state(effect.as_mut());
+    }
+
+    #[test]
+    fn test_neutral_settings_pass_through() {
+        let out = shaped(0.0, 0.0);
+        for (a, b) in out.iter().zip(hit().iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_attack_boosts_onset_only() {
+        let dry = hit();
+        let out = shaped(1.0, 0.0);
+        // First 5 ms of the hit gets louder, the tail is left alone
+        let onset = calculate_peak(&out[..220]) / calculate_peak(&dry[..220]);
+        let tail = calculate_rms(&out[6615..]) / calculate_rms(&dry[6615..]);
+        assert!(onset > 1.5, "onset gain {}", onset);
+        assert!((tail - 1.0).abs() < 0.05, "tail gain {}", tail);
+
+        let softened = shaped(-1.0, 0.0);
+        assert!(calculate_peak(&softened[..220]) < calculate_peak(&dry[..220]) * 0.7);
+    }
+
+    #[test]
+    fn test_sustain_shapes_tail() {
+        let dry = hit();
+        let longer = shaped(0.0, 1.0);
+        let shorter = shaped(0.0, -1.0);
+        let dry_tail = calculate_rms(&dry[4410..]);
+        assert!(calculate_rms(&longer[4410..]) > dry_tail * 1.2);
+        assert!(calculate_rms(&shorter[4410..]) < dry_tail * 0.8);
+    }
+}
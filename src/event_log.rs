@@ -3,6 +3,7 @@
 //! This module provides a simple ring buffer for logging commands as they're
 //! dispatched. The log is independent of App and can be tested in isolation.
 
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::time::Instant;
 
@@ -12,8 +13,9 @@ const MAX_LOG_SIZE: usize = 100;
 /// A single event log entry
 #[derive(Debug, Clone)]
 pub struct LogEntry {
-    /// Human-readable description (from AppCommand::description())
-    pub description: &'static str,
+    /// Human-readable description (from AppCommand::description(), or a
+    /// formatted status message)
+    pub description: Cow<'static, str>,
 
     /// Timestamp when the event occurred
     pub timestamp: Instant,
@@ -56,9 +58,9 @@ impl EventLog {
     }
 
     /// Log a command execution
-    pub fn log(&mut self, description: &'static str, is_undoable: bool) {
+    pub fn log(&mut self, description: impl Into<Cow<'static, str>>, is_undoable: bool) {
         let entry = LogEntry {
-            description,
+            description: description.into(),
             timestamp: Instant::now(),
             is_undoable,
         };
@@ -0,0 +1,276 @@
+//! Export dialog - choose output format and options before rendering
+//!
+//! Rows are navigated with j/k (or arrows/Tab) and values cycled with h/l.
+//! The filename row is a text field; Enter starts the export.
+
+use tui_input::Input;
+
+pub use crate::audio::export::ExportMode;
+use crate::audio::export::{
+    BitDepth, ExportFormat, ExportSettings, Normalization, EXPORT_SAMPLE_RATES, EXPORT_TAIL_SECS,
+};
+
+/// A row in the export dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportField {
+    #[default]
+    Filename,
+    Mode,
+    Format,
+    BitDepth,
+    SampleRate,
+    Dither,
+    Normalize,
+    Tail,
+    ReleaseNotes,
+    StemMasterFx,
+}
+
+impl ExportField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportField::Filename => "File",
+            ExportField::Mode => "Export",
+            ExportField::Format => "Format",
+            ExportField::BitDepth => "Bit depth",
+            ExportField::SampleRate => "Sample rate",
+            ExportField::Dither => "Dither",
+            ExportField::Normalize => "Normalize",
+            ExportField::Tail => "Tail",
+            ExportField::ReleaseNotes => "Note-offs",
+            ExportField::StemMasterFx => "Master FX",
+        }
+    }
+}
+
+/// Export dialog state
+#[derive(Debug, Clone, Default)]
+pub struct ExportDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Selected row
+    pub selected: ExportField,
+    /// Output file name (mix) or folder name (stems), relative to the project
+    pub filename: Input,
+    /// Mix or stems
+    pub mode: ExportMode,
+    /// Format, bit depth, rate, dither and normalization
+    pub settings: ExportSettings,
+    /// For stems: run each stem through the master effect chain
+    pub stem_master_fx: bool,
+    /// Project name used to build default file names
+    project_name: String,
+}
+
+impl ExportDialog {
+    /// Create a new export dialog
+    pub fn new() -> Self {
+        Self {
+            stem_master_fx: true,
+            ..Self::default()
+        }
+    }
+
+    /// Show the dialog in the given mode, keeping the previously chosen settings
+    pub fn show(&mut self, mode: ExportMode, project_name: &str) {
+        self.visible = true;
+        self.selected = ExportField::Filename;
+        self.mode = mode;
+        self.project_name = project_name.to_string();
+        self.filename = Input::new(self.default_filename());
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Rows shown for the current mode, top to bottom
+    pub fn fields(&self) -> Vec<ExportField> {
+        let mut fields = vec![
+            ExportField::Filename,
+            ExportField::Mode,
+            ExportField::Format,
+            ExportField::BitDepth,
+            ExportField::SampleRate,
+            ExportField::Dither,
+            ExportField::Normalize,
+            ExportField::Tail,
+            ExportField::ReleaseNotes,
+        ];
+        if self.mode == ExportMode::Stems {
+            fields.push(ExportField::StemMasterFx);
+        }
+        fields
+    }
+
+    /// Move the selection by `delta` rows (wraps around)
+    pub fn move_selection(&mut self, delta: i32) {
+        let fields = self.fields();
+        let current = fields.iter().position(|&f| f == self.selected).unwrap_or(0);
+        let next = (current as i32 + delta).rem_euclid(fields.len() as i32) as usize;
+        self.selected = fields[next];
+    }
+
+    /// Cycle the selected row's value forwards (`delta > 0`) or backwards
+    pub fn cycle(&mut self, delta: i32) {
+        match self.selected {
+            ExportField::Filename => {}
+            ExportField::Mode => {
+                self.mode = match self.mode {
+                    ExportMode::Mix => ExportMode::Stems,
+                    ExportMode::Stems => ExportMode::Mix,
+                };
+                self.filename = Input::new(self.default_filename());
+            }
+            ExportField::Format => {
+                let old_ext = self.settings.format.extension();
+                self.settings.format = cycle_in(&ExportFormat::ALL, self.settings.format, delta);
+                self.replace_extension(old_ext);
+            }
+            ExportField::BitDepth => {
+                self.settings.bit_depth = cycle_in(&BitDepth::ALL, self.settings.bit_depth, delta);
+            }
+            ExportField::SampleRate => {
+                self.settings.sample_rate =
+                    cycle_in(&EXPORT_SAMPLE_RATES, self.settings.sample_rate, delta);
+            }
+            ExportField::Dither => {
+                self.settings.dither = !self.settings.dither;
+            }
+            ExportField::Normalize => {
+                self.settings.normalization =
+                    cycle_in(&Normalization::ALL, self.settings.normalization, delta);
+            }
+            ExportField::Tail => {
+                self.settings.max_tail_secs =
+                    cycle_in(&EXPORT_TAIL_SECS, self.settings.max_tail_secs, delta);
+            }
+            ExportField::ReleaseNotes => {
+                self.settings.release_notes = !self.settings.release_notes;
+            }
+            ExportField::StemMasterFx => {
+                self.stem_master_fx = !self.stem_master_fx;
+            }
+        }
+    }
+
+    /// Why the chosen settings can't be exported, if they can't
+    pub fn error(&self) -> Option<String> {
+        self.settings.validate().err().map(|e| e.to_string())
+    }
+
+    /// Display text for a row's value
+    pub fn value_text(&self, field: ExportField) -> String {
+        let on_off = |b: bool| if b { "On" } else { "Off" }.to_string();
+        match field {
+            ExportField::Filename => self.filename.value().to_string(),
+            ExportField::Mode => match self.mode {
+                ExportMode::Mix => "Mix".to_string(),
+                ExportMode::Stems => "Stems".to_string(),
+            },
+            ExportField::Format => self.settings.format.label().to_string(),
+            ExportField::BitDepth => self.settings.bit_depth.label().to_string(),
+            ExportField::SampleRate => format!("{} Hz", self.settings.sample_rate),
+            ExportField::Dither if self.settings.bit_depth.is_float() => "n/a".to_string(),
+            ExportField::Dither => on_off(self.settings.dither),
+            ExportField::Normalize => self.settings.normalization.label().to_string(),
+            ExportField::Tail if self.settings.max_tail_secs == 0.0 => "Off".to_string(),
+            ExportField::Tail => format!("up to {}s", self.settings.max_tail_secs),
+            ExportField::ReleaseNotes => on_off(self.settings.release_notes),
+            ExportField::StemMasterFx => on_off(self.stem_master_fx),
+        }
+    }
+
+    /// Default file (mix) or folder (stems) name for the current settings
+    fn default_filename(&self) -> String {
+        match self.mode {
+            ExportMode::Mix => {
+                format!("{}.{}", self.project_name, self.settings.format.extension())
+            }
+            ExportMode::Stems => format!("{}-stems", self.project_name),
+        }
+    }
+
+    /// Swap a trailing `.old_ext` on the mix filename for the current format's
+    fn replace_extension(&mut self, old_ext: &str) {
+        if self.mode != ExportMode::Mix {
+            return;
+        }
+        let value = self.filename.value();
+        if let Some(stem) = value.strip_suffix(&format!(".{}", old_ext)) {
+            let renamed = format!("{}.{}", stem, self.settings.format.extension());
+            self.filename = Input::new(renamed);
+        }
+    }
+}
+
+/// Step `current` by `delta` positions within `options` (wraps around)
+fn cycle_in<T: Copy + PartialEq>(options: &[T], current: T, delta: i32) -> T {
+    let idx = options.iter().position(|&o| o == current).unwrap_or(0);
+    let next = (idx as i32 + delta).rem_euclid(options.len() as i32) as usize;
+    options[next]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_sets_default_filename() {
+        let mut dialog = ExportDialog::new();
+        dialog.show(ExportMode::Mix, "song");
+        assert!(dialog.visible);
+        assert_eq!(dialog.filename.value(), "song.wav");
+
+        dialog.show(ExportMode::Stems, "song");
+        assert_eq!(dialog.filename.value(), "song-stems");
+        assert_eq!(dialog.fields().last(), Some(&ExportField::StemMasterFx));
+    }
+
+    #[test]
+    fn test_format_change_updates_extension_and_flags_bit_depth() {
+        let mut dialog = ExportDialog::new();
+        dialog.show(ExportMode::Mix, "song");
+        dialog.settings.bit_depth = BitDepth::Float32;
+
+        dialog.selected = ExportField::Format;
+        dialog.cycle(1);
+        assert_eq!(dialog.settings.format, ExportFormat::Flac);
+        assert_eq!(dialog.filename.value(), "song.flac");
+        // FLAC can't hold float samples; the choice is kept but flagged
+        assert_eq!(dialog.settings.bit_depth, BitDepth::Float32);
+        assert_eq!(
+            dialog.error().as_deref(),
+            Some("FLAC does not support 32-bit float samples")
+        );
+
+        dialog.selected = ExportField::BitDepth;
+        dialog.cycle(1);
+        assert_eq!(dialog.settings.bit_depth, BitDepth::Int16);
+        assert_eq!(dialog.error(), None);
+    }
+
+    #[test]
+    fn test_cycle_wraps_both_directions() {
+        let mut dialog = ExportDialog::new();
+        dialog.show(ExportMode::Mix, "song");
+        dialog.selected = ExportField::SampleRate;
+
+        dialog.cycle(-1);
+        assert_eq!(dialog.settings.sample_rate, 96000);
+        dialog.cycle(1);
+        assert_eq!(dialog.settings.sample_rate, 44100);
+    }
+
+    #[test]
+    fn test_move_selection_skips_hidden_rows() {
+        let mut dialog = ExportDialog::new();
+        dialog.show(ExportMode::Mix, "song");
+
+        dialog.move_selection(-1);
+        assert_eq!(dialog.selected, ExportField::ReleaseNotes);
+        dialog.move_selection(1);
+        assert_eq!(dialog.selected, ExportField::Filename);
+    }
+}
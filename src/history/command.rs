@@ -6,7 +6,7 @@
 use crate::app::App;
 use crate::arrangement::PatternPlacement;
 use crate::input::context::StepGridContext;
-use crate::sequencer::Note;
+use crate::sequencer::{Note, NoteExpression};
 
 /// A reversible command that mutates App state
 ///
@@ -292,6 +292,67 @@ impl Command for DeleteNotesCmd {
     }
 }
 
+/// Set one per-note expression of a note
+#[derive(Debug)]
+pub struct SetNoteExpressionCmd {
+    pub pattern_id: usize,
+    pub channel: usize,
+    pub note_id: String,
+    pub expression: NoteExpression,
+    pub value: f32,
+    /// The value before the change (captured during execute)
+    previous: Option<f32>,
+}
+
+impl SetNoteExpressionCmd {
+    pub fn new(
+        pattern_id: usize,
+        channel: usize,
+        note_id: String,
+        expression: NoteExpression,
+        value: f32,
+    ) -> Self {
+        Self {
+            pattern_id,
+            channel,
+            note_id,
+            expression,
+            value,
+            previous: None,
+        }
+    }
+
+    /// Set the note's expression, returning the value it had
+    fn set(&self, app: &mut App, value: f32) -> Option<f32> {
+        let note = app
+            .get_channel_at_slot_mut(self.channel)?
+            .get_pattern_mut(self.pattern_id)?
+            .notes
+            .iter_mut()
+            .find(|n| n.id == self.note_id)?;
+        let previous = note.expressions.get(self.expression);
+        note.expressions.set(self.expression, value);
+        app.mark_dirty();
+        Some(previous)
+    }
+}
+
+impl Command for SetNoteExpressionCmd {
+    fn execute(&mut self, app: &mut App) {
+        self.previous = self.set(app, self.value);
+    }
+
+    fn undo(&mut self, app: &mut App) {
+        if let Some(previous) = self.previous {
+            self.set(app, previous);
+        }
+    }
+
+    fn description(&self) -> &str {
+        "Set note expression"
+    }
+}
+
 // ============================================================================
 // Playlist Commands
 // ============================================================================
@@ -720,6 +781,20 @@ impl Command for BatchCmd {
 // Effect Commands
 // ============================================================================
 
+/// Put `effect` into a mixer slot and queue the slot's full state for the
+/// audio thread, returning whatever was there before
+fn replace_effect_slot(
+    app: &mut App,
+    track: usize,
+    slot: usize,
+    effect: Option<crate::effects::EffectSlot>,
+) -> Option<crate::effects::EffectSlot> {
+    let previous = std::mem::replace(&mut app.mixer.tracks[track].effects[slot], effect);
+    app.queue_effect_slot_sync(track, slot);
+    app.mark_dirty();
+    previous
+}
+
 /// Add an effect to a mixer track slot
 #[derive(Debug)]
 pub struct AddEffectCmd {
@@ -744,24 +819,13 @@ impl AddEffectCmd {
 impl Command for AddEffectCmd {
     fn execute(&mut self, app: &mut App) {
         // Store the previous effect (if any) for undo
-        self.previous_effect = app.mixer.tracks[self.track].effects[self.slot].take();
-
-        // Add the new effect
         let effect_slot = crate::effects::EffectSlot::new(self.effect_type);
-        app.mixer.tracks[self.track].effects[self.slot] = Some(effect_slot);
-        app.audio
-            .set_effect(self.track, self.slot, Some(self.effect_type));
-        app.mark_dirty();
+        self.previous_effect = replace_effect_slot(app, self.track, self.slot, Some(effect_slot));
     }
 
     fn undo(&mut self, app: &mut App) {
-        // Restore the previous effect (or None)
-        app.mixer.tracks[self.track].effects[self.slot] = self.previous_effect.take();
-        let effect_type = app.mixer.tracks[self.track].effects[self.slot]
-            .as_ref()
-            .map(|e| e.effect_type);
-        app.audio.set_effect(self.track, self.slot, effect_type);
-        app.mark_dirty();
+        // Restore the previous effect (or None), parameters included
+        replace_effect_slot(app, self.track, self.slot, self.previous_effect.take());
     }
 
     fn description(&self) -> &str {
@@ -791,20 +855,13 @@ impl RemoveEffectCmd {
 impl Command for RemoveEffectCmd {
     fn execute(&mut self, app: &mut App) {
         // Store the removed effect for undo
-        self.removed_effect = app.mixer.tracks[self.track].effects[self.slot].take();
-        app.audio.set_effect(self.track, self.slot, None);
-        app.mark_dirty();
+        self.removed_effect = replace_effect_slot(app, self.track, self.slot, None);
     }
 
     fn undo(&mut self, app: &mut App) {
         // Restore the removed effect
-        if let Some(effect) = self.removed_effect.take() {
-            let effect_type = effect.effect_type;
-            app.mixer.tracks[self.track].effects[self.slot] = Some(effect.clone());
-            self.removed_effect = Some(effect);
-            app.audio
-                .set_effect(self.track, self.slot, Some(effect_type));
-            app.mark_dirty();
+        if let Some(effect) = self.removed_effect.clone() {
+            replace_effect_slot(app, self.track, self.slot, Some(effect));
         }
     }
 
@@ -812,3 +869,193 @@ impl Command for RemoveEffectCmd {
         "Remove effect"
     }
 }
+
+/// Paste a copied effect (type, parameters and bypass state) into a slot
+#[derive(Debug)]
+pub struct PasteEffectCmd {
+    pub track: usize,
+    pub slot: usize,
+    pub effect: crate::effects::EffectSlot,
+    /// The effect that was in the slot before pasting (for undo)
+    previous_effect: Option<crate::effects::EffectSlot>,
+}
+
+impl PasteEffectCmd {
+    pub fn new(track: usize, slot: usize, effect: crate::effects::EffectSlot) -> Self {
+        Self {
+            track,
+            slot,
+            effect,
+            previous_effect: None,
+        }
+    }
+}
+
+impl Command for PasteEffectCmd {
+    fn execute(&mut self, app: &mut App) {
+        self.previous_effect =
+            replace_effect_slot(app, self.track, self.slot, Some(self.effect.clone()));
+    }
+
+    fn undo(&mut self, app: &mut App) {
+        replace_effect_slot(app, self.track, self.slot, self.previous_effect.take());
+    }
+
+    fn description(&self) -> &str {
+        "Paste effect"
+    }
+}
+
+/// Swap two slots of a track's effect chain (also used to move a slot up/down)
+#[derive(Debug)]
+pub struct SwapEffectsCmd {
+    pub track: usize,
+    pub a: usize,
+    pub b: usize,
+}
+
+impl SwapEffectsCmd {
+    pub fn new(track: usize, a: usize, b: usize) -> Self {
+        Self { track, a, b }
+    }
+
+    fn swap(&self, app: &mut App) {
+        if self.a == self.b {
+            return;
+        }
+        app.mixer.tracks[self.track].effects.swap(self.a, self.b);
+        app.queue_effect_slot_sync(self.track, self.a);
+        app.queue_effect_slot_sync(self.track, self.b);
+        app.mark_dirty();
+    }
+}
+
+impl Command for SwapEffectsCmd {
+    fn execute(&mut self, app: &mut App) {
+        self.swap(app);
+    }
+
+    fn undo(&mut self, app: &mut App) {
+        // Swapping is its own inverse
+        self.swap(app);
+    }
+
+    fn description(&self) -> &str {
+        "Move effect"
+    }
+}
+
+/// Replace a track's whole effect chain with a copied one
+#[derive(Debug)]
+pub struct PasteEffectChainCmd {
+    pub track: usize,
+    pub chain: [Option<crate::effects::EffectSlot>; crate::effects::EFFECT_SLOTS],
+    /// The track's chain before pasting (for undo)
+    previous_chain: Option<[Option<crate::effects::EffectSlot>; crate::effects::EFFECT_SLOTS]>,
+}
+
+impl PasteEffectChainCmd {
+    pub fn new(
+        track: usize,
+        chain: [Option<crate::effects::EffectSlot>; crate::effects::EFFECT_SLOTS],
+    ) -> Self {
+        Self {
+            track,
+            chain,
+            previous_chain: None,
+        }
+    }
+
+    /// Put `chain` on the track, returning the chain it replaced
+    fn set_chain(
+        &self,
+        app: &mut App,
+        chain: [Option<crate::effects::EffectSlot>; crate::effects::EFFECT_SLOTS],
+    ) -> [Option<crate::effects::EffectSlot>; crate::effects::EFFECT_SLOTS] {
+        let mut previous = chain;
+        std::mem::swap(&mut app.mixer.tracks[self.track].effects, &mut previous);
+        for slot in 0..crate::effects::EFFECT_SLOTS {
+            app.queue_effect_slot_sync(self.track, slot);
+        }
+        app.mark_dirty();
+        previous
+    }
+}
+
+impl Command for PasteEffectChainCmd {
+    fn execute(&mut self, app: &mut App) {
+        self.previous_chain = Some(self.set_chain(app, self.chain.clone()));
+    }
+
+    fn undo(&mut self, app: &mut App) {
+        if let Some(previous) = self.previous_chain.take() {
+            self.set_chain(app, previous);
+        }
+    }
+
+    fn description(&self) -> &str {
+        "Paste effect chain"
+    }
+}
+
+/// Replace all parameters of an effect slot (applying a preset)
+#[derive(Debug)]
+pub struct ApplyEffectPresetCmd {
+    pub track: usize,
+    pub slot: usize,
+    pub params: std::collections::HashMap<crate::effects::EffectParamId, f32>,
+    /// The parameters before the preset was applied (for undo)
+    previous_params: Option<std::collections::HashMap<crate::effects::EffectParamId, f32>>,
+}
+
+impl ApplyEffectPresetCmd {
+    pub fn new(
+        track: usize,
+        slot: usize,
+        params: std::collections::HashMap<crate::effects::EffectParamId, f32>,
+    ) -> Self {
+        Self {
+            track,
+            slot,
+            params,
+            previous_params: None,
+        }
+    }
+
+    /// Put `params` into the slot and send each value to the audio thread
+    fn set_params(
+        &self,
+        app: &mut App,
+        params: std::collections::HashMap<crate::effects::EffectParamId, f32>,
+    ) {
+        for (&param_id, &value) in &params {
+            app.audio
+                .set_effect_param(self.track, self.slot, param_id, value);
+        }
+        if let Some(effect) = app.mixer.tracks[self.track].effects[self.slot].as_mut() {
+            effect.params = params;
+        }
+        app.sync_convolution_kernel(self.track, self.slot);
+        app.mark_dirty();
+    }
+}
+
+impl Command for ApplyEffectPresetCmd {
+    fn execute(&mut self, app: &mut App) {
+        let Some(effect) = app.mixer.tracks[self.track].effects[self.slot].as_ref() else {
+            return;
+        };
+        self.previous_params = Some(effect.params.clone());
+        self.set_params(app, self.params.clone());
+    }
+
+    fn undo(&mut self, app: &mut App) {
+        if let Some(previous) = self.previous_params.take() {
+            self.set_params(app, previous);
+        }
+    }
+
+    fn description(&self) -> &str {
+        "Apply preset"
+    }
+}
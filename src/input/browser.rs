@@ -3,6 +3,7 @@
 use crossterm::event::{KeyCode, KeyEvent};
 
 use crate::app::{App, Panel};
+use crate::browser::{BrowserMode, SelectionTarget};
 
 use super::mouse::MouseAction;
 
@@ -10,10 +11,19 @@ use super::mouse::MouseAction;
 pub fn handle_key(key: KeyEvent, app: &mut App) {
     // Handle Escape to cancel selection mode
     if key.code == KeyCode::Esc {
+        let target = app.ui.browser.target;
         if app.ui.browser.selection_mode {
             app.ui.browser.cancel_selection();
         }
-        app.ui.mode.switch_panel(Panel::ChannelRack);
+        match target {
+            // Back to the effect editor the IR was being picked from
+            Some(SelectionTarget::ImpulseResponse { track, slot }) => {
+                app.ui.mode.switch_panel(Panel::Mixer);
+                app.ui.mode.open_effect_editor(track, slot);
+            }
+            Some(SelectionTarget::EffectPlugin { .. }) => app.ui.mode.switch_panel(Panel::Mixer),
+            _ => app.ui.mode.switch_panel(Panel::ChannelRack),
+        }
         return;
     }
 
@@ -27,6 +37,12 @@ pub fn handle_key(key: KeyEvent, app: &mut App) {
             return; // Don't trigger auto-preview after mode switch
         }
 
+        // Cycle plugin grouping: category, vendor, files
+        KeyCode::Char('v') => {
+            app.ui.browser.cycle_plugin_grouping();
+            return;
+        }
+
         // Navigation
         KeyCode::Char('j') | KeyCode::Down => {
             app.ui.browser.move_down(1);
@@ -65,19 +81,12 @@ pub fn handle_key(key: KeyEvent, app: &mut App) {
                     // Toggle folder expansion
                     app.ui.browser.toggle_or_select();
                 } else if app.ui.browser.selection_mode {
-                    // Complete selection and assign sample/plugin to channel
+                    // Complete selection and assign the file to its target
                     let browser_mode = app.ui.browser.mode;
-                    if let Some((channel_idx, relative_path)) = app.ui.browser.complete_selection()
+                    if let Some((target, relative_path, plugin_id)) =
+                        app.ui.browser.complete_selection()
                     {
-                        match browser_mode {
-                            crate::browser::BrowserMode::Samples => {
-                                app.set_channel_sample(channel_idx, relative_path);
-                            }
-                            crate::browser::BrowserMode::Plugins => {
-                                app.set_channel_plugin(channel_idx, relative_path);
-                            }
-                        }
-                        app.ui.mode.switch_panel(Panel::ChannelRack);
+                        assign_selection(app, target, browser_mode, relative_path, plugin_id);
                     }
                 } else {
                     // Just preview the file
@@ -166,20 +175,12 @@ pub fn handle_mouse_action(action: &MouseAction, app: &mut App) {
                         // Toggle folder expansion
                         app.ui.browser.toggle_or_select();
                     } else if app.ui.browser.selection_mode {
-                        // Complete selection and assign sample/plugin to channel
+                        // Complete selection and assign the file to its target
                         let browser_mode = app.ui.browser.mode;
-                        if let Some((channel_idx, relative_path)) =
+                        if let Some((target, relative_path, plugin_id)) =
                             app.ui.browser.complete_selection()
                         {
-                            match browser_mode {
-                                crate::browser::BrowserMode::Samples => {
-                                    app.set_channel_sample(channel_idx, relative_path);
-                                }
-                                crate::browser::BrowserMode::Plugins => {
-                                    app.set_channel_plugin(channel_idx, relative_path);
-                                }
-                            }
-                            app.ui.mode.switch_panel(Panel::ChannelRack);
+                            assign_selection(app, target, browser_mode, relative_path, plugin_id);
                         }
                     } else {
                         // Just preview the file - browser previews go directly to master
@@ -245,3 +246,33 @@ pub fn handle_mouse_action(action: &MouseAction, app: &mut App) {
         }
     }
 }
+
+/// Assign a file picked in selection mode to its target and leave the browser
+fn assign_selection(
+    app: &mut App,
+    target: SelectionTarget,
+    browser_mode: BrowserMode,
+    relative_path: String,
+    plugin_id: Option<String>,
+) {
+    match target {
+        SelectionTarget::Channel(channel_idx) => {
+            match browser_mode {
+                BrowserMode::Samples => app.set_channel_sample(channel_idx, relative_path),
+                BrowserMode::Plugins => {
+                    app.set_channel_plugin(channel_idx, relative_path, plugin_id)
+                }
+            }
+            app.ui.mode.switch_panel(Panel::ChannelRack);
+        }
+        SelectionTarget::ImpulseResponse { track, slot } => {
+            app.set_effect_impulse_response(track, slot, relative_path);
+            app.ui.mode.switch_panel(Panel::Mixer);
+            app.ui.mode.open_effect_editor(track, slot);
+        }
+        SelectionTarget::EffectPlugin { track, slot } => {
+            app.set_effect_plugin(track, slot, relative_path, plugin_id);
+            app.ui.mode.switch_panel(Panel::Mixer);
+        }
+    }
+}
@@ -8,7 +8,6 @@ use crate::app::{App, Panel};
 use crate::command::AppCommand;
 use crate::coords::{AppCol, VimCol};
 use crate::mode::ViewMode;
-use crate::plugin_host::params::build_editor_params;
 use crate::sequencer::ChannelSource;
 
 use super::common::key_to_vim_char;
@@ -57,20 +56,16 @@ pub fn handle_key(key: KeyEvent, app: &mut App) {
                 }
             } else {
                 // Open plugin editor for plugin channels
-                let plugin_info = app.get_channel_at_slot(slot).and_then(|channel| {
-                    if let ChannelSource::Plugin { .. } = &channel.source {
-                        let params = build_editor_params(channel.plugin_params());
-                        Some((channel.name.clone(), params))
-                    } else {
-                        None
-                    }
-                });
-                if let Some((name, params)) = plugin_info {
-                    app.ui.plugin_editor.open(slot, &name, params);
-                }
+                app.open_plugin_editor(slot);
             }
             return;
         }
+        // 'O' (shift+o) to cycle which plugin channel this plugin's note output plays
+        KeyCode::Char('O') => {
+            let slot = app.ui.cursors.channel_rack.channel;
+            app.dispatch(AppCommand::CycleNoteOut(slot));
+            return;
+        }
         // '[' to switch to previous pattern
         KeyCode::Char('[') => {
             app.dispatch(AppCommand::PreviousPattern);
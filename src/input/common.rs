@@ -4,7 +4,6 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::app::App;
 use crate::command_picker::Command;
-use crate::plugin_host::params::{ParamDef, PluginParamId};
 
 /// Convert a KeyEvent to vim-compatible (char, is_ctrl) tuple.
 /// Returns None for keys that shouldn't be passed to vim.
@@ -31,34 +30,23 @@ pub fn key_to_vim_char(key: KeyEvent) -> Option<(char, bool)> {
 
 /// Send the currently selected parameter to the plugin and save to channel
 pub fn send_param_to_plugin(app: &mut App) {
-    let channel_idx = app.ui.plugin_editor.channel_idx;
-    if let Some(param) = app.ui.plugin_editor.selected_param() {
-        let param_value = param.value;
-
-        // Get the param ID from the registry
-        let param_id = PluginParamId::ALL
-            .get(app.ui.plugin_editor.selected_param)
-            .copied();
-
-        if let Some(param_id) = param_id {
-            // Save to channel's plugin_params for persistence
-            if let Some(channel) = app.channels.get_mut(channel_idx) {
-                if let Some(params) = channel.plugin_params_mut() {
-                    params.insert(param_id, param_value);
-                }
-            }
+    let slot = app.ui.plugin_editor.channel_idx;
+    let Some(param) = app.ui.plugin_editor.selected_param() else {
+        return;
+    };
+    let (param_id, value) = (param.id, param.value);
 
-            // Get the CLAP param ID and normalized value from registry
-            let def = ParamDef::get(param_id);
-            if let Some(def) = def {
-                let normalized = def.normalize(param_value);
-                app.audio
-                    .plugin_set_param(channel_idx, def.clap_id, normalized);
-            }
+    // The editor tracks the channel slot; the audio engine uses the Vec index
+    let Some(channel_idx) = app.channels.iter().position(|c| c.slot == slot) else {
+        return;
+    };
 
-            app.mark_dirty();
-        }
+    // Save to channel's plugin_params for persistence
+    if let Some(params) = app.channels[channel_idx].plugin_params_mut() {
+        params.insert(param_id, value);
     }
+    app.audio.plugin_set_param(channel_idx, param_id, value);
+    app.mark_dirty();
 }
 
 /// Execute a command from the picker
@@ -103,5 +91,9 @@ pub fn execute_command(cmd: Command, app: &mut App) -> bool {
             app.start_export();
             false
         }
+        Command::ExportStems => {
+            app.start_export_stems();
+            false
+        }
     }
 }
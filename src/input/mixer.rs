@@ -134,28 +134,89 @@ fn handle_effects_key(key: KeyEvent, app: &mut App) {
                 app.dispatch(AppCommand::RemoveEffect { track, slot });
             }
         }
+        // y = yank effect (copy without deleting)
+        KeyCode::Char('y') => {
+            if selected < EFFECT_SLOTS && !on_bypass {
+                let track = app.mixer.selected_track;
+                if let Some(effect) = app.mixer.tracks[track].effects[selected].clone() {
+                    app.ui.effect_register = Some(effect);
+                }
+            }
+        }
         // p = paste effect from register - only for effect slots, only on effect column
         KeyCode::Char('p') => {
             if selected < EFFECT_SLOTS && !on_bypass {
                 if let Some(effect) = app.ui.effect_register.clone() {
-                    let track = app.mixer.selected_track;
-                    let slot = app.mixer.selected_effect_slot;
-                    app.dispatch(AppCommand::AddEffect {
+                    app.dispatch(AppCommand::PasteEffect {
+                        track: app.mixer.selected_track,
+                        slot: selected,
+                        effect,
+                    });
+                }
+            }
+        }
+        // J/K = move effect down/up the chain (selection follows)
+        KeyCode::Char('J') => {
+            if selected + 1 < EFFECT_SLOTS {
+                move_effect(app, selected, selected + 1);
+            }
+        }
+        KeyCode::Char('K') => {
+            if selected > 0 && selected < EFFECT_SLOTS {
+                move_effect(app, selected, selected - 1);
+            }
+        }
+        // m = mark slot for swapping, s = swap selected slot with the mark
+        KeyCode::Char('m') => {
+            if selected < EFFECT_SLOTS {
+                let mark = (app.mixer.selected_track, selected);
+                app.ui.effect_swap_mark = if app.ui.effect_swap_mark == Some(mark) {
+                    None
+                } else {
+                    Some(mark)
+                };
+            }
+        }
+        KeyCode::Char('s') => {
+            let track = app.mixer.selected_track;
+            if let Some((mark_track, mark_slot)) = app.ui.effect_swap_mark {
+                if mark_track == track && selected < EFFECT_SLOTS && mark_slot != selected {
+                    app.dispatch(AppCommand::SwapEffects {
                         track,
-                        slot,
-                        effect_type: effect.effect_type,
+                        a: mark_slot,
+                        b: selected,
                     });
-                    // Copy parameters from register to the new effect
-                    if let Some(new_effect) = &mut app.mixer.tracks[track].effects[slot] {
-                        new_effect.params = effect.params;
-                    }
+                    app.ui.effect_swap_mark = None;
                 }
             }
         }
+        // Y = yank the whole chain, P = paste it over this track's chain
+        KeyCode::Char('Y') => {
+            let track = app.mixer.selected_track;
+            app.ui.effect_chain_register = Some(Box::new(app.mixer.tracks[track].effects.clone()));
+        }
+        KeyCode::Char('P') => {
+            if let Some(chain) = app.ui.effect_chain_register.clone() {
+                app.dispatch(AppCommand::PasteEffectChain {
+                    track: app.mixer.selected_track,
+                    chain,
+                });
+            }
+        }
         _ => {}
     }
 }
 
+/// Swap an effect with its neighbour and keep it selected
+fn move_effect(app: &mut App, from: usize, to: usize) {
+    app.dispatch(AppCommand::SwapEffects {
+        track: app.mixer.selected_track,
+        a: from,
+        b: to,
+    });
+    app.mixer.selected_effect_slot = to;
+}
+
 /// Handle keys when tracks are focused (default mode)
 fn handle_tracks_key(key: KeyEvent, app: &mut App) {
     let max_track = NUM_TRACKS - 1;
@@ -629,6 +690,132 @@ mod tests {
         );
     }
 
+    fn focus_slot(app: &mut App, track: usize, slot: usize) {
+        app.mixer.selected_track = track;
+        app.mixer.effects_focused = true;
+        app.mixer.selected_effect_slot = slot;
+        app.mixer.on_bypass_column = false;
+    }
+
+    fn undo(app: &mut App) {
+        let mut history = std::mem::take(&mut app.history);
+        history.undo(app);
+        app.history = history;
+    }
+
+    fn effect_type(app: &App, track: usize, slot: usize) -> Option<EffectType> {
+        app.mixer.tracks[track].effects[slot]
+            .as_ref()
+            .map(|e| e.effect_type)
+    }
+
+    #[test]
+    fn test_yank_then_paste_copies_params_undoably() {
+        use crate::effects::EffectParamId;
+        let (mut app, _temp) = create_test_app();
+        let mut effect = EffectSlot::new(EffectType::Reverb);
+        effect.set_param(EffectParamId::ReverbMix, 0.9);
+        effect.bypassed = true;
+        app.mixer.tracks[1].effects[0] = Some(effect.clone());
+
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('y')), &mut app);
+        assert!(
+            app.mixer.tracks[1].effects[0].is_some(),
+            "yank keeps the effect"
+        );
+
+        app.mixer.tracks[1].effects[4] = Some(EffectSlot::new(EffectType::Eq));
+        focus_slot(&mut app, 1, 4);
+        handle_key(key(KeyCode::Char('p')), &mut app);
+        let pasted = app.mixer.tracks[1].effects[4].as_ref().unwrap();
+        assert_eq!(pasted.get_param(EffectParamId::ReverbMix), 0.9);
+        assert!(pasted.bypassed);
+
+        undo(&mut app);
+        assert_eq!(effect_type(&app, 1, 4), Some(EffectType::Eq));
+    }
+
+    #[test]
+    fn test_move_effect_down_and_up() {
+        let (mut app, _temp) = create_test_app();
+        app.mixer.tracks[1].effects[0] = Some(EffectSlot::new(EffectType::Filter));
+        app.mixer.tracks[1].effects[1] = Some(EffectSlot::new(EffectType::Delay));
+
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('J')), &mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Delay));
+        assert_eq!(effect_type(&app, 1, 1), Some(EffectType::Filter));
+        assert_eq!(app.mixer.selected_effect_slot, 1, "selection follows");
+
+        handle_key(key(KeyCode::Char('J')), &mut app);
+        assert_eq!(effect_type(&app, 1, 2), Some(EffectType::Filter));
+        assert!(app.mixer.tracks[1].effects[1].is_none());
+
+        undo(&mut app);
+        undo(&mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Filter));
+        assert_eq!(effect_type(&app, 1, 1), Some(EffectType::Delay));
+
+        // K at the top of the chain does nothing
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('K')), &mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Filter));
+    }
+
+    #[test]
+    fn test_swap_with_marked_slot() {
+        let (mut app, _temp) = create_test_app();
+        app.mixer.tracks[1].effects[0] = Some(EffectSlot::new(EffectType::Filter));
+        app.mixer.tracks[1].effects[5] = Some(EffectSlot::new(EffectType::Compressor));
+
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('m')), &mut app);
+        assert_eq!(app.ui.effect_swap_mark, Some((1, 0)));
+
+        app.mixer.selected_effect_slot = 5;
+        handle_key(key(KeyCode::Char('s')), &mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Compressor));
+        assert_eq!(effect_type(&app, 1, 5), Some(EffectType::Filter));
+        assert_eq!(app.ui.effect_swap_mark, None);
+
+        undo(&mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Filter));
+    }
+
+    #[test]
+    fn test_swap_ignores_mark_on_other_track() {
+        let (mut app, _temp) = create_test_app();
+        app.mixer.tracks[1].effects[0] = Some(EffectSlot::new(EffectType::Filter));
+        app.ui.effect_swap_mark = Some((2, 3));
+
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('s')), &mut app);
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Filter));
+        assert!(app.mixer.tracks[1].effects[3].is_none());
+    }
+
+    #[test]
+    fn test_copy_effect_chain_to_another_track() {
+        let (mut app, _temp) = create_test_app();
+        app.mixer.tracks[1].effects[0] = Some(EffectSlot::new(EffectType::Filter));
+        app.mixer.tracks[1].effects[2] = Some(EffectSlot::new(EffectType::Reverb));
+        app.mixer.tracks[2].effects[1] = Some(EffectSlot::new(EffectType::Delay));
+
+        focus_slot(&mut app, 1, 0);
+        handle_key(key(KeyCode::Char('Y')), &mut app);
+        focus_slot(&mut app, 2, 0);
+        handle_key(key(KeyCode::Char('P')), &mut app);
+        assert_eq!(effect_type(&app, 2, 0), Some(EffectType::Filter));
+        assert!(app.mixer.tracks[2].effects[1].is_none());
+        assert_eq!(effect_type(&app, 2, 2), Some(EffectType::Reverb));
+
+        undo(&mut app);
+        assert!(app.mixer.tracks[2].effects[0].is_none());
+        assert_eq!(effect_type(&app, 2, 1), Some(EffectType::Delay));
+        assert_eq!(effect_type(&app, 1, 0), Some(EffectType::Filter));
+    }
+
     // ========================================================================
     // Mouse handling tests
     // ========================================================================
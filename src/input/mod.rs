@@ -25,6 +25,7 @@ use tui_input::backend::crossterm::EventHandler;
 
 use crate::app::{App, Panel};
 use crate::command::AppCommand;
+use crate::command_picker::Command;
 
 /// Handle a keyboard event
 /// Returns true if the app should quit
@@ -67,6 +68,20 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> bool {
 
     // From here on, we only handle Press events
 
+    // A running export blocks other input; Esc cancels it and the quit key
+    // cancels it and quits
+    if app.export_job.is_some() {
+        match key.code {
+            KeyCode::Esc => app.cancel_export(),
+            KeyCode::Char(c) if c == Command::Quit.key() => {
+                app.abort_export();
+                return true;
+            }
+            _ => {}
+        }
+        return false;
+    }
+
     // Handle input mode first (tempo entry, etc.)
     if app.ui.command_picker.input.active {
         return handle_input_mode_key(key, app);
@@ -87,6 +102,11 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> bool {
         return handle_projects_modal_key(key, app);
     }
 
+    // Handle export dialog (if visible)
+    if app.ui.export_dialog.visible {
+        return handle_export_dialog_key(key, app);
+    }
+
     // Handle plugin editor modal (if visible)
     if app.ui.plugin_editor.visible {
         return handle_plugin_editor_key(key, app);
@@ -200,12 +220,6 @@ fn handle_input_mode_key(key: KeyEvent, app: &mut App) -> bool {
                         app.mark_dirty();
                     }
                 }
-                InputTarget::ExportWav => {
-                    if let Some(filename) = app.ui.command_picker.get_export_filename() {
-                        let filename = filename.to_string();
-                        app.do_export(&filename);
-                    }
-                }
                 InputTarget::None => {}
             }
             app.ui.command_picker.cancel_input();
@@ -220,7 +234,6 @@ fn handle_input_mode_key(key: KeyEvent, app: &mut App) -> bool {
             // Limit input length based on target
             let max_len = match target {
                 InputTarget::Tempo => 6,
-                InputTarget::ExportWav => 100,
                 InputTarget::None => 100,
             };
             if app.ui.command_picker.input.input.value().len() < max_len
@@ -238,6 +251,35 @@ fn handle_input_mode_key(key: KeyEvent, app: &mut App) -> bool {
     }
 }
 
+/// Handle keyboard input when the export dialog is visible
+fn handle_export_dialog_key(key: KeyEvent, app: &mut App) -> bool {
+    use crate::export_dialog::ExportField;
+
+    let dialog = &mut app.ui.export_dialog;
+    let on_filename = dialog.selected == ExportField::Filename;
+
+    match key.code {
+        KeyCode::Esc => dialog.hide(),
+        KeyCode::Enter => app.confirm_export(),
+        KeyCode::Up | KeyCode::BackTab => dialog.move_selection(-1),
+        KeyCode::Down | KeyCode::Tab => dialog.move_selection(1),
+        KeyCode::Char('k') if !on_filename => dialog.move_selection(-1),
+        KeyCode::Char('j') if !on_filename => dialog.move_selection(1),
+        KeyCode::Left | KeyCode::Char('h') if !on_filename => dialog.cycle(-1),
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') if !on_filename => dialog.cycle(1),
+        _ if on_filename => {
+            if dialog.filename.value().len() < 100
+                || key.code == KeyCode::Backspace
+                || key.code == KeyCode::Delete
+            {
+                dialog.filename.handle_event(&Event::Key(key));
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
 /// Handle keyboard input when command picker is visible
 fn handle_command_picker_key(key: KeyEvent, app: &mut App) -> bool {
     match key.code {
@@ -466,6 +508,10 @@ fn execute_confirm_action(app: &mut App, action: &crate::projects_modal::Confirm
 
 /// Handle plugin editor modal keys
 fn handle_plugin_editor_key(key: KeyEvent, app: &mut App) -> bool {
+    if app.ui.plugin_preset_picker.visible {
+        return handle_plugin_preset_picker_key(key, app);
+    }
+
     match key.code {
         // Escape closes editor
         KeyCode::Esc => {
@@ -511,10 +557,75 @@ fn handle_plugin_editor_key(key: KeyEvent, app: &mut App) -> bool {
             common::send_param_to_plugin(app);
             false
         }
+        // Presets: [ and ] step through them, p lists them
+        KeyCode::Char('[') | KeyCode::Char(']') => {
+            let delta = if key.code == KeyCode::Char('[') {
+                -1
+            } else {
+                1
+            };
+            if let Err(e) = app.step_plugin_preset(delta) {
+                app.log_event(format!("failed to apply preset: {}", e), false);
+            }
+            false
+        }
+        KeyCode::Char('p') => {
+            let picker = &mut app.ui.plugin_preset_picker;
+            picker.visible = true;
+            picker.message = None;
+            false
+        }
         _ => false,
     }
 }
 
+/// Handle plugin preset picker keys (shown over the plugin editor)
+fn handle_plugin_preset_picker_key(key: KeyEvent, app: &mut App) -> bool {
+    if app.ui.plugin_preset_picker.is_naming() {
+        match key.code {
+            // Escape goes back to the list
+            KeyCode::Esc => app.ui.plugin_preset_picker.cancel_naming(),
+            // Enter saves under the typed name
+            KeyCode::Enter => {
+                let name = app
+                    .ui
+                    .plugin_preset_picker
+                    .input_value()
+                    .unwrap_or("")
+                    .to_string();
+                let message = match app.save_plugin_preset(&name) {
+                    Ok(()) => format!("Saved '{}'", name.trim()),
+                    Err(e) => e.to_string(),
+                };
+                app.ui.plugin_preset_picker.message = Some(message);
+                app.ui.plugin_preset_picker.cancel_naming();
+            }
+            // Pass other keys to input handler
+            _ => {
+                if let Some(input) = app.ui.plugin_preset_picker.input_mut() {
+                    input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        }
+        return false;
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('p') => app.ui.plugin_preset_picker.close(),
+        KeyCode::Char('j') | KeyCode::Down => app.ui.plugin_preset_picker.select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.ui.plugin_preset_picker.select_prev(),
+        // Enter applies the selected preset and returns to the editor
+        KeyCode::Enter => match app.apply_selected_plugin_preset() {
+            Ok(()) => app.ui.plugin_preset_picker.close(),
+            Err(e) => app.ui.plugin_preset_picker.message = Some(e),
+        },
+        // s saves the current sound as a new preset
+        KeyCode::Char('s') => app.ui.plugin_preset_picker.start_naming(),
+        _ => {}
+    }
+    false
+}
+
 /// Handle keyboard input when context menu is visible
 fn handle_context_menu_key(key: KeyEvent, app: &mut App) -> bool {
     match key.code {
@@ -579,7 +690,19 @@ fn handle_effect_picker_key(key: KeyEvent, app: &mut App) -> bool {
             let effect_types = EffectType::all();
             if app.ui.effect_picker_selection < effect_types.len() {
                 let effect_type = effect_types[app.ui.effect_picker_selection];
-                app.add_effect(effect_type);
+                app.ui.effect_picker_selection = 0;
+                app.ui.mode.close_modal();
+                if effect_type == EffectType::Plugin {
+                    // The slot is filled once a plugin is picked in the browser
+                    let track = app.mixer.selected_track;
+                    let slot = app.mixer.selected_effect_slot;
+                    app.ui.browser.start_effect_plugin_selection(track, slot);
+                    app.ui.mode.switch_panel(Panel::Browser);
+                    app.ui.show_browser = true;
+                } else {
+                    app.add_effect(effect_type);
+                }
+                return false;
             }
             app.ui.effect_picker_selection = 0;
             app.ui.mode.close_modal();
@@ -591,7 +714,7 @@ fn handle_effect_picker_key(key: KeyEvent, app: &mut App) -> bool {
 
 /// Handle effect editor modal keys
 fn handle_effect_editor_key(key: KeyEvent, app: &mut App) -> bool {
-    use crate::effects::get_param_defs;
+    use crate::effects::{get_param_defs, EffectType};
     use crate::mode::AppMode;
 
     // Get current track/slot/param from mode
@@ -614,6 +737,10 @@ fn handle_effect_editor_key(key: KeyEvent, app: &mut App) -> bool {
         }
     };
 
+    if app.ui.preset_picker.visible {
+        return handle_preset_picker_key(key, app, track_idx, slot_idx);
+    }
+
     let param_defs = get_param_defs(effect_slot.effect_type);
 
     match key.code {
@@ -622,9 +749,14 @@ fn handle_effect_editor_key(key: KeyEvent, app: &mut App) -> bool {
             app.ui.mode.close_modal();
             false
         }
+        // Browse presets for this effect type
+        KeyCode::Char('p') if !param_defs.is_empty() => {
+            app.open_effect_presets(track_idx, slot_idx);
+            false
+        }
         // Navigate up/down
         KeyCode::Char('j') | KeyCode::Down => {
-            if selected_param < param_defs.len() - 1 {
+            if selected_param + 1 < param_defs.len() {
                 if let AppMode::EffectEditor {
                     selected_param: ref mut p,
                     ..
@@ -708,10 +840,67 @@ fn handle_effect_editor_key(key: KeyEvent, app: &mut App) -> bool {
             }
             false
         }
+        // Pick an impulse response for a convolution reverb in the browser
+        KeyCode::Char('i') if effect_slot.effect_type == EffectType::Convolution => {
+            app.ui.mode.close_modal();
+            app.ui
+                .browser
+                .start_impulse_response_selection(track_idx, slot_idx);
+            app.ui.mode.switch_panel(Panel::Browser);
+            app.ui.show_browser = true;
+            false
+        }
         _ => false,
     }
 }
 
+/// Handle preset picker keys (shown over the effect editor)
+fn handle_preset_picker_key(
+    key: KeyEvent,
+    app: &mut App,
+    track_idx: usize,
+    slot_idx: usize,
+) -> bool {
+    if app.ui.preset_picker.is_naming() {
+        match key.code {
+            // Escape goes back to the list
+            KeyCode::Esc => app.ui.preset_picker.cancel_naming(),
+            // Enter saves under the typed name
+            KeyCode::Enter => {
+                let name = app.ui.preset_picker.input_value().unwrap_or("").to_string();
+                let message = match app.save_effect_preset(track_idx, slot_idx, &name) {
+                    Ok(()) => format!("Saved '{}'", name.trim()),
+                    Err(e) => e.to_string(),
+                };
+                app.ui.preset_picker.message = Some(message);
+                app.ui.preset_picker.cancel_naming();
+            }
+            // Pass other keys to input handler
+            _ => {
+                if let Some(input) = app.ui.preset_picker.input_mut() {
+                    input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        }
+        return false;
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('p') => app.ui.preset_picker.close(),
+        KeyCode::Char('j') | KeyCode::Down => app.ui.preset_picker.select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.ui.preset_picker.select_prev(),
+        // Enter applies the selected preset and returns to the editor
+        KeyCode::Enter => {
+            app.apply_selected_preset(track_idx, slot_idx);
+            app.ui.preset_picker.close();
+        }
+        // s saves the current parameters as a new preset
+        KeyCode::Char('s') => app.ui.preset_picker.start_naming(),
+        _ => {}
+    }
+    false
+}
+
 /// Handle a mouse event
 ///
 /// This follows the same pattern as handle_key:
@@ -1181,8 +1370,7 @@ fn execute_context_menu_action(
             let current = app.current_jump_position();
             app.ui.global_jumplist.push(current);
             if let Some(MenuContext::ChannelRack { channel }) = context {
-                app.ui.browser.start_selection(channel);
-                app.ui.browser.mode = crate::browser::BrowserMode::Plugins;
+                app.ui.browser.start_plugin_selection(channel);
             }
             app.ui.mode.switch_panel(Panel::Browser);
             app.ui.show_browser = true;
@@ -7,6 +7,7 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use crate::app::App;
 use crate::command::AppCommand;
 use crate::mode::ViewMode;
+use crate::sequencer::NoteExpression;
 
 use super::common::key_to_vim_char;
 use super::vim::{self, VimAction};
@@ -119,6 +120,20 @@ pub fn handle_key(key: KeyEvent, app: &mut App) {
             transpose_note(app, 1);
             return;
         }
+        // 'E' to show the next per-note expression lane (or hide it)
+        KeyCode::Char('E') => {
+            cycle_expression_lane(app);
+            return;
+        }
+        // '+' and '-' to raise/lower the lane's expression on the note
+        KeyCode::Char('+') | KeyCode::Char('=') => {
+            adjust_note_expression(app, 1.0);
+            return;
+        }
+        KeyCode::Char('-') | KeyCode::Char('_') => {
+            adjust_note_expression(app, -1.0);
+            return;
+        }
         _ => {}
     }
 
@@ -332,9 +347,10 @@ fn nudge_note(app: &mut App, delta: i32) {
         .get(channel)
         .and_then(|c| c.get_pattern(pattern))
         .and_then(|s| s.get_note_at(pitch, step))
-        .map(|n| (n.pitch, n.start_step, n.duration));
+        .cloned();
 
-    if let Some((note_pitch, start_step, duration)) = note_info {
+    if let Some(mut note) = note_info {
+        let (note_pitch, start_step, duration) = (note.pitch, note.start_step, note.duration);
         let new_start = (start_step as i32 + delta).clamp(0, 15 - duration as i32 + 1) as usize;
         if new_start != start_step {
             // Delete old note
@@ -344,8 +360,8 @@ fn nudge_note(app: &mut App, delta: i32) {
                 pitch: note_pitch,
                 start_step,
             });
-            // Add new note at nudged position
-            let note = crate::sequencer::Note::new(pitch, new_start, duration);
+            // Add it back at the nudged position, keeping velocity and expressions
+            note.start_step = new_start;
             app.dispatch(AppCommand::AddNote {
                 channel,
                 pattern,
@@ -368,9 +384,10 @@ fn transpose_note(app: &mut App, delta: i32) {
         .get(channel)
         .and_then(|c| c.get_pattern(pattern))
         .and_then(|s| s.get_note_at(pitch, step))
-        .map(|n| (n.pitch, n.start_step, n.duration));
+        .cloned();
 
-    if let Some((old_pitch, start_step, duration)) = note_info {
+    if let Some(mut note) = note_info {
+        let (old_pitch, start_step) = (note.pitch, note.start_step);
         let new_pitch =
             (old_pitch as i32 + delta).clamp(PIANO_MIN_PITCH as i32, PIANO_MAX_PITCH as i32) as u8;
         if new_pitch != old_pitch {
@@ -381,8 +398,8 @@ fn transpose_note(app: &mut App, delta: i32) {
                 pitch: old_pitch,
                 start_step,
             });
-            // Add new note at transposed pitch
-            let note = crate::sequencer::Note::new(new_pitch, start_step, duration);
+            // Add it back at the transposed pitch, keeping velocity and expressions
+            note.pitch = new_pitch;
             app.dispatch(AppCommand::AddNote {
                 channel,
                 pattern,
@@ -392,6 +409,54 @@ fn transpose_note(app: &mut App, delta: i32) {
     }
 }
 
+/// Show the next expression lane: off, then each expression in turn
+fn cycle_expression_lane(app: &mut App) {
+    let lane = &mut app.ui.cursors.piano_roll.expression_lane;
+    *lane = match *lane {
+        None => Some(NoteExpression::ALL[0]),
+        Some(current) => NoteExpression::ALL
+            .iter()
+            .position(|&e| e == current)
+            .and_then(|i| NoteExpression::ALL.get(i + 1))
+            .copied(),
+    };
+}
+
+/// Move the lane's expression on the note at the cursor by `steps` increments
+fn adjust_note_expression(app: &mut App, steps: f32) {
+    let Some(expression) = app.ui.cursors.piano_roll.expression_lane else {
+        return;
+    };
+    let pitch = app.ui.cursors.piano_roll.pitch;
+    let step = app.ui.cursors.piano_roll.step;
+    let channel = app.ui.cursors.channel_rack.channel;
+    let pattern = app.current_pattern;
+
+    let Some((note_id, current)) = app
+        .channels
+        .get(channel)
+        .and_then(|c| c.get_pattern(pattern))
+        .and_then(|s| s.get_note_at(pitch, step))
+        .map(|n| (n.id.clone(), n.expressions.get(expression)))
+    else {
+        return;
+    };
+
+    // Snap to the lane's grid so repeated edits don't drift
+    let (min, max) = expression.range();
+    let increment = expression.step();
+    let value = (((current / increment).round() + steps) * increment).clamp(min, max);
+    if value != current {
+        app.dispatch(AppCommand::SetNoteExpression {
+            channel,
+            pattern,
+            note_id,
+            expression,
+            value,
+        });
+    }
+}
+
 /// Get notes in range as YankedNote data (relative offsets from anchor)
 fn get_piano_roll_data(app: &App, range: &vim::Range) -> Vec<crate::sequencer::YankedNote> {
     use crate::sequencer::YankedNote;
@@ -444,6 +509,7 @@ fn get_piano_roll_data(app: &App, range: &vim::Range) -> Vec<crate::sequencer::Y
                     pitch_offset: note.pitch as i32 - anchor_pitch as i32,
                     step_offset: note.start_step as i32 - start.col as i32,
                     duration: note.duration,
+                    expressions: note.expressions,
                 });
             }
         }
@@ -525,7 +591,8 @@ fn paste_piano_roll_data(app: &mut App) {
                     .clamp(0, (PIANO_NUM_STEPS - yanked.duration) as i32)
                     as usize;
 
-                let note = Note::new(new_pitch, new_step, yanked.duration);
+                let mut note = Note::new(new_pitch, new_step, yanked.duration);
+                note.expressions = yanked.expressions;
                 slice.add_note(note);
             }
         }
@@ -872,4 +939,62 @@ mod tests {
             "ESC should exit PianoRoll when not placing"
         );
     }
+    // ========================================================================
+    // Expression lane tests
+    // ========================================================================
+
+    #[test]
+    fn test_expression_lane_keys_edit_cursor_note() {
+        use crate::audio::AudioHandle;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let project_path = temp_dir.path().join("test-project");
+        std::fs::create_dir_all(&project_path).expect("Failed to create project dir");
+        let audio = AudioHandle::dummy();
+        let mut app = crate::app::App::new(project_path.to_str().unwrap(), audio);
+        app.set_view_mode(ViewMode::PianoRoll);
+        app.dispatch(AppCommand::AddNote {
+            channel: 0,
+            pattern: app.current_pattern,
+            note: Note::new(60, 0, 4),
+        });
+        app.ui.cursors.piano_roll.pitch = 60;
+        app.ui.cursors.piano_roll.step = 2;
+
+        let press = |app: &mut App, c: char| {
+            handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), app)
+        };
+        let pressure = |app: &App| {
+            app.channels[0]
+                .get_pattern(app.current_pattern)
+                .unwrap()
+                .notes[0]
+                .expressions
+                .pressure
+        };
+
+        // No lane shown: '+' does nothing
+        press(&mut app, '+');
+        assert_eq!(pressure(&app), 0.0);
+
+        press(&mut app, 'E');
+        assert_eq!(
+            app.ui.cursors.piano_roll.expression_lane,
+            Some(NoteExpression::PitchBend)
+        );
+        press(&mut app, 'E');
+        for _ in 0..3 {
+            press(&mut app, '+');
+        }
+        press(&mut app, '-');
+        assert!((pressure(&app) - 0.2).abs() < 1e-6);
+
+        // Cycling past the last lane hides it
+        for _ in 0..3 {
+            press(&mut app, 'E');
+        }
+        assert_eq!(app.ui.cursors.piano_roll.expression_lane, None);
+    }
 }
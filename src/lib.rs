@@ -17,12 +17,14 @@ pub mod coords;
 pub mod cursor;
 pub mod effects;
 pub mod event_log;
+pub mod export_dialog;
 pub mod history;
 pub mod input;
 pub mod mixer;
 pub mod mode;
 pub mod playback;
 pub mod plugin_host;
+pub mod preset_picker;
 pub mod project;
 pub mod projects_modal;
 pub mod sequencer;
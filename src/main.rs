@@ -5,12 +5,14 @@
 #![deny(warnings)]
 
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use std::fs;
 
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use crossterm::{
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyboardEnhancementFlags,
@@ -22,10 +24,13 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use termdaw::app::App;
+use termdaw::audio::export::{BitDepth, ExportFormat, ExportMode, ExportSettings};
+use termdaw::audio::export_job::{run_export, ExportOutcome, ExportRequest};
+use termdaw::audio::offline::{find_missing_samples, RenderControl};
 use termdaw::audio::{AudioEngine, ProjectSetup};
 use termdaw::input;
 use termdaw::mixer::Mixer;
-use termdaw::plugin_host::ClapPluginLoader;
+use termdaw::plugin_host::{sandbox, ClapPluginLoader, PluginLoader, SandboxPluginLoader};
 use termdaw::project;
 use termdaw::ui;
 
@@ -36,6 +41,10 @@ use termdaw::ui;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Run each plugin in its own process, so a crashing plugin is muted
+    /// instead of taking the session down
+    #[arg(long, global = true)]
+    sandbox_plugins: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -45,11 +54,56 @@ enum Commands {
         #[command(subcommand)]
         action: ProjectsAction,
     },
+    /// Render a project to an audio file without opening the UI
+    ///
+    /// Exits with 1 on general errors, 3 if samples are missing and 4 if
+    /// plugins are missing or fail to load.
+    Render {
+        /// Project name, or path to a project folder
+        project: String,
+        /// Output file (or folder with --stems)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format [default: from the output extension, else wav]
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        /// Bit depth
+        #[arg(long, value_enum, default_value = "16")]
+        bit_depth: BitDepthArg,
+        /// Sample rate in Hz
+        #[arg(long, default_value_t = 44100)]
+        sample_rate: u32,
+        /// Disable dither when reducing to 16/24-bit
+        #[arg(long)]
+        no_dither: bool,
+        /// Longest effect/plugin tail to render after the last bar, in seconds
+        #[arg(long, default_value_t = 10.0)]
+        tail: f32,
+        /// Write one file per mixer track into the output folder
+        #[arg(long)]
+        stems: bool,
+        /// Bars to render, as shown in the playlist: START-END, START- or -END
+        #[arg(long, value_parser = parse_bar_range)]
+        range: Option<BarRange>,
+    },
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
         shell: clap_complete::Shell,
     },
+    /// Host a single plugin for a sandboxed session (started by termdaw)
+    #[command(hide = true)]
+    PluginHost {
+        /// Plugin bundle to load
+        path: PathBuf,
+        #[arg(long)]
+        sample_rate: f64,
+        #[arg(long)]
+        buffer_size: u32,
+        /// Plugin to load from a multi-plugin bundle
+        #[arg(long)]
+        plugin_id: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -80,30 +134,122 @@ enum ProjectsAction {
     },
 }
 
+/// Output format for `render`
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    Wav,
+    Flac,
+}
+
+/// Bit depth for `render`
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BitDepthArg {
+    #[value(name = "16")]
+    Int16,
+    #[value(name = "24")]
+    Int24,
+    /// 32-bit float (WAV only)
+    #[value(name = "32")]
+    Float32,
+}
+
+/// Inclusive 1-based bar range for `render`
+#[derive(Clone, Copy, Debug)]
+struct BarRange {
+    start: usize,
+    end: Option<usize>,
+}
+
+/// Exit code when the project references samples that can't be loaded
+const EXIT_MISSING_SAMPLES: i32 = 3;
+/// Exit code when the project references plugins that can't be loaded
+const EXIT_MISSING_PLUGINS: i32 = 4;
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let plugin_loader = plugin_loader(cli.sandbox_plugins)?;
 
     // Handle subcommands that don't launch the DAW
     match &cli.command {
         Some(Commands::Projects { action }) => match action {
             ProjectsAction::List => list_projects(),
-            ProjectsAction::Open { name } => run_daw(name.clone()),
+            ProjectsAction::Open { name } => run_daw(name.clone(), plugin_loader),
             ProjectsAction::New { name } => new_project(name.as_deref()),
             ProjectsAction::Rename { old, new } => rename_project(old, new),
             ProjectsAction::Delete { name } => delete_project(name),
         },
+        Some(Commands::Render {
+            project,
+            output,
+            format,
+            bit_depth,
+            sample_rate,
+            no_dither,
+            tail,
+            stems,
+            range,
+        }) => {
+            let format = match format {
+                Some(FormatArg::Wav) => ExportFormat::Wav,
+                Some(FormatArg::Flac) => ExportFormat::Flac,
+                None => match output.extension().and_then(|e| e.to_str()) {
+                    Some(ext) if ext.eq_ignore_ascii_case("flac") => ExportFormat::Flac,
+                    _ => ExportFormat::Wav,
+                },
+            };
+            let bit_depth = match bit_depth {
+                BitDepthArg::Int16 => BitDepth::Int16,
+                BitDepthArg::Int24 => BitDepth::Int24,
+                BitDepthArg::Float32 => BitDepth::Float32,
+            };
+            let settings = ExportSettings {
+                format,
+                bit_depth,
+                sample_rate: *sample_rate,
+                dither: !no_dither,
+                max_tail_secs: *tail,
+                ..ExportSettings::default()
+            };
+            let mode = if *stems {
+                ExportMode::Stems
+            } else {
+                ExportMode::Mix
+            };
+            render_project(project, output, settings, mode, *range, plugin_loader)
+        }
         Some(Commands::Completions { shell }) => {
             print_completions(*shell);
             Ok(())
         }
+        Some(Commands::PluginHost {
+            path,
+            sample_rate,
+            buffer_size,
+            plugin_id,
+        }) => Ok(sandbox::serve(
+            path,
+            plugin_id.as_deref(),
+            *sample_rate,
+            *buffer_size,
+        )?),
         None => {
             // Default: open/create untitled project
             let project_name = termdaw::project::generate_project_name();
-            run_daw(project_name)
+            run_daw(project_name, plugin_loader)
         }
     }
 }
 
+/// Loader for every plugin in the session: in-process, or one child
+/// process per plugin with `--sandbox-plugins`
+fn plugin_loader(sandboxed: bool) -> Result<Arc<dyn PluginLoader>> {
+    if sandboxed {
+        Ok(Arc::new(SandboxPluginLoader::new()?))
+    } else {
+        Ok(Arc::new(ClapPluginLoader))
+    }
+}
+
 /// List available projects
 fn list_projects() -> Result<()> {
     let dir = termdaw::templates::projects_dir();
@@ -214,6 +360,150 @@ fn delete_project(name: &str) -> Result<()> {
     }
 }
 
+/// Parse a `--range` value: `START-END`, `START-` or `-END` (1-based, inclusive)
+fn parse_bar_range(value: &str) -> Result<BarRange, String> {
+    let (start, end) = value
+        .split_once('-')
+        .ok_or_else(|| "expected START-END, START- or -END".to_string())?;
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&bar| bar >= 1)
+            .ok_or_else(|| format!("invalid bar number '{}'", s))
+    };
+
+    let start = if start.trim().is_empty() {
+        1
+    } else {
+        parse(start)?
+    };
+    let end = if end.trim().is_empty() {
+        None
+    } else {
+        Some(parse(end)?)
+    };
+    if end.is_some_and(|end| end < start) {
+        return Err("range ends before it starts".to_string());
+    }
+    Ok(BarRange { start, end })
+}
+
+/// Render a project to disk without touching the terminal or audio device
+fn render_project(
+    project: &str,
+    output: &Path,
+    settings: ExportSettings,
+    mode: ExportMode,
+    range: Option<BarRange>,
+    plugin_loader: Arc<dyn PluginLoader>,
+) -> Result<()> {
+    let project_path = if project::is_valid_project(Path::new(project)) {
+        PathBuf::from(project)
+    } else {
+        termdaw::templates::projects_dir().join(project)
+    };
+    if !project::is_valid_project(&project_path) {
+        eprintln!("Error: Project '{}' not found", project);
+        std::process::exit(1);
+    }
+    if let Err(e) = settings.validate() {
+        eprintln!("Error: {}, use --bit-depth 16 or 24", e);
+        std::process::exit(1);
+    }
+
+    let proj = match project::load_project(&project_path) {
+        Ok(proj) => proj,
+        Err(e) => {
+            eprintln!("Error loading project: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let samples_path = project_path.join("samples");
+    let plugins_path = project_path.join("plugins");
+
+    let mut missing = find_missing_samples(&proj.channels, &samples_path);
+    if let Some(mixer) = &proj.mixer {
+        // load_project already tried every impulse response
+        for slot in mixer.tracks.iter().flat_map(|t| t.effects.iter().flatten()) {
+            if let (Some(path), None) = (&slot.ir_path, &slot.impulse_response) {
+                if !missing.contains(path) {
+                    missing.push(path.clone());
+                }
+            }
+        }
+    }
+    for channel in &proj.channels {
+        let names = channel.legacy_plugin_params();
+        if !names.is_empty() {
+            eprintln!(
+                "Warning: Channel '{}' has unrecognized parameters: {}",
+                channel.name,
+                names.join(", ")
+            );
+        }
+    }
+    for sample in &missing {
+        eprintln!("Error: Missing sample '{}'", sample);
+    }
+    if !missing.is_empty() {
+        std::process::exit(EXIT_MISSING_SAMPLES);
+    }
+
+    let arrangement = match range {
+        Some(range) => proj
+            .arrangement
+            .crop_bars(range.start - 1, range.end.unwrap_or(usize::MAX)),
+        None => proj.arrangement,
+    };
+
+    let request = ExportRequest {
+        channels: proj.channels,
+        patterns: proj.patterns,
+        arrangement,
+        mixer: proj.mixer.unwrap_or_else(Mixer::new),
+        samples_path,
+        plugins_path,
+        plugin_loader,
+        bpm: proj.bpm,
+        settings,
+        mode,
+        pre_master_fx: false,
+        require_plugins: true,
+        output: output.to_path_buf(),
+    };
+
+    let started = Instant::now();
+    match run_export(&request, &RenderControl::new()) {
+        ExportOutcome::Written(paths) => {
+            for path in &paths {
+                println!("Wrote {}", path.display());
+            }
+            println!("Rendered in {:.1}s", started.elapsed().as_secs_f32());
+            Ok(())
+        }
+        ExportOutcome::Empty => {
+            eprintln!("Error: Nothing to render (arrangement is empty)");
+            std::process::exit(1);
+        }
+        ExportOutcome::Cancelled => {
+            eprintln!("Error: Render cancelled");
+            std::process::exit(1);
+        }
+        ExportOutcome::MissingPlugins(plugins) => {
+            for (plugin, error) in &plugins {
+                eprintln!("Error: Plugin '{}' failed to load: {}", plugin, error);
+            }
+            std::process::exit(EXIT_MISSING_PLUGINS);
+        }
+        ExportOutcome::Failed(e) => {
+            eprintln!("Error writing output: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Print shell completions
 fn print_completions(shell: clap_complete::Shell) {
     clap_complete::generate(
@@ -225,7 +515,7 @@ fn print_completions(shell: clap_complete::Shell) {
 }
 
 /// Run the DAW with a specific project
-fn run_daw(project_name: String) -> Result<()> {
+fn run_daw(project_name: String, plugin_loader: Arc<dyn PluginLoader>) -> Result<()> {
     // Ensure templates are downloaded (first run setup)
     if let Err(e) = termdaw::templates::ensure_templates() {
         eprintln!("Warning: Could not download templates: {}", e);
@@ -257,8 +547,6 @@ fn run_daw(project_name: String) -> Result<()> {
         (vec![], Mixer::new(), 140.0)
     };
 
-    // Create plugin loader for audio engine setup
-    let plugin_loader = ClapPluginLoader;
     let plugins_path = project_path.join("plugins");
 
     // Initialize audio engine with project setup
@@ -266,7 +554,7 @@ fn run_daw(project_name: String) -> Result<()> {
         channels: &channels,
         mixer: &mixer,
         plugins_path: &plugins_path,
-        plugin_loader: &plugin_loader,
+        plugin_loader: &*plugin_loader,
         bpm,
     };
     let (mut audio_engine, audio_handle) = AudioEngine::new(Some(project_setup))
@@ -284,11 +572,27 @@ fn run_daw(project_name: String) -> Result<()> {
     )
     .is_ok();
 
+    // Put the terminal back before a panic is reported, or the shell is left
+    // in raw mode
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            PopKeyboardEnhancementFlags,
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        );
+        default_hook(info);
+    }));
+
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state with audio handle
     let mut app = App::new(&project_name, audio_handle);
+    app.set_plugin_loader(plugin_loader);
+    app.adopt_plugin_instances(audio_engine.take_plugin_instances());
 
     // Run the main loop
     let result = run_app(&mut terminal, &mut app, &mut audio_engine);
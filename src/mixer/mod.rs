@@ -116,6 +116,9 @@ pub struct Mixer {
     /// Peak levels per track (updated from audio thread)
     #[serde(skip)]
     pub peak_levels: [StereoLevels; NUM_TRACKS],
+    /// Latency per track in frames (updated from audio thread)
+    #[serde(skip)]
+    pub track_latency: [usize; NUM_TRACKS],
 }
 
 impl Default for Mixer {
@@ -148,6 +151,7 @@ impl Mixer {
             effects_focused: false,
             viewport_offset: 0,
             peak_levels: [StereoLevels::default(); NUM_TRACKS],
+            track_latency: [0; NUM_TRACKS],
         }
     }
 
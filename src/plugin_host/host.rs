@@ -3,25 +3,52 @@
 //! This module provides a CLAP plugin host that can load and process audio
 //! through CLAP plugins. It wraps clack-host to provide a simpler API.
 
-use std::path::Path;
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use clack_host::events::event_types::{NoteOffEvent, NoteOnEvent, ParamValueEvent};
+use clack_extensions::latency::PluginLatency;
+use clack_extensions::note_ports::{
+    NoteDialect, NoteDialects, NotePortInfoBuffer, PluginNotePorts,
+};
+use clack_extensions::params::{ParamInfoBuffer, ParamInfoFlags, PluginParams};
+use clack_extensions::preset_discovery::{
+    FileType, Flags, IndexerImplementation, Location, LocationInfo, MetadataReceiverImplementation,
+    PresetDiscoveryFactory, Provider, Soundpack, Timestamp, UniversalPluginId,
+};
+use clack_extensions::preset_load::PluginPresetLoad;
+use clack_extensions::state::PluginState;
+use clack_extensions::tail::{PluginTail, TailLength};
+use clack_host::events::event_types::{
+    MidiEvent, NoteExpressionEvent, NoteExpressionType, NoteOffEvent, NoteOnEvent, ParamValueEvent,
+    TransportEvent, TransportFlags,
+};
+use clack_host::events::spaces::CoreEventSpace;
+use clack_host::events::{EventFlags, EventHeader, Match};
 use clack_host::factory::plugin::PluginFactory;
 use clack_host::prelude::*;
 use clack_host::process::{StartedPluginAudioProcessor, StoppedPluginAudioProcessor};
-use clack_host::utils::Cookie;
+use clack_host::utils::{BeatTime, Cookie, SecondsTime};
 
-use super::PluginInfo;
+use super::mpe::{self, MpeAllocator};
+use super::presets::{PluginPreset, PresetLocation};
+use super::scanner::PluginDescription;
+use super::transport::{PluginTransport, BEATS_PER_BAR, BEAT_UNIT};
+use super::{ParamFormatter, PluginInfo, PluginParam, StateSaver};
+use crate::sequencer::{NoteExpression, NoteExpressions};
 
 /// Shared host state (thread-safe)
+///
+/// Plugins raise these flags from any thread; the main thread services them
+/// in [`PluginHost::service_main_thread`].
 #[derive(Default)]
 struct DawHostShared {
-    #[allow(dead_code)]
     restart_requested: AtomicBool,
+    /// Only meaningful to hosts that stop processing idle plugins; the
+    /// engine processes every plugin each block
     #[allow(dead_code)]
     process_requested: AtomicBool,
-    #[allow(dead_code)]
     callback_requested: AtomicBool,
 }
 
@@ -47,16 +74,36 @@ impl<'a> SharedHandler<'a> for DawHostShared {
 struct DawHostMainThread<'a> {
     #[allow(dead_code)]
     shared: &'a DawHostShared,
+    /// Tail extension, if the plugin implements it
+    tail: Option<PluginTail>,
+    /// Latency extension, if the plugin implements it
+    latency: Option<PluginLatency>,
+    /// Params extension, if the plugin implements it
+    params: Option<PluginParams>,
+    /// State extension, if the plugin implements it
+    state: Option<PluginState>,
+    /// Preset-load extension, if the plugin implements it
+    preset_load: Option<PluginPresetLoad>,
+    /// Note-ports extension, if the plugin implements it
+    note_ports: Option<PluginNotePorts>,
 }
 
 impl<'a> MainThreadHandler<'a> for DawHostMainThread<'a> {
-    fn initialized(&mut self, _instance: InitializedPluginHandle<'a>) {
-        // Plugin is initialized
+    fn initialized(&mut self, instance: InitializedPluginHandle<'a>) {
+        self.tail = instance.get_extension();
+        self.latency = instance.get_extension();
+        self.params = instance.get_extension();
+        self.state = instance.get_extension();
+        self.preset_load = instance.get_extension();
+        self.note_ports = instance.get_extension();
     }
 }
 
 /// Audio processor handler
-struct DawAudioProcessor;
+struct DawAudioProcessor {
+    /// Tail extension (queried on the audio thread)
+    tail: Option<PluginTail>,
+}
 
 impl<'a> AudioProcessorHandler<'a> for DawAudioProcessor {}
 
@@ -70,20 +117,81 @@ impl HostHandlers for DawHost {
 }
 
 /// MIDI note event to send to a plugin
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct MidiNote {
+    /// Frame within the block the note starts or ends at
+    pub time: u32,
     pub note: u8,
     pub velocity: f32,
     pub is_note_on: bool,
+    /// Per-note expressions, applied from the note-on
+    pub expressions: NoteExpressions,
 }
 
 /// Parameter change event to send to a plugin
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ParamChange {
+    /// Frame within the block the value changes at
+    pub time: u32,
     pub param_id: u32,
     pub value: f64,
 }
 
+/// An input event built for the current block, by kind and buffer index.
+/// MIDI events for MPE plugins are the zone setup, note-offs, and note-ons
+/// with the messages leading them (a stolen channel's note-off, then the
+/// note's expressions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventRef {
+    Param { time: u32, index: usize },
+    NoteOff { time: u32, index: usize },
+    NoteOn { time: u32, index: usize },
+    Expression { time: u32, index: usize },
+    MidiSetup { index: usize },
+    MidiNoteOff { time: u32, index: usize },
+    MidiNoteOn { time: u32, index: usize },
+}
+
+impl EventRef {
+    /// Order by time; at the same frame, parameter changes land before the
+    /// notes they should affect and note-offs before retriggering note-ons.
+    /// Expressions follow their note-on, as CLAP only applies them to
+    /// notes that are already playing. Events of one kind keep the order
+    /// they were queued in, and no two events share a key, so an unstable
+    /// sort gives the same order every time.
+    fn sort_key(&self) -> (u32, u8, usize) {
+        match *self {
+            EventRef::Param { time, index } => (time, 0, index),
+            EventRef::MidiSetup { index } => (0, 1, index),
+            EventRef::NoteOff { time, index } => (time, 2, index),
+            EventRef::MidiNoteOff { time, index } => (time, 3, index),
+            EventRef::NoteOn { time, index } => (time, 4, index),
+            EventRef::Expression { time, index } => (time, 5, index),
+            EventRef::MidiNoteOn { time, index } => (time, 6, index),
+        }
+    }
+}
+
+/// How a plugin takes its notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoteInput {
+    /// CLAP note events, with per-note expressions
+    Clap,
+    /// MIDI with a channel per note (MPE)
+    Mpe,
+}
+
+/// CLAP note expression carrying a per-note expression, with the value
+/// scaled to CLAP's range
+fn clap_expression(expression: NoteExpression, value: f32) -> (NoteExpressionType, f64) {
+    match expression {
+        NoteExpression::PitchBend => (NoteExpressionType::Tuning, value as f64),
+        NoteExpression::Pressure => (NoteExpressionType::Pressure, value as f64),
+        NoteExpression::Timbre => (NoteExpressionType::Brightness, value as f64),
+        NoteExpression::Pan => (NoteExpressionType::Pan, (value as f64 + 1.0) / 2.0),
+    }
+}
+
 /// A loaded and activated plugin that can process audio.
 /// This struct owns everything needed to process audio through a CLAP plugin.
 pub struct PluginHost {
@@ -101,9 +209,60 @@ pub struct PluginHost {
     activated: bool,
 }
 
+/// How the host introduces itself to plugins
+fn host_info() -> Result<HostInfo, String> {
+    HostInfo::new(
+        "TermDAW",
+        "TermDAW Project",
+        "https://github.com/termdaw",
+        "0.1.0",
+    )
+    .map_err(|e| format!("Failed to create host info: {:?}", e))
+}
+
+/// Read every plugin a bundle's factory offers, without instantiating any
+pub fn describe_bundle(path: &Path) -> Result<Vec<PluginDescription>, String> {
+    let bundle = unsafe { PluginBundle::load(path) }
+        .map_err(|e| format!("Failed to load plugin bundle: {:?}", e))?;
+    let factory: PluginFactory = bundle
+        .get_factory()
+        .ok_or_else(|| "No plugin factory found".to_string())?;
+
+    let text = |s: Option<&std::ffi::CStr>| {
+        s.map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let plugins: Vec<_> = factory
+        .plugin_descriptors()
+        .filter(|d| d.id().is_some())
+        .map(|d| PluginDescription {
+            id: text(d.id()),
+            name: text(d.name()),
+            vendor: text(d.vendor()),
+            version: text(d.version()),
+            features: d
+                .features()
+                .map(|f| f.to_string_lossy().to_string())
+                .collect(),
+        })
+        .collect();
+    if plugins.is_empty() {
+        return Err("No plugins in bundle".to_string());
+    }
+    Ok(plugins)
+}
+
 impl PluginHost {
     /// Load and create a plugin from a path
-    pub fn load(path: &Path, sample_rate: f64, buffer_size: u32) -> Result<Self, String> {
+    ///
+    /// `plugin_id` picks one plugin from a multi-plugin bundle; without it
+    /// the bundle's first plugin is loaded.
+    pub fn load(
+        path: &Path,
+        plugin_id: Option<&str>,
+        sample_rate: f64,
+        buffer_size: u32,
+    ) -> Result<Self, String> {
         // Load the plugin bundle
         let bundle = unsafe { PluginBundle::load(path) }
             .map_err(|e| format!("Failed to load plugin bundle: {:?}", e))?;
@@ -113,11 +272,17 @@ impl PluginHost {
             .get_factory()
             .ok_or_else(|| "No plugin factory found".to_string())?;
 
-        // Get the first plugin descriptor
-        let descriptor = factory
-            .plugin_descriptors()
-            .next()
-            .ok_or_else(|| "No plugins in bundle".to_string())?;
+        // Get the requested plugin descriptor, or the first one
+        let descriptor = match plugin_id {
+            Some(wanted) => factory
+                .plugin_descriptors()
+                .find(|d| d.id().is_some_and(|id| id.to_bytes() == wanted.as_bytes()))
+                .ok_or_else(|| format!("No plugin {} in bundle", wanted))?,
+            None => factory
+                .plugin_descriptors()
+                .next()
+                .ok_or_else(|| "No plugins in bundle".to_string())?,
+        };
 
         let plugin_id = descriptor
             .id()
@@ -132,19 +297,19 @@ impl PluginHost {
             .map(|s: &std::ffi::CStr| s.to_string_lossy().to_string())
             .unwrap_or_default();
 
-        // Create host info
-        let host_info = HostInfo::new(
-            "TermDAW",
-            "TermDAW Project",
-            "https://github.com/termdaw",
-            "0.1.0",
-        )
-        .map_err(|e| format!("Failed to create host info: {:?}", e))?;
-
         // Create the plugin instance
+        let host_info = host_info()?;
         let instance = PluginInstance::<DawHost>::new(
             |_| DawHostShared::default(),
-            |shared| DawHostMainThread { shared },
+            |shared| DawHostMainThread {
+                shared,
+                tail: None,
+                latency: None,
+                params: None,
+                state: None,
+                preset_load: None,
+                note_ports: None,
+            },
             &bundle,
             descriptor.id().ok_or("No plugin ID")?,
             &host_info,
@@ -158,14 +323,226 @@ impl PluginHost {
             params: Vec::new(),
         };
 
-        Ok(Self {
+        let mut host = Self {
             bundle,
             instance,
             info,
             sample_rate,
             buffer_size,
             activated: false,
-        })
+        };
+        host.info.params = host.discover_params();
+        Ok(host)
+    }
+
+    /// Processing latency reported through the latency extension, in frames.
+    /// Returns 0 if the plugin doesn't implement it.
+    fn latency_frames(&mut self) -> u32 {
+        let Some(ext) = self.instance.access_handler(|h| h.latency) else {
+            return 0;
+        };
+        ext.get(&mut self.instance.plugin_handle())
+    }
+
+    /// How the plugin wants notes on its first note port: MPE if it prefers
+    /// it or only speaks MIDI, CLAP note events otherwise
+    fn note_input(&mut self) -> NoteInput {
+        let Some(ext) = self.instance.access_handler(|h| h.note_ports) else {
+            return NoteInput::Clap;
+        };
+        let mut handle = self.instance.plugin_handle();
+        if ext.count(&mut handle, true) == 0 {
+            return NoteInput::Clap;
+        }
+        let mut buffer = NotePortInfoBuffer::new();
+        let Some(port) = ext.get(&mut handle, 0, true, &mut buffer) else {
+            return NoteInput::Clap;
+        };
+
+        let dialects = port.supported_dialects;
+        let midi_only = !dialects.contains(NoteDialects::CLAP)
+            && (dialects.intersects(NoteDialects::MIDI)
+                || dialects.intersects(NoteDialects::MIDI_MPE));
+        if port.preferred_dialect == Some(NoteDialect::MidiMpe) || midi_only {
+            NoteInput::Mpe
+        } else {
+            NoteInput::Clap
+        }
+    }
+
+    /// Read the plugin's parameter list through the params extension.
+    /// Hidden and read-only (meter) parameters are left out.
+    fn discover_params(&mut self) -> Vec<PluginParam> {
+        let Some(ext) = self.instance.access_handler(|h| h.params) else {
+            return Vec::new();
+        };
+        let mut handle = self.instance.plugin_handle();
+        let mut buffer = ParamInfoBuffer::new();
+        let mut params = Vec::new();
+
+        for index in 0..ext.count(&mut handle) {
+            let Some(info) = ext.get_info(&mut handle, index, &mut buffer) else {
+                continue;
+            };
+            if info.flags.contains(ParamInfoFlags::IS_HIDDEN)
+                || info.flags.contains(ParamInfoFlags::IS_READONLY)
+            {
+                continue;
+            }
+            let id = info.id;
+            let mut param = PluginParam {
+                id: id.get(),
+                name: String::from_utf8_lossy(info.name).into_owned(),
+                module: String::from_utf8_lossy(info.module).into_owned(),
+                value: info.default_value,
+                min: info.min_value,
+                max: info.max_value,
+                default: info.default_value,
+                stepped: info.flags.contains(ParamInfoFlags::IS_STEPPED),
+            };
+            if let Some(value) = ext.get_value(&mut handle, id) {
+                param.value = value;
+            }
+            params.push(param);
+        }
+        params
+    }
+
+    /// Format a parameter value with the plugin's own text (e.g. "120 ms")
+    pub fn value_to_text(&mut self, param_id: u32, value: f64) -> Option<String> {
+        let ext = self.instance.access_handler(|h| h.params)?;
+        let mut buffer = [MaybeUninit::<u8>::uninit(); 128];
+        let text = ext
+            .value_to_text(
+                &mut self.instance.plugin_handle(),
+                ClapId::new(param_id),
+                value,
+                &mut buffer,
+            )
+            .ok()?;
+        Some(String::from_utf8_lossy(text).into_owned())
+    }
+
+    /// Save the plugin's opaque state through the state extension.
+    /// Returns None if the plugin doesn't implement it.
+    pub fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let Some(ext) = self.instance.access_handler(|h| h.state) else {
+            return Ok(None);
+        };
+        let mut data = Vec::new();
+        ext.save(&mut self.instance.plugin_handle(), &mut data)
+            .map_err(|e| format!("Failed to save plugin state: {}", e))?;
+        Ok(Some(data))
+    }
+
+    /// Run the callback the plugin asked for with `request_callback`, if any
+    ///
+    /// Returns true if the plugin asked to be restarted (deactivated and
+    /// activated again, e.g. after its latency or ports changed). The
+    /// processor lives on the audio thread, so the caller rebuilds the
+    /// plugin from its saved state instead.
+    pub fn service_main_thread(&mut self) -> bool {
+        let (callback, restart) = self.instance.access_shared_handler(|shared| {
+            (
+                shared.callback_requested.swap(false, Ordering::SeqCst),
+                shared.restart_requested.swap(false, Ordering::SeqCst),
+            )
+        });
+        if callback {
+            self.instance.call_on_main_thread_callback();
+        }
+        restart
+    }
+
+    /// Restore state previously returned by `save_state`
+    ///
+    /// Plugins without the state extension ignore it.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        let Some(ext) = self.instance.access_handler(|h| h.state) else {
+            return Ok(());
+        };
+        ext.load(&mut self.instance.plugin_handle(), &mut &data[..])
+            .map_err(|e| format!("Failed to load plugin state: {}", e))
+    }
+
+    /// List the presets the plugin provides through CLAP preset discovery
+    ///
+    /// Every provider in the bundle is asked for the presets at the
+    /// locations it declares; only presets for this plugin (or for no
+    /// particular plugin) are kept. Plugins without the preset-discovery
+    /// factory have none.
+    pub fn factory_presets(&self) -> Vec<PluginPreset> {
+        let Some(factory) = self.bundle.get_factory::<PresetDiscoveryFactory>() else {
+            return Vec::new();
+        };
+        let Ok(host_info) = host_info() else {
+            return Vec::new();
+        };
+
+        let mut presets = Vec::new();
+        for index in 0..factory.provider_count() {
+            let Some(provider_id) = factory.provider_descriptor(index).and_then(|d| d.id()) else {
+                continue;
+            };
+            let Ok(mut provider) = Provider::instantiate(
+                PresetIndexer::default(),
+                &self.bundle,
+                provider_id,
+                &host_info,
+            ) else {
+                continue;
+            };
+            let indexer = std::mem::take(provider.indexer_mut());
+
+            for location in &indexer.locations {
+                let mut receiver = PresetReceiver::new(&self.info.id);
+                match location {
+                    None => {
+                        provider.get_metadata(Location::Plugin, &mut receiver);
+                    }
+                    Some(root) => {
+                        for file in preset_files(root, &indexer.extensions) {
+                            let Ok(path) = CString::new(file.to_string_lossy().as_bytes()) else {
+                                continue;
+                            };
+                            receiver.file = Some(file);
+                            provider.get_metadata(Location::File { path: &path }, &mut receiver);
+                        }
+                    }
+                }
+                presets.extend(receiver.finish());
+            }
+        }
+        presets.sort_by_key(|p| p.name.to_lowercase());
+        presets
+    }
+
+    /// Load one of the plugin's own presets through the preset-load extension
+    pub fn load_preset_from(&mut self, location: &PresetLocation) -> Result<(), String> {
+        let ext = self
+            .instance
+            .access_handler(|h| h.preset_load)
+            .ok_or_else(|| "Plugin can't load presets".to_string())?;
+        let c_string =
+            |s: &str| CString::new(s).map_err(|_| format!("Invalid preset location: {}", s));
+
+        let (path, load_key) = match location {
+            PresetLocation::File { path, load_key } => {
+                (Some(c_string(&path.to_string_lossy())?), load_key)
+            }
+            PresetLocation::Plugin { load_key } => (None, load_key),
+        };
+        let load_key = load_key.as_deref().map(c_string).transpose()?;
+        let location = match &path {
+            Some(path) => Location::File { path },
+            None => Location::Plugin,
+        };
+        ext.from_location(
+            &mut self.instance.plugin_handle(),
+            location,
+            load_key.as_deref(),
+        )
+        .map_err(|e| format!("Failed to load preset: {:?}", e))
     }
 
     /// Get the plugin info
@@ -175,7 +552,7 @@ impl PluginHost {
 
     /// Activate the plugin and return a processor for the audio thread.
     /// The processor must be used on the audio thread and returned via deactivate().
-    pub fn activate(&mut self) -> Result<ActivePluginProcessor, String> {
+    pub fn activate(&mut self) -> Result<ClapProcessor, String> {
         if self.activated {
             return Err("Plugin already activated".to_string());
         }
@@ -188,7 +565,12 @@ impl PluginHost {
 
         let activated = self
             .instance
-            .activate(|_, _| DawAudioProcessor, audio_config)
+            .activate(
+                |_, main_thread| DawAudioProcessor {
+                    tail: main_thread.tail,
+                },
+                audio_config,
+            )
             .map_err(|e| format!("Failed to activate plugin: {:?}", e))?;
 
         let started = activated
@@ -197,14 +579,20 @@ impl PluginHost {
 
         self.activated = true;
 
-        Ok(ActivePluginProcessor::new(
+        // CLAP only guarantees the latency is valid once the plugin is active
+        let latency = self.latency_frames();
+        let note_input = self.note_input();
+
+        Ok(ClapProcessor::new(
             started,
             self.buffer_size as usize,
+            latency,
+            note_input,
         ))
     }
 
     /// Deactivate the plugin after audio processing is done.
-    pub fn deactivate(&mut self, processor: ActivePluginProcessor) {
+    pub fn deactivate(&mut self, processor: ClapProcessor) {
         if self.activated {
             let stopped = processor.stop();
             self.instance.deactivate(stopped);
@@ -213,9 +601,223 @@ impl PluginHost {
     }
 }
 
-/// An active plugin processor that can process audio.
+impl ParamFormatter for PluginHost {
+    fn format(&mut self, param_id: u32, value: f64) -> Option<String> {
+        self.value_to_text(param_id, value)
+    }
+
+    fn load_preset(&mut self, preset: &PluginPreset) -> Result<PluginPreset, String> {
+        let location = preset
+            .location
+            .as_ref()
+            .ok_or_else(|| format!("{} isn't one of the plugin's presets", preset.name))?;
+        self.load_preset_from(location)?;
+
+        self.info.params = self.discover_params();
+        let params = self.info.params.iter().map(|p| (p.id, p.value)).collect();
+        let state = PluginHost::save_state(self)?;
+        Ok(PluginPreset::snapshot(
+            &preset.name,
+            &self.info.id,
+            params,
+            state,
+        ))
+    }
+}
+
+impl StateSaver for PluginHost {
+    fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+        PluginHost::save_state(self)
+    }
+
+    fn service_main_thread(&mut self) -> bool {
+        PluginHost::service_main_thread(self)
+    }
+}
+
+/// Collects the locations and file types a preset provider declares
+#[derive(Default)]
+struct PresetIndexer {
+    /// Declared locations: a file or directory, or None for the plugin itself
+    locations: Vec<Option<PathBuf>>,
+    /// Extensions of the provider's preset files, without the dot
+    extensions: Vec<String>,
+}
+
+impl IndexerImplementation for PresetIndexer {
+    fn declare_filetype(&mut self, file_type: FileType) -> Result<(), HostError> {
+        if let Some(extension) = file_type.file_extension {
+            let extension = extension.to_string_lossy();
+            self.extensions
+                .push(extension.trim_start_matches('.').to_lowercase());
+        }
+        Ok(())
+    }
+
+    fn declare_location(&mut self, location: LocationInfo) -> Result<(), HostError> {
+        self.locations.push(match location.location {
+            Location::File { path } => Some(PathBuf::from(&*path.to_string_lossy())),
+            Location::Plugin => None,
+        });
+        Ok(())
+    }
+
+    fn declare_soundpack(&mut self, _soundpack: Soundpack) -> Result<(), HostError> {
+        Ok(())
+    }
+}
+
+/// Collects the presets a provider reports for one location
+struct PresetReceiver<'a> {
+    /// Id of the plugin presets are wanted for
+    plugin_id: &'a str,
+    /// Preset file being read, or None for presets inside the plugin
+    file: Option<PathBuf>,
+    /// Presets so far, each with whether it names a plugin and whether
+    /// that's ours
+    presets: Vec<(PluginPreset, bool, bool)>,
+}
+
+impl<'a> PresetReceiver<'a> {
+    fn new(plugin_id: &'a str) -> Self {
+        Self {
+            plugin_id,
+            file: None,
+            presets: Vec::new(),
+        }
+    }
+
+    /// Presets meant for our plugin, or for no plugin in particular
+    fn finish(self) -> impl Iterator<Item = PluginPreset> {
+        self.presets
+            .into_iter()
+            .filter(|(_, names_plugin, ours)| !names_plugin || *ours)
+            .map(|(preset, _, _)| preset)
+    }
+}
+
+impl MetadataReceiverImplementation for PresetReceiver<'_> {
+    fn on_error(&mut self, _error_code: i32, _error_message: Option<&CStr>) {}
+
+    fn begin_preset(
+        &mut self,
+        name: Option<&CStr>,
+        load_key: Option<&CStr>,
+    ) -> Result<(), HostError> {
+        let load_key = load_key.map(|k| k.to_string_lossy().into_owned());
+        let file_name = self
+            .file
+            .as_deref()
+            .and_then(Path::file_stem)
+            .map(|s| s.to_string_lossy().into_owned());
+        let name = name
+            .map(|n| n.to_string_lossy().into_owned())
+            .or(file_name)
+            .unwrap_or_else(|| "Untitled".to_string());
+        let location = match &self.file {
+            Some(path) => PresetLocation::File {
+                path: path.clone(),
+                load_key,
+            },
+            None => PresetLocation::Plugin { load_key },
+        };
+        self.presets.push((
+            PluginPreset::factory(&name, self.plugin_id, location),
+            false,
+            false,
+        ));
+        Ok(())
+    }
+
+    fn add_plugin_id(&mut self, plugin_id: UniversalPluginId) {
+        if let Some((_, names_plugin, ours)) = self.presets.last_mut() {
+            *names_plugin = true;
+            *ours |= plugin_id.abi.to_bytes() == b"clap"
+                && plugin_id.id.to_bytes() == self.plugin_id.as_bytes();
+        }
+    }
+
+    fn set_soundpack_id(&mut self, _soundpack_id: &CStr) {}
+
+    fn set_flags(&mut self, _flags: Flags) {}
+
+    fn add_creator(&mut self, _creator: &CStr) {}
+
+    fn set_description(&mut self, _description: &CStr) {}
+
+    fn set_timestamps(
+        &mut self,
+        _creation_time: Option<Timestamp>,
+        _modification_time: Option<Timestamp>,
+    ) {
+    }
+
+    fn add_feature(&mut self, _feature: &CStr) {}
+
+    fn add_extra_info(&mut self, _key: &CStr, _value: &CStr) {}
+}
+
+/// Preset files under a declared location: the file itself, or every file
+/// in the directory with one of the provider's extensions (any file if it
+/// declared none)
+fn preset_files(root: &Path, extensions: &[String]) -> Vec<PathBuf> {
+    if root.is_file() {
+        return vec![root.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|path| {
+            extensions.is_empty()
+                || path.extension().is_some_and(|ext| {
+                    let ext = ext.to_string_lossy().to_lowercase();
+                    extensions.contains(&ext)
+                })
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Build the CLAP transport event for a block starting at `transport`'s position
+fn transport_event(transport: &PluginTransport) -> TransportEvent {
+    let mut flags = TransportFlags::HAS_TEMPO
+        | TransportFlags::HAS_BEATS_TIMELINE
+        | TransportFlags::HAS_SECONDS_TIMELINE
+        | TransportFlags::HAS_TIME_SIGNATURE;
+    flags.set(TransportFlags::IS_PLAYING, transport.playing);
+    flags.set(
+        TransportFlags::IS_LOOP_ACTIVE,
+        transport.loop_beats.is_some(),
+    );
+    let (loop_start, loop_end) = transport.loop_beats.unwrap_or_default();
+
+    TransportEvent {
+        header: EventHeader::new_core(0, EventFlags::empty()),
+        flags,
+        song_pos_beats: BeatTime::from_float(transport.position_beats),
+        song_pos_seconds: SecondsTime::from_float(
+            transport.beats_to_seconds(transport.position_beats),
+        ),
+        tempo: transport.tempo,
+        tempo_inc: 0.0,
+        loop_start_beats: BeatTime::from_float(loop_start),
+        loop_end_beats: BeatTime::from_float(loop_end),
+        loop_start_seconds: SecondsTime::from_float(transport.beats_to_seconds(loop_start)),
+        loop_end_seconds: SecondsTime::from_float(transport.beats_to_seconds(loop_end)),
+        bar_start: BeatTime::from_float(transport.bar_start_beats()),
+        bar_number: transport.bar_number(),
+        time_signature_numerator: BEATS_PER_BAR,
+        time_signature_denominator: BEAT_UNIT,
+    }
+}
+
+/// An active plugin running in this process.
 /// Must be used on the audio thread.
-pub struct ActivePluginProcessor {
+pub struct ClapProcessor {
     processor: StartedPluginAudioProcessor<DawHost>,
     /// Pre-allocated input audio buffers (stereo)
     input_buffers: [Vec<f32>; 2],
@@ -231,12 +833,35 @@ pub struct ActivePluginProcessor {
     note_off_events: Vec<NoteOffEvent>,
     /// Parameter change events buffer
     param_events: Vec<ParamValueEvent>,
+    /// Note expression events buffer
+    expression_events: Vec<NoteExpressionEvent>,
+    /// MIDI events buffer (MPE plugins)
+    midi_events: Vec<MidiEvent>,
+    /// Order the built events are handed to the plugin in
+    event_order: Vec<EventRef>,
+    /// Notes the plugin sent out during the last process call
+    output_notes: Vec<MidiNote>,
+    /// Parameter changes the plugin made during the last process call
+    output_params: Vec<ParamChange>,
+    /// Processing latency reported by the plugin at activation (in frames)
+    latency: u32,
+    /// How the plugin takes its notes
+    note_input: NoteInput,
+    /// Member channels of sounding MPE notes
+    mpe: MpeAllocator,
+    /// Whether the MPE zone has been set up
+    mpe_configured: bool,
     /// Steady time counter (in frames)
     steady_time: u64,
 }
 
-impl ActivePluginProcessor {
-    fn new(processor: StartedPluginAudioProcessor<DawHost>, buffer_size: usize) -> Self {
+impl ClapProcessor {
+    fn new(
+        processor: StartedPluginAudioProcessor<DawHost>,
+        buffer_size: usize,
+        latency: u32,
+        note_input: NoteInput,
+    ) -> Self {
         Self {
             processor,
             input_buffers: [vec![0.0; buffer_size], vec![0.0; buffer_size]],
@@ -246,77 +871,127 @@ impl ActivePluginProcessor {
             note_on_events: Vec::new(),
             note_off_events: Vec::new(),
             param_events: Vec::new(),
+            expression_events: Vec::new(),
+            midi_events: Vec::new(),
+            event_order: Vec::new(),
+            output_notes: Vec::new(),
+            output_params: Vec::new(),
+            latency,
+            note_input,
+            mpe: MpeAllocator::default(),
+            mpe_configured: false,
             steady_time: 0,
         }
     }
 
     /// Process audio through the plugin.
-    /// Takes MIDI notes, parameter changes, and returns stereo audio output.
+    /// Takes MIDI notes, parameter changes and the song position at the start
+    /// of the block, and returns stereo audio output.
     pub fn process(
         &mut self,
         notes: &[MidiNote],
         params: &[ParamChange],
+        transport: &PluginTransport,
         output_left: &mut [f32],
         output_right: &mut [f32],
     ) {
         let frame_count = output_left.len().min(output_right.len());
+        self.ensure_capacity(frame_count);
+
+        self.run(notes, params, transport, frame_count, true);
+
+        // Copy output to provided buffers
+        output_left[..frame_count].copy_from_slice(&self.output_buffers[0][..frame_count]);
+        output_right[..frame_count].copy_from_slice(&self.output_buffers[1][..frame_count]);
+    }
 
-        // Resize buffers if needed
+    /// Process a stereo buffer in place through an audio effect plugin.
+    /// The buffer is fed to the plugin's input port and replaced by its output.
+    pub fn process_effect(
+        &mut self,
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        let frame_count = left.len().min(right.len());
+        self.ensure_capacity(frame_count);
+
+        self.input_buffers[0][..frame_count].copy_from_slice(&left[..frame_count]);
+        self.input_buffers[1][..frame_count].copy_from_slice(&right[..frame_count]);
+
+        self.run(&[], params, transport, frame_count, false);
+
+        left[..frame_count].copy_from_slice(&self.output_buffers[0][..frame_count]);
+        right[..frame_count].copy_from_slice(&self.output_buffers[1][..frame_count]);
+    }
+
+    /// Resize buffers if needed
+    fn ensure_capacity(&mut self, frame_count: usize) {
         if self.output_buffers[0].len() < frame_count {
             self.output_buffers[0].resize(frame_count, 0.0);
             self.output_buffers[1].resize(frame_count, 0.0);
             self.input_buffers[0].resize(frame_count, 0.0);
             self.input_buffers[1].resize(frame_count, 0.0);
         }
+    }
 
+    /// Run one process call over the first `frame_count` frames of the
+    /// internal buffers. `constant_input` marks the input as silence.
+    fn run(
+        &mut self,
+        notes: &[MidiNote],
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        frame_count: usize,
+        constant_input: bool,
+    ) {
         // Clear output buffers
         self.output_buffers[0][..frame_count].fill(0.0);
         self.output_buffers[1][..frame_count].fill(0.0);
 
-        // Build input events from MIDI notes
+        // Build input events from MIDI notes, clamping times into the block
         self.note_on_events.clear();
         self.note_off_events.clear();
         self.param_events.clear();
+        self.expression_events.clear();
+        self.midi_events.clear();
+        self.event_order.clear();
+        let last_frame = frame_count.saturating_sub(1) as u32;
 
-        for note in notes {
-            if note.is_note_on {
-                // Pckn: Port, Channel, Key (MIDI note), NoteID
-                let event = NoteOnEvent::new(
-                    0, // time offset 0
-                    Pckn::new(0u16, 0u16, note.note as u16, note.note as u32),
-                    note.velocity as f64,
-                );
-                self.note_on_events.push(event);
-            } else {
-                let event = NoteOffEvent::new(
-                    0, // time offset 0
-                    Pckn::new(0u16, 0u16, note.note as u16, note.note as u32),
-                    0.0,
-                );
-                self.note_off_events.push(event);
-            }
+        match self.note_input {
+            NoteInput::Clap => self.build_clap_notes(notes, last_frame),
+            NoteInput::Mpe => self.build_mpe_notes(notes, last_frame),
         }
 
         // Build parameter change events
         for param in params {
-            let event = ParamValueEvent::new(
-                0, // time offset 0
+            let time = param.time.min(last_frame);
+            let index = self.param_events.len();
+            self.param_events.push(ParamValueEvent::new(
+                time,
                 ClapId::new(param.param_id),
                 Pckn::match_all(),
                 param.value,
                 Cookie::empty(),
-            );
-            self.param_events.push(event);
+            ));
+            self.event_order.push(EventRef::Param { time, index });
         }
 
+        // CLAP requires input events ordered by time
+        self.event_order
+            .sort_unstable_by_key(|event| event.sort_key());
+
         // Set up audio buffers
         let input_audio = self.input_ports.with_input_buffers([AudioPortBuffer {
             latency: 0,
-            channels: AudioPortBufferType::f32_input_only(
-                self.input_buffers
-                    .iter_mut()
-                    .map(|b| InputChannel::constant(&mut b[..frame_count])),
-            ),
+            channels: AudioPortBufferType::f32_input_only(self.input_buffers.iter_mut().map(|b| {
+                if constant_input {
+                    InputChannel::constant(&mut b[..frame_count])
+                } else {
+                    InputChannel::variable(&mut b[..frame_count])
+                }
+            })),
         }]);
 
         let mut output_audio = self.output_ports.with_output_buffers([AudioPortBuffer {
@@ -330,36 +1005,217 @@ impl ActivePluginProcessor {
 
         // Set up events - combine all events into a single buffer
         let mut input_event_buffer = EventBuffer::new();
-        for event in &self.note_on_events {
-            input_event_buffer.push(event);
-        }
-        for event in &self.note_off_events {
-            input_event_buffer.push(event);
-        }
-        for event in &self.param_events {
-            input_event_buffer.push(event);
+        for event in &self.event_order {
+            match *event {
+                EventRef::NoteOn { index, .. } => {
+                    input_event_buffer.push(&self.note_on_events[index])
+                }
+                EventRef::NoteOff { index, .. } => {
+                    input_event_buffer.push(&self.note_off_events[index])
+                }
+                EventRef::Param { index, .. } => input_event_buffer.push(&self.param_events[index]),
+                EventRef::Expression { index, .. } => {
+                    input_event_buffer.push(&self.expression_events[index])
+                }
+                EventRef::MidiSetup { index }
+                | EventRef::MidiNoteOff { index, .. }
+                | EventRef::MidiNoteOn { index, .. } => {
+                    input_event_buffer.push(&self.midi_events[index])
+                }
+            }
         }
         let input_events = InputEvents::from_buffer(&input_event_buffer);
         let mut output_event_buffer = EventBuffer::new();
         let mut output_events = OutputEvents::from_buffer(&mut output_event_buffer);
 
         // Process audio
+        let transport_event = transport_event(transport);
         let _status = self.processor.process(
             &input_audio,
             &mut output_audio,
             &input_events,
             &mut output_events,
             Some(self.steady_time),
-            None,
+            Some(&transport_event),
         );
 
-        // Copy output to provided buffers
-        output_left[..frame_count].copy_from_slice(&self.output_buffers[0][..frame_count]);
-        output_right[..frame_count].copy_from_slice(&self.output_buffers[1][..frame_count]);
-
+        self.collect_output(&output_event_buffer);
         self.steady_time += frame_count as u64;
     }
 
+    /// Build CLAP note events, each note-on followed by its expressions
+    fn build_clap_notes(&mut self, notes: &[MidiNote], last_frame: u32) {
+        for note in notes {
+            let time = note.time.min(last_frame);
+            // Pckn: Port, Channel, Key (MIDI note), NoteID
+            let pckn = Pckn::new(0u16, 0u16, note.note as u16, note.note as u32);
+            if note.is_note_on {
+                let index = self.note_on_events.len();
+                self.note_on_events
+                    .push(NoteOnEvent::new(time, pckn, note.velocity as f64));
+                self.event_order.push(EventRef::NoteOn { time, index });
+
+                for (expression, value) in note.expressions.changed() {
+                    let (expression_type, value) = clap_expression(expression, value);
+                    let index = self.expression_events.len();
+                    self.expression_events.push(NoteExpressionEvent::new(
+                        time,
+                        pckn,
+                        expression_type,
+                        value,
+                    ));
+                    self.event_order.push(EventRef::Expression { time, index });
+                }
+            } else {
+                let index = self.note_off_events.len();
+                self.note_off_events
+                    .push(NoteOffEvent::new(time, pckn, 0.0));
+                self.event_order.push(EventRef::NoteOff { time, index });
+            }
+        }
+    }
+
+    /// Build MPE MIDI messages, giving each note a member channel
+    fn build_mpe_notes(&mut self, notes: &[MidiNote], last_frame: u32) {
+        if !self.mpe_configured {
+            for data in mpe::configuration_messages() {
+                let index = self.push_midi(0, data);
+                self.event_order.push(EventRef::MidiSetup { index });
+            }
+            self.mpe_configured = true;
+        }
+
+        for note in notes {
+            let time = note.time.min(last_frame);
+            if note.is_note_on {
+                let (channel, evicted) = self.mpe.note_on(note.note);
+                // A full zone takes over a channel; end the note on it
+                // first, queued with the note-on so it lands just before
+                if let Some(key) = evicted {
+                    let index = self.push_midi(time, mpe::note_off_message(channel, key));
+                    self.event_order.push(EventRef::MidiNoteOn { time, index });
+                }
+                let messages =
+                    mpe::note_on_messages(channel, note.note, note.velocity, &note.expressions);
+                for data in messages {
+                    let index = self.push_midi(time, data);
+                    self.event_order.push(EventRef::MidiNoteOn { time, index });
+                }
+            } else if let Some(channel) = self.mpe.note_off(note.note) {
+                let index = self.push_midi(time, mpe::note_off_message(channel, note.note));
+                self.event_order.push(EventRef::MidiNoteOff { time, index });
+            }
+        }
+    }
+
+    /// Queue a MIDI message on note port 0, returning its buffer index
+    fn push_midi(&mut self, time: u32, data: [u8; 3]) -> usize {
+        self.midi_events.push(MidiEvent::new(time, 0, data));
+        self.midi_events.len() - 1
+    }
+
+    /// Notes the plugin sent out during the last process call
+    /// (arpeggiators, sequencers, MIDI effects)
+    pub fn output_notes(&self) -> &[MidiNote] {
+        &self.output_notes
+    }
+
+    /// Parameter changes the plugin made itself during the last process
+    /// call (its own GUI, internal modulation, randomize buttons)
+    pub fn output_params(&self) -> &[ParamChange] {
+        &self.output_params
+    }
+
+    /// Keep the note and parameter events the plugin wrote to its output
+    fn collect_output(&mut self, events: &EventBuffer) {
+        self.output_notes.clear();
+        self.output_params.clear();
+        let key_of = |key: Match<u16>| key.into_specific().filter(|&k| k < 128).map(|k| k as u8);
+
+        for event in events {
+            let time = event.header().time();
+            match event.as_core_event() {
+                Some(CoreEventSpace::NoteOn(e)) => {
+                    if let Some(note) = key_of(e.key()) {
+                        self.output_notes.push(MidiNote {
+                            time,
+                            note,
+                            velocity: e.velocity() as f32,
+                            is_note_on: true,
+                            expressions: NoteExpressions::default(),
+                        });
+                    }
+                }
+                Some(CoreEventSpace::NoteOff(e)) => {
+                    if let Some(note) = key_of(e.key()) {
+                        self.output_notes.push(MidiNote {
+                            time,
+                            note,
+                            velocity: 0.0,
+                            is_note_on: false,
+                            expressions: NoteExpressions::default(),
+                        });
+                    }
+                }
+                Some(CoreEventSpace::Midi(e)) => {
+                    let [status, note, velocity] = e.data();
+                    let note = note & 0x7f;
+                    match status & 0xf0 {
+                        0x90 if velocity > 0 => self.output_notes.push(MidiNote {
+                            time,
+                            note,
+                            velocity: velocity as f32 / 127.0,
+                            is_note_on: true,
+                            expressions: NoteExpressions::default(),
+                        }),
+                        0x80 | 0x90 => self.output_notes.push(MidiNote {
+                            time,
+                            note,
+                            velocity: 0.0,
+                            is_note_on: false,
+                            expressions: NoteExpressions::default(),
+                        }),
+                        _ => {}
+                    }
+                }
+                Some(CoreEventSpace::ParamValue(e)) => {
+                    if let Some(id) = e.param_id() {
+                        self.output_params.push(ParamChange {
+                            time,
+                            param_id: id.get(),
+                            value: e.value(),
+                        });
+                    }
+                }
+                // Gestures only bracket the value events above (for undo
+                // grouping); everything else has no host-side meaning yet
+                _ => {}
+            }
+        }
+    }
+
+    /// Length of the plugin's tail in frames (reverb/delay/release after input stops).
+    ///
+    /// Returns 0 if the plugin doesn't implement the tail extension and
+    /// `u32::MAX` for an infinite tail, following the CLAP convention.
+    pub fn tail_frames(&mut self) -> u32 {
+        let Some(tail) = self.processor.access_handler(|h| h.tail) else {
+            return 0;
+        };
+        match tail.get(&mut self.processor.plugin_handle()) {
+            TailLength::Finite(frames) => frames,
+            TailLength::Infinite => u32::MAX,
+        }
+    }
+
+    /// Processing latency in frames, as reported when the plugin was activated.
+    ///
+    /// A plugin that changes its latency asks for a restart, which reactivates
+    /// it and yields a new processor with the updated value.
+    pub fn latency_frames(&self) -> u32 {
+        self.latency
+    }
+
     /// Stop processing and return the processor for deactivation
     fn stop(self) -> StoppedPluginAudioProcessor<DawHost> {
         self.processor.stop_processing()
@@ -0,0 +1,740 @@
+//! Wire format between the app and a sandboxed plugin host process
+//!
+//! Messages travel over the child's stdin/stdout as length-prefixed frames
+//! of little-endian fields. Audio is sent as raw `f32` samples, so a block
+//! costs a couple of copies but no parsing.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use super::host::{MidiNote, ParamChange};
+use super::presets::{PluginPreset, PresetLocation};
+use super::transport::PluginTransport;
+use super::{PluginInfo, PluginParam};
+use crate::sequencer::NoteExpressions;
+
+/// Largest frame accepted, to fail fast on a corrupted stream
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Message from the app to the plugin host process
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Restore a saved state blob (before activation)
+    LoadState(Vec<u8>),
+    /// Activate the plugin and start processing
+    Activate,
+    /// Process one block; `input` is only sent for effects
+    Process {
+        frames: u32,
+        transport: PluginTransport,
+        notes: Vec<MidiNote>,
+        params: Vec<ParamChange>,
+        input: Option<[Vec<f32>; 2]>,
+    },
+    /// Ask for the plugin's tail length
+    TailFrames,
+    /// Save the plugin's state
+    SaveState,
+    /// Format a parameter value the way the plugin displays it
+    FormatValue { param_id: u32, value: f64 },
+    /// List the presets the plugin provides
+    FactoryPresets,
+    /// Load one of the plugin's presets and snapshot the result
+    LoadPreset(PluginPreset),
+}
+
+/// Borrowed form of [`Request::Process`], so the audio thread can encode a
+/// block without copying it into a request first
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessBlock<'a> {
+    pub frames: u32,
+    pub transport: &'a PluginTransport,
+    pub notes: &'a [MidiNote],
+    pub params: &'a [ParamChange],
+    pub input: Option<[&'a [f32]; 2]>,
+}
+
+impl ProcessBlock<'_> {
+    /// Encode as a `Request::Process` message
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.clear();
+        Encoder(out).process(self);
+    }
+}
+
+/// Message from the plugin host process to the app
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    /// The plugin loaded; sent once at startup
+    Ready(PluginInfo),
+    /// The plugin activated with this latency (in frames)
+    Activated { latency: u32 },
+    /// A request without a result succeeded
+    Done,
+    /// A request failed
+    Error(String),
+    /// Output of a processed block
+    Processed {
+        output: [Vec<f32>; 2],
+        notes: Vec<MidiNote>,
+        params: Vec<ParamChange>,
+    },
+    /// Tail length in frames (`u32::MAX` if infinite)
+    Tail(u32),
+    /// Saved state, or None if the plugin has no state extension
+    State(Option<Vec<u8>>),
+    /// Formatted parameter value, or None if the plugin can't format it
+    Text(Option<String>),
+    /// The plugin asked to be restarted (sent unprompted)
+    RestartRequested,
+    /// Presets the plugin provides
+    Presets(Vec<PluginPreset>),
+    /// Snapshot of the plugin after loading a preset
+    Preset(PluginPreset),
+}
+
+/// Write one message as a length-prefixed frame
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Read one frame; None at a clean end of stream
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(invalid("frame too large"));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+impl Request {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.clear();
+        let mut w = Encoder(out);
+        match self {
+            Request::LoadState(data) => {
+                w.u8(0);
+                w.bytes(data);
+            }
+            Request::Activate => w.u8(1),
+            Request::Process {
+                frames,
+                transport,
+                notes,
+                params,
+                input,
+            } => w.process(&ProcessBlock {
+                frames: *frames,
+                transport,
+                notes,
+                params,
+                input: input.as_ref().map(|[left, right]| [&left[..], &right[..]]),
+            }),
+            Request::TailFrames => w.u8(3),
+            Request::SaveState => w.u8(4),
+            Request::FormatValue { param_id, value } => {
+                w.u8(5);
+                w.u32(*param_id);
+                w.f64(*value);
+            }
+            Request::FactoryPresets => w.u8(6),
+            Request::LoadPreset(preset) => {
+                w.u8(7);
+                w.preset(preset);
+            }
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> io::Result<Self> {
+        let mut r = Decoder(data);
+        let request = match r.u8()? {
+            0 => Request::LoadState(r.bytes()?),
+            1 => Request::Activate,
+            2 => Request::Process {
+                frames: r.u32()?,
+                transport: r.transport()?,
+                notes: r.notes()?,
+                params: r.params()?,
+                input: match r.u8()? {
+                    0 => None,
+                    _ => Some([r.samples()?, r.samples()?]),
+                },
+            },
+            3 => Request::TailFrames,
+            4 => Request::SaveState,
+            5 => Request::FormatValue {
+                param_id: r.u32()?,
+                value: r.f64()?,
+            },
+            6 => Request::FactoryPresets,
+            7 => Request::LoadPreset(r.preset()?),
+            _ => return Err(invalid("unknown request")),
+        };
+        Ok(request)
+    }
+}
+
+impl Response {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.clear();
+        let mut w = Encoder(out);
+        match self {
+            Response::Ready(info) => {
+                w.u8(0);
+                w.info(info);
+            }
+            Response::Activated { latency } => {
+                w.u8(1);
+                w.u32(*latency);
+            }
+            Response::Done => w.u8(2),
+            Response::Error(message) => {
+                w.u8(3);
+                w.str(message);
+            }
+            Response::Processed {
+                output: [left, right],
+                notes,
+                params,
+            } => {
+                w.u8(4);
+                w.samples(left);
+                w.samples(right);
+                w.notes(notes);
+                w.params(params);
+            }
+            Response::Tail(frames) => {
+                w.u8(5);
+                w.u32(*frames);
+            }
+            Response::State(state) => {
+                w.u8(6);
+                w.option(state.as_deref(), Encoder::bytes);
+            }
+            Response::Text(text) => {
+                w.u8(7);
+                w.option(text.as_deref(), Encoder::str);
+            }
+            Response::RestartRequested => w.u8(8),
+            Response::Presets(presets) => {
+                w.u8(9);
+                w.u32(presets.len() as u32);
+                for preset in presets {
+                    w.preset(preset);
+                }
+            }
+            Response::Preset(preset) => {
+                w.u8(10);
+                w.preset(preset);
+            }
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> io::Result<Self> {
+        let mut r = Decoder(data);
+        let response = match r.u8()? {
+            0 => Response::Ready(r.info()?),
+            1 => Response::Activated { latency: r.u32()? },
+            2 => Response::Done,
+            3 => Response::Error(r.str()?),
+            4 => Response::Processed {
+                output: [r.samples()?, r.samples()?],
+                notes: r.notes()?,
+                params: r.params()?,
+            },
+            5 => Response::Tail(r.u32()?),
+            6 => Response::State(r.option(Decoder::bytes)?),
+            7 => Response::Text(r.option(Decoder::str)?),
+            8 => Response::RestartRequested,
+            9 => {
+                let len = r.len(1)?;
+                Response::Presets((0..len).map(|_| r.preset()).collect::<io::Result<_>>()?)
+            }
+            10 => Response::Preset(r.preset()?),
+            _ => return Err(invalid("unknown response")),
+        };
+        Ok(response)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+struct Encoder<'a>(&'a mut Vec<u8>);
+
+impl Encoder<'_> {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+
+    fn bytes(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v);
+    }
+
+    fn str(&mut self, v: &str) {
+        self.bytes(v.as_bytes());
+    }
+
+    fn option<T: ?Sized>(&mut self, v: Option<&T>, write: impl FnOnce(&mut Self, &T)) {
+        self.bool(v.is_some());
+        if let Some(v) = v {
+            write(self, v);
+        }
+    }
+
+    fn samples(&mut self, v: &[f32]) {
+        self.u32(v.len() as u32);
+        for &s in v {
+            self.f32(s);
+        }
+    }
+
+    fn transport(&mut self, t: &PluginTransport) {
+        self.bool(t.playing);
+        self.f64(t.tempo);
+        self.f64(t.position_beats);
+        self.bool(t.loop_beats.is_some());
+        let (start, end) = t.loop_beats.unwrap_or_default();
+        self.f64(start);
+        self.f64(end);
+    }
+
+    fn notes(&mut self, notes: &[MidiNote]) {
+        self.u32(notes.len() as u32);
+        for note in notes {
+            self.u32(note.time);
+            self.u8(note.note);
+            self.f32(note.velocity);
+            self.bool(note.is_note_on);
+            let e = &note.expressions;
+            for value in [e.pitch_bend, e.pressure, e.timbre, e.pan] {
+                self.f32(value);
+            }
+        }
+    }
+
+    fn process(&mut self, block: &ProcessBlock) {
+        self.u8(2);
+        self.u32(block.frames);
+        self.transport(block.transport);
+        self.notes(block.notes);
+        self.params(block.params);
+        match block.input {
+            Some([left, right]) => {
+                self.u8(1);
+                self.samples(left);
+                self.samples(right);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn params(&mut self, params: &[ParamChange]) {
+        self.u32(params.len() as u32);
+        for param in params {
+            self.u32(param.time);
+            self.u32(param.param_id);
+            self.f64(param.value);
+        }
+    }
+
+    fn preset(&mut self, preset: &PluginPreset) {
+        self.str(&preset.name);
+        self.str(&preset.plugin_id);
+        self.u32(preset.params.len() as u32);
+        for (&id, &value) in &preset.params {
+            self.u32(id);
+            self.f64(value);
+        }
+        self.option(preset.state.as_deref(), Encoder::bytes);
+        self.option(preset.location.as_ref(), |w, location| match location {
+            PresetLocation::File { path, load_key } => {
+                w.u8(0);
+                w.str(&path.to_string_lossy());
+                w.option(load_key.as_deref(), Encoder::str);
+            }
+            PresetLocation::Plugin { load_key } => {
+                w.u8(1);
+                w.option(load_key.as_deref(), Encoder::str);
+            }
+        });
+    }
+
+    fn info(&mut self, info: &PluginInfo) {
+        self.str(&info.id);
+        self.str(&info.name);
+        self.str(&info.vendor);
+        self.u32(info.params.len() as u32);
+        for param in &info.params {
+            self.u32(param.id);
+            self.str(&param.name);
+            self.str(&param.module);
+            self.f64(param.value);
+            self.f64(param.min);
+            self.f64(param.max);
+            self.f64(param.default);
+            self.bool(param.stepped);
+        }
+    }
+}
+
+struct Decoder<'a>(&'a [u8]);
+
+impl Decoder<'_> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        if self.0.len() < N {
+            return Err(invalid("message truncated"));
+        }
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(head.try_into().expect("split at N"))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    /// Length prefix of a sequence, checked against what's left to read
+    fn len(&mut self, item_size: usize) -> io::Result<usize> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(item_size) > self.0.len() {
+            return Err(invalid("message truncated"));
+        }
+        Ok(len)
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.len(1)?;
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head.to_vec())
+    }
+
+    fn str(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| invalid("invalid UTF-8"))
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> io::Result<T>,
+    ) -> io::Result<Option<T>> {
+        if self.bool()? {
+            read(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn samples(&mut self) -> io::Result<Vec<f32>> {
+        let len = self.len(4)?;
+        (0..len).map(|_| self.f32()).collect()
+    }
+
+    fn transport(&mut self) -> io::Result<PluginTransport> {
+        let playing = self.bool()?;
+        let tempo = self.f64()?;
+        let position_beats = self.f64()?;
+        let looping = self.bool()?;
+        let range = (self.f64()?, self.f64()?);
+        Ok(PluginTransport {
+            playing,
+            tempo,
+            position_beats,
+            loop_beats: looping.then_some(range),
+        })
+    }
+
+    fn notes(&mut self) -> io::Result<Vec<MidiNote>> {
+        let len = self.len(26)?;
+        (0..len)
+            .map(|_| {
+                Ok(MidiNote {
+                    time: self.u32()?,
+                    note: self.u8()?,
+                    velocity: self.f32()?,
+                    is_note_on: self.bool()?,
+                    expressions: NoteExpressions {
+                        pitch_bend: self.f32()?,
+                        pressure: self.f32()?,
+                        timbre: self.f32()?,
+                        pan: self.f32()?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn params(&mut self) -> io::Result<Vec<ParamChange>> {
+        let len = self.len(16)?;
+        (0..len)
+            .map(|_| {
+                Ok(ParamChange {
+                    time: self.u32()?,
+                    param_id: self.u32()?,
+                    value: self.f64()?,
+                })
+            })
+            .collect()
+    }
+
+    fn preset(&mut self) -> io::Result<PluginPreset> {
+        let name = self.str()?;
+        let plugin_id = self.str()?;
+        let len = self.len(12)?;
+        let params = (0..len)
+            .map(|_| Ok((self.u32()?, self.f64()?)))
+            .collect::<io::Result<HashMap<_, _>>>()?;
+        let state = self.option(Decoder::bytes)?;
+        let location = self.option(|r| {
+            Ok(match r.u8()? {
+                0 => PresetLocation::File {
+                    path: PathBuf::from(r.str()?),
+                    load_key: r.option(Decoder::str)?,
+                },
+                _ => PresetLocation::Plugin {
+                    load_key: r.option(Decoder::str)?,
+                },
+            })
+        })?;
+        Ok(PluginPreset {
+            location,
+            ..PluginPreset::snapshot(&name, &plugin_id, params, state)
+        })
+    }
+
+    fn info(&mut self) -> io::Result<PluginInfo> {
+        let id = self.str()?;
+        let name = self.str()?;
+        let vendor = self.str()?;
+        let len = self.len(4)?;
+        let params = (0..len)
+            .map(|_| {
+                Ok(PluginParam {
+                    id: self.u32()?,
+                    name: self.str()?,
+                    module: self.str()?,
+                    value: self.f64()?,
+                    min: self.f64()?,
+                    max: self.f64()?,
+                    default: self.f64()?,
+                    stepped: self.bool()?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(PluginInfo {
+            id,
+            name,
+            vendor,
+            params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip_request(request: Request) {
+        let mut buf = Vec::new();
+        request.encode(&mut buf);
+        assert_eq!(Request::decode(&buf).unwrap(), request);
+    }
+
+    fn round_trip_response(response: Response) {
+        let mut buf = Vec::new();
+        response.encode(&mut buf);
+        assert_eq!(Response::decode(&buf).unwrap(), response);
+    }
+
+    #[test]
+    fn test_requests_round_trip() {
+        round_trip_request(Request::LoadState(vec![1, 2, 3]));
+        round_trip_request(Request::Activate);
+        round_trip_request(Request::Process {
+            frames: 4,
+            transport: PluginTransport {
+                playing: true,
+                tempo: 128.0,
+                position_beats: 3.5,
+                loop_beats: Some((0.0, 16.0)),
+            },
+            notes: vec![MidiNote {
+                time: 2,
+                note: 60,
+                velocity: 0.8,
+                is_note_on: true,
+                expressions: NoteExpressions {
+                    pitch_bend: -2.5,
+                    pressure: 0.6,
+                    timbre: 0.5,
+                    pan: 0.25,
+                },
+            }],
+            params: vec![ParamChange {
+                time: 1,
+                param_id: 7,
+                value: 0.25,
+            }],
+            input: Some([vec![0.1; 4], vec![-0.1; 4]]),
+        });
+        round_trip_request(Request::SaveState);
+        round_trip_request(Request::FormatValue {
+            param_id: 3,
+            value: 440.0,
+        });
+        round_trip_request(Request::FactoryPresets);
+        round_trip_request(Request::LoadPreset(PluginPreset::factory(
+            "Init",
+            "com.example.synth",
+            PresetLocation::Plugin {
+                load_key: Some("init".to_string()),
+            },
+        )));
+    }
+
+    #[test]
+    fn test_process_block_encodes_like_request() {
+        let transport = PluginTransport::default();
+        let params = [ParamChange {
+            time: 0,
+            param_id: 1,
+            value: 0.5,
+        }];
+        let (left, right) = (vec![0.1; 4], vec![0.2; 4]);
+
+        let mut borrowed = Vec::new();
+        ProcessBlock {
+            frames: 4,
+            transport: &transport,
+            notes: &[],
+            params: &params,
+            input: Some([&left, &right]),
+        }
+        .encode(&mut borrowed);
+        let mut owned = Vec::new();
+        Request::Process {
+            frames: 4,
+            transport,
+            notes: Vec::new(),
+            params: params.to_vec(),
+            input: Some([left, right]),
+        }
+        .encode(&mut owned);
+        assert_eq!(borrowed, owned);
+    }
+
+    #[test]
+    fn test_responses_round_trip() {
+        round_trip_response(Response::Ready(PluginInfo {
+            id: "com.example.synth".to_string(),
+            name: "Synth".to_string(),
+            vendor: "Example".to_string(),
+            params: vec![PluginParam {
+                id: 1,
+                name: "Cutoff".to_string(),
+                module: "Filter".to_string(),
+                value: 1000.0,
+                min: 20.0,
+                max: 20000.0,
+                default: 1000.0,
+                stepped: false,
+            }],
+        }));
+        round_trip_response(Response::Processed {
+            output: [vec![0.5; 3], vec![0.25; 3]],
+            notes: Vec::new(),
+            params: vec![ParamChange {
+                time: 0,
+                param_id: 2,
+                value: 1.0,
+            }],
+        });
+        round_trip_response(Response::State(None));
+        round_trip_response(Response::State(Some(vec![9; 10])));
+        round_trip_response(Response::Text(Some("1.0 kHz".to_string())));
+        round_trip_response(Response::Error("boom".to_string()));
+        round_trip_response(Response::Presets(vec![PluginPreset::factory(
+            "Bass",
+            "com.example.synth",
+            PresetLocation::File {
+                path: PathBuf::from("/presets/bass.fxp"),
+                load_key: None,
+            },
+        )]));
+        round_trip_response(Response::Preset(PluginPreset::snapshot(
+            "Bass",
+            "com.example.synth",
+            HashMap::from([(1, 0.5), (2, 3.0)]),
+            Some(vec![4, 5]),
+        )));
+    }
+
+    #[test]
+    fn test_truncated_message_is_rejected() {
+        let mut buf = Vec::new();
+        Response::Processed {
+            output: [vec![0.5; 8], vec![0.5; 8]],
+            notes: Vec::new(),
+            params: Vec::new(),
+        }
+        .encode(&mut buf);
+        buf.truncate(buf.len() / 2);
+        assert!(Response::decode(&buf).is_err());
+    }
+
+    #[test]
+    fn test_frames_split_a_stream() {
+        let mut stream = Vec::new();
+        write_frame(&mut stream, b"one").unwrap();
+        write_frame(&mut stream, b"").unwrap();
+        let mut reader = &stream[..];
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(b"one".to_vec()));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(Vec::new()));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+}
@@ -6,6 +6,7 @@
 
 use std::path::Path;
 
+use super::presets::PluginPreset;
 use super::{ActivePluginProcessor, PluginHost, PluginInfo};
 
 /// Error type for plugin loading failures
@@ -25,17 +26,72 @@ pub struct LoadedPlugin {
     pub processor: ActivePluginProcessor,
     /// Plugin metadata
     pub info: PluginInfo,
+    /// The main-thread side of the instance, kept to save its state
+    pub instance: Box<dyn StateSaver>,
+    /// Why the saved state was rejected, if it was; the plugin still loads
+    /// with its defaults
+    pub state_error: Option<String>,
+}
+
+/// Formats parameter values the way the plugin displays them
+pub trait ParamFormatter {
+    /// Text for `value` of parameter `param_id`, or None if the plugin
+    /// can't format it
+    fn format(&mut self, param_id: u32, value: f64) -> Option<String>;
+
+    /// Have the plugin load one of its factory presets, and snapshot its
+    /// parameters and state afterwards as a host preset
+    fn load_preset(&mut self, preset: &PluginPreset) -> Result<PluginPreset, String> {
+        Err(format!("{} can't be loaded by this plugin", preset.name))
+    }
+}
+
+/// Main-thread handle of a live plugin instance: saves its state and
+/// services its requests
+pub trait StateSaver {
+    /// The plugin's opaque state blob, or None if it has no state extension
+    fn save_state(&mut self) -> Result<Option<Vec<u8>>, String>;
+
+    /// Run any callback the plugin asked for on the main thread; returns
+    /// true if it asked to be restarted
+    fn service_main_thread(&mut self) -> bool {
+        false
+    }
+}
+
+/// A plugin instantiated (but not activated) to read its parameters
+pub struct InspectedPlugin {
+    /// Plugin metadata, including the discovered parameters
+    pub info: PluginInfo,
+    /// Keeps the instance alive to format parameter values
+    pub formatter: Box<dyn ParamFormatter>,
+    /// Factory presets the plugin offers through preset discovery
+    pub presets: Vec<PluginPreset>,
 }
 
 /// Trait for loading plugins - enables mocking in tests
 pub trait PluginLoader: Send + Sync {
     /// Load and activate a plugin, returning a ready-to-use processor
+    ///
+    /// `plugin_id` picks a plugin from a multi-plugin bundle (the first one
+    /// if None). `state` is a blob saved from an earlier instance; it is
+    /// restored before activation.
     fn load_plugin(
         &self,
         path: &Path,
+        plugin_id: Option<&str>,
         sample_rate: f64,
         buffer_size: u32,
+        state: Option<&[u8]>,
     ) -> Result<LoadedPlugin, PluginLoadError>;
+
+    /// Instantiate a plugin on the calling (main) thread without activating
+    /// it, to read its parameters
+    fn inspect_plugin(
+        &self,
+        path: &Path,
+        plugin_id: Option<&str>,
+    ) -> Result<InspectedPlugin, PluginLoadError>;
 }
 
 /// Default CLAP plugin loader using clack-host
@@ -45,20 +101,46 @@ impl PluginLoader for ClapPluginLoader {
     fn load_plugin(
         &self,
         path: &Path,
+        plugin_id: Option<&str>,
         sample_rate: f64,
         buffer_size: u32,
+        state: Option<&[u8]>,
     ) -> Result<LoadedPlugin, PluginLoadError> {
         // Load the plugin
-        let mut host = PluginHost::load(path, sample_rate, buffer_size)
+        let mut host = PluginHost::load(path, plugin_id, sample_rate, buffer_size)
             .map_err(PluginLoadError::LoadFailed)?;
 
+        // A state the plugin rejects shouldn't keep it from loading; the
+        // saved parameters are still applied afterwards
+        let state_error = state.and_then(|state| host.load_state(state).err());
+
         // Get info before activation
         let info = host.info().clone();
 
         // Activate and get the processor
         let processor = host.activate().map_err(PluginLoadError::ActivationFailed)?;
 
-        Ok(LoadedPlugin { processor, info })
+        Ok(LoadedPlugin {
+            processor: processor.into(),
+            info,
+            instance: Box::new(host),
+            state_error,
+        })
+    }
+
+    fn inspect_plugin(
+        &self,
+        path: &Path,
+        plugin_id: Option<&str>,
+    ) -> Result<InspectedPlugin, PluginLoadError> {
+        // The instance is never activated, so the audio settings don't matter
+        let host = PluginHost::load(path, plugin_id, 44_100.0, 512)
+            .map_err(PluginLoadError::LoadFailed)?;
+        Ok(InspectedPlugin {
+            info: host.info().clone(),
+            presets: host.factory_presets(),
+            formatter: Box::new(host),
+        })
     }
 }
 
@@ -72,12 +154,38 @@ impl Default for ClapPluginLoader {
 pub mod mock {
     use super::*;
 
+    use crate::plugin_host::PluginParam;
+
     /// Mock plugin loader for testing
     pub struct MockPluginLoader {
         /// If true, load_plugin will return an error
         pub should_fail: bool,
         /// Error message to return on failure
         pub error_message: String,
+        /// Parameters reported by inspect_plugin
+        pub params: Vec<PluginParam>,
+        /// Factory presets reported by inspect_plugin
+        pub presets: Vec<PluginPreset>,
+    }
+
+    /// Formatter that appends " u" to every value so tests can tell it ran
+    struct MockFormatter;
+
+    impl ParamFormatter for MockFormatter {
+        fn format(&mut self, _param_id: u32, value: f64) -> Option<String> {
+            Some(format!("{:.1} u", value))
+        }
+
+        /// "Loads" a factory preset as parameter 0 at 1.0 with the preset's
+        /// name as state
+        fn load_preset(&mut self, preset: &PluginPreset) -> Result<PluginPreset, String> {
+            Ok(PluginPreset::snapshot(
+                &preset.name,
+                &preset.plugin_id,
+                std::collections::HashMap::from([(0, 1.0)]),
+                Some(preset.name.as_bytes().to_vec()),
+            ))
+        }
     }
 
     impl MockPluginLoader {
@@ -86,6 +194,8 @@ pub mod mock {
             Self {
                 should_fail: false,
                 error_message: "Mock failure".to_string(),
+                params: Vec::new(),
+                presets: Vec::new(),
             }
         }
 
@@ -94,6 +204,8 @@ pub mod mock {
             Self {
                 should_fail: true,
                 error_message: message.to_string(),
+                params: Vec::new(),
+                presets: Vec::new(),
             }
         }
     }
@@ -108,8 +220,10 @@ pub mod mock {
         fn load_plugin(
             &self,
             path: &Path,
+            _plugin_id: Option<&str>,
             _sample_rate: f64,
             _buffer_size: u32,
+            _state: Option<&[u8]>,
         ) -> Result<LoadedPlugin, PluginLoadError> {
             if self.should_fail {
                 Err(PluginLoadError::LoadFailed(self.error_message.clone()))
@@ -124,6 +238,30 @@ pub mod mock {
                 )))
             }
         }
+
+        fn inspect_plugin(
+            &self,
+            path: &Path,
+            _plugin_id: Option<&str>,
+        ) -> Result<InspectedPlugin, PluginLoadError> {
+            if self.should_fail {
+                return Err(PluginLoadError::LoadFailed(self.error_message.clone()));
+            }
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            Ok(InspectedPlugin {
+                info: PluginInfo {
+                    id: format!("mock.{}", name),
+                    name,
+                    vendor: "Mock".to_string(),
+                    params: self.params.clone(),
+                },
+                formatter: Box::new(MockFormatter),
+                presets: self.presets.clone(),
+            })
+        }
     }
 }
 
@@ -134,7 +272,7 @@ mod tests {
     #[test]
     fn test_mock_loader_failing() {
         let loader = mock::MockPluginLoader::failing("test error");
-        let result = loader.load_plugin(Path::new("/fake/path.clap"), 44100.0, 512);
+        let result = loader.load_plugin(Path::new("/fake/path.clap"), None, 44100.0, 512, None);
         assert!(result.is_err());
         match result {
             Err(PluginLoadError::LoadFailed(msg)) => {
@@ -8,31 +8,85 @@ use crossbeam_channel::{Receiver, Sender};
 
 #[allow(dead_code)]
 mod host;
+mod ipc;
 pub mod loader;
+pub mod mpe;
 pub mod params;
+pub mod presets;
+mod processor;
+pub mod sandbox;
+pub mod scanner;
+pub mod state;
+pub mod transport;
 
 #[cfg(test)]
 pub use loader::mock::MockPluginLoader;
-pub use loader::{ClapPluginLoader, LoadedPlugin, PluginLoadError, PluginLoader};
-pub use params::PluginParamId;
+pub use loader::{
+    ClapPluginLoader, InspectedPlugin, LoadedPlugin, ParamFormatter, PluginLoadError, PluginLoader,
+    StateSaver,
+};
 
 #[allow(unused_imports)]
-pub use host::{ActivePluginProcessor, MidiNote, ParamChange, PluginHost};
-
-/// A loaded plugin's parameter info
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+pub use host::{MidiNote, ParamChange, PluginHost};
+pub use presets::{PluginPreset, PluginPresetLibrary, PresetLocation};
+pub use processor::ActivePluginProcessor;
+pub use sandbox::SandboxPluginLoader;
+pub use scanner::{scan_plugin_directories, PluginCategory, PluginDescription, ScannedBundle};
+
+/// A plugin parameter as reported by the CLAP params extension
+///
+/// Values are in the plugin's own range (`min..=max`), which is also what
+/// parameter events carry. Stepped parameters only take whole values.
+#[derive(Debug, Clone, PartialEq)]
 pub struct PluginParam {
+    /// CLAP parameter id (stable across sessions, used as the project key)
     pub id: u32,
     pub name: String,
-    pub value: f32,
-    pub min: f32,
-    pub max: f32,
-    pub default: f32,
+    /// Group path shown by the plugin, e.g. "Filter/Envelope" (may be empty)
+    pub module: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    /// Whether the parameter only takes integer values (switches, enums)
+    pub stepped: bool,
+}
+
+impl PluginParam {
+    /// Position of the value within the range (0.0-1.0)
+    pub fn normalized(&self) -> f64 {
+        let range = self.max - self.min;
+        if range > 0.0 {
+            ((self.value - self.min) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Move the value by `delta`: whole steps for stepped parameters,
+    /// percent of the range for continuous ones
+    pub fn adjust(&mut self, delta: f64) {
+        let value = if self.stepped {
+            let steps = if delta > 0.0 { 1.0 } else { -1.0 };
+            self.value.round() + steps
+        } else {
+            self.value + delta * (self.max - self.min) * 0.01
+        };
+        self.value = value.clamp(self.min, self.max);
+    }
+
+    /// Plain-number display for when the plugin can't format the value
+    pub fn format_value(&self) -> String {
+        if self.stepped {
+            format!("{:.0}", self.value)
+        } else {
+            format!("{:.2}", self.value)
+        }
+    }
 }
 
 /// Info about a loaded plugin
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct PluginInfo {
     pub id: String,
@@ -81,8 +135,27 @@ pub enum PluginEvent {
     ParamChanged {
         channel: usize,
         param_id: u32,
-        value: f32,
+        value: f64,
+    },
+    /// A mixer insert plugin changed one of its parameters
+    EffectParamChanged {
+        track: usize,
+        slot: usize,
+        param_id: u32,
+        value: f64,
     },
+    /// A channel's plugin sent a note out
+    NoteOut {
+        channel: usize,
+        note: u8,
+        velocity: f32,
+        is_note_on: bool,
+    },
+    /// A channel's sandboxed plugin crashed or hung and is now silent
+    Crashed { channel: usize },
+    /// A mixer insert's sandboxed plugin crashed or hung and now passes
+    /// audio through
+    EffectCrashed { track: usize, slot: usize },
 }
 
 /// Handle for sending commands to the plugin host
@@ -143,66 +216,3 @@ impl PluginHandle {
         events
     }
 }
-
-/// Scan for CLAP plugins in standard directories
-#[allow(dead_code)]
-pub fn scan_plugin_directories() -> Vec<PathBuf> {
-    let mut plugins = Vec::new();
-
-    // Standard CLAP plugin directories
-    let dirs = get_plugin_directories();
-
-    for dir in dirs {
-        if dir.exists() {
-            if let Ok(entries) = std::fs::read_dir(&dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().map(|e| e == "clap").unwrap_or(false) {
-                        plugins.push(path);
-                    }
-                }
-            }
-        }
-    }
-
-    plugins
-}
-
-/// Get standard plugin directories for the current platform
-fn get_plugin_directories() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
-
-    // User home directory plugins
-    if let Some(home) = dirs::home_dir() {
-        dirs.push(home.join(".clap"));
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        dirs.push(PathBuf::from("/Library/Audio/Plug-Ins/CLAP"));
-        if let Some(home) = dirs::home_dir() {
-            dirs.push(home.join("Library/Audio/Plug-Ins/CLAP"));
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        if let Some(home) = dirs::home_dir() {
-            dirs.push(home.join(".local/lib/clap"));
-        }
-        dirs.push(PathBuf::from("/usr/lib/clap"));
-        dirs.push(PathBuf::from("/usr/local/lib/clap"));
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        if let Some(program_files) = std::env::var_os("PROGRAMFILES") {
-            dirs.push(PathBuf::from(program_files).join("Common Files/CLAP"));
-        }
-        if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
-            dirs.push(PathBuf::from(local_app_data).join("Programs/Common/CLAP"));
-        }
-    }
-
-    dirs
-}
@@ -0,0 +1,145 @@
+//! MPE (MIDI Polyphonic Expression) for plugins that take MIDI
+//!
+//! MIDI only has channel-wide pitch bend and pressure, so MPE gives every
+//! sounding note a MIDI channel of its own. Channel 1 is the zone's manager;
+//! notes take member channels 2-16 in turn, and their expressions go out as
+//! that channel's pitch bend, channel pressure, CC74 (timbre) and CC10 (pan)
+//! just before the note-on.
+
+use crate::sequencer::NoteExpressions;
+
+/// Member channels of the lower zone (MIDI channels 2-16, zero-based)
+const MEMBER_CHANNELS: usize = 15;
+
+/// Per-note pitch bend range of member channels (the MPE default)
+pub const MPE_PITCH_BEND_RANGE: f32 = 48.0;
+
+/// Hands out member channels to sounding notes
+#[derive(Debug, Clone, Default)]
+pub struct MpeAllocator {
+    /// Key sounding on each member channel
+    keys: [Option<u8>; MEMBER_CHANNELS],
+    /// Member channel to try first for the next note
+    next: usize,
+}
+
+impl MpeAllocator {
+    /// Pick a member channel (zero-based, 1-15) for a new note on `key`
+    ///
+    /// Free channels are used in turn so a note's release tail isn't cut
+    /// short by the next note's expressions. With all 15 in use, the
+    /// oldest-assigned channel is taken over; its key is returned as well,
+    /// so the caller can end that note first.
+    pub fn note_on(&mut self, key: u8) -> (u8, Option<u8>) {
+        let index = (0..MEMBER_CHANNELS)
+            .map(|i| (self.next + i) % MEMBER_CHANNELS)
+            .find(|&i| self.keys[i].is_none())
+            .unwrap_or(self.next);
+        let evicted = self.keys[index].replace(key);
+        self.next = (index + 1) % MEMBER_CHANNELS;
+        (index as u8 + 1, evicted)
+    }
+
+    /// Free the channel `key` is sounding on and return it
+    pub fn note_off(&mut self, key: u8) -> Option<u8> {
+        let index = self.keys.iter().position(|&k| k == Some(key))?;
+        self.keys[index] = None;
+        Some(index as u8 + 1)
+    }
+}
+
+/// MPE Configuration Message: a lower zone with all 15 member channels
+/// (RPN 6 on the manager channel)
+pub fn configuration_messages() -> [[u8; 3]; 3] {
+    [
+        [0xB0, 101, 0],
+        [0xB0, 100, 6],
+        [0xB0, 6, MEMBER_CHANNELS as u8],
+    ]
+}
+
+/// Messages that start a note on a member channel: its expressions, then
+/// the note-on itself
+pub fn note_on_messages(
+    channel: u8,
+    key: u8,
+    velocity: f32,
+    expressions: &NoteExpressions,
+) -> [[u8; 3]; 5] {
+    let bend = (expressions.pitch_bend / MPE_PITCH_BEND_RANGE * 8192.0 + 8192.0)
+        .round()
+        .clamp(0.0, 16383.0) as u16;
+    let pan = (expressions.pan + 1.0) / 2.0;
+    [
+        [0xE0 | channel, (bend & 0x7f) as u8, (bend >> 7) as u8],
+        [0xD0 | channel, to_7bit(expressions.pressure), 0],
+        [0xB0 | channel, 74, to_7bit(expressions.timbre)],
+        [0xB0 | channel, 10, to_7bit(pan)],
+        [0x90 | channel, key & 0x7f, to_7bit(velocity).max(1)],
+    ]
+}
+
+/// Note-off for a note sounding on a member channel
+pub fn note_off_message(channel: u8, key: u8) -> [u8; 3] {
+    [0x80 | channel, key & 0x7f, 0]
+}
+
+/// Scale 0..1 to a 7-bit MIDI value
+fn to_7bit(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 127.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_take_member_channels_in_turn() {
+        let mut mpe = MpeAllocator::default();
+        assert_eq!(mpe.note_on(60), (1, None));
+        assert_eq!(mpe.note_on(64), (2, None));
+        assert_eq!(mpe.note_off(60), Some(1));
+        assert_eq!(mpe.note_off(60), None);
+
+        // Freed channels are reused only after the others
+        assert_eq!(mpe.note_on(67), (3, None));
+        for key in 0..12 {
+            mpe.note_on(key);
+        }
+        assert_eq!(mpe.note_on(72), (1, None));
+        assert_eq!(mpe.note_off(64), Some(2));
+    }
+
+    #[test]
+    fn test_full_zone_takes_over_oldest_channel() {
+        let mut mpe = MpeAllocator::default();
+        for key in 0..15 {
+            mpe.note_on(key);
+        }
+        // The oldest note is handed back so it can be ended
+        assert_eq!(mpe.note_on(100), (1, Some(0)));
+        assert_eq!(mpe.note_off(0), None);
+        assert_eq!(mpe.note_off(100), Some(1));
+    }
+
+    #[test]
+    fn test_note_on_messages_carry_expressions() {
+        let expressions = NoteExpressions {
+            pitch_bend: 12.0,
+            pressure: 1.0,
+            timbre: 0.5,
+            pan: -1.0,
+        };
+        let [bend, pressure, timbre, pan, note_on] = note_on_messages(3, 60, 0.8, &expressions);
+        // +12 of 48 semitones: a quarter of the way up from the center
+        assert_eq!(bend, [0xE3, 0, 80]);
+        assert_eq!(pressure, [0xD3, 127, 0]);
+        assert_eq!(timbre, [0xB3, 74, 64]);
+        assert_eq!(pan, [0xB3, 10, 0]);
+        assert_eq!(note_on, [0x93, 60, 102]);
+        assert_eq!(note_off_message(3, 60), [0x83, 60, 0]);
+
+        let [center, ..] = note_on_messages(1, 60, 0.8, &NoteExpressions::default());
+        assert_eq!(center, [0xE1, 0, 64]);
+    }
+}
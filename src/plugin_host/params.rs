@@ -1,255 +1,258 @@
-//! Plugin parameter definitions - single source of truth.
+//! Plugin parameter storage.
 //!
-//! All plugin parameter metadata lives here. Other modules should
-//! reference these definitions rather than hardcoding values.
+//! Parameters are discovered from the plugin itself (see `PluginHost`) and
+//! stored in the project keyed by CLAP parameter id, holding the plain value
+//! the plugin receives in parameter events. Projects saved before discovery
+//! existed keyed the bundled SimpleSynth's parameters by name in display
+//! units; those are converted when the project is loaded.
 
-// Allow dead code - many items are defined for API completeness
-#![allow(dead_code)]
-
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
-/// Typed plugin parameter identifiers for the SimpleSynth plugin.
-/// Using an enum prevents typos and allows compile-time checking.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum PluginParamId {
-    Attack,
-    Decay,
-    Sustain,
-    Release,
-    Gain,
-    Waveform,
-}
+use super::PluginParam;
 
-impl PluginParamId {
-    pub const ALL: &'static [Self] = &[
-        Self::Attack,
-        Self::Decay,
-        Self::Sustain,
-        Self::Release,
-        Self::Gain,
-        Self::Waveform,
-    ];
-
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::Attack => "Attack",
-            Self::Decay => "Decay",
-            Self::Sustain => "Sustain",
-            Self::Release => "Release",
-            Self::Gain => "Gain",
-            Self::Waveform => "Waveform",
-        }
-    }
+/// CLAP id of the bundled SimpleSynth
+const SIMPLE_SYNTH_ID: &str = "com.termdaw.simple-synth";
 
-    /// Get the parameter definition for this ID
-    pub fn def(&self) -> &'static ParamDef {
-        ParamDef::get(*self).expect("all PluginParamIds have definitions")
-    }
-}
+/// Bundle name of the bundled SimpleSynth, for projects saved without a
+/// plugin id
+const SIMPLE_SYNTH_BUNDLE: &str = "Simple Synth";
 
-/// How to display the parameter value
-#[derive(Debug, Clone, Copy)]
-pub enum ParamDisplay {
-    /// Continuous value with unit suffix (e.g., "100 ms")
-    Continuous { unit: &'static str, decimals: u8 },
-    /// Discrete choices (e.g., waveform selector)
-    Discrete { choices: &'static [&'static str] },
+/// A SimpleSynth parameter as stored by older projects
+struct LegacyParam {
+    name: &'static str,
+    /// nih-plug Rabin fingerprint hash of the parameter's string id
+    clap_id: u32,
+    min: f64,
+    max: f64,
+    stepped: bool,
 }
 
-/// Complete parameter definition
-#[derive(Debug, Clone)]
-pub struct ParamDef {
-    pub id: PluginParamId,
-    pub clap_id: u32,
-    pub min: f32,
-    pub max: f32,
-    pub default: f32,
-    pub display: ParamDisplay,
+impl LegacyParam {
+    /// Convert a stored display value to the value the plugin expects:
+    /// nih-plug exposes continuous parameters normalized and stepped ones as
+    /// their index
+    fn to_clap_value(&self, value: f64) -> f64 {
+        if self.stepped {
+            value.round()
+        } else {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        }
+    }
 }
 
-/// Parameter definitions for the built-in SimpleSynth plugin.
-/// These CLAP IDs are nih-plug Rabin fingerprint hashes.
-pub static SIMPLE_SYNTH_PARAMS: &[ParamDef] = &[
-    ParamDef {
-        id: PluginParamId::Attack,
+/// SimpleSynth parameters as keyed by projects saved before discovery
+static LEGACY_SIMPLE_SYNTH_PARAMS: &[LegacyParam] = &[
+    LegacyParam {
+        name: "Attack",
         clap_id: 96920,
         min: 1.0,
         max: 5000.0,
-        default: 10.0,
-        display: ParamDisplay::Continuous {
-            unit: "ms",
-            decimals: 0,
-        },
+        stepped: false,
     },
-    ParamDef {
-        id: PluginParamId::Decay,
+    LegacyParam {
+        name: "Decay",
         clap_id: 99330,
         min: 1.0,
         max: 5000.0,
-        default: 100.0,
-        display: ParamDisplay::Continuous {
-            unit: "ms",
-            decimals: 0,
-        },
+        stepped: false,
     },
-    ParamDef {
-        id: PluginParamId::Sustain,
+    LegacyParam {
+        name: "Sustain",
         clap_id: 114257,
         min: 0.0,
         max: 1.0,
-        default: 0.7,
-        display: ParamDisplay::Continuous {
-            unit: "",
-            decimals: 2,
-        },
+        stepped: false,
     },
-    ParamDef {
-        id: PluginParamId::Release,
+    LegacyParam {
+        name: "Release",
         clap_id: 112793,
         min: 1.0,
         max: 5000.0,
-        default: 200.0,
-        display: ParamDisplay::Continuous {
-            unit: "ms",
-            decimals: 0,
-        },
+        stepped: false,
     },
-    ParamDef {
-        id: PluginParamId::Gain,
+    LegacyParam {
+        name: "Gain",
         clap_id: 3165055,
         min: 0.0,
         max: 1.0,
-        default: 0.5,
-        display: ParamDisplay::Continuous {
-            unit: "",
-            decimals: 2,
-        },
+        stepped: false,
     },
-    ParamDef {
-        id: PluginParamId::Waveform,
+    LegacyParam {
+        name: "Waveform",
         clap_id: 3642105,
         min: 0.0,
         max: 3.0,
-        default: 2.0,
-        display: ParamDisplay::Discrete {
-            choices: &["Sine", "Triangle", "Saw", "Square"],
-        },
+        stepped: true,
     },
 ];
 
-impl ParamDef {
-    /// Get parameter definition by ID
-    pub fn get(id: PluginParamId) -> Option<&'static ParamDef> {
-        SIMPLE_SYNTH_PARAMS.iter().find(|p| p.id == id)
-    }
-
-    /// Get parameter definition by CLAP ID
-    pub fn get_by_clap_id(clap_id: u32) -> Option<&'static ParamDef> {
-        SIMPLE_SYNTH_PARAMS.iter().find(|p| p.clap_id == clap_id)
-    }
-
-    /// Normalize value to 0.0-1.0 range for CLAP
-    pub fn normalize(&self, value: f32) -> f64 {
-        if matches!(self.id, PluginParamId::Waveform) {
-            // Discrete/enum param - send raw value
-            value.round() as f64
-        } else {
-            // Continuous param - normalize to 0-1
-            ((value - self.min) / (self.max - self.min)) as f64
-        }
-    }
-
-    /// Denormalize from 0.0-1.0 range
-    pub fn denormalize(&self, normalized: f64) -> f32 {
-        self.min + (normalized as f32) * (self.max - self.min)
-    }
-
-    /// Format value for display
-    pub fn format_value(&self, value: f32) -> String {
-        match &self.display {
-            ParamDisplay::Continuous { unit, decimals } => {
-                if *decimals == 0 {
-                    format!("{:.0}{}", value, unit)
-                } else {
-                    format!("{:.1$}{2}", value, *decimals as usize, unit)
-                }
+/// Split a channel's stored parameters into those keyed by CLAP id and those
+/// keyed by name
+pub fn split_stored_params(
+    stored: HashMap<String, f64>,
+) -> (HashMap<u32, f64>, HashMap<String, f64>) {
+    let mut params = HashMap::with_capacity(stored.len());
+    let mut named = HashMap::new();
+    for (key, value) in stored {
+        match key.parse::<u32>() {
+            Ok(clap_id) => {
+                params.insert(clap_id, value);
             }
-            ParamDisplay::Discrete { choices } => {
-                let idx = (value as usize).min(choices.len().saturating_sub(1));
-                choices[idx].to_string()
+            Err(_) => {
+                named.insert(key, value);
             }
         }
     }
+    (params, named)
+}
 
-    /// Check if this is a discrete (enum-like) parameter
-    pub fn is_discrete(&self) -> bool {
-        matches!(self.display, ParamDisplay::Discrete { .. })
+/// Whether a plugin channel hosts the bundled SimpleSynth
+pub fn is_simple_synth(path: &str, plugin_id: Option<&str>) -> bool {
+    match plugin_id {
+        Some(id) => id == SIMPLE_SYNTH_ID,
+        None => Path::new(path).file_stem().and_then(|s| s.to_str()) == Some(SIMPLE_SYNTH_BUNDLE),
     }
 }
 
-/// Build default parameter values for a new plugin channel
-pub fn default_plugin_params() -> HashMap<PluginParamId, f32> {
-    SIMPLE_SYNTH_PARAMS
-        .iter()
-        .map(|p| (p.id, p.default))
-        .collect()
+/// Convert SimpleSynth parameters keyed by name into CLAP id keys
+///
+/// Names SimpleSynth never had stay in `named`. A value already stored
+/// under the CLAP id wins over the named one.
+pub fn migrate_simple_synth_params(
+    params: &mut HashMap<u32, f64>,
+    named: &mut HashMap<String, f64>,
+) {
+    named.retain(
+        |name, value| match LEGACY_SIMPLE_SYNTH_PARAMS.iter().find(|p| p.name == name) {
+            Some(legacy) => {
+                params
+                    .entry(legacy.clap_id)
+                    .or_insert(legacy.to_clap_value(*value));
+                false
+            }
+            None => true,
+        },
+    );
 }
 
-/// Build PluginParam structs for the plugin editor UI
-pub fn build_editor_params(stored: &HashMap<PluginParamId, f32>) -> Vec<super::PluginParam> {
-    SIMPLE_SYNTH_PARAMS
-        .iter()
-        .enumerate()
-        .map(|(idx, def)| super::PluginParam {
-            id: idx as u32,
-            name: def.id.as_str().to_string(),
-            value: stored.get(&def.id).copied().unwrap_or(def.default),
-            min: def.min,
-            max: def.max,
-            default: def.default,
+/// Overlay stored values onto a plugin's discovered parameters for the editor
+///
+/// Stored values are clamped to the parameter's current range; parameters
+/// without a stored value keep the value the plugin reported.
+pub fn build_editor_params(
+    discovered: Vec<PluginParam>,
+    stored: &HashMap<u32, f64>,
+) -> Vec<PluginParam> {
+    discovered
+        .into_iter()
+        .map(|mut param| {
+            if let Some(&value) = stored.get(&param.id) {
+                param.value = value.clamp(param.min, param.max);
+            }
+            param
         })
         .collect()
 }
 
 /// Build initial plugin state for audio thread
-pub fn build_init_params(stored: &HashMap<PluginParamId, f32>) -> Vec<(u32, f64)> {
-    SIMPLE_SYNTH_PARAMS
-        .iter()
-        .map(|def| {
-            let value = stored.get(&def.id).copied().unwrap_or(def.default);
-            let normalized = def.normalize(value);
-            (def.clap_id, normalized)
-        })
-        .collect()
+pub fn build_init_params(stored: &HashMap<u32, f64>) -> Vec<(u32, f64)> {
+    stored.iter().map(|(&id, &value)| (id, value)).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_all_params_have_definitions() {
-        for id in PluginParamId::ALL {
-            assert!(ParamDef::get(*id).is_some());
+    fn stored(pairs: &[(&str, f64)]) -> HashMap<String, f64> {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    fn param(id: u32, min: f64, max: f64) -> PluginParam {
+        PluginParam {
+            id,
+            name: format!("P{}", id),
+            module: String::new(),
+            value: min,
+            min,
+            max,
+            default: min,
+            stepped: false,
         }
     }
 
     #[test]
-    fn test_normalize_continuous() {
-        let attack = ParamDef::get(PluginParamId::Attack).unwrap();
-        // min should normalize to 0
-        assert!((attack.normalize(1.0) - 0.0).abs() < 0.001);
-        // max should normalize to 1
-        assert!((attack.normalize(5000.0) - 1.0).abs() < 0.001);
+    fn test_split_keeps_clap_ids_and_names_apart() {
+        let (params, named) = split_stored_params(stored(&[
+            ("7", 0.25),
+            ("4000000000", 3.0),
+            ("Attack", 10.0),
+        ]));
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[&7], 0.25);
+        assert_eq!(params[&4_000_000_000], 3.0);
+        assert_eq!(named, stored(&[("Attack", 10.0)]));
     }
 
     #[test]
-    fn test_discrete_passthrough() {
-        let waveform = ParamDef::get(PluginParamId::Waveform).unwrap();
-        // Discrete values should pass through as-is
-        assert_eq!(waveform.normalize(0.0), 0.0);
-        assert_eq!(waveform.normalize(2.0), 2.0);
-        assert_eq!(waveform.normalize(3.0), 3.0);
+    fn test_legacy_simple_synth_params_migrate() {
+        let mut params = HashMap::from([(112793, 0.5)]);
+        let mut named = stored(&[
+            ("Attack", 5000.0),
+            ("Sustain", 0.7),
+            ("Waveform", 2.0),
+            ("Release", 1.0),
+            ("Bogus", 1.0),
+        ]);
+        migrate_simple_synth_params(&mut params, &mut named);
+        assert_eq!(params.len(), 4);
+        assert!((params[&96920] - 1.0).abs() < 1e-9);
+        assert!((params[&114257] - 0.7).abs() < 1e-9);
+        assert_eq!(params[&3642105], 2.0);
+        assert_eq!(params[&112793], 0.5, "the CLAP id value wins");
+        assert_eq!(named, stored(&[("Bogus", 1.0)]), "unknown names are kept");
+    }
+
+    #[test]
+    fn test_is_simple_synth() {
+        assert!(is_simple_synth("Simple Synth.clap", None));
+        assert!(is_simple_synth("other.clap", Some(SIMPLE_SYNTH_ID)));
+        assert!(!is_simple_synth("Simple Synth.clap", Some("com.acme.pad")));
+        assert!(!is_simple_synth("Pad.clap", None));
+    }
+
+    #[test]
+    fn test_editor_params_overlay_stored_values() {
+        let mut stored = HashMap::new();
+        stored.insert(1, 0.5);
+        stored.insert(2, 99.0);
+        stored.insert(3, 0.1);
+
+        let params = build_editor_params(vec![param(1, 0.0, 1.0), param(2, 0.0, 10.0)], &stored);
+        assert_eq!(params.len(), 2, "stored values for unknown ids are ignored");
+        assert_eq!(params[0].value, 0.5);
+        assert_eq!(params[1].value, 10.0);
+    }
+
+    #[test]
+    fn test_adjust_steps_and_clamps() {
+        let mut continuous = param(1, 0.0, 200.0);
+        continuous.adjust(1.0);
+        assert!((continuous.value - 2.0).abs() < 1e-9);
+        continuous.adjust(-5.0);
+        assert_eq!(continuous.value, 0.0);
+
+        let mut stepped = PluginParam {
+            stepped: true,
+            ..param(2, 0.0, 3.0)
+        };
+        stepped.adjust(0.1);
+        assert_eq!(stepped.value, 1.0);
+        stepped.value = 3.0;
+        stepped.adjust(1.0);
+        assert_eq!(stepped.value, 3.0);
     }
 }
@@ -0,0 +1,209 @@
+//! Plugin presets
+//!
+//! A host-side preset is a snapshot of a plugin channel: its parameter values
+//! and the plugin's state blob, saved per CLAP plugin id as
+//! `<dir>/<plugin id>/<name>.json`. Plugins that implement CLAP preset
+//! discovery also offer factory presets; those are listed with where the
+//! plugin loads them from and turn into a snapshot once the plugin has
+//! loaded one.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::effects::preset::{preset_file_name, PresetError};
+
+/// Where a plugin loads one of its factory presets from
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetLocation {
+    /// A preset file; `load_key` picks one preset in files holding several
+    File {
+        path: PathBuf,
+        load_key: Option<String>,
+    },
+    /// A preset built into the plugin
+    Plugin { load_key: Option<String> },
+}
+
+/// A named sound for one plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPreset {
+    /// Display name
+    pub name: String,
+    /// CLAP id of the plugin the preset is for
+    pub plugin_id: String,
+    /// Parameter values keyed by CLAP parameter id
+    #[serde(default)]
+    pub params: HashMap<u32, f64>,
+    /// Opaque plugin state from the CLAP state extension
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "super::state::base64_blob"
+    )]
+    pub state: Option<Vec<u8>>,
+    /// Where the plugin loads it from, for presets the plugin provides
+    /// (read-only; params and state are empty until it's loaded)
+    #[serde(skip)]
+    pub location: Option<PresetLocation>,
+}
+
+impl PluginPreset {
+    /// Capture a plugin's parameter values and state under `name`
+    pub fn snapshot(
+        name: &str,
+        plugin_id: &str,
+        params: HashMap<u32, f64>,
+        state: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            plugin_id: plugin_id.to_string(),
+            params,
+            state,
+            location: None,
+        }
+    }
+
+    /// A preset the plugin provides, loaded from `location`
+    pub fn factory(name: &str, plugin_id: &str, location: PresetLocation) -> Self {
+        Self {
+            location: Some(location),
+            ..Self::snapshot(name, plugin_id, HashMap::new(), None)
+        }
+    }
+
+    /// Whether the plugin provides the preset (read-only)
+    pub fn is_factory(&self) -> bool {
+        self.location.is_some()
+    }
+}
+
+/// The user's plugin presets
+#[derive(Debug, Clone)]
+pub struct PluginPresetLibrary {
+    /// One subdirectory per plugin id
+    dir: PathBuf,
+}
+
+impl Default for PluginPresetLibrary {
+    fn default() -> Self {
+        Self::new(crate::templates::user_presets_dir().join("Clap"))
+    }
+}
+
+impl PluginPresetLibrary {
+    /// Create a library over the given directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The user's presets for a plugin, sorted by name. Unreadable files are
+    /// skipped.
+    pub fn list(&self, plugin_id: &str) -> Vec<PluginPreset> {
+        let Some(dir) = self.plugin_dir(plugin_id) else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut presets: Vec<PluginPreset> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| fs::read_to_string(p).ok())
+            .filter_map(|json| serde_json::from_str::<PluginPreset>(&json).ok())
+            .filter(|p| p.plugin_id == plugin_id)
+            .collect();
+        presets.sort_by_key(|p| p.name.to_lowercase());
+        presets
+    }
+
+    /// Save a preset, overwriting one with the same name for the same plugin
+    ///
+    /// Returns the path of the written file.
+    pub fn save(&self, preset: &PluginPreset) -> Result<PathBuf, PresetError> {
+        let file_name = preset_file_name(&preset.name).ok_or(PresetError::EmptyName)?;
+        let dir = self
+            .plugin_dir(&preset.plugin_id)
+            .ok_or(PresetError::Unsupported("A plugin without an id"))?;
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(file_name);
+        let json = serde_json::to_string_pretty(preset)?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// Directory holding a plugin's presets
+    fn plugin_dir(&self, plugin_id: &str) -> Option<PathBuf> {
+        let name = preset_file_name(plugin_id)?;
+        Some(self.dir.join(Path::new(&name).file_stem()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn snapshot(name: &str, plugin_id: &str) -> PluginPreset {
+        let params = HashMap::from([(7, 0.25), (42, 3.0)]);
+        PluginPreset::snapshot(name, plugin_id, params, Some(vec![1, 2, 3]))
+    }
+
+    #[test]
+    fn test_save_and_list_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let library = PluginPresetLibrary::new(dir.path());
+        let preset = snapshot(" Warm Pad ", "com.acme.synth");
+
+        let path = library.save(&preset).unwrap();
+        assert!(path.ends_with("com_acme_synth/Warm Pad.json"));
+
+        let presets = library.list("com.acme.synth");
+        assert_eq!(presets, vec![preset]);
+        assert_eq!(presets[0].name, "Warm Pad");
+        assert_eq!(presets[0].state.as_deref(), Some(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn test_presets_are_kept_per_plugin() {
+        let dir = TempDir::new().unwrap();
+        let library = PluginPresetLibrary::new(dir.path());
+        library.save(&snapshot("Bass", "com.acme.synth")).unwrap();
+        library.save(&snapshot("Lead", "com.acme.synth")).unwrap();
+        library.save(&snapshot("Room", "com.acme/verb")).unwrap();
+
+        let names: Vec<_> = library
+            .list("com.acme.synth")
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["Bass", "Lead"]);
+        assert_eq!(library.list("com.acme/verb").len(), 1);
+        assert!(library.list("com.other").is_empty());
+    }
+
+    #[test]
+    fn test_save_rejects_empty_name() {
+        let dir = TempDir::new().unwrap();
+        let library = PluginPresetLibrary::new(dir.path());
+        assert!(matches!(
+            library.save(&snapshot("  ", "com.acme.synth")),
+            Err(PresetError::EmptyName)
+        ));
+    }
+
+    #[test]
+    fn test_factory_presets_are_read_only() {
+        let location = PresetLocation::Plugin {
+            load_key: Some("init".to_string()),
+        };
+        let preset = PluginPreset::factory("Init", "com.acme.synth", location);
+        assert!(preset.is_factory());
+        assert!(!snapshot("Mine", "com.acme.synth").is_factory());
+    }
+}
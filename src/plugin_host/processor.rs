@@ -0,0 +1,122 @@
+//! Audio-thread side of a plugin, wherever the plugin actually runs
+//!
+//! A plugin either runs in this process ([`ClapProcessor`]) or in a sandbox
+//! child process ([`SandboxedProcessor`]). The engine only sees
+//! [`ActivePluginProcessor`], which forwards to whichever it is.
+
+use super::host::{ClapProcessor, MidiNote, ParamChange};
+use super::sandbox::SandboxedProcessor;
+use super::transport::PluginTransport;
+
+/// An active plugin processor that can process audio.
+/// Must be used on the audio thread.
+pub struct ActivePluginProcessor {
+    backend: Backend,
+}
+
+enum Backend {
+    InProcess(Box<ClapProcessor>),
+    Sandboxed(SandboxedProcessor),
+}
+
+impl From<ClapProcessor> for ActivePluginProcessor {
+    fn from(processor: ClapProcessor) -> Self {
+        Self {
+            backend: Backend::InProcess(Box::new(processor)),
+        }
+    }
+}
+
+impl From<SandboxedProcessor> for ActivePluginProcessor {
+    fn from(processor: SandboxedProcessor) -> Self {
+        Self {
+            backend: Backend::Sandboxed(processor),
+        }
+    }
+}
+
+impl ActivePluginProcessor {
+    /// Process audio through the plugin.
+    /// Takes MIDI notes, parameter changes and the song position at the start
+    /// of the block, and returns stereo audio output.
+    pub fn process(
+        &mut self,
+        notes: &[MidiNote],
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
+    ) {
+        match &mut self.backend {
+            Backend::InProcess(p) => p.process(notes, params, transport, output_left, output_right),
+            Backend::Sandboxed(p) => p.process(notes, params, transport, output_left, output_right),
+        }
+    }
+
+    /// Process a stereo buffer in place through an audio effect plugin.
+    /// The buffer is fed to the plugin's input port and replaced by its output.
+    pub fn process_effect(
+        &mut self,
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        match &mut self.backend {
+            Backend::InProcess(p) => p.process_effect(params, transport, left, right),
+            Backend::Sandboxed(p) => p.process_effect(params, transport, left, right),
+        }
+    }
+
+    /// Notes the plugin sent out during the last process call
+    pub fn output_notes(&self) -> &[MidiNote] {
+        match &self.backend {
+            Backend::InProcess(p) => p.output_notes(),
+            Backend::Sandboxed(p) => p.output_notes(),
+        }
+    }
+
+    /// Parameter changes the plugin made itself during the last process call
+    pub fn output_params(&self) -> &[ParamChange] {
+        match &self.backend {
+            Backend::InProcess(p) => p.output_params(),
+            Backend::Sandboxed(p) => p.output_params(),
+        }
+    }
+
+    /// Length of the plugin's tail in frames (`u32::MAX` if infinite)
+    pub fn tail_frames(&mut self) -> u32 {
+        match &mut self.backend {
+            Backend::InProcess(p) => p.tail_frames(),
+            Backend::Sandboxed(p) => p.tail_frames(),
+        }
+    }
+
+    /// Processing latency in frames, as reported when the plugin was activated
+    pub fn latency_frames(&self) -> u32 {
+        match &self.backend {
+            Backend::InProcess(p) => p.latency_frames(),
+            Backend::Sandboxed(p) => p.latency_frames(),
+        }
+    }
+
+    /// Wait for every block however long it takes, for offline renders.
+    /// Sandboxed plugins otherwise give up on a block once its time is up;
+    /// in-process plugins always run to completion.
+    pub fn set_offline(&mut self) {
+        match &mut self.backend {
+            Backend::InProcess(_) => {}
+            Backend::Sandboxed(p) => p.set_offline(),
+        }
+    }
+
+    /// True the first time it's called after the plugin crashed or hung.
+    /// A crashed plugin outputs silence (instruments) or passes its input
+    /// through (effects) from then on; in-process plugins can't be caught.
+    pub fn take_crash(&mut self) -> bool {
+        match &mut self.backend {
+            Backend::InProcess(_) => false,
+            Backend::Sandboxed(p) => p.take_crash(),
+        }
+    }
+}
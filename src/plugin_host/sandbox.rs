@@ -0,0 +1,998 @@
+//! Out-of-process plugin sandbox
+//!
+//! With sandboxing on, every plugin instance runs in its own
+//! `termdaw plugin-host` child process and talks to the app over the child's
+//! stdin/stdout (see [`ipc`](super::ipc)). The child moves the stdout pipe to
+//! a private descriptor before loading the plugin, so whatever the plugin
+//! prints can't end up in the middle of a frame. A plugin that crashes only takes
+//! its child down; one that hangs is killed after a timeout. Either way the
+//! engine sees [`take_crash`](super::ActivePluginProcessor::take_crash) and mutes it instead of
+//! the whole session going down with the plugin.
+//!
+//! The audio thread never waits on a host for longer than the block it is
+//! rendering: a block whose reply is late plays as if the plugin had failed,
+//! and the late reply is thrown away when it arrives.
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
+
+use super::host::{ClapProcessor, MidiNote, ParamChange, PluginHost};
+use super::ipc::{read_frame, write_frame, ProcessBlock, Request, Response};
+use super::presets::PluginPreset;
+use super::transport::PluginTransport;
+use super::{
+    InspectedPlugin, LoadedPlugin, ParamFormatter, PluginInfo, PluginLoadError, PluginLoader,
+    StateSaver,
+};
+
+/// How long a child gets to load its plugin and report in
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a main-thread request (state, activation, formatting) may take
+const MAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a block may take before the plugin is considered hung. Well
+/// past any real buffer length, so only a plugin that's stuck trips it.
+/// Real-time blocks stop waiting long before this; offline renders wait it out.
+const PROCESS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How often a host's main thread services its plugin while no main-thread
+/// request comes in
+const SERVICE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Audio replies the audio thread can hand back before it has to free one
+/// itself (the reader thread empties the queue with every frame it reads)
+const SPENT_CAPACITY: usize = 8;
+
+/// Loads every plugin into its own sandboxed child process
+pub struct SandboxPluginLoader {
+    /// Executable started as the plugin host (this binary)
+    exe: PathBuf,
+}
+
+impl SandboxPluginLoader {
+    /// Loader that starts the running executable as the plugin host
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            exe: std::env::current_exe()?,
+        })
+    }
+
+    /// Start a plugin host for `path` and wait for it to report the plugin
+    fn spawn(
+        &self,
+        path: &Path,
+        plugin_id: Option<&str>,
+        sample_rate: f64,
+        buffer_size: u32,
+    ) -> Result<(Arc<Connection>, PluginInfo), PluginLoadError> {
+        let mut command = Command::new(&self.exe);
+        command
+            .arg("plugin-host")
+            .arg(path)
+            .arg("--sample-rate")
+            .arg(sample_rate.to_string())
+            .arg("--buffer-size")
+            .arg(buffer_size.to_string());
+        if let Some(id) = plugin_id {
+            command.arg("--plugin-id").arg(id);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Plugin chatter would draw over the UI
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                PluginLoadError::LoadFailed(format!("Failed to start plugin host: {}", e))
+            })?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let connection = Connection::new(Box::new(stdin), Box::new(stdout), Some(child));
+
+        match connection.wait_main(STARTUP_TIMEOUT) {
+            Some(Response::Ready(info)) => Ok((connection, info)),
+            Some(Response::Error(e)) => Err(PluginLoadError::LoadFailed(e)),
+            _ => Err(PluginLoadError::LoadFailed(
+                "Plugin host exited during startup".to_string(),
+            )),
+        }
+    }
+}
+
+impl PluginLoader for SandboxPluginLoader {
+    fn load_plugin(
+        &self,
+        path: &Path,
+        plugin_id: Option<&str>,
+        sample_rate: f64,
+        buffer_size: u32,
+        state: Option<&[u8]>,
+    ) -> Result<LoadedPlugin, PluginLoadError> {
+        let (connection, info) = self.spawn(path, plugin_id, sample_rate, buffer_size)?;
+
+        // Same as in-process: a rejected state doesn't keep the plugin out
+        let state_error = state.and_then(|state| {
+            match connection.request_main(&Request::LoadState(state.to_vec())) {
+                Some(Response::Error(e)) => Some(e),
+                _ => None,
+            }
+        });
+
+        let latency = match connection.request_main(&Request::Activate) {
+            Some(Response::Activated { latency }) => latency,
+            Some(Response::Error(e)) => return Err(PluginLoadError::ActivationFailed(e)),
+            _ => {
+                return Err(PluginLoadError::ActivationFailed(
+                    "Plugin host stopped responding".to_string(),
+                ))
+            }
+        };
+
+        Ok(LoadedPlugin {
+            processor: SandboxedProcessor::new(Arc::clone(&connection), latency, sample_rate)
+                .into(),
+            info,
+            instance: Box::new(SandboxInstance { connection }),
+            state_error,
+        })
+    }
+
+    fn inspect_plugin(
+        &self,
+        path: &Path,
+        plugin_id: Option<&str>,
+    ) -> Result<InspectedPlugin, PluginLoadError> {
+        // The instance is never activated, so the audio settings don't matter
+        let (connection, info) = self.spawn(path, plugin_id, 44_100.0, 512)?;
+        let presets = match connection.request_main(&Request::FactoryPresets) {
+            Some(Response::Presets(presets)) => presets,
+            _ => Vec::new(),
+        };
+        Ok(InspectedPlugin {
+            info,
+            formatter: Box::new(SandboxInstance { connection }),
+            presets,
+        })
+    }
+}
+
+/// Pipes to one plugin host process
+///
+/// Responses are read on a background thread and sorted onto an audio and a
+/// main-thread queue. The host answers audio requests on a thread of its own
+/// (see [`serve`]), so a block doesn't wait behind a state save, and the
+/// audio thread skips a block rather than wait for the main thread to finish
+/// writing a request.
+struct Connection {
+    writer: Mutex<Box<dyn Write + Send>>,
+    /// Replies to `Process` and `TailFrames`
+    audio_rx: Receiver<Response>,
+    /// Replies to everything else
+    main_rx: Receiver<Response>,
+    /// Audio replies the audio thread is done with, freed on the reader thread
+    spent_tx: Sender<Response>,
+    /// Cleared when the child exits, breaks the protocol or times out
+    alive: Arc<AtomicBool>,
+    /// Set when the plugin asks to be restarted
+    restart_requested: Arc<AtomicBool>,
+    /// The process itself (None for in-memory test hosts), reaped by the
+    /// reader thread once its output closes
+    child: Arc<Mutex<Option<Child>>>,
+    /// Longest a block may take before the host is killed
+    process_timeout: Duration,
+}
+
+impl Connection {
+    fn new(
+        writer: Box<dyn Write + Send>,
+        mut reader: Box<dyn Read + Send>,
+        child: Option<Child>,
+    ) -> Arc<Self> {
+        let (audio_tx, audio_rx) = unbounded();
+        let (main_tx, main_rx) = unbounded();
+        let (spent_tx, spent_rx) = bounded::<Response>(SPENT_CAPACITY);
+        let alive = Arc::new(AtomicBool::new(true));
+        let restart_requested = Arc::new(AtomicBool::new(false));
+        let child = Arc::new(Mutex::new(child));
+
+        let reader_alive = Arc::clone(&alive);
+        let reader_restart = Arc::clone(&restart_requested);
+        let reader_child = Arc::clone(&child);
+        thread::spawn(move || {
+            while let Ok(Some(frame)) = read_frame(&mut reader) {
+                spent_rx.try_iter().for_each(drop);
+                let Ok(response) = Response::decode(&frame) else {
+                    break;
+                };
+                let tx = match response {
+                    Response::Processed { .. } | Response::Tail(_) => &audio_tx,
+                    Response::RestartRequested => {
+                        reader_restart.store(true, Ordering::SeqCst);
+                        continue;
+                    }
+                    _ => &main_tx,
+                };
+                if tx.send(response).is_err() {
+                    break;
+                }
+            }
+            reader_alive.store(false, Ordering::SeqCst);
+
+            // Waiting for the process here keeps it off whichever thread
+            // drops the connection, which may be the audio thread
+            let child = reader_child.lock().ok().and_then(|mut child| child.take());
+            if let Some(mut child) = child {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        });
+
+        Arc::new(Self {
+            writer: Mutex::new(writer),
+            audio_rx,
+            main_rx,
+            spent_tx,
+            alive,
+            restart_requested,
+            child,
+            process_timeout: PROCESS_TIMEOUT,
+        })
+    }
+
+    fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Send an encoded request and wait for its reply on `rx`. A host that
+    /// can't be written to or doesn't answer in time is killed; returns None then.
+    fn request(
+        &self,
+        payload: &[u8],
+        rx: &Receiver<Response>,
+        timeout: Duration,
+    ) -> Option<Response> {
+        if !self.is_alive() {
+            return None;
+        }
+        let sent = match self.writer.lock() {
+            Ok(mut writer) => write_frame(&mut *writer, payload).is_ok(),
+            Err(_) => false,
+        };
+        if sent {
+            if let Ok(response) = rx.recv_timeout(timeout) {
+                return Some(response);
+            }
+        }
+        self.kill();
+        None
+    }
+
+    /// Send an encoded audio request; false if it wasn't sent. Unless
+    /// `wait` is set, a writer busy with a main-thread request means the
+    /// block is skipped.
+    fn send_audio(&self, payload: &[u8], wait: bool) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+        let writer = if wait {
+            self.writer.lock().ok()
+        } else {
+            self.writer.try_lock().ok()
+        };
+        let Some(mut writer) = writer else {
+            return false;
+        };
+        if write_frame(&mut *writer, payload).is_err() {
+            drop(writer);
+            self.kill();
+            return false;
+        }
+        true
+    }
+
+    fn request_main(&self, request: &Request) -> Option<Response> {
+        let mut payload = Vec::new();
+        request.encode(&mut payload);
+        self.request(&payload, &self.main_rx, MAIN_TIMEOUT)
+    }
+
+    /// Hand an audio reply back to the reader thread to be freed there
+    fn recycle(&self, response: Response) {
+        // With the queue full, it's freed here after all
+        let _ = self.spent_tx.try_send(response);
+    }
+
+    /// Wait for an unprompted main-thread message (the startup report)
+    fn wait_main(&self, timeout: Duration) -> Option<Response> {
+        let response = self.main_rx.recv_timeout(timeout).ok();
+        if response.is_none() {
+            self.kill();
+        }
+        response
+    }
+
+    fn kill(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        if let Ok(mut child) = self.child.lock() {
+            if let Some(child) = child.as_mut() {
+                let _ = child.kill();
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Only signals the process; the reader thread reaps it
+        self.kill();
+    }
+}
+
+/// Audio-thread side of a sandboxed plugin
+pub struct SandboxedProcessor {
+    connection: Arc<Connection>,
+    /// Processing latency reported at activation (in frames)
+    latency: u32,
+    /// Sample rate the plugin was activated at, to time blocks
+    sample_rate: f64,
+    /// Wait for every reply (offline renders) instead of giving up when the
+    /// block's time is up
+    offline: bool,
+    /// When the request whose reply is still outstanding was sent; the next
+    /// request waits until that reply is in and discarded
+    late_since: Option<Instant>,
+    /// Encoded request, reused so blocks stop allocating once it has grown
+    payload: Vec<u8>,
+    /// Notes the plugin sent out during the last process call
+    output_notes: Vec<MidiNote>,
+    /// Parameter changes the plugin made during the last process call
+    output_params: Vec<ParamChange>,
+    /// Whether the crash has been reported already
+    crash_reported: bool,
+}
+
+impl SandboxedProcessor {
+    fn new(connection: Arc<Connection>, latency: u32, sample_rate: f64) -> Self {
+        Self {
+            connection,
+            latency,
+            sample_rate,
+            offline: false,
+            late_since: None,
+            payload: Vec::new(),
+            output_notes: Vec::new(),
+            output_params: Vec::new(),
+            crash_reported: false,
+        }
+    }
+
+    /// Send the request encoded in `payload` and wait up to `budget` for
+    /// its reply (the full hang timeout offline)
+    ///
+    /// Returns None if the request couldn't be sent or its reply didn't come
+    /// in time. A late reply is discarded by the next request, and the host
+    /// is killed once a reply is `process_timeout` late.
+    fn request(&mut self, budget: Duration) -> Option<Response> {
+        let now = Instant::now();
+        let timeout = self.connection.process_timeout;
+        let deadline = now + if self.offline { timeout } else { budget };
+        let audio_rx = &self.connection.audio_rx;
+
+        if let Some(sent_at) = self.late_since {
+            match audio_rx.recv_deadline(deadline.min(sent_at + timeout)) {
+                Ok(stale) => {
+                    self.connection.recycle(stale);
+                    self.late_since = None;
+                }
+                Err(_) => {
+                    if Instant::now() >= sent_at + timeout {
+                        self.connection.kill();
+                    }
+                    return None;
+                }
+            }
+        }
+
+        if !self.connection.send_audio(&self.payload, self.offline) {
+            return None;
+        }
+        match audio_rx.recv_deadline(deadline) {
+            Ok(response) => Some(response),
+            Err(_) if self.offline => {
+                self.connection.kill();
+                None
+            }
+            Err(_) => {
+                self.late_since = Some(now);
+                None
+            }
+        }
+    }
+
+    /// Run the block encoded in `payload` in the host and copy its output to
+    /// `left` and `right`, which are left alone if it failed or was late
+    fn run(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.output_notes.clear();
+        self.output_params.clear();
+        let frames = left.len().min(right.len());
+        let budget = Duration::from_secs_f64(frames as f64 / self.sample_rate);
+        let Some(response) = self.request(budget) else {
+            return;
+        };
+        if let Response::Processed {
+            output: [out_left, out_right],
+            notes,
+            params,
+        } = &response
+        {
+            copy_prefix(out_left, left);
+            copy_prefix(out_right, right);
+            self.output_notes.extend_from_slice(notes);
+            self.output_params.extend_from_slice(params);
+        }
+        self.connection.recycle(response);
+    }
+
+    pub fn process(
+        &mut self,
+        notes: &[MidiNote],
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
+    ) {
+        let frames = output_left.len().min(output_right.len());
+        output_left.fill(0.0);
+        output_right.fill(0.0);
+        ProcessBlock {
+            frames: frames as u32,
+            transport,
+            notes,
+            params,
+            input: None,
+        }
+        .encode(&mut self.payload);
+        self.run(output_left, output_right);
+    }
+
+    /// Effects pass their input through unchanged if the host fails
+    pub fn process_effect(
+        &mut self,
+        params: &[ParamChange],
+        transport: &PluginTransport,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        let frames = left.len().min(right.len());
+        ProcessBlock {
+            frames: frames as u32,
+            transport,
+            notes: &[],
+            params,
+            input: Some([&left[..frames], &right[..frames]]),
+        }
+        .encode(&mut self.payload);
+        self.run(left, right);
+    }
+
+    pub fn output_notes(&self) -> &[MidiNote] {
+        &self.output_notes
+    }
+
+    pub fn output_params(&self) -> &[ParamChange] {
+        &self.output_params
+    }
+
+    pub fn tail_frames(&mut self) -> u32 {
+        Request::TailFrames.encode(&mut self.payload);
+        match self.request(self.connection.process_timeout) {
+            Some(Response::Tail(frames)) => frames,
+            Some(response) => {
+                self.connection.recycle(response);
+                0
+            }
+            None => 0,
+        }
+    }
+
+    pub fn set_offline(&mut self) {
+        self.offline = true;
+    }
+
+    pub fn latency_frames(&self) -> u32 {
+        self.latency
+    }
+
+    pub fn take_crash(&mut self) -> bool {
+        if self.crash_reported || self.connection.is_alive() {
+            return false;
+        }
+        self.crash_reported = true;
+        true
+    }
+}
+
+/// Copy as much of `src` as fits into `dst`
+fn copy_prefix(src: &[f32], dst: &mut [f32]) {
+    let len = src.len().min(dst.len());
+    dst[..len].copy_from_slice(&src[..len]);
+}
+
+/// Main-thread side of a sandboxed plugin
+struct SandboxInstance {
+    connection: Arc<Connection>,
+}
+
+impl StateSaver for SandboxInstance {
+    fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+        match self.connection.request_main(&Request::SaveState) {
+            Some(Response::State(state)) => Ok(state),
+            Some(Response::Error(e)) => Err(e),
+            _ => Err("Plugin host is not running".to_string()),
+        }
+    }
+
+    fn service_main_thread(&mut self) -> bool {
+        // The host services its plugin itself and only reports restarts
+        self.connection
+            .restart_requested
+            .swap(false, Ordering::SeqCst)
+    }
+}
+
+impl ParamFormatter for SandboxInstance {
+    fn format(&mut self, param_id: u32, value: f64) -> Option<String> {
+        match self
+            .connection
+            .request_main(&Request::FormatValue { param_id, value })
+        {
+            Some(Response::Text(text)) => text,
+            _ => None,
+        }
+    }
+
+    fn load_preset(&mut self, preset: &PluginPreset) -> Result<PluginPreset, String> {
+        match self
+            .connection
+            .request_main(&Request::LoadPreset(preset.clone()))
+        {
+            Some(Response::Preset(snapshot)) => Ok(snapshot),
+            Some(Response::Error(e)) => Err(e),
+            _ => Err("Plugin host is not running".to_string()),
+        }
+    }
+}
+
+/// Entry point of a `termdaw plugin-host` child: load the plugin at `path`
+/// (`plugin_id` from a multi-plugin bundle) and serve requests from stdin
+/// until the app closes the pipe
+///
+/// Blocks are processed on a thread of their own, so a slow state save or
+/// preset load on the plugin's main thread doesn't hold up the app's audio.
+pub fn serve(
+    path: &Path,
+    plugin_id: Option<&str>,
+    sample_rate: f64,
+    buffer_size: u32,
+) -> io::Result<()> {
+    let output = Mutex::new(take_stdout()?);
+    let mut payload = Vec::new();
+
+    let mut host = match PluginHost::load(path, plugin_id, sample_rate, buffer_size) {
+        Ok(host) => host,
+        Err(e) => return send(&output, &mut payload, Response::Error(e)),
+    };
+    send(&output, &mut payload, Response::Ready(host.info().clone()))?;
+
+    let (audio_tx, audio_rx) = unbounded();
+    let (main_tx, main_rx) = unbounded();
+    let (processor_tx, processor_rx) = unbounded();
+    thread::scope(|scope| {
+        let reader = scope.spawn(|| route_requests(audio_tx, main_tx));
+        let audio = scope.spawn(|| serve_audio(&audio_rx, &processor_rx, &output));
+        let served = serve_main(&mut host, &main_rx, &processor_tx, &output);
+
+        let routed = reader.join().expect("request reader panicked");
+        if let Some(processor) = audio.join().expect("audio thread panicked") {
+            host.deactivate(processor);
+        }
+        served.and(routed)
+    })
+}
+
+/// Read requests from stdin and pass each to the thread that serves it
+fn route_requests(audio: Sender<Request>, main: Sender<Request>) -> io::Result<()> {
+    let mut input = io::stdin().lock();
+    while let Some(frame) = read_frame(&mut input)? {
+        let request = Request::decode(&frame)?;
+        let tx = match request {
+            Request::Process { .. } | Request::TailFrames => &audio,
+            _ => &main,
+        };
+        if tx.send(request).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Serve main-thread requests, servicing the plugin in between; returns
+/// once the requests stop
+fn serve_main(
+    host: &mut PluginHost,
+    requests: &Receiver<Request>,
+    processor_tx: &Sender<ClapProcessor>,
+    output: &Mutex<impl Write>,
+) -> io::Result<()> {
+    let mut payload = Vec::new();
+    loop {
+        let request = match requests.recv_timeout(SERVICE_INTERVAL) {
+            Ok(request) => Some(request),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        if let Some(request) = request {
+            let response = match request {
+                Request::LoadState(data) => match host.load_state(&data) {
+                    Ok(()) => Response::Done,
+                    Err(e) => Response::Error(e),
+                },
+                Request::Activate => match host.activate() {
+                    Ok(active) => {
+                        let latency = active.latency_frames();
+                        // Handed over before the app hears back, so its
+                        // first block finds the processor there
+                        let _ = processor_tx.send(active);
+                        Response::Activated { latency }
+                    }
+                    Err(e) => Response::Error(e),
+                },
+                Request::SaveState => match host.save_state() {
+                    Ok(state) => Response::State(state),
+                    Err(e) => Response::Error(e),
+                },
+                Request::FormatValue { param_id, value } => {
+                    Response::Text(host.value_to_text(param_id, value))
+                }
+                Request::FactoryPresets => Response::Presets(host.factory_presets()),
+                Request::LoadPreset(preset) => match host.load_preset(&preset) {
+                    Ok(snapshot) => Response::Preset(snapshot),
+                    Err(e) => Response::Error(e),
+                },
+                Request::Process { .. } | Request::TailFrames => {
+                    Response::Error("Audio request on the main thread".to_string())
+                }
+            };
+            send(output, &mut payload, response)?;
+        }
+
+        // This thread is the plugin's main thread
+        if host.service_main_thread() {
+            send(output, &mut payload, Response::RestartRequested)?;
+        }
+    }
+}
+
+/// Serve `Process` and `TailFrames` requests until they stop, returning the
+/// active processor (if any) to be deactivated
+fn serve_audio(
+    requests: &Receiver<Request>,
+    processors: &Receiver<ClapProcessor>,
+    output: &Mutex<impl Write>,
+) -> Option<ClapProcessor> {
+    let mut processor: Option<ClapProcessor> = None;
+    let mut payload = Vec::new();
+    for request in requests {
+        if let Ok(active) = processors.try_recv() {
+            processor = Some(active);
+        }
+        let response = match request {
+            Request::Process {
+                frames,
+                transport,
+                notes,
+                params,
+                input,
+            } => match processor.as_mut() {
+                Some(processor) => {
+                    let frames = frames as usize;
+                    let output = match input {
+                        Some([mut left, mut right]) => {
+                            left.resize(frames, 0.0);
+                            right.resize(frames, 0.0);
+                            processor.process_effect(&params, &transport, &mut left, &mut right);
+                            [left, right]
+                        }
+                        None => {
+                            let (mut left, mut right) = (vec![0.0; frames], vec![0.0; frames]);
+                            processor.process(&notes, &params, &transport, &mut left, &mut right);
+                            [left, right]
+                        }
+                    };
+                    Response::Processed {
+                        output,
+                        notes: processor.output_notes().to_vec(),
+                        params: processor.output_params().to_vec(),
+                    }
+                }
+                None => Response::Error("Plugin is not active".to_string()),
+            },
+            Request::TailFrames => {
+                Response::Tail(processor.as_mut().map_or(0, |p| p.tail_frames()))
+            }
+            _ => Response::Error("Main-thread request on the audio thread".to_string()),
+        };
+        // A closed output means the app is gone; the requests stop next
+        if send(output, &mut payload, response).is_err() {
+            break;
+        }
+    }
+    processor
+}
+
+/// Take the stdout pipe for the protocol and point stdout at stderr (which
+/// the app discards), so a plugin writing to stdout can't corrupt the frames
+#[cfg(unix)]
+fn take_stdout() -> io::Result<impl Write + Send> {
+    use std::os::fd::{AsFd, AsRawFd};
+
+    let stdout = io::stdout();
+    let pipe = stdout.as_fd().try_clone_to_owned()?;
+    // SAFETY: both descriptors are open for the life of the process, and
+    // nothing has been written to stdout yet
+    if unsafe { libc::dup2(io::stderr().as_raw_fd(), stdout.as_raw_fd()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(io::BufWriter::new(std::fs::File::from(pipe)))
+}
+
+/// Elsewhere there's no portable way to move the pipe; keep using stdout
+#[cfg(not(unix))]
+fn take_stdout() -> io::Result<impl Write + Send> {
+    Ok(io::stdout())
+}
+
+fn send(output: &Mutex<impl Write>, payload: &mut Vec<u8>, response: Response) -> io::Result<()> {
+    response.encode(payload);
+    let mut output = output
+        .lock()
+        .map_err(|_| io::Error::other("plugin host output poisoned"))?;
+    write_frame(&mut *output, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A connection to a fake host on a thread, over real pipes. The fake
+    /// answers `Process` requests with a constant until `blocks` runs out,
+    /// then does whatever `after` does.
+    fn fake_host(blocks: usize, after: impl FnOnce() + Send + 'static) -> Arc<Connection> {
+        let (app_reader, host_writer) = io::pipe().unwrap();
+        let (mut host_reader, app_writer) = io::pipe().unwrap();
+        thread::spawn(move || {
+            let host_writer = Mutex::new(host_writer);
+            let mut payload = Vec::new();
+            for _ in 0..blocks {
+                let Ok(Some(frame)) = read_frame(&mut host_reader) else {
+                    return;
+                };
+                let Ok(Request::Process { frames, .. }) = Request::decode(&frame) else {
+                    return;
+                };
+                let output = [vec![0.5; frames as usize], vec![0.5; frames as usize]];
+                let response = Response::Processed {
+                    output,
+                    notes: Vec::new(),
+                    params: Vec::new(),
+                };
+                send(&host_writer, &mut payload, response).unwrap();
+            }
+            after();
+            drop(host_writer);
+            drop(host_reader);
+        });
+        Connection::new(Box::new(app_writer), Box::new(app_reader), None)
+    }
+
+    /// Sample rate that gives the tests' 8-frame blocks 200 ms to come back
+    const SLOW_RATE: f64 = 40.0;
+
+    fn process(processor: &mut SandboxedProcessor) -> Vec<f32> {
+        let (mut left, mut right) = (vec![1.0; 8], vec![1.0; 8]);
+        let transport = PluginTransport::default();
+        processor.process(&[], &[], &transport, &mut left, &mut right);
+        left
+    }
+
+    #[test]
+    fn test_crashed_host_is_muted_and_reported_once() {
+        let (crash_tx, crash_rx) = unbounded::<()>();
+        let connection = fake_host(1, move || {
+            let _ = crash_rx.recv();
+        });
+        let mut processor = SandboxedProcessor::new(connection, 0, SLOW_RATE);
+
+        assert_eq!(process(&mut processor), vec![0.5; 8]);
+        assert!(!processor.take_crash());
+
+        // The host exits: silence, and the crash surfaces exactly once
+        drop(crash_tx);
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+        assert!(processor.take_crash());
+        assert!(!processor.take_crash());
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+    }
+
+    #[test]
+    fn test_hung_host_times_out() {
+        let (release_tx, release_rx) = unbounded::<()>();
+        let connection = fake_host(0, move || {
+            let _ = release_rx.recv();
+        });
+        let mut connection = Arc::try_unwrap(connection).ok().unwrap();
+        connection.process_timeout = Duration::from_millis(20);
+        let mut processor = SandboxedProcessor::new(Arc::new(connection), 0, 8000.0);
+
+        // Late blocks are silent; the host is only given up on once the
+        // reply is a whole timeout late
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+        assert!(!processor.take_crash());
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+        assert!(processor.take_crash());
+        drop(release_tx);
+    }
+
+    #[test]
+    fn test_late_reply_is_silent_and_discarded() {
+        let (app_reader, host_writer) = io::pipe().unwrap();
+        let (mut host_reader, app_writer) = io::pipe().unwrap();
+        thread::spawn(move || {
+            let host_writer = Mutex::new(host_writer);
+            for (value, delay) in [(0.25, 50), (0.5, 0)] {
+                let Ok(Some(frame)) = read_frame(&mut host_reader) else {
+                    return;
+                };
+                let Ok(Request::Process { frames, .. }) = Request::decode(&frame) else {
+                    return;
+                };
+                thread::sleep(Duration::from_millis(delay));
+                let response = Response::Processed {
+                    output: [vec![value; frames as usize], vec![value; frames as usize]],
+                    notes: Vec::new(),
+                    params: Vec::new(),
+                };
+                send(&host_writer, &mut Vec::new(), response).unwrap();
+            }
+            let _ = read_frame(&mut host_reader);
+        });
+        let connection = Connection::new(Box::new(app_writer), Box::new(app_reader), None);
+        // 8 frames at 8 kHz: a millisecond to answer
+        let mut processor = SandboxedProcessor::new(connection, 0, 8000.0);
+
+        let started = Instant::now();
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+        assert!(started.elapsed() < Duration::from_millis(40));
+
+        thread::sleep(Duration::from_millis(80));
+        processor.sample_rate = SLOW_RATE;
+        assert_eq!(process(&mut processor), vec![0.5; 8], "not the late reply");
+        assert!(!processor.take_crash());
+    }
+
+    #[test]
+    fn test_block_is_skipped_while_writer_is_busy() {
+        let (done_tx, done_rx) = unbounded::<()>();
+        let connection = fake_host(1, move || {
+            let _ = done_rx.recv();
+        });
+        let mut processor = SandboxedProcessor::new(Arc::clone(&connection), 0, SLOW_RATE);
+
+        let writer = connection.writer.lock().unwrap();
+        let started = Instant::now();
+        assert_eq!(process(&mut processor), vec![0.0; 8]);
+        assert!(started.elapsed() < Duration::from_millis(100));
+        drop(writer);
+
+        assert_eq!(process(&mut processor), vec![0.5; 8]);
+        assert!(!processor.take_crash());
+        drop(done_tx);
+    }
+
+    #[test]
+    fn test_offline_waits_past_the_block() {
+        let (app_reader, host_writer) = io::pipe().unwrap();
+        let (mut host_reader, app_writer) = io::pipe().unwrap();
+        thread::spawn(move || {
+            let Ok(Some(_)) = read_frame(&mut host_reader) else {
+                return;
+            };
+            thread::sleep(Duration::from_millis(20));
+            let response = Response::Processed {
+                output: [vec![0.5; 8], vec![0.5; 8]],
+                notes: Vec::new(),
+                params: Vec::new(),
+            };
+            send(&Mutex::new(host_writer), &mut Vec::new(), response).unwrap();
+            let _ = read_frame(&mut host_reader);
+        });
+        let connection = Connection::new(Box::new(app_writer), Box::new(app_reader), None);
+        let mut processor = SandboxedProcessor::new(connection, 0, 8000.0);
+        processor.set_offline();
+
+        assert_eq!(process(&mut processor), vec![0.5; 8]);
+    }
+
+    #[test]
+    fn test_block_doesnt_wait_behind_main_request() {
+        let (app_reader, host_writer) = io::pipe().unwrap();
+        let (mut host_reader, app_writer) = io::pipe().unwrap();
+        let (saving_tx, saving_rx) = unbounded::<()>();
+        let (release_tx, release_rx) = unbounded::<()>();
+        // Like the real host: a save that takes a while on one thread while
+        // blocks keep being answered on another
+        thread::spawn(move || {
+            let host_writer = Mutex::new(host_writer);
+            thread::scope(|scope| {
+                while let Ok(Some(frame)) = read_frame(&mut host_reader) {
+                    match Request::decode(&frame).unwrap() {
+                        Request::SaveState => {
+                            saving_tx.send(()).unwrap();
+                            let (host_writer, release_rx) = (&host_writer, &release_rx);
+                            scope.spawn(move || {
+                                let _ = release_rx.recv();
+                                send(host_writer, &mut Vec::new(), Response::State(None))
+                            });
+                        }
+                        Request::Process { frames, .. } => {
+                            let response = Response::Processed {
+                                output: [vec![0.5; frames as usize], vec![0.5; frames as usize]],
+                                notes: Vec::new(),
+                                params: Vec::new(),
+                            };
+                            send(&host_writer, &mut Vec::new(), response).unwrap();
+                        }
+                        _ => {}
+                    }
+                }
+            });
+        });
+        let connection = Connection::new(Box::new(app_writer), Box::new(app_reader), None);
+        let mut connection = Arc::try_unwrap(connection).ok().unwrap();
+        connection.process_timeout = Duration::from_millis(200);
+        let connection = Arc::new(connection);
+
+        let mut instance = SandboxInstance {
+            connection: Arc::clone(&connection),
+        };
+        let save = thread::spawn(move || instance.save_state());
+        saving_rx.recv().unwrap();
+        let mut processor = SandboxedProcessor::new(connection, 0, SLOW_RATE);
+        for _ in 0..3 {
+            assert_eq!(process(&mut processor), vec![0.5; 8]);
+        }
+        assert!(!processor.take_crash());
+
+        drop(release_tx);
+        assert_eq!(save.join().unwrap(), Ok(None));
+    }
+
+    #[test]
+    fn test_failed_effect_passes_audio_through() {
+        let mut processor = SandboxedProcessor::new(fake_host(0, || {}), 0, SLOW_RATE);
+        let (mut left, mut right) = (vec![0.25; 8], vec![0.25; 8]);
+        let transport = PluginTransport::default();
+        processor.process_effect(&[], &transport, &mut left, &mut right);
+        assert_eq!(left, vec![0.25; 8]);
+    }
+}
@@ -0,0 +1,449 @@
+//! Plugin discovery: finds CLAP bundles and reads the plugins they offer
+//!
+//! Reading a bundle's factory runs the bundle's code, so results are cached
+//! in the config dir keyed by path and modification time. Bundles that fail
+//! to open are blacklisted until they change on disk. The bundle being read
+//! is written to the cache first, so one that crashes the whole scan is
+//! blacklisted on the next start instead of crashing it again.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// One plugin offered by a bundle's factory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginDescription {
+    /// CLAP plugin id, e.g. "org.surge-synth-team.surge-xt"
+    pub id: String,
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+    /// CLAP feature tags, e.g. "instrument", "synthesizer", "stereo"
+    pub features: Vec<String>,
+}
+
+/// Main kind of a plugin, from its CLAP feature tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PluginCategory {
+    Instrument,
+    AudioEffect,
+    NoteEffect,
+    Analyzer,
+    Other,
+}
+
+impl PluginCategory {
+    /// Get the display name
+    pub fn name(self) -> &'static str {
+        match self {
+            PluginCategory::Instrument => "Instruments",
+            PluginCategory::AudioEffect => "Audio Effects",
+            PluginCategory::NoteEffect => "Note Effects",
+            PluginCategory::Analyzer => "Analyzers",
+            PluginCategory::Other => "Other",
+        }
+    }
+}
+
+impl PluginDescription {
+    /// The plugin's main category; instruments win over effects for plugins
+    /// tagged as both
+    pub fn category(&self) -> PluginCategory {
+        let has = |feature: &str| self.features.iter().any(|f| f == feature);
+        if has("instrument") {
+            PluginCategory::Instrument
+        } else if has("audio-effect") {
+            PluginCategory::AudioEffect
+        } else if has("note-effect") {
+            PluginCategory::NoteEffect
+        } else if has("analyzer") {
+            PluginCategory::Analyzer
+        } else {
+            PluginCategory::Other
+        }
+    }
+}
+
+/// A bundle on disk and the plugins it offers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedBundle {
+    pub path: PathBuf,
+    pub plugins: Vec<PluginDescription>,
+}
+
+/// What scanning a bundle produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ScanResult {
+    Plugins(Vec<PluginDescription>),
+    /// Blacklisted until the bundle changes
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Modification time of the bundle when it was scanned (seconds since epoch)
+    mtime: u64,
+    result: ScanResult,
+}
+
+/// Scan results of every bundle seen so far, saved as JSON
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginCache {
+    #[serde(default)]
+    bundles: BTreeMap<PathBuf, CacheEntry>,
+    /// Bundle being read when the cache was saved; still set on load means
+    /// it crashed the scan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scanning: Option<PathBuf>,
+    /// Where the cache is saved (None keeps it in memory)
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+impl PluginCache {
+    /// Load the cache saved at `file`, starting empty if there is none
+    pub fn load(file: &Path) -> Self {
+        let mut cache: Self = std::fs::read_to_string(file)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        cache.file = Some(file.to_path_buf());
+
+        if let Some(path) = cache.scanning.take() {
+            if let Some(mtime) = modified_secs(&path) {
+                cache.bundles.insert(
+                    path,
+                    CacheEntry {
+                        mtime,
+                        result: ScanResult::Failed("Crashed while scanning".to_string()),
+                    },
+                );
+            }
+            cache.save();
+        }
+        cache
+    }
+
+    /// Write the cache back to its file; failures only cost a rescan
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Some(dir) = file.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(file, json);
+        }
+    }
+
+    /// Plugins offered by each of `bundles`, reading with `describe` only
+    /// the bundles that are new or changed since they were last scanned.
+    /// Blacklisted bundles are left out.
+    pub fn scan(
+        &mut self,
+        bundles: &[PathBuf],
+        describe: impl Fn(&Path) -> Result<Vec<PluginDescription>, String>,
+    ) -> Vec<ScannedBundle> {
+        let mut changed = false;
+        let mut scanned = Vec::new();
+
+        for path in bundles {
+            let Some(mtime) = modified_secs(path) else {
+                continue;
+            };
+            let cached = self.bundles.get(path).filter(|e| e.mtime == mtime);
+            let result = match cached {
+                Some(entry) => entry.result.clone(),
+                None => {
+                    self.scanning = Some(path.clone());
+                    self.save();
+                    let result = match describe(path) {
+                        Ok(plugins) => ScanResult::Plugins(plugins),
+                        Err(e) => ScanResult::Failed(e),
+                    };
+                    self.scanning = None;
+                    self.bundles.insert(
+                        path.clone(),
+                        CacheEntry {
+                            mtime,
+                            result: result.clone(),
+                        },
+                    );
+                    changed = true;
+                    result
+                }
+            };
+            if let ScanResult::Plugins(plugins) = result {
+                scanned.push(ScannedBundle {
+                    path: path.clone(),
+                    plugins,
+                });
+            }
+        }
+
+        // Forget bundles that were removed from disk
+        let before = self.bundles.len();
+        self.bundles.retain(|path, _| path.exists());
+        if changed || self.bundles.len() != before {
+            self.save();
+        }
+        scanned
+    }
+
+    /// Blacklisted bundles and why they failed
+    pub fn failures(&self) -> Vec<(&Path, &str)> {
+        self.bundles
+            .iter()
+            .filter_map(|(path, entry)| match &entry.result {
+                ScanResult::Failed(e) => Some((path.as_path(), e.as_str())),
+                ScanResult::Plugins(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Modification time of `path` in seconds since the epoch
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// Get the plugin cache file (~/.config/termdaw/plugin-cache.json)
+pub fn plugin_cache_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("termdaw")
+        .join("plugin-cache.json")
+}
+
+/// Directories to search for plugins: those in `CLAP_PATH` first, then the
+/// standard ones for the platform
+pub fn plugin_search_paths() -> Vec<PathBuf> {
+    search_paths(std::env::var_os("CLAP_PATH").as_deref())
+}
+
+fn search_paths(clap_path: Option<&OsStr>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = clap_path
+        .map(|paths| std::env::split_paths(paths).collect())
+        .unwrap_or_default();
+    for dir in get_plugin_directories() {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs.retain(|d| !d.as_os_str().is_empty());
+    dirs
+}
+
+/// Every CLAP bundle in `dirs` and their subdirectories, sorted by path.
+/// macOS bundles are directories; they are listed but not descended into.
+pub fn find_bundles(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut bundles = Vec::new();
+    for dir in dirs {
+        let mut walker = WalkDir::new(dir)
+            .min_depth(1)
+            .follow_links(true)
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let is_clap = entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("clap"));
+            if is_clap {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                bundles.push(entry.into_path());
+            }
+        }
+    }
+    bundles.sort();
+    bundles.dedup();
+    bundles
+}
+
+/// Scan every CLAP bundle in `extra_dirs`, `CLAP_PATH` and the standard
+/// directories, through the plugin cache in the config dir
+pub fn scan_plugin_directories(extra_dirs: &[PathBuf]) -> Vec<ScannedBundle> {
+    let mut dirs = extra_dirs.to_vec();
+    dirs.extend(plugin_search_paths());
+    let bundles = find_bundles(&dirs);
+    let mut cache = PluginCache::load(&plugin_cache_path());
+    cache.scan(&bundles, super::host::describe_bundle)
+}
+
+/// Get standard plugin directories for the current platform
+fn get_plugin_directories() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    // User home directory plugins
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".clap"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs.push(PathBuf::from("/Library/Audio/Plug-Ins/CLAP"));
+        if let Some(home) = dirs::home_dir() {
+            dirs.push(home.join("Library/Audio/Plug-Ins/CLAP"));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(home) = dirs::home_dir() {
+            dirs.push(home.join(".local/lib/clap"));
+        }
+        dirs.push(PathBuf::from("/usr/lib/clap"));
+        dirs.push(PathBuf::from("/usr/local/lib/clap"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(program_files) = std::env::var_os("PROGRAMFILES") {
+            dirs.push(PathBuf::from(program_files).join("Common Files/CLAP"));
+        }
+        if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local_app_data).join("Programs/Common/CLAP"));
+        }
+    }
+
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    fn plugin(id: &str, features: &[&str]) -> PluginDescription {
+        PluginDescription {
+            id: id.to_string(),
+            name: id.to_string(),
+            vendor: "Acme".to_string(),
+            version: "1.0".to_string(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_category_from_features() {
+        assert_eq!(
+            plugin("a", &["instrument", "audio-effect"]).category(),
+            PluginCategory::Instrument
+        );
+        assert_eq!(
+            plugin("b", &["stereo", "audio-effect"]).category(),
+            PluginCategory::AudioEffect
+        );
+        assert_eq!(
+            plugin("c", &["note-effect"]).category(),
+            PluginCategory::NoteEffect
+        );
+        assert_eq!(plugin("d", &[]).category(), PluginCategory::Other);
+    }
+
+    #[test]
+    fn test_clap_path_comes_first() {
+        let joined = std::env::join_paths(["/opt/a", "/opt/b"]).unwrap();
+        let dirs = search_paths(Some(&joined));
+        assert_eq!(dirs[0], PathBuf::from("/opt/a"));
+        assert_eq!(dirs[1], PathBuf::from("/opt/b"));
+        assert_eq!(dirs[2..], get_plugin_directories()[..]);
+    }
+
+    #[test]
+    fn test_find_bundles_recurses_but_not_into_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("vendor")).unwrap();
+        std::fs::write(dir.path().join("vendor/synth.clap"), b"").unwrap();
+        std::fs::create_dir_all(dir.path().join("fx.clap/Contents")).unwrap();
+        std::fs::write(dir.path().join("fx.clap/Contents/inner.clap"), b"").unwrap();
+        std::fs::write(dir.path().join("readme.txt"), b"").unwrap();
+
+        let bundles = find_bundles(&[dir.path().to_path_buf()]);
+        assert_eq!(
+            bundles,
+            vec![
+                dir.path().join("fx.clap"),
+                dir.path().join("vendor/synth.clap")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("synth.clap");
+        std::fs::write(&bundle, b"").unwrap();
+        let cache_file = dir.path().join("cache.json");
+        let reads = Cell::new(0);
+        let describe = |_: &Path| {
+            reads.set(reads.get() + 1);
+            Ok(vec![plugin("synth", &["instrument"])])
+        };
+
+        let first = PluginCache::load(&cache_file).scan(std::slice::from_ref(&bundle), describe);
+        let second = PluginCache::load(&cache_file).scan(std::slice::from_ref(&bundle), describe);
+        assert_eq!(reads.get(), 1);
+        assert_eq!(first, second);
+        assert_eq!(second[0].plugins[0].id, "synth");
+    }
+
+    #[test]
+    fn test_failed_bundles_are_blacklisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("broken.clap");
+        std::fs::write(&bundle, b"").unwrap();
+        let cache_file = dir.path().join("cache.json");
+        let reads = Cell::new(0);
+        let describe = |_: &Path| {
+            reads.set(reads.get() + 1);
+            Err("No plugin factory found".to_string())
+        };
+
+        let mut cache = PluginCache::load(&cache_file);
+        assert!(cache
+            .scan(std::slice::from_ref(&bundle), describe)
+            .is_empty());
+        let mut cache = PluginCache::load(&cache_file);
+        assert!(cache
+            .scan(std::slice::from_ref(&bundle), describe)
+            .is_empty());
+        assert_eq!(reads.get(), 1);
+        assert_eq!(
+            cache.failures(),
+            vec![(bundle.as_path(), "No plugin factory found")]
+        );
+    }
+
+    #[test]
+    fn test_bundle_that_crashed_the_scan_is_blacklisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("crashy.clap");
+        std::fs::write(&bundle, b"").unwrap();
+        let cache_file = dir.path().join("cache.json");
+
+        // What a scan that died inside the bundle leaves behind
+        let crashed = PluginCache {
+            scanning: Some(bundle.clone()),
+            ..Default::default()
+        };
+        std::fs::write(&cache_file, serde_json::to_string(&crashed).unwrap()).unwrap();
+
+        let mut cache = PluginCache::load(&cache_file);
+        let scanned = cache.scan(std::slice::from_ref(&bundle), |_| panic!("rescanned"));
+        assert!(scanned.is_empty());
+        assert_eq!(cache.failures().len(), 1);
+    }
+}
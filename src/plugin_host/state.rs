@@ -0,0 +1,265 @@
+//! Plugin state persistence.
+//!
+//! Plugins that implement the CLAP state extension hand the host an opaque
+//! blob holding everything they need to come back as they were (parameters,
+//! loaded files, internal modes). The blob is stored base64-encoded next to
+//! the plugin's path in `project.json` and restored before the plugin is
+//! activated.
+//!
+//! State can only be saved from the instance's main thread, so the
+//! main-thread half of every running plugin is kept in [`PluginInstances`]
+//! and asked for its state when the project is saved or exported. The same
+//! handles run the callbacks plugins request on the main thread.
+
+use std::collections::HashMap;
+
+use super::StateSaver;
+
+/// Where a running plugin lives in the project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluginSlot {
+    /// Instrument on the channel with this UI slot
+    Channel(usize),
+    /// Insert effect on a mixer track
+    Effect { track: usize, slot: usize },
+}
+
+/// A running plugin and the bundle it was loaded from
+struct LiveInstance {
+    path: String,
+    instance: Box<dyn StateSaver>,
+}
+
+/// Main-thread handles of the running plugins, for saving their state
+#[derive(Default)]
+pub struct PluginInstances {
+    live: HashMap<PluginSlot, LiveInstance>,
+}
+
+impl PluginInstances {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track the instance now running in `slot`, replacing the previous one
+    pub fn insert(&mut self, slot: PluginSlot, path: &str, instance: Box<dyn StateSaver>) {
+        self.live.insert(
+            slot,
+            LiveInstance {
+                path: path.to_string(),
+                instance,
+            },
+        );
+    }
+
+    /// Forget the instance in `slot`
+    pub fn remove(&mut self, slot: PluginSlot) {
+        self.live.remove(&slot);
+    }
+
+    /// Move the instances of `other` into this set, except for slots this
+    /// set already tracks (its instances are the newer ones)
+    pub fn adopt(&mut self, other: PluginInstances) {
+        for (slot, live) in other.live {
+            self.live.entry(slot).or_insert(live);
+        }
+    }
+
+    /// Number of tracked instances
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Whether no instances are tracked
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    /// Service every instance's main-thread requests, returning the slots
+    /// whose plugins asked to be restarted
+    pub fn service_main_thread(&mut self) -> Vec<PluginSlot> {
+        let mut restarts: Vec<PluginSlot> = self
+            .live
+            .iter_mut()
+            .filter_map(|(slot, live)| live.instance.service_main_thread().then_some(*slot))
+            .collect();
+        restarts.sort_by_key(|slot| match *slot {
+            PluginSlot::Channel(channel) => (0, channel, 0),
+            PluginSlot::Effect { track, slot } => (1, track, slot),
+        });
+        restarts
+    }
+
+    /// Save the state of the instance in `slot`
+    ///
+    /// Returns None if nothing is running there, the slot now holds a
+    /// different plugin than `path`, or the plugin has no state to save.
+    /// On a save failure the caller keeps whatever state it had.
+    pub fn save(&mut self, slot: PluginSlot, path: &str) -> Result<Option<Vec<u8>>, String> {
+        match self.live.get_mut(&slot).filter(|live| live.path == path) {
+            Some(live) => live.instance.save_state(),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Serde helpers storing an optional state blob as a base64 string
+pub mod base64_blob {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(state: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match state {
+            Some(bytes) => serializer.serialize_str(&STANDARD.encode(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| STANDARD.decode(text).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+
+    /// Hands out a fixed save result
+    struct FixedState(Result<Option<Vec<u8>>, String>);
+
+    impl StateSaver for FixedState {
+        fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+            self.0.clone()
+        }
+    }
+
+    /// Asks for a restart the first time it is serviced
+    struct RestartOnce(bool);
+
+    impl StateSaver for RestartOnce {
+        fn save_state(&mut self) -> Result<Option<Vec<u8>>, String> {
+            Ok(None)
+        }
+
+        fn service_main_thread(&mut self) -> bool {
+            std::mem::take(&mut self.0)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Stored {
+        #[serde(default, skip_serializing_if = "Option::is_none", with = "base64_blob")]
+        state: Option<Vec<u8>>,
+    }
+
+    #[test]
+    fn test_blob_round_trips_as_base64() {
+        let stored = Stored {
+            state: Some(vec![0, 1, 2, 255]),
+        };
+        let json = serde_json::to_string(&stored).unwrap();
+        assert_eq!(json, r#"{"state":"AAEC/w=="}"#);
+        assert_eq!(serde_json::from_str::<Stored>(&json).unwrap(), stored);
+
+        let empty = Stored { state: None };
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "{}");
+        assert_eq!(serde_json::from_str::<Stored>("{}").unwrap(), empty);
+        assert!(serde_json::from_str::<Stored>(r#"{"state":"not base64!"}"#).is_err());
+    }
+
+    #[test]
+    fn test_save_only_matches_same_plugin() {
+        let mut instances = PluginInstances::new();
+        let slot = PluginSlot::Effect { track: 1, slot: 2 };
+        instances.insert(slot, "verb.clap", Box::new(FixedState(Ok(Some(vec![7])))));
+
+        assert_eq!(instances.save(slot, "verb.clap"), Ok(Some(vec![7])));
+        assert_eq!(instances.save(slot, "delay.clap"), Ok(None));
+        assert_eq!(
+            instances.save(PluginSlot::Channel(2), "verb.clap"),
+            Ok(None)
+        );
+
+        instances.remove(slot);
+        assert!(instances.is_empty());
+    }
+
+    #[test]
+    fn test_adopt_keeps_newer_instances() {
+        let mut app = PluginInstances::new();
+        app.insert(
+            PluginSlot::Channel(0),
+            "a.clap",
+            Box::new(FixedState(Ok(Some(vec![1])))),
+        );
+
+        let mut engine = PluginInstances::new();
+        engine.insert(
+            PluginSlot::Channel(0),
+            "a.clap",
+            Box::new(FixedState(Ok(Some(vec![2])))),
+        );
+        engine.insert(
+            PluginSlot::Channel(1),
+            "b.clap",
+            Box::new(FixedState(Ok(Some(vec![3])))),
+        );
+
+        app.adopt(engine);
+        assert_eq!(app.len(), 2);
+        assert_eq!(
+            app.save(PluginSlot::Channel(0), "a.clap"),
+            Ok(Some(vec![1]))
+        );
+        assert_eq!(
+            app.save(PluginSlot::Channel(1), "b.clap"),
+            Ok(Some(vec![3]))
+        );
+    }
+
+    #[test]
+    fn test_service_reports_restart_requests_once() {
+        let mut instances = PluginInstances::new();
+        let effect = PluginSlot::Effect { track: 0, slot: 1 };
+        instances.insert(effect, "verb.clap", Box::new(RestartOnce(true)));
+        instances.insert(
+            PluginSlot::Channel(3),
+            "a.clap",
+            Box::new(RestartOnce(true)),
+        );
+        instances.insert(
+            PluginSlot::Channel(1),
+            "b.clap",
+            Box::new(RestartOnce(false)),
+        );
+
+        assert_eq!(
+            instances.service_main_thread(),
+            vec![PluginSlot::Channel(3), effect]
+        );
+        assert!(instances.service_main_thread().is_empty());
+    }
+
+    #[test]
+    fn test_save_failure_returns_error() {
+        let mut instances = PluginInstances::new();
+        let slot = PluginSlot::Channel(0);
+        instances.insert(slot, "synth.clap", Box::new(FixedState(Err("boom".into()))));
+        assert_eq!(instances.save(slot, "synth.clap"), Err("boom".to_string()));
+
+        instances.insert(slot, "synth.clap", Box::new(FixedState(Ok(None))));
+        assert_eq!(instances.save(slot, "synth.clap"), Ok(None));
+        assert_eq!(instances.len(), 1);
+    }
+}
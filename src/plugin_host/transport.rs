@@ -0,0 +1,112 @@
+//! Song position handed to plugins with every process call.
+//!
+//! The sequencer runs on the main thread and only knows which step it is on,
+//! so it sends a [`PluginTransport`] whenever that changes. The audio thread
+//! advances it frame by frame in between, giving tempo-synced plugins
+//! (arpeggiators, LFOs, delays) a smooth timeline to follow.
+
+/// Beats per bar (the sequencer is fixed to 4/4)
+pub const BEATS_PER_BAR: u16 = 4;
+
+/// Note value of one beat (quarter notes)
+pub const BEAT_UNIT: u16 = 4;
+
+/// Play state and song position as plugins see it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluginTransport {
+    /// Whether the song is playing
+    pub playing: bool,
+    /// Tempo in BPM
+    pub tempo: f64,
+    /// Song position in beats
+    pub position_beats: f64,
+    /// Range playback wraps around, in beats (start, end)
+    pub loop_beats: Option<(f64, f64)>,
+}
+
+impl Default for PluginTransport {
+    fn default() -> Self {
+        Self::stopped(120.0)
+    }
+}
+
+impl PluginTransport {
+    /// Stopped at the start of the song
+    pub fn stopped(tempo: f64) -> Self {
+        Self {
+            playing: false,
+            tempo,
+            position_beats: 0.0,
+            loop_beats: None,
+        }
+    }
+
+    /// Length of `beats` in seconds at the current tempo
+    pub fn beats_to_seconds(&self, beats: f64) -> f64 {
+        if self.tempo > 0.0 {
+            beats * 60.0 / self.tempo
+        } else {
+            0.0
+        }
+    }
+
+    /// Zero-based index of the bar the position is in
+    pub fn bar_number(&self) -> i32 {
+        (self.position_beats / BEATS_PER_BAR as f64).floor() as i32
+    }
+
+    /// Position of the current bar's first beat
+    pub fn bar_start_beats(&self) -> f64 {
+        self.bar_number() as f64 * BEATS_PER_BAR as f64
+    }
+
+    /// Move the position on by `frames` if playing, wrapping at the loop end
+    pub fn advance(&mut self, frames: usize, sample_rate: u32) {
+        if !self.playing || sample_rate == 0 {
+            return;
+        }
+        self.position_beats += frames as f64 / sample_rate as f64 * self.tempo / 60.0;
+        if let Some((start, end)) = self.loop_beats {
+            if end > start && self.position_beats >= end {
+                self.position_beats = start + (self.position_beats - start) % (end - start);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_follows_tempo_and_wraps() {
+        let mut transport = PluginTransport {
+            playing: true,
+            tempo: 120.0,
+            position_beats: 0.0,
+            loop_beats: Some((0.0, 4.0)),
+        };
+        // Half a second at 120 BPM is one beat
+        transport.advance(22_050, 44_100);
+        assert!((transport.position_beats - 1.0).abs() < 1e-9);
+        assert!((transport.beats_to_seconds(transport.position_beats) - 0.5).abs() < 1e-9);
+
+        transport.advance(44_100 * 2, 44_100);
+        assert!(
+            (transport.position_beats - 1.0).abs() < 1e-9,
+            "wrapped to 5 - 4"
+        );
+    }
+
+    #[test]
+    fn test_stopped_transport_holds_position() {
+        let mut transport = PluginTransport {
+            position_beats: 6.5,
+            ..PluginTransport::stopped(90.0)
+        };
+        transport.advance(44_100, 44_100);
+        assert_eq!(transport.position_beats, 6.5);
+        assert_eq!(transport.bar_number(), 1);
+        assert_eq!(transport.bar_start_beats(), 4.0);
+    }
+}
@@ -0,0 +1,235 @@
+//! Preset picker - browse, apply and save presets
+//!
+//! Opened from the effect editor and the plugin editor. Lists the factory and
+//! user presets for the edited effect's type or plugin; a name prompt saves
+//! the current sound.
+
+use tui_input::Input;
+
+use crate::effects::preset::EffectPreset;
+use crate::plugin_host::PluginPreset;
+
+/// A preset the picker can list
+pub trait Preset {
+    /// Display name
+    fn name(&self) -> &str;
+    /// Whether it ships with the effect or plugin (read-only)
+    fn is_factory(&self) -> bool;
+}
+
+impl Preset for EffectPreset {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_factory(&self) -> bool {
+        self.factory
+    }
+}
+
+impl Preset for PluginPreset {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_factory(&self) -> bool {
+        PluginPreset::is_factory(self)
+    }
+}
+
+/// What the picker is doing
+#[derive(Default)]
+pub enum PresetPickerMode {
+    /// Browsing the preset list
+    #[default]
+    Browse,
+    /// Typing a name to save the current parameters under
+    Naming { input: Input },
+}
+
+impl std::fmt::Debug for PresetPickerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Browse => write!(f, "Browse"),
+            Self::Naming { input } => f
+                .debug_struct("Naming")
+                .field("input", &input.value())
+                .finish(),
+        }
+    }
+}
+
+/// Preset picker state
+#[derive(Debug)]
+pub struct PresetPicker<P = EffectPreset> {
+    /// Whether the picker is currently visible
+    pub visible: bool,
+    /// Presets for the edited effect or plugin (factory first)
+    pub presets: Vec<P>,
+    /// Selected preset index
+    pub selected: usize,
+    /// Current mode
+    pub mode: PresetPickerMode,
+    /// Result of the last save, shown under the list
+    pub message: Option<String>,
+}
+
+impl<P> Default for PresetPicker<P> {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            presets: Vec::new(),
+            selected: 0,
+            mode: PresetPickerMode::Browse,
+            message: None,
+        }
+    }
+}
+
+impl<P: Preset> PresetPicker<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the picker with the given presets
+    pub fn open(&mut self, presets: Vec<P>) {
+        self.visible = true;
+        self.presets = presets;
+        self.selected = 0;
+        self.mode = PresetPickerMode::Browse;
+        self.message = None;
+    }
+
+    /// Hide the picker
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.mode = PresetPickerMode::Browse;
+    }
+
+    /// Move selection up
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Move selection down
+    pub fn select_next(&mut self) {
+        if !self.presets.is_empty() {
+            self.selected = (self.selected + 1).min(self.presets.len() - 1);
+        }
+    }
+
+    /// Step the selection by `delta`, wrapping around the list
+    pub fn cycle(&mut self, delta: isize) {
+        let len = self.presets.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// The selected preset, if any
+    pub fn selected_preset(&self) -> Option<&P> {
+        self.presets.get(self.selected)
+    }
+
+    /// Start typing a name, prefilled with the selected user preset's name
+    /// so saving over it is one keypress
+    pub fn start_naming(&mut self) {
+        let name = self
+            .selected_preset()
+            .filter(|p| !p.is_factory())
+            .map(|p| p.name().to_string())
+            .unwrap_or_default();
+        self.mode = PresetPickerMode::Naming {
+            input: Input::new(name),
+        };
+    }
+
+    /// Stop typing a name and go back to the list
+    pub fn cancel_naming(&mut self) {
+        self.mode = PresetPickerMode::Browse;
+    }
+
+    /// Check if currently typing a name
+    pub fn is_naming(&self) -> bool {
+        matches!(self.mode, PresetPickerMode::Naming { .. })
+    }
+
+    /// Get mutable reference to the name input if naming
+    pub fn input_mut(&mut self) -> Option<&mut Input> {
+        match &mut self.mode {
+            PresetPickerMode::Naming { input } => Some(input),
+            PresetPickerMode::Browse => None,
+        }
+    }
+
+    /// Get the typed name if naming
+    pub fn input_value(&self) -> Option<&str> {
+        match &self.mode {
+            PresetPickerMode::Naming { input } => Some(input.value()),
+            PresetPickerMode::Browse => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::EffectType;
+    use std::collections::HashMap;
+
+    fn preset(name: &str, factory: bool) -> EffectPreset {
+        EffectPreset {
+            name: name.to_string(),
+            effect_type: EffectType::Reverb,
+            params: HashMap::new(),
+            factory,
+        }
+    }
+
+    #[test]
+    fn test_navigation_stays_in_bounds() {
+        let mut picker = PresetPicker::new();
+        picker.select_next();
+        assert_eq!(picker.selected, 0);
+
+        picker.open(vec![preset("A", true), preset("B", false)]);
+        picker.select_next();
+        picker.select_next();
+        assert_eq!(picker.selected_preset().unwrap().name, "B");
+        picker.select_prev();
+        picker.select_prev();
+        assert_eq!(picker.selected, 0);
+    }
+
+    #[test]
+    fn test_naming_prefills_user_preset_only() {
+        let mut picker = PresetPicker::new();
+        picker.open(vec![preset("Factory", true), preset("Mine", false)]);
+        picker.start_naming();
+        assert_eq!(picker.input_value(), Some(""));
+
+        picker.cancel_naming();
+        picker.select_next();
+        picker.start_naming();
+        assert!(picker.is_naming());
+        assert_eq!(picker.input_value(), Some("Mine"));
+    }
+
+    #[test]
+    fn test_cycle_wraps_around() {
+        let mut picker = PresetPicker::new();
+        picker.cycle(1);
+        assert_eq!(picker.selected, 0);
+
+        picker.open(vec![
+            preset("A", true),
+            preset("B", false),
+            preset("C", false),
+        ]);
+        picker.cycle(-1);
+        assert_eq!(picker.selected_preset().unwrap().name, "C");
+        picker.cycle(1);
+        picker.cycle(1);
+        assert_eq!(picker.selected_preset().unwrap().name, "B");
+    }
+}
@@ -127,7 +127,25 @@ pub fn load_project(path: &Path) -> Result<ProjectFile, ProjectError> {
     }
 
     let json = fs::read_to_string(&project_file)?;
-    let project: ProjectFile = serde_json::from_str(&json)?;
+    let mut project: ProjectFile = serde_json::from_str(&json)?;
+
+    for channel in &mut project.channels {
+        channel.upgrade_legacy_params();
+    }
+
+    if let Some(mixer) = project.mixer.as_mut() {
+        let samples_dir = path.join("samples");
+        for slot in mixer
+            .tracks
+            .iter_mut()
+            .flat_map(|track| track.effects.iter_mut().flatten())
+        {
+            slot.upgrade_legacy_params();
+            // An unreadable IR leaves the reverb dry but keeps its path, so
+            // the file can be restored without reassigning it
+            let _ = slot.load_impulse_response(&samples_dir);
+        }
+    }
 
     Ok(project)
 }
@@ -291,4 +309,128 @@ mod tests {
             "Bass should have notes in pattern 1"
         );
     }
+
+    #[test]
+    fn test_load_maps_deprecated_filter_resonance_onto_q() {
+        use crate::effects::{EffectParamId, EffectSlot, EffectType};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut mixer = Mixer::default();
+        let mut slot = EffectSlot::new(EffectType::Filter);
+        // An old filter slot: cutoff, mode and the deprecated resonance only
+        slot.params.clear();
+        slot.set_param(EffectParamId::FilterCutoff, 800.0);
+        slot.set_param(EffectParamId::FilterMode, 1.0);
+        slot.set_param(EffectParamId::FilterResonance, 1.0);
+        mixer.tracks[1].effects[0] = Some(slot);
+        let mut project = ProjectFile::new("old");
+        project.mixer = Some(mixer);
+        save_project(dir.path(), &project).unwrap();
+
+        let loaded = load_project(dir.path()).unwrap();
+        let slot = loaded.mixer.unwrap().tracks[1].effects[0].clone().unwrap();
+        assert!(!slot.params.contains_key(&EffectParamId::FilterResonance));
+        assert_eq!(slot.get_param(EffectParamId::FilterCutoff), 800.0);
+        assert_eq!(slot.get_param(EffectParamId::FilterMode), 1.0);
+        assert!(
+            (slot.get_param(EffectParamId::FilterQ) - crate::effects::filter::MAX_Q).abs() < 1e-3
+        );
+        assert_eq!(slot.get_param(EffectParamId::FilterSlope), 0.0);
+        assert_eq!(slot.get_param(EffectParamId::FilterModel), 0.0);
+    }
+
+    #[test]
+    fn test_load_migrates_only_simple_synth_param_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = ProjectFile::new("old");
+        project.channels = vec![
+            Channel::with_plugin("Synth", "Simple Synth.clap"),
+            Channel::with_plugin("Pad", "Pad.clap"),
+        ];
+        // Parameters as saved before discovery: keyed by name
+        let mut json = serde_json::to_value(&project).unwrap();
+        json["channels"][0]["source"]["params"] =
+            serde_json::json!({"Sustain": 0.7, "Shimmer": 0.2, "7": 0.5});
+        json["channels"][1]["source"]["params"] = serde_json::json!({"Attack": 10.0});
+        std::fs::write(dir.path().join(PROJECT_FILE_NAME), json.to_string()).unwrap();
+
+        let loaded = load_project(dir.path()).unwrap();
+        let synth = &loaded.channels[0];
+        assert_eq!(synth.plugin_params().len(), 2);
+        assert!((synth.plugin_params()[&114257] - 0.7).abs() < 1e-9);
+        assert_eq!(synth.plugin_params()[&7], 0.5);
+        assert_eq!(synth.legacy_plugin_params(), vec!["Shimmer"]);
+        let pad = &loaded.channels[1];
+        assert!(pad.plugin_params().is_empty(), "not SimpleSynth's names");
+        assert_eq!(pad.legacy_plugin_params(), vec!["Attack"]);
+
+        // Unmatched names survive a save
+        save_project(dir.path(), &loaded).unwrap();
+        let reloaded = load_project(dir.path()).unwrap();
+        assert_eq!(reloaded.channels[0].legacy_plugin_params(), vec!["Shimmer"]);
+        assert_eq!(reloaded.channels[1].legacy_plugin_params(), vec!["Attack"]);
+    }
+
+    #[test]
+    fn test_load_resolves_impulse_response_from_samples() {
+        use crate::effects::{EffectSlot, EffectType};
+
+        let dir = tempfile::tempdir().unwrap();
+        let ir_dir = dir.path().join("samples").join("irs");
+        std::fs::create_dir_all(&ir_dir).unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(ir_dir.join("hall.wav"), spec).unwrap();
+        for i in 0..64 {
+            writer.write_sample(1.0f32 / (i + 1) as f32).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut mixer = Mixer::default();
+        let mut slot = EffectSlot::new(EffectType::Convolution);
+        slot.ir_path = Some("irs/hall.wav".to_string());
+        mixer.tracks[1].effects[0] = Some(slot);
+        let mut missing = EffectSlot::new(EffectType::Convolution);
+        missing.ir_path = Some("irs/gone.wav".to_string());
+        mixer.tracks[1].effects[1] = Some(missing);
+        let mut project = ProjectFile::new("ir");
+        project.mixer = Some(mixer);
+        save_project(dir.path(), &project).unwrap();
+
+        let loaded = load_project(dir.path()).unwrap();
+        let track = &loaded.mixer.unwrap().tracks[1];
+        let slot = track.effects[0].as_ref().unwrap();
+        assert_eq!(slot.ir_path.as_deref(), Some("irs/hall.wav"));
+        assert_eq!(slot.impulse_response.as_ref().unwrap().frames(), 64);
+        let missing = track.effects[1].as_ref().unwrap();
+        assert_eq!(missing.ir_path.as_deref(), Some("irs/gone.wav"));
+        assert!(missing.impulse_response.is_none());
+    }
+
+    #[test]
+    fn test_plugin_effect_slot_round_trips() {
+        use crate::effects::{EffectSlot, EffectType};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut mixer = Mixer::default();
+        let mut slot = EffectSlot::plugin("fx/Verb.clap");
+        slot.plugin_params.insert(7, 0.25);
+        slot.plugin_params.insert(1024, -3.5);
+        mixer.tracks[2].effects[4] = Some(slot);
+        let mut project = ProjectFile::new("plugin fx");
+        project.mixer = Some(mixer);
+        save_project(dir.path(), &project).unwrap();
+
+        let loaded = load_project(dir.path()).unwrap();
+        let slot = loaded.mixer.unwrap().tracks[2].effects[4].clone().unwrap();
+        assert_eq!(slot.effect_type, EffectType::Plugin);
+        assert_eq!(slot.plugin_path.as_deref(), Some("fx/Verb.clap"));
+        assert_eq!(slot.plugin_params.get(&7), Some(&0.25));
+        assert_eq!(slot.plugin_params.get(&1024), Some(&-3.5));
+        assert_eq!(slot.display_name(), "Verb");
+    }
 }
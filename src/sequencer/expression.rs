@@ -0,0 +1,189 @@
+//! Per-note expressions
+//!
+//! Each piano roll note can bend its pitch, press harder, change timbre and
+//! move in the stereo field on its own. Plugins get the values as CLAP note
+//! expressions, or as MPE channel messages if they want MIDI. A value holds
+//! for the whole note.
+
+use serde::{Deserialize, Serialize};
+
+/// Widest per-note pitch bend, in semitones either way
+pub const MAX_PITCH_BEND: f32 = 12.0;
+
+/// One kind of per-note expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoteExpression {
+    /// Pitch offset in semitones (-12..12)
+    PitchBend,
+    /// Aftertouch (0..1)
+    Pressure,
+    /// Brightness, MPE's CC74 (0..1)
+    Timbre,
+    /// Stereo position (-1 left .. 1 right)
+    Pan,
+}
+
+impl NoteExpression {
+    pub const ALL: [Self; 4] = [Self::PitchBend, Self::Pressure, Self::Timbre, Self::Pan];
+
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PitchBend => "Pitch bend",
+            Self::Pressure => "Pressure",
+            Self::Timbre => "Timbre",
+            Self::Pan => "Pan",
+        }
+    }
+
+    /// Lowest and highest value
+    pub fn range(self) -> (f32, f32) {
+        match self {
+            Self::PitchBend => (-MAX_PITCH_BEND, MAX_PITCH_BEND),
+            Self::Pressure | Self::Timbre => (0.0, 1.0),
+            Self::Pan => (-1.0, 1.0),
+        }
+    }
+
+    /// Value of a note that doesn't use the expression
+    pub fn neutral(self) -> f32 {
+        match self {
+            Self::PitchBend | Self::Pressure | Self::Pan => 0.0,
+            Self::Timbre => 0.5,
+        }
+    }
+
+    /// How far one edit in the piano roll lane moves the value
+    pub fn step(self) -> f32 {
+        match self {
+            Self::PitchBend => 0.5,
+            Self::Pressure | Self::Timbre | Self::Pan => 0.1,
+        }
+    }
+
+    /// Position of `value` within the range (0..1)
+    pub fn normalized(self, value: f32) -> f32 {
+        let (min, max) = self.range();
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    }
+
+    /// Display text for a value ("+2.0 st", "70%", "L30")
+    pub fn format(self, value: f32) -> String {
+        match self {
+            Self::PitchBend => format!("{:+.1} st", value),
+            Self::Pressure | Self::Timbre => format!("{:.0}%", value * 100.0),
+            Self::Pan => {
+                let percent = (value * 100.0).round() as i32;
+                match percent {
+                    0 => "C".to_string(),
+                    p if p < 0 => format!("L{}", -p),
+                    p => format!("R{}", p),
+                }
+            }
+        }
+    }
+}
+
+/// Expression values of one note
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteExpressions {
+    /// Semitones (-12..12)
+    pub pitch_bend: f32,
+    /// 0..1
+    pub pressure: f32,
+    /// 0..1, 0.5 neutral
+    pub timbre: f32,
+    /// -1 left .. 1 right
+    pub pan: f32,
+}
+
+impl Default for NoteExpressions {
+    fn default() -> Self {
+        Self {
+            pitch_bend: NoteExpression::PitchBend.neutral(),
+            pressure: NoteExpression::Pressure.neutral(),
+            timbre: NoteExpression::Timbre.neutral(),
+            pan: NoteExpression::Pan.neutral(),
+        }
+    }
+}
+
+impl NoteExpressions {
+    /// Value of one expression
+    pub fn get(&self, expression: NoteExpression) -> f32 {
+        match expression {
+            NoteExpression::PitchBend => self.pitch_bend,
+            NoteExpression::Pressure => self.pressure,
+            NoteExpression::Timbre => self.timbre,
+            NoteExpression::Pan => self.pan,
+        }
+    }
+
+    /// Set one expression, clamped to its range
+    pub fn set(&mut self, expression: NoteExpression, value: f32) {
+        let (min, max) = expression.range();
+        let value = value.clamp(min, max);
+        match expression {
+            NoteExpression::PitchBend => self.pitch_bend = value,
+            NoteExpression::Pressure => self.pressure = value,
+            NoteExpression::Timbre => self.timbre = value,
+            NoteExpression::Pan => self.pan = value,
+        }
+    }
+
+    /// Whether every expression is at its neutral value
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Expressions that differ from neutral, with their values
+    pub fn changed(&self) -> impl Iterator<Item = (NoteExpression, f32)> + '_ {
+        NoteExpression::ALL
+            .into_iter()
+            .map(|e| (e, self.get(e)))
+            .filter(|&(e, value)| value != e.neutral())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_clamps_to_range() {
+        let mut expressions = NoteExpressions::default();
+        expressions.set(NoteExpression::PitchBend, 30.0);
+        expressions.set(NoteExpression::Pressure, -1.0);
+        expressions.set(NoteExpression::Pan, -0.25);
+        assert_eq!(expressions.pitch_bend, MAX_PITCH_BEND);
+        assert_eq!(expressions.pressure, 0.0);
+
+        let changed: Vec<_> = expressions.changed().collect();
+        assert_eq!(
+            changed,
+            vec![
+                (NoteExpression::PitchBend, MAX_PITCH_BEND),
+                (NoteExpression::Pan, -0.25)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_fields_are_neutral() {
+        let expressions: NoteExpressions = serde_json::from_str(r#"{"pressure":0.5}"#).unwrap();
+        assert_eq!(expressions.pressure, 0.5);
+        assert_eq!(expressions.timbre, 0.5);
+        assert!(!expressions.is_neutral());
+        assert!(NoteExpressions::default().is_neutral());
+    }
+
+    #[test]
+    fn test_format_values() {
+        assert_eq!(NoteExpression::PitchBend.format(-2.0), "-2.0 st");
+        assert_eq!(NoteExpression::Pressure.format(0.7), "70%");
+        assert_eq!(NoteExpression::Pan.format(-0.3), "L30");
+        assert_eq!(NoteExpression::Pan.format(0.0), "C");
+        assert_eq!(NoteExpression::Pan.normalized(0.0), 0.5);
+    }
+}
@@ -8,7 +8,13 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::plugin_host::PluginParamId;
+use crate::plugin_host::params::{
+    is_simple_synth, migrate_simple_synth_params, split_stored_params,
+};
+
+pub mod expression;
+
+pub use expression::{NoteExpression, NoteExpressions};
 
 // ============================================================================
 // Note (unchanged)
@@ -28,6 +34,9 @@ pub struct Note {
     /// Velocity (0.0-1.0)
     #[serde(default = "default_velocity")]
     pub velocity: f32,
+    /// Per-note pitch bend, pressure, timbre and pan
+    #[serde(default, skip_serializing_if = "NoteExpressions::is_neutral")]
+    pub expressions: NoteExpressions,
 }
 
 fn default_velocity() -> f32 {
@@ -49,6 +58,7 @@ impl Note {
             start_step,
             duration,
             velocity: velocity.clamp(0.0, 1.0),
+            expressions: NoteExpressions::default(),
         }
     }
 
@@ -64,7 +74,7 @@ impl Note {
 
 /// Sound source type for a channel
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(tag = "type", rename_all = "snake_case", from = "StoredChannelSource")]
 pub enum ChannelSource {
     /// Sample-based - plays an audio file via step sequencer
     Sampler {
@@ -74,11 +84,74 @@ pub enum ChannelSource {
     /// Plugin-based - plays MIDI notes through a CLAP plugin
     Plugin {
         path: String,
+        /// Plugin picked from a multi-plugin bundle (the first one if None)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        plugin_id: Option<String>,
+        /// Parameter values keyed by CLAP parameter id
+        #[serde(default)]
+        params: HashMap<u32, f64>,
+        /// Parameter values keyed by name, as saved before parameter
+        /// discovery, that couldn't be matched to a CLAP id; kept so they
+        /// aren't lost on save
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        legacy_params: HashMap<String, f64>,
+        /// Opaque plugin state from the CLAP state extension
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::plugin_host::state::base64_blob"
+        )]
+        state: Option<Vec<u8>>,
+    },
+}
+
+/// `ChannelSource` as read from a project, where parameters saved before
+/// discovery are keyed by name instead of CLAP id
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StoredChannelSource {
+    Sampler {
+        #[serde(default)]
+        path: Option<String>,
+    },
+    Plugin {
+        path: String,
+        #[serde(default)]
+        plugin_id: Option<String>,
         #[serde(default)]
-        params: HashMap<PluginParamId, f32>,
+        params: HashMap<String, f64>,
+        #[serde(default)]
+        legacy_params: HashMap<String, f64>,
+        #[serde(default, with = "crate::plugin_host::state::base64_blob")]
+        state: Option<Vec<u8>>,
     },
 }
 
+impl From<StoredChannelSource> for ChannelSource {
+    fn from(stored: StoredChannelSource) -> Self {
+        match stored {
+            StoredChannelSource::Sampler { path } => Self::Sampler { path },
+            StoredChannelSource::Plugin {
+                path,
+                plugin_id,
+                params,
+                mut legacy_params,
+                state,
+            } => {
+                let (params, names) = split_stored_params(params);
+                legacy_params.extend(names);
+                Self::Plugin {
+                    path,
+                    plugin_id,
+                    params,
+                    legacy_params,
+                    state,
+                }
+            }
+        }
+    }
+}
+
 impl Default for ChannelSource {
     fn default() -> Self {
         Self::Sampler { path: None }
@@ -179,6 +252,10 @@ pub struct Channel {
     #[serde(default = "default_mixer_track")]
     pub mixer_track: usize,
 
+    /// Slot of the channel this channel's plugin note output plays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_out: Option<usize>,
+
     /// Sequencer data for each pattern (keyed by pattern ID)
     #[serde(default)]
     pub pattern_data: HashMap<usize, PatternSlice>,
@@ -193,6 +270,7 @@ impl Channel {
             slot: 0,
             source: ChannelSource::Sampler { path: None },
             mixer_track: 1,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -204,6 +282,7 @@ impl Channel {
             slot,
             source: ChannelSource::Sampler { path: None },
             mixer_track,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -217,6 +296,7 @@ impl Channel {
                 path: Some(sample_path.to_string()),
             },
             mixer_track: 1,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -235,6 +315,7 @@ impl Channel {
                 path: Some(sample_path.to_string()),
             },
             mixer_track,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -246,9 +327,13 @@ impl Channel {
             slot: 0,
             source: ChannelSource::Plugin {
                 path: plugin_path.to_string(),
+                plugin_id: None,
                 params: HashMap::new(),
+                legacy_params: HashMap::new(),
+                state: None,
             },
             mixer_track: 1,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -265,9 +350,13 @@ impl Channel {
             slot,
             source: ChannelSource::Plugin {
                 path: plugin_path.to_string(),
+                plugin_id: None,
                 params: HashMap::new(),
+                legacy_params: HashMap::new(),
+                state: None,
             },
             mixer_track,
+            note_out: None,
             pattern_data: HashMap::new(),
         }
     }
@@ -294,8 +383,8 @@ impl Channel {
     }
 
     /// Get plugin params (returns empty map for samplers)
-    pub fn plugin_params(&self) -> &HashMap<PluginParamId, f32> {
-        static EMPTY: std::sync::LazyLock<HashMap<PluginParamId, f32>> =
+    pub fn plugin_params(&self) -> &HashMap<u32, f64> {
+        static EMPTY: std::sync::LazyLock<HashMap<u32, f64>> =
             std::sync::LazyLock::new(HashMap::new);
         match &self.source {
             ChannelSource::Plugin { params, .. } => params,
@@ -304,13 +393,62 @@ impl Channel {
     }
 
     /// Get mutable plugin params (returns None for samplers)
-    pub fn plugin_params_mut(&mut self) -> Option<&mut HashMap<PluginParamId, f32>> {
+    pub fn plugin_params_mut(&mut self) -> Option<&mut HashMap<u32, f64>> {
         match &mut self.source {
             ChannelSource::Plugin { params, .. } => Some(params),
             ChannelSource::Sampler { .. } => None,
         }
     }
 
+    /// Get the plugin id picked from a multi-plugin bundle
+    pub fn plugin_id(&self) -> Option<&str> {
+        match &self.source {
+            ChannelSource::Plugin { plugin_id, .. } => plugin_id.as_deref(),
+            ChannelSource::Sampler { .. } => None,
+        }
+    }
+
+    /// Name-keyed parameters from an older project that didn't match the
+    /// plugin (empty for samplers)
+    pub fn legacy_plugin_params(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = match &self.source {
+            ChannelSource::Plugin { legacy_params, .. } => {
+                legacy_params.keys().map(String::as_str).collect()
+            }
+            ChannelSource::Sampler { .. } => Vec::new(),
+        };
+        names.sort_unstable();
+        names
+    }
+
+    /// Bring a plugin channel saved before parameter discovery up to CLAP
+    /// id keys
+    ///
+    /// Only the bundled SimpleSynth's names are known; any other name (or
+    /// any name on another plugin) stays in `legacy_params`.
+    pub fn upgrade_legacy_params(&mut self) {
+        if let ChannelSource::Plugin {
+            path,
+            plugin_id,
+            params,
+            legacy_params,
+            ..
+        } = &mut self.source
+        {
+            if is_simple_synth(path, plugin_id.as_deref()) {
+                migrate_simple_synth_params(params, legacy_params);
+            }
+        }
+    }
+
+    /// Get the saved plugin state blob (None for samplers or stateless plugins)
+    pub fn plugin_state(&self) -> Option<&[u8]> {
+        match &self.source {
+            ChannelSource::Plugin { state, .. } => state.as_deref(),
+            ChannelSource::Sampler { .. } => None,
+        }
+    }
+
     /// Get or create pattern data for a pattern
     pub fn get_or_create_pattern(&mut self, pattern_id: usize, length: usize) -> &mut PatternSlice {
         self.pattern_data
@@ -392,6 +530,24 @@ pub fn default_patterns() -> Vec<Pattern> {
     vec![Pattern::new(0, 16)]
 }
 
+/// Note routes by channel index, as the audio engine addresses channels:
+/// entry `i` is the index of the plugin channel that channel `i`'s plugin
+/// note output plays, if any
+pub fn note_routes(channels: &[Channel]) -> Vec<Option<usize>> {
+    channels
+        .iter()
+        .enumerate()
+        .map(|(idx, channel)| {
+            let target_slot = channel.note_out?;
+            channel.plugin_path()?;
+            channels
+                .iter()
+                .position(|c| c.slot == target_slot && c.plugin_path().is_some())
+                .filter(|&target| target != idx)
+        })
+        .collect()
+}
+
 // ============================================================================
 // Yanked data types for vim registers
 // ============================================================================
@@ -406,6 +562,8 @@ pub struct YankedNote {
     pub step_offset: i32,
     /// Note duration
     pub duration: usize,
+    /// The note's expression values
+    pub expressions: NoteExpressions,
 }
 
 /// Yanked placement data for playlist copy/paste
@@ -438,6 +596,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_note_routes_map_slots_to_plugin_channel_indices() {
+        let mut arp = Channel::with_plugin_at_slot("arp", "arp.clap", 4, 1);
+        arp.note_out = Some(7);
+        let synth = Channel::with_plugin_at_slot("synth", "synth.clap", 7, 2);
+        let mut drums = Channel::new_at_slot("drums", 2, 3);
+        drums.note_out = Some(7);
+        let mut to_sampler = Channel::with_plugin_at_slot("x", "x.clap", 9, 4);
+        to_sampler.note_out = Some(2);
+        let mut to_self = Channel::with_plugin_at_slot("y", "y.clap", 5, 5);
+        to_self.note_out = Some(5);
+
+        let routes = note_routes(&[arp, synth, drums, to_sampler, to_self]);
+        assert_eq!(routes, vec![Some(1), None, None, None, None]);
+    }
+
     #[test]
     fn test_channel_new_creates_empty_sampler() {
         let channel = Channel::new("Test");
@@ -59,6 +59,19 @@ pub fn projects_dir() -> PathBuf {
     }
 }
 
+/// Get the factory effect presets directory (bundled with the templates)
+pub fn factory_presets_dir() -> PathBuf {
+    templates_dir().join("presets")
+}
+
+/// Get the user effect presets directory (~/.config/termdaw/presets)
+pub fn user_presets_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("termdaw")
+        .join("presets")
+}
+
 /// Check if templates are available (local or installed)
 pub fn templates_exist() -> bool {
     // Check local first (development), then installed
@@ -10,7 +10,7 @@ use ratatui::{
 
 use super::areas::AreaId;
 use crate::app::{App, Panel};
-use crate::browser::BrowserMode;
+use crate::browser::{BrowserMode, PluginGrouping};
 
 /// Render the browser panel
 pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
@@ -53,7 +53,11 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
     } else {
         Color::White
     };
-    let tabs = Tabs::new(vec!["Samples", "Plugins"])
+    let plugins_tab = match app.ui.browser.mode {
+        BrowserMode::Plugins => format!("Plugins: {}", app.ui.browser.plugin_grouping.name()),
+        BrowserMode::Samples => "Plugins".to_string(),
+    };
+    let tabs = Tabs::new(vec!["Samples".to_string(), plugins_tab])
         .select(match app.ui.browser.mode {
             BrowserMode::Samples => 0,
             BrowserMode::Plugins => 1,
@@ -87,7 +91,10 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
     if app.ui.browser.visible_entries.is_empty() {
         let msg = match app.ui.browser.mode {
             BrowserMode::Samples => "No samples found\n\nAdd .wav/.mp3/.flac files to:\nsamples/",
-            BrowserMode::Plugins => "No plugins found\n\nAdd .clap files to:\nplugins/",
+            BrowserMode::Plugins => match app.ui.browser.plugin_grouping {
+                PluginGrouping::Files => "No plugins found\n\nAdd .clap files to:\nplugins/",
+                _ => "No plugins found\n\nInstall CLAP plugins or\nadd folders to CLAP_PATH",
+            },
         };
         let paragraph = Paragraph::new(msg).style(Style::default().fg(Color::DarkGray));
         frame.render_widget(paragraph, content_area);
@@ -89,6 +89,11 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
     );
 
     if in_piano_roll_mode {
+        // The expression lane below the grid isn't clickable
+        let grid_area = Rect {
+            height: grid_area.height.saturating_sub(piano_roll::lane_rows(app)),
+            ..grid_area
+        };
         app.ui
             .screen_areas
             .register(AreaId::PianoRollGrid, grid_area);
@@ -2,6 +2,7 @@
 //!
 //! Renders the piano roll note editor when in piano roll mode.
 //! Shows the channel name, pitch labels (white/black key coloring), and note grid.
+//! A per-note expression lane can be shown below the grid.
 
 use ratatui::{
     layout::Rect,
@@ -13,6 +14,7 @@ use ratatui::{
 
 use crate::app::App;
 use crate::input::vim::Position;
+use crate::sequencer::NoteExpression;
 
 use super::piano_roll_view_model::{ChannelSidebarView, PianoRollViewModel};
 use super::{render_header, HEADER_ROWS, MUTE_WIDTH, NOTE_WIDTH, SAMPLE_WIDTH, TRACK_WIDTH};
@@ -24,6 +26,12 @@ use super::{render_header, HEADER_ROWS, MUTE_WIDTH, NOTE_WIDTH, SAMPLE_WIDTH, TR
 /// Minimum visible pitch (C2)
 const MIN_PITCH: u8 = 36;
 
+/// Rows taken by the expression lane (title + values)
+const LANE_ROWS: u16 = 2;
+
+/// Bar heights for lane values, lowest to highest
+const LANE_BARS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
 // ============================================================================
 // Rendering
 // ============================================================================
@@ -34,11 +42,86 @@ pub fn render(frame: &mut Frame, inner: Rect, app: &mut App, focused: bool) {
     render_header(frame, inner, app, true);
 
     // Calculate visible rows and build ViewModel
-    let visible_rows = (inner.height - HEADER_ROWS) as usize;
+    let lane_rows = lane_rows(app);
+    let visible_rows = inner.height.saturating_sub(HEADER_ROWS + lane_rows) as usize;
     let vm = PianoRollViewModel::from_app(app, visible_rows, focused);
 
     // Render grid using ViewModel
     render_grid(frame, inner, &vm, visible_rows);
+
+    if let Some(expression) = vm.expression_lane {
+        if inner.height >= HEADER_ROWS + lane_rows {
+            let y = inner.y + inner.height - lane_rows;
+            render_lane(frame, inner, y, expression, &vm);
+        }
+    }
+}
+
+/// Rows the expression lane takes at the bottom of the panel
+pub(super) fn lane_rows(app: &App) -> u16 {
+    if app.ui.cursors.piano_roll.expression_lane.is_some() {
+        LANE_ROWS
+    } else {
+        0
+    }
+}
+
+/// Render the expression lane: a title row with the cursor note's value,
+/// then a bar per note start in the step columns
+fn render_lane(
+    frame: &mut Frame,
+    inner: Rect,
+    y: u16,
+    expression: NoteExpression,
+    vm: &PianoRollViewModel,
+) {
+    let label_width = (MUTE_WIDTH + TRACK_WIDTH + SAMPLE_WIDTH - 1 + 2 + NOTE_WIDTH) as usize;
+
+    // Title row
+    let value = vm
+        .note_at(vm.cursor_pitch, vm.cursor_step)
+        .map(|n| expression.format(n.expressions.get(expression)))
+        .unwrap_or_else(|| "-".to_string());
+    let title = Line::from(vec![
+        Span::styled(
+            format!("{:<width$}", expression.name(), width = label_width),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(value, Style::default().fg(Color::White)),
+        Span::styled(
+            "  +/- adjust  E next lane",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(title), Rect::new(inner.x, y, inner.width, 1));
+
+    // Value row
+    let mut spans = vec![Span::raw(" ".repeat(label_width))];
+    for step in 0..16usize {
+        let sep = if step % 4 == 0 { "┃" } else { "│" };
+        spans.push(Span::styled(sep, Style::default().fg(Color::DarkGray)));
+
+        let is_cursor = vm.is_focused && vm.cursor_step == step;
+        let cell = match vm.lane_value_at(expression, step) {
+            Some(value) => {
+                let level = (expression.normalized(value) * (LANE_BARS.len() - 1) as f32).round();
+                LANE_BARS[level as usize].repeat(2)
+            }
+            None => "  ".to_string(),
+        };
+        let style = if is_cursor {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else {
+            Style::default().fg(Color::Magenta)
+        };
+        spans.push(Span::styled(cell, style));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)),
+        Rect::new(inner.x, y + 1, inner.width, 1),
+    );
 }
 
 /// Render the piano roll grid from ViewModel
@@ -9,6 +9,7 @@
 use crate::app::App;
 use crate::input::vim::{Position, Range};
 use crate::mixer::TrackId;
+use crate::sequencer::{NoteExpression, NoteExpressions};
 
 /// Minimum visible pitch (C2)
 const MIN_PITCH: u8 = 36;
@@ -45,6 +46,8 @@ pub struct NoteView {
     pub duration: usize,
     /// Note ID for identification
     pub id: String,
+    /// Per-note expression values
+    pub expressions: NoteExpressions,
 }
 
 impl NoteView {
@@ -88,6 +91,8 @@ pub struct PianoRollViewModel {
     pub is_playing: bool,
     /// Current playhead step (0-15)
     pub playhead_step: usize,
+    /// Per-note expression lane shown below the grid (if any)
+    pub expression_lane: Option<NoteExpression>,
 }
 
 impl PianoRollViewModel {
@@ -116,6 +121,7 @@ impl PianoRollViewModel {
                         start_step: n.start_step,
                         duration: n.duration,
                         id: n.id.clone(),
+                        expressions: n.expressions,
                     })
                     .collect()
             })
@@ -144,6 +150,7 @@ impl PianoRollViewModel {
             current_pattern: pattern_id,
             is_playing,
             playhead_step,
+            expression_lane: app.ui.cursors.piano_roll.expression_lane,
         }
     }
 
@@ -248,6 +255,16 @@ impl PianoRollViewModel {
             .iter()
             .any(|n| n.pitch == pitch && n.start_step == step)
     }
+
+    /// Lane value of the note starting at a step, preferring the note on
+    /// the cursor pitch when a chord starts there
+    pub fn lane_value_at(&self, expression: NoteExpression, step: usize) -> Option<f32> {
+        self.notes
+            .iter()
+            .filter(|n| n.start_step == step)
+            .max_by_key(|n| n.pitch == self.cursor_pitch)
+            .map(|n| n.expressions.get(expression))
+    }
 }
 
 #[cfg(test)]
@@ -289,10 +306,32 @@ mod tests {
             start_step: 4,
             duration: 4,
             id: "test".to_string(),
+            expressions: NoteExpressions::default(),
         };
         assert!(!note.covers_step(3));
         assert!(note.covers_step(4));
         assert!(note.covers_step(7));
         assert!(!note.covers_step(8));
     }
+
+    #[test]
+    fn test_lane_value_prefers_cursor_pitch() {
+        let (app, _temp) = create_test_app();
+        let mut vm = PianoRollViewModel::from_app(&app, 10, true);
+        let note = |pitch: u8, pressure: f32| NoteView {
+            pitch,
+            start_step: 0,
+            duration: 4,
+            id: pitch.to_string(),
+            expressions: NoteExpressions {
+                pressure,
+                ..NoteExpressions::default()
+            },
+        };
+        vm.notes = vec![note(60, 0.2), note(64, 0.9), note(67, 0.4)];
+        vm.cursor_pitch = 64;
+
+        assert_eq!(vm.lane_value_at(NoteExpression::Pressure, 0), Some(0.9));
+        assert_eq!(vm.lane_value_at(NoteExpression::Pressure, 1), None);
+    }
 }
@@ -29,7 +29,7 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
     // Calculate centered popup size
     let popup_width = 24;
-    let popup_height = 18;
+    let popup_height = 19;
 
     let popup_area = centered_rect(popup_width, popup_height, area);
 
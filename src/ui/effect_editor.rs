@@ -8,9 +8,25 @@ use ratatui::{
     Frame,
 };
 
+use super::eq_curve::EqCurveWidget;
 use crate::app::App;
-use crate::effects::{get_param_defs, EffectType, ParamDisplay};
+use crate::effects::eq::{EqBand, EQ_BANDS};
+use crate::effects::gate::GateState;
+use crate::effects::{get_param_defs, EffectParamId, EffectType, ParamDisplay};
 use crate::mode::AppMode;
+use crate::preset_picker::{Preset, PresetPicker, PresetPickerMode};
+
+/// Gain reduction shown by a full meter (dB)
+const GR_METER_RANGE_DB: f32 = 24.0;
+
+/// Height of the EQ response curve (rows)
+const EQ_CURVE_HEIGHT: u16 = 6;
+
+/// Most parameter rows shown at once before the list scrolls
+const MAX_PARAM_ROWS: usize = 11;
+
+/// Most presets shown at once before the preset list scrolls
+const MAX_PRESET_ROWS: usize = 10;
 
 /// Render effect-related modals
 pub fn render(frame: &mut Frame, app: &App) {
@@ -23,6 +39,9 @@ pub fn render(frame: &mut Frame, app: &App) {
             ..
         } => {
             render_effect_editor(frame, app, *track_idx, *slot_idx, *selected_param);
+            if app.ui.preset_picker.visible {
+                render_preset_picker(frame, &app.ui.preset_picker);
+            }
         }
         _ => {}
     }
@@ -32,9 +51,9 @@ pub fn render(frame: &mut Frame, app: &App) {
 fn render_effect_picker(frame: &mut Frame, app: &App) {
     let area = frame.area();
 
-    // Center the modal
+    // Center the modal (one row per effect type, plus spacer and help)
     let modal_width = 30;
-    let modal_height = 8;
+    let modal_height = EffectType::all().len() as u16 + 4;
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
     let modal_area = Rect::new(x, y, modal_width, modal_height);
@@ -101,9 +120,17 @@ fn render_effect_editor(
 
     let param_defs = get_param_defs(effect_slot.effect_type);
 
+    // The EQ draws its response curve above the parameters
+    let curve_height = if effect_slot.effect_type == EffectType::Eq {
+        EQ_CURVE_HEIGHT + 1
+    } else {
+        0
+    };
+
     // Modal size based on number of parameters
     let modal_width = 50;
-    let modal_height = (param_defs.len() + 4).min(15) as u16;
+    let modal_height =
+        (param_defs.len().min(MAX_PARAM_ROWS) as u16 + 4 + curve_height).min(area.height);
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
     let modal_area = Rect::new(x, y, modal_width, modal_height);
@@ -112,7 +139,7 @@ fn render_effect_editor(
     frame.render_widget(Clear, modal_area);
 
     // Modal border with effect name
-    let title = effect_slot.effect_type.name().to_string();
+    let title = effect_slot.display_name().to_string();
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
@@ -121,9 +148,30 @@ fn render_effect_editor(
     let inner = block.inner(modal_area);
     frame.render_widget(block, modal_area);
 
+    if curve_height > 0 {
+        let bands: Vec<EqBand> = (0..EQ_BANDS)
+            .map(|band| EqBand::from_slot(effect_slot, band))
+            .collect();
+        // Params are laid out as 4 per band
+        let widget = EqCurveWidget::new(&bands, app.audio.sample_rate() as f32)
+            .with_selected_band(Some(selected_param / 4));
+        let curve_area = Rect::new(
+            inner.x + 1,
+            inner.y,
+            inner.width.saturating_sub(2),
+            EQ_CURVE_HEIGHT.min(inner.height),
+        );
+        frame.render_widget(widget, curve_area);
+    }
+
+    // Scroll so the selected parameter stays visible
+    let params_top = inner.y + curve_height;
+    let visible_rows = (inner.height.saturating_sub(curve_height + 1)) as usize;
+    let scroll = selected_param.saturating_sub(visible_rows.saturating_sub(1));
+
     // Render each parameter
-    for (i, def) in param_defs.iter().enumerate() {
-        let row_y = inner.y + i as u16;
+    for (i, def) in param_defs.iter().enumerate().skip(scroll) {
+        let row_y = params_top + (i - scroll) as u16;
         if row_y >= inner.y + inner.height - 1 {
             break;
         }
@@ -144,6 +192,24 @@ fn render_effect_editor(
 
         // Build line based on parameter type
         let line = match &def.display {
+            ParamDisplay::Discrete { choices }
+                if !radios_fit(choices, inner.width.saturating_sub(11) as usize) =>
+            {
+                // Too many choices for radio buttons: show the current one only
+                let current_idx = (value as usize).min(choices.len().saturating_sub(1));
+                Line::from(vec![
+                    Span::styled(selector, Style::default().fg(Color::Cyan)),
+                    Span::styled(format!("{:10}", def.id.name()), name_style),
+                    Span::styled(
+                        format!(" < {} >", choices[current_idx]),
+                        Style::default().fg(if is_selected {
+                            Color::Yellow
+                        } else {
+                            Color::White
+                        }),
+                    ),
+                ])
+            }
             ParamDisplay::Discrete { choices } => {
                 // Show discrete choices as radio buttons (same style as plugin editor)
                 let current_idx = (value as usize).min(choices.len().saturating_sub(1));
@@ -204,4 +270,211 @@ fn render_effect_editor(
         let para = Paragraph::new(line);
         frame.render_widget(para, Rect::new(inner.x, row_y, inner.width, 1));
     }
+
+    // Live status of some effects (meter, indicator or file), on the last row
+    let status = match effect_slot.effect_type {
+        EffectType::Compressor => {
+            let reduction = app.audio.get_gain_reduction(track_idx, slot_idx);
+            Some((
+                "GR",
+                vec![
+                    Span::styled(
+                        meter_bar(reduction / GR_METER_RANGE_DB),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(
+                        format!(" {:>8}", format!("-{:.1}dB", reduction)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ],
+            ))
+        }
+        EffectType::Gate => {
+            let state = app
+                .audio
+                .get_gate_state(track_idx, slot_idx)
+                .unwrap_or_default();
+            let color = match state {
+                GateState::Open => Color::Green,
+                GateState::Hold => Color::Yellow,
+                GateState::Closed => Color::Red,
+            };
+            let reduction = app.audio.get_gain_reduction(track_idx, slot_idx);
+            let range = effect_slot.get_param(EffectParamId::GateRange).max(1.0);
+            Some((
+                "Gate",
+                vec![
+                    Span::styled(meter_bar(reduction / range), Style::default().fg(color)),
+                    Span::styled(
+                        format!(" ● {}", state.name()),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ),
+                ],
+            ))
+        }
+        EffectType::Convolution => {
+            let (name, color) = match (&effect_slot.ir_path, &effect_slot.impulse_response) {
+                (Some(path), Some(_)) => (
+                    std::path::Path::new(path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |n| n.to_string_lossy().to_string()),
+                    Color::White,
+                ),
+                (Some(path), None) => (format!("{} (missing)", path), Color::Red),
+                (None, _) => ("none".to_string(), Color::DarkGray),
+            };
+            Some((
+                "IR",
+                vec![
+                    Span::styled(name, Style::default().fg(color)),
+                    Span::styled("  i: load", Style::default().fg(Color::DarkGray)),
+                ],
+            ))
+        }
+        EffectType::Plugin => {
+            let path = effect_slot.plugin_path.as_deref().unwrap_or("none");
+            Some((
+                "Plugin",
+                vec![Span::styled(path, Style::default().fg(Color::White))],
+            ))
+        }
+        _ => None,
+    };
+    if let Some((label, value)) = status {
+        render_status_row(frame, inner, label, value);
+    }
+}
+
+/// Render a labelled status line on the last row of the editor
+fn render_status_row(frame: &mut Frame, inner: Rect, label: &str, value: Vec<Span>) {
+    if inner.height == 0 {
+        return;
+    }
+    let mut spans = vec![
+        Span::raw(" "),
+        Span::styled(
+            format!("{:10}", label),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    spans.extend(value);
+    let row_y = inner.y + inner.height - 1;
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)),
+        Rect::new(inner.x, row_y, inner.width, 1),
+    );
+}
+
+/// A 10-cell meter filled to `ratio` (0..1)
+fn meter_bar(ratio: f32) -> String {
+    let bar_width = 10;
+    let filled = (ratio.clamp(0.0, 1.0) * bar_width as f32) as usize;
+    (0..bar_width)
+        .map(|j| if j < filled { '█' } else { '░' })
+        .collect()
+}
+
+/// Render a preset picker over the effect or plugin editor
+pub(super) fn render_preset_picker<P: Preset>(frame: &mut Frame, picker: &PresetPicker<P>) {
+    let area = frame.area();
+
+    // One row per visible preset, plus status, prompt/help and borders
+    let modal_width = 40;
+    let list_rows = picker.presets.len().clamp(1, MAX_PRESET_ROWS) as u16;
+    let modal_height = (list_rows + 4).min(area.height);
+    let x = (area.width.saturating_sub(modal_width)) / 2;
+    let y = (area.height.saturating_sub(modal_height)) / 2;
+    let modal_area = Rect::new(x, y, modal_width, modal_height);
+
+    frame.render_widget(Clear, modal_area);
+
+    let block = Block::default()
+        .title("Presets")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(modal_area);
+    frame.render_widget(block, modal_area);
+
+    if picker.presets.is_empty() {
+        frame.render_widget(
+            Paragraph::new(" No presets yet").style(Style::default().fg(Color::DarkGray)),
+            Rect::new(inner.x, inner.y, inner.width, 1),
+        );
+    }
+
+    // Scroll so the selected preset stays visible
+    let scroll = picker
+        .selected
+        .saturating_sub((list_rows as usize).saturating_sub(1));
+    for (i, preset) in picker
+        .presets
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(list_rows as usize)
+    {
+        let is_selected = i == picker.selected;
+        let style = if is_selected {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let selector = if is_selected { ">" } else { " " };
+        let mut spans = vec![Span::styled(
+            format!("{} {}", selector, preset.name()),
+            style,
+        )];
+        if preset.is_factory() {
+            spans.push(Span::styled(
+                "  factory",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(Line::from(spans)),
+            Rect::new(inner.x, inner.y + (i - scroll) as u16, inner.width, 1),
+        );
+    }
+
+    let status_y = inner.y + inner.height.saturating_sub(2);
+    if let Some(message) = &picker.message {
+        frame.render_widget(
+            Paragraph::new(format!(" {}", message)).style(Style::default().fg(Color::Yellow)),
+            Rect::new(inner.x, status_y, inner.width, 1),
+        );
+    }
+
+    let bottom_y = inner.y + inner.height.saturating_sub(1);
+    match picker.input_value() {
+        Some(value) => {
+            let prompt = " Save as: ";
+            let line = Line::from(vec![
+                Span::styled(prompt, Style::default().fg(Color::Cyan)),
+                Span::styled(value.to_string(), Style::default().fg(Color::White)),
+            ]);
+            frame.render_widget(
+                Paragraph::new(line),
+                Rect::new(inner.x, bottom_y, inner.width, 1),
+            );
+            if let PresetPickerMode::Naming { input } = &picker.mode {
+                let cursor_x = inner.x + prompt.len() as u16 + input.visual_cursor() as u16;
+                frame.set_cursor_position((cursor_x.min(inner.x + inner.width - 1), bottom_y));
+            }
+        }
+        None => {
+            frame.render_widget(
+                Paragraph::new("Enter: apply  s: save  Esc: back")
+                    .style(Style::default().fg(Color::DarkGray)),
+                Rect::new(inner.x, bottom_y, inner.width, 1),
+            );
+        }
+    }
+}
+
+/// Whether a row of radio buttons for `choices` fits in `width` columns
+fn radios_fit(choices: &[&str], width: usize) -> bool {
+    let needed: usize = choices.iter().map(|c| c.chars().count() + 4).sum();
+    needed <= width
 }
@@ -59,9 +59,9 @@ pub enum VertexKind {
 /// 2  16
 /// 4  32
 /// 64 128
-const BRAILLE_BASE: u32 = 0x2800;
+pub(super) const BRAILLE_BASE: u32 = 0x2800;
 
-fn braille_dot(x: u8, y: u8) -> u8 {
+pub(super) fn braille_dot(x: u8, y: u8) -> u8 {
     match (x, y) {
         (0, 0) => 1,
         (0, 1) => 2,
@@ -192,7 +192,7 @@ impl Widget for EnvelopeWidget {
         for i in 0..points.len() - 1 {
             let (x1, y1) = points[i];
             let (x2, y2) = points[i + 1];
-            self.draw_braille_line(&mut braille_map, x1, y1, x2, y2);
+            draw_braille_line(&mut braille_map, x1, y1, x2, y2);
         }
 
         // Render braille characters
@@ -241,43 +241,40 @@ impl Widget for EnvelopeWidget {
     }
 }
 
-impl EnvelopeWidget {
-    /// Draw a line in braille coordinates
-    fn draw_braille_line(
-        &self,
-        map: &mut HashMap<(u16, u16), u8>,
-        x1: f32,
-        y1: f32,
-        x2: f32,
-        y2: f32,
-    ) {
-        let dx = x2 - x1;
-        let dy = y2 - y1;
-        let steps = dx.abs().max(dy.abs()).ceil() as i32;
-
-        if steps == 0 {
-            self.set_braille_dot(map, x1, y1);
-            return;
-        }
+/// Draw a line in braille coordinates
+pub(super) fn draw_braille_line(
+    map: &mut HashMap<(u16, u16), u8>,
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+) {
+    let dx = x2 - x1;
+    let dy = y2 - y1;
+    let steps = dx.abs().max(dy.abs()).ceil() as i32;
+
+    if steps == 0 {
+        set_braille_dot(map, x1, y1);
+        return;
+    }
 
-        for i in 0..=steps {
-            let t = i as f32 / steps as f32;
-            let x = x1 + t * dx;
-            let y = y1 + t * dy;
-            self.set_braille_dot(map, x, y);
-        }
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let x = x1 + t * dx;
+        let y = y1 + t * dy;
+        set_braille_dot(map, x, y);
     }
+}
 
-    /// Set a single braille dot
-    fn set_braille_dot(&self, map: &mut HashMap<(u16, u16), u8>, bx: f32, by: f32) {
-        let cell_x = (bx / 2.0).floor() as u16;
-        let cell_y = (by / 4.0).floor() as u16;
-        let dot_x = (bx as u8) % 2;
-        let dot_y = (by as u8) % 4;
+/// Set a single braille dot
+pub(super) fn set_braille_dot(map: &mut HashMap<(u16, u16), u8>, bx: f32, by: f32) {
+    let cell_x = (bx / 2.0).floor() as u16;
+    let cell_y = (by / 4.0).floor() as u16;
+    let dot_x = (bx as u8) % 2;
+    let dot_y = (by as u8) % 4;
 
-        let dots = map.entry((cell_x, cell_y)).or_insert(0);
-        *dots |= braille_dot(dot_x, dot_y);
-    }
+    let dots = map.entry((cell_x, cell_y)).or_insert(0);
+    *dots |= braille_dot(dot_x, dot_y);
 }
 
 /// Simple envelope renderer that uses the widget
@@ -0,0 +1,131 @@
+//! EQ frequency-response curve
+//!
+//! Draws the combined response of all EQ bands with Braille characters on a
+//! log frequency axis (20 Hz - 20 kHz), with a marker at each active band.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use std::collections::HashMap;
+
+use super::envelope::{draw_braille_line, set_braille_dot, BRAILLE_BASE};
+use crate::effects::eq::{response_db, BandKind, EqBand};
+
+/// Lowest frequency on the x axis
+const MIN_FREQ: f32 = 20.0;
+/// Highest frequency on the x axis
+const MAX_FREQ: f32 = 20000.0;
+/// The y axis spans +/- this many dB
+const RANGE_DB: f32 = 18.0;
+
+/// Widget that draws an EQ response curve
+pub struct EqCurveWidget<'a> {
+    bands: &'a [EqBand],
+    sample_rate: f32,
+    /// Band whose marker is highlighted
+    selected_band: Option<usize>,
+}
+
+impl<'a> EqCurveWidget<'a> {
+    pub fn new(bands: &'a [EqBand], sample_rate: f32) -> Self {
+        Self {
+            bands,
+            sample_rate,
+            selected_band: None,
+        }
+    }
+
+    /// Highlight a band's marker
+    pub fn with_selected_band(mut self, band: Option<usize>) -> Self {
+        self.selected_band = band;
+        self
+    }
+}
+
+/// Frequency at a fraction 0.0-1.0 across the log axis
+fn freq_at(fraction: f32) -> f32 {
+    MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(fraction)
+}
+
+/// Fraction 0.0-1.0 across the log axis for a frequency
+fn fraction_of(freq: f32) -> f32 {
+    ((freq / MIN_FREQ).ln() / (MAX_FREQ / MIN_FREQ).ln()).clamp(0.0, 1.0)
+}
+
+/// Fraction 0.0 (top) to 1.0 (bottom) for a gain in dB
+fn y_fraction_of(db: f32) -> f32 {
+    (1.0 - (db.clamp(-RANGE_DB, RANGE_DB) + RANGE_DB) / (2.0 * RANGE_DB)).clamp(0.0, 1.0)
+}
+
+impl Widget for EqCurveWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 4 || area.height < 2 {
+            return;
+        }
+
+        let bw = area.width as f32 * 2.0;
+        let bh = area.height as f32 * 4.0;
+        let to_y = |db: f32| y_fraction_of(db) * (bh - 1.0);
+
+        // 0 dB reference line, dotted
+        let mut zero_map: HashMap<(u16, u16), u8> = HashMap::new();
+        let zero_y = to_y(0.0);
+        for x in (0..bw as u16).step_by(4) {
+            set_braille_dot(&mut zero_map, x as f32, zero_y);
+        }
+
+        // Response curve, one point per braille column
+        let mut curve_map: HashMap<(u16, u16), u8> = HashMap::new();
+        let mut prev: Option<(f32, f32)> = None;
+        for x in 0..bw as u16 {
+            let freq = freq_at(x as f32 / (bw - 1.0));
+            let point = (
+                x as f32,
+                to_y(response_db(self.bands, freq, self.sample_rate)),
+            );
+            match prev {
+                Some((px, py)) => draw_braille_line(&mut curve_map, px, py, point.0, point.1),
+                None => set_braille_dot(&mut curve_map, point.0, point.1),
+            }
+            prev = Some(point);
+        }
+
+        let mut draw = |map: &HashMap<(u16, u16), u8>, style: Style| {
+            for ((cell_x, cell_y), dots) in map {
+                let x = area.x + cell_x;
+                let y = area.y + cell_y;
+                if x < area.x + area.width && y < area.y + area.height {
+                    if let Some(cell) = buf.cell_mut((x, y)) {
+                        let ch = char::from_u32(BRAILLE_BASE + *dots as u32).unwrap_or('?');
+                        cell.set_char(ch);
+                        cell.set_style(style);
+                    }
+                }
+            }
+        };
+        draw(&zero_map, Style::default().fg(Color::DarkGray));
+        draw(&curve_map, Style::default().fg(Color::Yellow));
+
+        // Band markers on top of the curve
+        for (idx, band) in self.bands.iter().enumerate() {
+            if band.kind == BandKind::Off {
+                continue;
+            }
+            let db = response_db(self.bands, band.freq, self.sample_rate);
+            let x = area.x + (fraction_of(band.freq) * (area.width - 1) as f32).round() as u16;
+            let y = area.y + (y_fraction_of(db) * (area.height - 1) as f32).round() as u16;
+            let color = if self.selected_band == Some(idx) {
+                Color::Cyan
+            } else {
+                Color::White
+            };
+            if let Some(cell) = buf.cell_mut((x, y)) {
+                cell.set_char(char::from_digit(idx as u32 + 1, 10).unwrap_or('●'));
+                cell.set_style(Style::default().fg(color));
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Export dialog UI rendering
+
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::audio::export::ExportMode;
+use crate::export_dialog::ExportField;
+
+/// Width of the label column
+const LABEL_WIDTH: usize = 13;
+
+/// Render the export dialog if visible
+pub fn render(frame: &mut Frame, app: &App) {
+    let dialog = &app.ui.export_dialog;
+    if !dialog.visible {
+        return;
+    }
+
+    let area = frame.area();
+    let fields = dialog.fields();
+
+    // Rows + blank line + help line + borders
+    let popup_width = 46.min(area.width.saturating_sub(4));
+    let popup_height = (fields.len() as u16 + 4).min(area.height);
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let title = match dialog.mode {
+        ExportMode::Mix => " Export ",
+        ExportMode::Stems => " Export Stems ",
+    };
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for &field in &fields {
+        let selected = field == dialog.selected;
+        let label_style = if selected {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let value_style = if selected {
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        let value = if field == ExportField::Filename {
+            dialog.value_text(field)
+        } else if selected {
+            format!("< {} >", dialog.value_text(field))
+        } else {
+            format!("  {}", dialog.value_text(field))
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(if selected { "> " } else { "  " }, label_style),
+            Span::styled(
+                format!("{:<width$}", field.label(), width = LABEL_WIDTH),
+                label_style,
+            ),
+            Span::styled(value, value_style),
+        ]));
+    }
+
+    // Blank unless the settings can't be exported
+    lines.push(match dialog.error() {
+        Some(error) => Line::from(Span::styled(
+            format!("  {}", error),
+            Style::default().fg(Color::Red),
+        )),
+        None => Line::from(""),
+    });
+    lines.push(Line::from(Span::styled(
+        "[j/k] Move  [h/l] Change  [Enter] Export  [Esc] Cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+
+    // Show the text cursor while editing the filename
+    if dialog.selected == ExportField::Filename {
+        let value_x = inner.x + 2 + LABEL_WIDTH as u16;
+        let width = inner.width.saturating_sub(2 + LABEL_WIDTH as u16);
+        let cursor = (dialog.filename.visual_cursor() as u16).min(width.saturating_sub(1));
+        frame.set_cursor_position((value_x + cursor, inner.y));
+    }
+}
+
+/// Render the export progress modal while an export job is running
+pub fn render_progress(frame: &mut Frame, app: &App) {
+    let Some(job) = &app.export_job else {
+        return;
+    };
+
+    let area = frame.area();
+    let popup_width = 46.min(area.width.saturating_sub(4));
+    let popup_height = 6.min(area.height);
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let title = match job.mode() {
+        ExportMode::Mix => " Exporting ",
+        ExportMode::Stems => " Exporting Stems ",
+    };
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let (done, total) = job.progress();
+    let ratio = if total == 0 {
+        0.0
+    } else {
+        (done as f64 / total as f64).clamp(0.0, 1.0)
+    };
+    let label = if job.is_cancelling() {
+        "cancelling...".to_string()
+    } else if total > 0 && done >= total {
+        "rendering tail and writing...".to_string()
+    } else {
+        format!("bar {}/{}", done, total)
+    };
+
+    let file_name = job
+        .output()
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let row = |offset: u16| Rect {
+        x: inner.x + 1,
+        y: inner.y + offset,
+        width: inner.width.saturating_sub(2),
+        height: 1,
+    };
+
+    frame.render_widget(
+        Paragraph::new(file_name).style(Style::default().fg(Color::White)),
+        row(0),
+    );
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
+            .ratio(ratio)
+            .label(label),
+        row(1),
+    );
+    frame.render_widget(
+        Paragraph::new(format!(
+            "{:.1}s elapsed  [Esc] Cancel  [q] Quit",
+            job.elapsed().as_secs_f32()
+        ))
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center),
+        row(3),
+    );
+}
@@ -142,6 +142,14 @@ fn render_track(
         RouteDestination::Master => "→M".to_string(),
         RouteDestination::Track(t) => format!("→{}", t.index()),
     };
+    // Plugin/effect latency, when the track has any
+    let latency = app.mixer.track_latency[track_idx];
+    let route_text = if latency > 0 {
+        let ms = latency as f32 * 1000.0 / app.audio.sample_rate() as f32;
+        format!("{} {}", route_text, format_latency_ms(ms))
+    } else {
+        route_text
+    };
     let route_style = Style::default().fg(Color::DarkGray);
     let route = Paragraph::new(format!(
         "{:^width$}",
@@ -433,10 +441,19 @@ fn render_effects_panel(frame: &mut Frame, x: u16, y: u16, width: u16, height: u
                 let name = match slot.effect_type {
                     EffectType::Filter => {
                         let mode = slot.get_param(crate::effects::EffectParamId::FilterMode) as u32;
-                        match mode {
-                            0 => "Filter LP",
-                            1 => "Filter HP",
-                            _ => "Filter BP",
+                        let ladder =
+                            slot.get_param(crate::effects::EffectParamId::FilterModel) >= 1.0;
+                        match (ladder, mode) {
+                            (false, 0) => "Filter LP",
+                            (false, 1) => "Filter HP",
+                            (false, 2) => "Filter BP",
+                            (false, 3) => "Filter Nt",
+                            (false, _) => "Filter Pk",
+                            (true, 0) => "Ladder LP",
+                            (true, 1) => "Ladder HP",
+                            (true, 2) => "Ladder BP",
+                            (true, 3) => "Ladder Nt",
+                            (true, _) => "Ladder Pk",
                         }
                     }
                     EffectType::Delay => {
@@ -463,6 +480,47 @@ fn render_effects_panel(frame: &mut Frame, x: u16, y: u16, width: u16, height: u
                             _ => "Enh Loud",
                         }
                     }
+                    EffectType::Compressor => {
+                        let sidechain =
+                            slot.get_param(crate::effects::EffectParamId::CompressorSidechain);
+                        if sidechain >= 1.0 {
+                            "Comp SC"
+                        } else {
+                            "Comp"
+                        }
+                    }
+                    EffectType::Eq => "EQ",
+                    EffectType::Chorus => "Chorus",
+                    EffectType::Flanger => "Flanger",
+                    EffectType::Phaser => "Phaser",
+                    EffectType::Distortion => {
+                        let mode =
+                            slot.get_param(crate::effects::EffectParamId::DistortionMode) as u32;
+                        match mode {
+                            0 => "Dist Soft",
+                            1 => "Dist Hard",
+                            2 => "Dist Fold",
+                            _ => "Dist Tube",
+                        }
+                    }
+                    EffectType::Bitcrusher => "Crusher",
+                    EffectType::Convolution => "Conv Rev",
+                    EffectType::Gate => {
+                        let sidechain =
+                            slot.get_param(crate::effects::EffectParamId::GateSidechain);
+                        if sidechain >= 1.0 {
+                            "Gate SC"
+                        } else {
+                            "Gate"
+                        }
+                    }
+                    EffectType::TransientShaper => "Transient",
+                    EffectType::Plugin => {
+                        // Plugin file stem, cut to the width of the built-in labels
+                        let name = slot.display_name();
+                        let end = name.char_indices().nth(10).map_or(name.len(), |(i, _)| i);
+                        &name[..end]
+                    }
                 };
                 (name, slot.bypassed, true)
             }
@@ -478,6 +536,9 @@ fn render_effects_panel(frame: &mut Frame, x: u16, y: u16, width: u16, height: u
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD)
+        } else if app.ui.effect_swap_mark == Some((selected, i)) {
+            // Slot marked for swapping with 'm'
+            Style::default().fg(Color::Magenta)
         } else if has_effect && !bypassed {
             Style::default().fg(Color::White)
         } else {
@@ -630,3 +691,12 @@ fn render_effects_panel(frame: &mut Frame, x: u16, y: u16, width: u16, height: u
     let vol_widget = Paragraph::new(Line::from(vol_chars));
     frame.render_widget(vol_widget, Rect::new(content_x, vol_y, content_width, 1));
 }
+
+/// Compact latency label that fits beside the route indicator
+fn format_latency_ms(ms: f32) -> String {
+    if ms < 10.0 {
+        format!("{:.1}ms", ms)
+    } else {
+        format!("{:.0}ms", ms)
+    }
+}
@@ -9,7 +9,9 @@ mod confirm_dialog;
 pub mod context_menu;
 mod effect_editor;
 mod envelope;
+mod eq_curve;
 mod event_log;
+mod export_dialog;
 mod mixer;
 mod playlist;
 pub mod plugin_editor;
@@ -103,6 +105,10 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     // Projects modal (rendered on top of command picker)
     projects_modal::render(frame, app);
 
+    // Export dialog and progress of a running export
+    export_dialog::render(frame, app);
+    export_dialog::render_progress(frame, app);
+
     // Plugin editor modal (rendered on top of everything else)
     plugin_editor::render(frame, app);
 
@@ -2,9 +2,11 @@
 //!
 //! A popup modal for editing plugin parameters with vim-style navigation.
 //! Features:
-//! - High-quality ADSR envelope visualization using tiny-skia + kitty graphics
-//! - Horizontal faders for parameters
-//! - Waveform selector
+//! - Parameters discovered from the plugin, values formatted by the plugin
+//! - Horizontal faders for continuous parameters
+//! - Factory and user presets, stepped through with [/] or picked from a list
+//! - High-quality ADSR envelope visualization using tiny-skia + kitty graphics,
+//!   shown when the plugin has Attack/Decay/Sustain/Release parameters
 
 use std::time::Instant;
 
@@ -16,23 +18,35 @@ use ratatui::{
     Frame,
 };
 
+use super::effect_editor::render_preset_picker;
 use super::envelope::{EnvelopeParams, EnvelopeRenderer};
 use crate::app::App;
-use crate::plugin_host::PluginParam;
+use crate::plugin_host::{ParamFormatter, PluginParam, PluginPreset};
+
+/// Parameter names the envelope visualization is drawn from
+const ENVELOPE_PARAMS: [&str; 4] = ["Attack", "Decay", "Sustain", "Release"];
 
 /// Plugin editor state
 #[derive(Default)]
 pub struct PluginEditorState {
     /// Whether the editor is visible
     pub visible: bool,
-    /// Channel index being edited
+    /// Channel slot being edited
     pub channel_idx: usize,
     /// Currently selected parameter index
     pub selected_param: usize,
     /// Plugin name
     pub plugin_name: String,
+    /// CLAP id of the plugin, which its presets are saved under
+    pub plugin_id: String,
+    /// Name of the last preset applied, shown in the title
+    pub preset_name: Option<String>,
     /// Plugin parameters
     pub params: Vec<PluginParam>,
+    /// Why the plugin's parameters couldn't be read, shown in their place
+    pub load_error: Option<String>,
+    /// Plugin instance used to format values (None if it couldn't be loaded)
+    formatter: Option<Box<dyn ParamFormatter>>,
     /// Envelope renderer (handles caching and graphics protocol)
     envelope_renderer: EnvelopeRenderer,
     /// When the preview started (note on)
@@ -48,7 +62,10 @@ impl std::fmt::Debug for PluginEditorState {
             .field("channel_idx", &self.channel_idx)
             .field("selected_param", &self.selected_param)
             .field("plugin_name", &self.plugin_name)
+            .field("plugin_id", &self.plugin_id)
+            .field("preset_name", &self.preset_name)
             .field("params", &self.params)
+            .field("load_error", &self.load_error)
             .finish()
     }
 }
@@ -61,20 +78,33 @@ impl PluginEditorState {
     }
 
     /// Open the editor for a channel
-    pub fn open(&mut self, channel_idx: usize, plugin_name: &str, params: Vec<PluginParam>) {
+    pub fn open(
+        &mut self,
+        channel_idx: usize,
+        plugin_id: &str,
+        plugin_name: &str,
+        params: Vec<PluginParam>,
+        formatter: Option<Box<dyn ParamFormatter>>,
+    ) {
         self.visible = true;
         self.channel_idx = channel_idx;
+        self.plugin_id = plugin_id.to_string();
         self.plugin_name = plugin_name.to_string();
+        self.preset_name = None;
         // Always use the passed params (which come from channel.plugin_params)
         // to ensure we show the current saved state
         self.params = params;
+        self.load_error = None;
+        self.formatter = formatter;
+        self.selected_param = self.selected_param.min(self.params.len().saturating_sub(1));
         // Invalidate envelope cache so it regenerates with new params
         self.envelope_renderer.invalidate();
     }
 
-    /// Close the editor
+    /// Close the editor, releasing the plugin instance used for formatting
     pub fn close(&mut self) {
         self.visible = false;
+        self.formatter = None;
     }
 
     /// Move selection up
@@ -91,89 +121,70 @@ impl PluginEditorState {
         }
     }
 
-    /// Adjust selected parameter value (delta is -1 to 1)
+    /// Adjust selected parameter value: percent of the range for continuous
+    /// parameters, one step (in the direction of `delta`) for stepped ones
     pub fn adjust_value(&mut self, delta: f32) {
         if let Some(param) = self.params.get_mut(self.selected_param) {
-            // Handle discrete parameters (like Waveform) differently
-            if param.name == "Waveform" {
-                // Cycle through discrete values
-                let current = param.value.round() as i32;
-                let max = param.max as i32;
-                let new_val = if delta > 0.0 {
-                    (current + 1).min(max)
-                } else {
-                    (current - 1).max(0)
-                };
-                param.value = new_val as f32;
-            } else {
-                // Continuous parameters: 1% of range per step
-                let range = param.max - param.min;
-                let step = range * 0.01;
-                param.value = (param.value + delta * step).clamp(param.min, param.max);
-            }
+            param.adjust(delta as f64);
             // Note: envelope renderer handles parameter changes via set_source()
             // No need to invalidate - that would cause flicker by recreating the protocol
         }
     }
 
+    /// Show a value the plugin changed itself
+    pub fn set_param_value(&mut self, param_id: u32, value: f64) {
+        if let Some(param) = self.params.iter_mut().find(|p| p.id == param_id) {
+            param.value = value.clamp(param.min, param.max);
+        }
+    }
+
     /// Get the currently selected parameter
     pub fn selected_param(&self) -> Option<&PluginParam> {
         self.params.get(self.selected_param)
     }
 
-    /// Get parameter value by name
-    pub fn get_param_value(&self, name: &str) -> Option<f32> {
-        self.params.iter().find(|p| p.name == name).map(|p| p.value)
+    /// Display text for a parameter's value, formatted by the plugin when possible
+    pub fn value_text(&mut self, index: usize) -> String {
+        let Some(param) = self.params.get(index) else {
+            return String::new();
+        };
+        self.formatter
+            .as_mut()
+            .and_then(|f| f.format(param.id, param.value))
+            .unwrap_or_else(|| param.format_value())
+    }
+
+    /// Have the plugin load one of its own presets and snapshot the result
+    pub fn load_preset(&mut self, preset: &PluginPreset) -> Result<PluginPreset, String> {
+        self.formatter
+            .as_mut()
+            .ok_or_else(|| "Plugin isn't loaded".to_string())?
+            .load_preset(preset)
+    }
+
+    fn param_index(&self, name: &str) -> Option<usize> {
+        self.params.iter().position(|p| p.name == name)
     }
 
     /// Get normalized parameter value (0.0-1.0) by name
     pub fn get_param_normalized(&self, name: &str) -> Option<f32> {
-        self.params.iter().find(|p| p.name == name).map(|p| {
-            let range = p.max - p.min;
-            if range > 0.0 {
-                (p.value - p.min) / range
-            } else {
-                0.0
-            }
-        })
-    }
-
-    /// Get the CLAP param ID for the currently selected parameter.
-    /// Maps the param name to nih-plug's Rabin fingerprint hash.
-    pub fn get_selected_clap_param_id(&self) -> Option<u32> {
-        self.params.get(self.selected_param).and_then(|p| {
-            // nih-plug uses Rabin fingerprint: hash = hash * 31 + byte
-            // These are pre-computed for the simple-synth params
-            match p.name.as_str() {
-                "Attack" => Some(96920),     // hash of "atk"
-                "Decay" => Some(99330),      // hash of "dec"
-                "Sustain" => Some(114257),   // hash of "sus"
-                "Release" => Some(112793),   // hash of "rel"
-                "Gain" => Some(3165055),     // hash of "gain"
-                "Waveform" => Some(3642105), // hash of "wave"
-                _ => None,
-            }
-        })
-    }
-
-    /// Get the selected parameter's normalized value (0.0-1.0) for sending to the plugin.
-    /// CLAP/nih-plug expects normalized values for continuous params.
-    pub fn get_selected_param_value(&self) -> Option<f64> {
-        self.params.get(self.selected_param).map(|p| {
-            match p.name.as_str() {
-                // Waveform is discrete - use the enum index directly
-                "Waveform" => p.value.round() as f64,
-                // All other params need to be normalized to 0.0-1.0
-                _ => {
-                    let range = p.max - p.min;
-                    if range > 0.0 {
-                        ((p.value - p.min) / range) as f64
-                    } else {
-                        0.0
-                    }
-                }
-            }
-        })
+        self.param_index(name)
+            .map(|i| self.params[i].normalized() as f32)
+    }
+
+    /// Get a time parameter in milliseconds by name, read from the plugin's
+    /// display text ("120 ms", "1.5 s"); falls back to the raw value
+    fn get_param_millis(&mut self, name: &str) -> Option<f32> {
+        let index = self.param_index(name)?;
+        let text = self.value_text(index);
+        Some(parse_millis(&text).unwrap_or(self.params[index].value as f32))
+    }
+
+    /// Whether the plugin has the parameters the envelope view is drawn from
+    pub fn has_envelope(&self) -> bool {
+        ENVELOPE_PARAMS
+            .iter()
+            .all(|name| self.param_index(name).is_some())
     }
 
     /// Get current ADSR envelope parameters
@@ -207,13 +218,13 @@ impl PluginEditorState {
 
     /// Calculate the current playhead position as a fraction (0.0 to 1.0) across the envelope.
     /// Returns None if not currently previewing or if the envelope has completed.
-    pub fn get_playhead_position(&self) -> Option<f32> {
+    pub fn get_playhead_position(&mut self) -> Option<f32> {
         let start = self.preview_start?;
 
         // Get ADSR times in milliseconds
-        let attack_ms = self.get_param_value("Attack").unwrap_or(10.0);
-        let decay_ms = self.get_param_value("Decay").unwrap_or(100.0);
-        let release_ms = self.get_param_value("Release").unwrap_or(200.0);
+        let attack_ms = self.get_param_millis("Attack").unwrap_or(10.0).max(1.0);
+        let decay_ms = self.get_param_millis("Decay").unwrap_or(100.0).max(1.0);
+        let release_ms = self.get_param_millis("Release").unwrap_or(200.0).max(1.0);
 
         // Calculate total envelope width (same as in envelope.rs)
         // We use a fixed sustain display width of 25% of total
@@ -266,6 +277,20 @@ impl PluginEditorState {
     }
 }
 
+/// Parse a plugin's time display ("120 ms", "1.5 s", "2sec") into milliseconds
+fn parse_millis(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(text.len());
+    let value: f32 = text[..split].parse().ok()?;
+    match text[split..].trim().to_lowercase().as_str() {
+        "ms" => Some(value),
+        "s" | "sec" => Some(value * 1000.0),
+        _ => None,
+    }
+}
+
 /// Render the plugin editor modal
 pub fn render(frame: &mut Frame, app: &mut App) {
     if !app.ui.plugin_editor.visible {
@@ -283,7 +308,10 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     frame.render_widget(Clear, popup_area);
 
     // Render the popup border
-    let title = format!(" {} ", app.ui.plugin_editor.plugin_name);
+    let title = match &app.ui.plugin_editor.preset_name {
+        Some(preset) => format!(" {} - {} ", app.ui.plugin_editor.plugin_name, preset),
+        None => format!(" {} ", app.ui.plugin_editor.plugin_name),
+    };
     let block = Block::default()
         .title(title)
         .title_alignment(Alignment::Center)
@@ -294,12 +322,17 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     frame.render_widget(block, popup_area);
 
     // Render content
-    if app.ui.plugin_editor.params.is_empty() {
+    if let Some(error) = &app.ui.plugin_editor.load_error {
+        let error = Paragraph::new(error.as_str())
+            .style(Style::default().fg(Color::Red))
+            .alignment(Alignment::Center);
+        frame.render_widget(error, inner);
+    } else if app.ui.plugin_editor.params.is_empty() {
         let no_params = Paragraph::new("No parameters available")
             .style(Style::default().fg(Color::DarkGray))
             .alignment(Alignment::Center);
         frame.render_widget(no_params, inner);
-    } else {
+    } else if app.ui.plugin_editor.has_envelope() {
         // Give the envelope more space - use about 40% of the modal height
         let envelope_height = (inner.height as f32 * 0.4).max(8.0) as u16;
 
@@ -319,11 +352,23 @@ pub fn render(frame: &mut Frame, app: &mut App) {
             width: inner.width,
             height: inner.height.saturating_sub(envelope_height + 2),
         };
-        render_params(frame, params_area, &app.ui.plugin_editor);
+        render_params(frame, params_area, &mut app.ui.plugin_editor);
+    } else {
+        let params_area = Rect {
+            x: inner.x,
+            y: inner.y + 1,
+            width: inner.width,
+            height: inner.height.saturating_sub(2),
+        };
+        render_params(frame, params_area, &mut app.ui.plugin_editor);
     }
 
     // Render help footer
     render_footer(frame, popup_area);
+
+    if app.ui.plugin_preset_picker.visible {
+        render_preset_picker(frame, &app.ui.plugin_preset_picker);
+    }
 }
 
 /// Render the ADSR envelope visualization
@@ -446,25 +491,31 @@ fn render_envelope_fallback(frame: &mut Frame, area: Rect, params: EnvelopeParam
 }
 
 /// Render parameter list with horizontal faders
-fn render_params(frame: &mut Frame, area: Rect, state: &PluginEditorState) {
+///
+/// Rows are spaced out when they all fit; long parameter lists scroll to
+/// keep the selection visible.
+fn render_params(frame: &mut Frame, area: Rect, state: &mut PluginEditorState) {
     let fader_width = 30usize;
-    let mut y = area.y;
-
-    for (i, param) in state.params.iter().enumerate() {
-        if y >= area.y + area.height - 2 {
-            break;
-        }
+    let rows = area.height.saturating_sub(1) as usize;
+    if rows == 0 {
+        return;
+    }
+    let spacing = if state.params.len() * 2 <= rows { 2 } else { 1 };
+    let visible = rows.div_ceil(spacing);
+    let scroll = (state.selected_param + 1).saturating_sub(visible);
+    let name_width = state
+        .params
+        .iter()
+        .map(|p| p.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(10, 16);
 
+    for (row, i) in (scroll..state.params.len()).take(visible).enumerate() {
+        let y = area.y + (row * spacing) as u16;
         let is_selected = i == state.selected_param;
-
-        // Calculate fill percentage
-        let range = param.max - param.min;
-        let normalized = if range > 0.0 {
-            ((param.value - param.min) / range).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-        let fill_count = (normalized * fader_width as f32) as usize;
+        let value_str = state.value_text(i);
+        let param = &state.params[i];
 
         // Build the fader line
         let prefix = if is_selected { "▸ " } else { "  " };
@@ -475,29 +526,19 @@ fn render_params(frame: &mut Frame, area: Rect, state: &PluginEditorState) {
         } else {
             Style::default().fg(Color::White)
         };
+        let name: String = param.name.chars().take(name_width).collect();
 
-        // Format value based on parameter type
-        let value_str = if param.name == "Sustain" || param.name == "Gain" {
-            format!("{:>5.0}%", param.value * 100.0)
-        } else if param.name == "Waveform" {
-            // Order matches synth's Waveform enum: Sine, Square, Saw, Triangle
-            match param.value.round() as u8 {
-                0 => "  SINE".to_string(),
-                1 => "   SQR".to_string(),
-                2 => "   SAW".to_string(),
-                _ => "   TRI".to_string(),
-            }
-        } else {
-            format!("{:>5.0}ms", param.value)
-        };
-
-        // Build fader string
         let mut fader_spans: Vec<Span> = Vec::new();
         fader_spans.push(Span::styled(prefix, name_style));
-        fader_spans.push(Span::styled(format!("{:<10}", param.name), name_style));
+        fader_spans.push(Span::styled(
+            format!("{:<width$}", name, width = name_width),
+            name_style,
+        ));
+
+        // Stepped parameters (switches, choices) show only their value text
+        if !param.stepped {
+            let fill_count = (param.normalized() * fader_width as f64) as usize;
 
-        // Fader track with colored start marker
-        if param.name != "Waveform" {
             // Start marker (vertex color)
             fader_spans.push(Span::styled("░", Style::default().fg(Color::Cyan)));
 
@@ -519,25 +560,6 @@ fn render_params(frame: &mut Frame, area: Rect, state: &PluginEditorState) {
             }
 
             fader_spans.push(Span::styled("▏", Style::default().fg(Color::DarkGray)));
-        } else {
-            // Waveform selector - show options with proper spacing
-            // Order: Sine(0), Square(1), Saw(2), Triangle(3) - matches synth enum
-            let wave_val = param.value.round() as u8;
-            let waves = [("∿", 0u8), ("⊓", 1), ("⩘", 2), ("△", 3)];
-
-            for (symbol, val) in waves {
-                if wave_val == val {
-                    fader_spans.push(Span::styled(
-                        format!(" ◉ {} ", symbol),
-                        Style::default().fg(Color::Yellow),
-                    ));
-                } else {
-                    fader_spans.push(Span::styled(
-                        format!(" ○ {} ", symbol),
-                        Style::default().fg(Color::DarkGray),
-                    ));
-                }
-            }
         }
 
         // Value display
@@ -552,8 +574,6 @@ fn render_params(frame: &mut Frame, area: Rect, state: &PluginEditorState) {
 
         let line = Line::from(fader_spans);
         frame.render_widget(Paragraph::new(line), Rect::new(area.x, y, area.width, 1));
-
-        y += 2; // Add spacing between parameters
     }
 }
 
@@ -568,13 +588,15 @@ fn render_footer(frame: &mut Frame, popup_area: Rect) {
 
     let footer = Paragraph::new(Line::from(vec![
         Span::styled("j/k", Style::default().fg(Color::Cyan)),
-        Span::styled(" navigate  ", Style::default().fg(Color::DarkGray)),
+        Span::styled(" select  ", Style::default().fg(Color::DarkGray)),
         Span::styled("h/l", Style::default().fg(Color::Cyan)),
         Span::styled(" adjust  ", Style::default().fg(Color::DarkGray)),
         Span::styled("H/L", Style::default().fg(Color::Cyan)),
         Span::styled(" fine  ", Style::default().fg(Color::DarkGray)),
         Span::styled("s", Style::default().fg(Color::Cyan)),
         Span::styled(" preview  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("p [ ]", Style::default().fg(Color::Cyan)),
+        Span::styled(" presets  ", Style::default().fg(Color::DarkGray)),
         Span::styled("Esc", Style::default().fg(Color::Cyan)),
         Span::styled(" close", Style::default().fg(Color::DarkGray)),
     ]))